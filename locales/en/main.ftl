@@ -1,5 +1,6 @@
 # General UI
 app-title = SomaFM Player
+language-name = English
 app-description = A rusty soma.fm player
 
 # Main UI
@@ -7,6 +8,20 @@ stations = Stations
 history = History
 loading = Loading
 loading-stations = Loading stations...
+refreshing-stations = Refreshing station list...
+stations-updated = Station list updated
+station-added = New on SomaFM: {$station}
+station-renamed = SomaFM renamed {$old} to {$new}
+station-removed = Gone from SomaFM: {$station}
+station-new-badge = NEW
+stream-moved = The stream of {$station} is gone; looking the station up again...
+stream-not-found = The stream of {$station} could not be found
+stream-silent = The stream has been silent for {$seconds} seconds; restarting...
+output-underrun = Nothing has played for {$seconds} seconds; restarting playback...
+output-underrun-gave-up = Playback keeps stalling; stopped
+catalog-offline = SomaFM is unreachable, showing cached stations if any. Retrying in the background.
+catalog-online = SomaFM is reachable again, station list updated
+splash-skip = Press any key to skip
 no-station-selected = No station selected
 volume = Volume
 playback-time = Playback time
@@ -16,10 +31,15 @@ station-id = ID
 station-title = Title
 station-genre = Genre
 station-dj = DJ
+station-source = Source
+stream-format = Stream
+stream-server = Server
+stream-website = Website
 
 # Playback states
 playing = Playing
 paused = Paused
+paused-disconnected = Paused (disconnected)
 stopped = Stopped
 
 # Controls
@@ -30,6 +50,29 @@ controls-start = Start
 controls-pause = Pause
 controls-volume = Volume
 controls-help = Help
+underruns = Underruns
+data-used = {$size} used
+data-today = {$size} today
+data-today-cap = {$size} of {$cap} today
+data-cap-reached = Today's data cap of {$cap} is used up; playback stopped
+offline = OFFLINE
+rec = REC
+recording-started = Recording to {$path}
+recording-saved = Saved recording {$path} ({$length}, {$size})
+recording-failed = Could not start recording: {$error}
+recording-needs-playback = Start a station to record it
+replay-saved = Saved what just played to {$path} ({$length}, {$size})
+replay-empty = Nothing has played yet to save
+replay-failed = Could not save what just played: {$error}
+history-exported = Exported {$count} plays to {$path}
+history-export-failed = Could not export the track log: {$error}
+history-export-no-log = the track log could not be opened
+live = LIVE
+time-shift-off = Rewinding is off (audio_time_shift_seconds = 0)
+output-lost = Audio device disconnected, waiting for another one
+output-restored = Audio output moved to the default device
+update-badge = {$version} AVAILABLE
+update-available = somars {$version} is available: {$url}
 
 # Messages
 connecting-to-stream = Connecting to stream...
@@ -41,12 +84,111 @@ failed-audio-sink = Failed to lock audio sink
 failed-decoder-construction = Failed to construct audio decoder: {$error}
 stream-from = Initializing stream from: {$url}
 got-response = Got response, starting stream...
-bit-rate = Bit rate: {$rate}kbps
+no-metadata = The stream sends no track titles
+no-bitrate = The stream doesn't say its bitrate; buffering for {$kbps} kbps
+format-mismatch = Stream claims to be {$claimed} but is {$actual}, decoding it as {$actual}
 udp-starting = Starting UDP command listener on port {$port}
 udp-error = UDP error: {$error}
 station-not-found = Station ID not found: {$id}
 auto-playing = Auto-playing station: {$id}
 underrun-detected = Audio buffer underrun detected, restarting playback...
+quality-downgraded = Switched to low-bitrate stream after repeated buffer underruns
+quality-restored = Connection stable again, switched back to full-quality stream
+stream-quality = Stream quality
+station-sort = Station order
+sort-catalog = catalog
+sort-listeners = listeners
+sort-title = title
+sort-genre = genre
+genre = Genre
+genre-all = all
+equalizer = Equalizer
+experimental = experimental
+experimental-needs = needs --enable-feature {$feature}
+experimental-off = {$feature} is experimental; start somars with --enable-feature {$feature} to try it
+experimental-on = Experimental features on: {$features}
+eq-flat = flat
+eq-bass-boost = bass boost
+eq-voice = voice
+eq-custom = custom
+preset = Preset
+preset-empty = empty, press Shift and the number to save the selected station
+alarm-set = Alarm set for {$time}: {$station}
+alarm-ringing = Alarm! Playing {$station}
+favorite-added = Liked {$track}
+favorite-exists = Already liked {$track}
+favorite-no-track = Nothing to like: no track is playing
+favorite-failed = Failed to save the favorite
+session-summary = {$station} — {$length}, {$tracks} tracks
+
+# Track search
+track-search-title = Track search
+track-search-hint = Type an artist, title or station
+track-search-none = No plays found
+track-search-results = results
+
+# History search
+history-search-title = History search
+history-filter-errors = errors
+history-filter-playback = playback
+
+# Language popup
+language-title = Language
+language-complete = Every message is translated
+language-missing = Missing {$count} messages:
+language-changed = Language: {$language}
+
+# Recent songs panel
+recent-songs-title = Recently played
+
+# Scheduled shows
+upcoming-shows-title = Upcoming shows
+show-on-air = on air
+show-reminder = {$show} starts at {$time} on {$station}
+
+# Listening stats popup
+stats-title = Listening Stats
+stats-total = Listened in total:
+stats-empty = Nothing listened to yet
+
+# Key hints
+hints-stations = Stations
+hint-play = play
+hint-pause = pause
+hint-select = select
+hint-volume = volume
+hint-rewind = -/+10s
+hint-rewind-more = -30s/-60s
+hint-live = live
+hint-preset = preset
+hint-random = random
+hint-back = previous
+hint-search = search tracks
+hint-like = like
+hint-record = record
+hint-save-replay = save last minutes
+hint-export-history = export history
+hint-quality = quality
+hint-equalizer = equalizer
+hint-sort = sort
+hint-genre = genre
+hint-history = scroll history
+hint-session = fold session
+hint-history-filter = filter history
+hint-history-search = search history
+hint-refresh = refresh
+hint-language = language
+hint-stats = stats
+hint-help = help
+hint-hints = hide hints
+hint-quit = quit
+hint-type = search
+hint-erase = erase
+hint-scroll = scroll
+hint-close = close
+hint-choose = choose
+hint-keep = keep
+hint-clear = clear
 
 # Help screen
 help-title = Help
@@ -54,12 +196,36 @@ help-keyboard = Keyboard Controls
 help-enter = Play selected station
 help-space = Stop/Start playback
 help-volume = Adjust volume
+help-time-shift = Rewind 10 seconds or skip forward; [ and {"{"} rewind 30 and 60
+help-live = Back to the live stream
+help-quality = Cycle stream quality (highest/high/low)
+help-equalizer = Cycle equalizer preset (flat/bass boost/voice/custom)
+help-record = Start or stop recording the stream
+help-save-replay = Save the last minutes of the stream to a file
+help-export-history = Export the track log to a CSV or JSON file
+help-refresh = Refresh station list
+help-sort = Cycle station order (catalog/listeners/title/genre)
+help-track-search = Search the track log
+help-language = Choose the interface language
+help-stats = Show the time listened to each station
+help-genre = Show only one genre (next/previous)
+help-genre-chip = Toggle the genre chip with the underlined key
+help-presets = Tune preset (Shift to save the selected station)
+help-back = Back to the previous station
+help-like = Like the playing track
+help-sessions = Collapse or expand a listening session in the history
+help-history-filter = Show all history messages, only errors or only playback
+help-history-search = Search the history as you type; Enter keeps the search, Esc clears it
+help-random = Play a random station from the list
 help-arrows = Navigate stations
 help-quit = Quit application
 help-toggle-help = Toggle this help screen
+help-hints = Show or hide the key hints for this screen
 help-cli = Command Line Arguments
 help-log-level = Set log verbosity (1=minimal, 2=verbose)
 help-station = Auto-play station with given ID on startup
+help-url = Play a stream URL instead of a SomaFM station
+help-alarm = Start the station at a set time, fading in with --fade-in <SECONDS>
 help-listen = Enable UDP control listener
 help-port = Set UDP port (default: 8069)
 help-show-help = Show command line help
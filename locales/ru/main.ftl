@@ -1,5 +1,6 @@
 # General UI
 app-title = Плеер SomaFM
+language-name = Русский
 app-description = Плеер soma.fm на Rust
 
 # Main UI
@@ -7,6 +8,20 @@ stations = Станции
 history = История
 loading = Загрузка
 loading-stations = Загрузка станций...
+refreshing-stations = Обновление списка станций...
+stations-updated = Список станций обновлён
+station-added = Новое на SomaFM: {$station}
+station-renamed = SomaFM переименовал {$old} в {$new}
+station-removed = Больше нет на SomaFM: {$station}
+station-new-badge = НОВАЯ
+stream-moved = Поток {$station} пропал; ищем станцию заново...
+stream-not-found = Поток {$station} не найден
+stream-silent = Поток молчит уже {$seconds} с; перезапуск...
+output-underrun = Ничего не играет уже {$seconds} с; перезапуск воспроизведения...
+output-underrun-gave-up = Воспроизведение постоянно прерывается; остановлено
+catalog-offline = SomaFM недоступен, показаны сохранённые станции, если они есть. Повторная попытка в фоне.
+catalog-online = SomaFM снова доступен, список станций обновлён
+splash-skip = Нажмите любую клавишу, чтобы пропустить
 no-station-selected = Станция не выбрана
 volume = Громкость
 playback-time = Время воспроизведения
@@ -16,10 +31,15 @@ station-id = ID
 station-title = Название
 station-genre = Жанр
 station-dj = Диджей
+station-source = Источник
+stream-format = Поток
+stream-server = Сервер
+stream-website = Сайт
 
 # Playback states
 playing = Воспроизведение
 paused = Пауза
+paused-disconnected = Пауза (отключено)
 stopped = Остановлено
 
 # Controls
@@ -30,6 +50,29 @@ controls-start = Старт
 controls-pause = Пауза
 controls-volume = Громкость
 controls-help = Помощь
+underruns = Опустошения буфера
+data-used = израсходовано {$size}
+data-today = {$size} за сегодня
+data-today-cap = {$size} из {$cap} за сегодня
+data-cap-reached = Дневной лимит трафика {$cap} исчерпан; воспроизведение остановлено
+offline = НЕТ СЕТИ
+rec = ЗАПИСЬ
+recording-started = Запись в {$path}
+recording-saved = Запись сохранена: {$path} ({$length}, {$size})
+recording-failed = Не удалось начать запись: {$error}
+recording-needs-playback = Включите станцию, чтобы записать её
+replay-saved = Только что сыгранное сохранено в {$path} ({$length}, {$size})
+replay-empty = Пока нечего сохранять
+replay-failed = Не удалось сохранить сыгранное: {$error}
+history-exported = Выгружено треков: {$count}, в {$path}
+history-export-failed = Не удалось выгрузить журнал треков: {$error}
+history-export-no-log = журнал треков не открылся
+live = ЭФИР
+time-shift-off = Перемотка выключена (audio_time_shift_seconds = 0)
+output-lost = Аудиоустройство отключено, ожидание другого
+output-restored = Звук переключён на устройство по умолчанию
+update-badge = ДОСТУПНА {$version}
+update-available = Доступна новая версия somars {$version}: {$url}
 
 # Messages
 connecting-to-stream = Подключение к потоку...
@@ -41,12 +84,111 @@ failed-audio-sink = Не удалось получить доступ к ауд
 failed-decoder-construction = Не удалось создать аудио декодер: {$error}
 stream-from = Инициализация потока из: {$url}
 got-response = Получен ответ, запуск потока...
-bit-rate = Битрейт: {$rate}кбит/с
+no-metadata = Поток не передаёт названия треков
+no-bitrate = Поток не сообщает битрейт; буферизация рассчитана на {$kbps} кбит/с
+format-mismatch = Поток заявлен как {$claimed}, но на деле {$actual}, декодируется как {$actual}
 udp-starting = Запуск UDP-слушателя на порту {$port}
 udp-error = Ошибка UDP: {$error}
 station-not-found = Станция с ID не найдена: {$id}
 auto-playing = Автоматическое воспроизведение станции: {$id}
 underrun-detected = Аудио буфер пуст, перезапуск воспроизведения...
+quality-downgraded = Переключение на поток с низким битрейтом из-за частых опустошений буфера
+quality-restored = Соединение стабильно, возврат к потоку в полном качестве
+stream-quality = Качество потока
+station-sort = Порядок станций
+sort-catalog = каталог
+sort-listeners = слушатели
+sort-title = название
+sort-genre = жанр
+genre = Жанр
+genre-all = все
+equalizer = Эквалайзер
+experimental = экспериментально
+experimental-needs = нужен --enable-feature {$feature}
+experimental-off = {$feature} — экспериментальная функция; запустите somars с --enable-feature {$feature}, чтобы попробовать
+experimental-on = Включены экспериментальные функции: {$features}
+eq-flat = ровно
+eq-bass-boost = басы
+eq-voice = голос
+eq-custom = свой
+preset = Пресет
+preset-empty = пусто, нажмите Shift и цифру, чтобы сохранить выбранную станцию
+alarm-set = Будильник на {$time}: {$station}
+alarm-ringing = Будильник! Включаю {$station}
+favorite-added = В избранном: {$track}
+favorite-exists = Уже в избранном: {$track}
+favorite-no-track = Нечего добавить: трек не играет
+favorite-failed = Не удалось сохранить в избранное
+session-summary = {$station} — {$length}, треков: {$tracks}
+
+# Track search
+track-search-title = Поиск треков
+track-search-hint = Введите исполнителя, название или станцию
+track-search-none = Ничего не найдено
+track-search-results = результатов
+
+# History search
+history-search-title = Поиск по истории
+history-filter-errors = ошибки
+history-filter-playback = воспроизведение
+
+# Language popup
+language-title = Язык
+language-complete = Все сообщения переведены
+language-missing = Не переведено сообщений: {$count}
+language-changed = Язык: {$language}
+
+# Recent songs panel
+recent-songs-title = Недавно играло
+
+# Scheduled shows
+upcoming-shows-title = Ближайшие передачи
+show-on-air = в эфире
+show-reminder = {$show} начнётся в {$time} на {$station}
+
+# Listening stats popup
+stats-title = Статистика прослушивания
+stats-total = Всего прослушано:
+stats-empty = Пока ничего не прослушано
+
+# Key hints
+hints-stations = Станции
+hint-play = играть
+hint-pause = пауза
+hint-select = выбор
+hint-volume = громкость
+hint-rewind = -/+10с
+hint-rewind-more = -30с/-60с
+hint-live = эфир
+hint-preset = пресет
+hint-random = случайная
+hint-back = предыдущая
+hint-search = поиск треков
+hint-like = нравится
+hint-record = запись
+hint-save-replay = сохранить услышанное
+hint-export-history = экспорт истории
+hint-quality = качество
+hint-equalizer = эквалайзер
+hint-sort = сортировка
+hint-genre = жанр
+hint-history = прокрутка истории
+hint-session = свернуть сеанс
+hint-history-filter = фильтр истории
+hint-history-search = поиск в истории
+hint-refresh = обновить
+hint-language = язык
+hint-stats = статистика
+hint-help = справка
+hint-hints = скрыть подсказки
+hint-quit = выход
+hint-type = поиск
+hint-erase = стереть
+hint-scroll = прокрутка
+hint-close = закрыть
+hint-choose = выбрать
+hint-keep = оставить
+hint-clear = сбросить
 
 # Help screen
 help-title = Справка
@@ -54,12 +196,36 @@ help-keyboard = Управление с клавиатуры
 help-enter = Воспроизвести выбранную станцию
 help-space = Остановить/Начать воспроизведение
 help-volume = Регулировка громкости
+help-time-shift = Перемотать на 10 секунд назад или вперёд; [ и {"{"} — на 30 и 60 назад
+help-live = Вернуться в прямой эфир
+help-quality = Переключить качество потока (highest/high/low)
+help-equalizer = Переключить пресет эквалайзера (ровно/басы/голос/свой)
+help-record = Начать или остановить запись потока
+help-save-replay = Сохранить последние минуты потока в файл
+help-export-history = Выгрузить журнал треков в файл CSV или JSON
+help-refresh = Обновить список станций
+help-sort = Переключить порядок станций (каталог/слушатели/название/жанр)
+help-track-search = Поиск по журналу треков
+help-language = Выбрать язык интерфейса
+help-stats = Показать время прослушивания каждой станции
+help-genre = Показать только один жанр (следующий/предыдущий)
+help-genre-chip = Включить или выключить жанр с подчёркнутой клавишей
+help-presets = Включить пресет (с Shift — сохранить выбранную станцию)
+help-back = Вернуться к предыдущей станции
+help-like = Добавить играющий трек в избранное
+help-sessions = Свернуть или развернуть сеанс прослушивания в истории
+help-history-filter = Показывать все сообщения истории, только ошибки или только воспроизведение
+help-history-search = Искать в истории по мере ввода; Enter оставляет поиск, Esc сбрасывает
+help-random = Включить случайную станцию из списка
 help-arrows = Навигация по станциям
 help-quit = Выйти из приложения
 help-toggle-help = Показать/скрыть эту справку
+help-hints = Показать/скрыть подсказки клавиш для этого экрана
 help-cli = Аргументы командной строки
 help-log-level = Уровень логирования (1=минимальный, 2=подробный)
 help-station = Автоматически воспроизводить станцию при запуске
+help-url = Воспроизвести поток по URL вместо станции SomaFM
+help-alarm = Включить станцию в заданное время, с плавным нарастанием через --fade-in <СЕКУНДЫ>
 help-listen = Включить UDP-управление
 help-port = Установить UDP-порт (по умолчанию: 8069)
 help-show-help = Показать справку по командной строке
@@ -6,7 +6,19 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
+/// Stations `tune random` picks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RandomScope {
+    /// The station list as shown, so within the genre filter
+    List,
+    /// Every station, ignoring the genre filter
+    All,
+    /// Stations saved in preset slots
+    Presets,
+}
+
 #[derive(Debug, Clone, PartialEq, Display, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum Action {
     // System events
     Tick,
@@ -35,27 +47,138 @@ pub enum Action {
 
     // Station selection
     TuneStation(String),
+    TuneUrl(String),
     TuneNext,
     TunePrev,
 
+    // Stream quality
+    SetStreamQuality(crate::station::StreamQuality),
+    CycleStreamQuality,
+    /// Switch to the next equalizer preset
+    CycleEqualizer,
+    /// Start or stop recording the playing stream
+    ToggleRecording,
+    /// Save the last minutes of the stream to a file
+    SaveReplay,
+    /// Where the replay was saved, or why it wasn't
+    ReplaySaved(Result<(std::path::PathBuf, crate::audio::recorder::RecordingStatus), String>),
+    /// Write the track log to a file in the export folder
+    ExportHistory,
+    /// Move through the time shift by seconds; negative rewinds
+    TimeShift(i64),
+    /// Catch up with the live stream
+    GoLive,
+
     // Volume
     VolumeUp,
     VolumeDown,
     SetVolume(f32),
+    /// Change the volume by a signed amount, unmuting first
+    AdjustVolume(f32),
+    Mute,
+    Unmute,
 
     // UI
     ToggleHelp,
+    /// Show or hide the key hints for the current context
+    ToggleKeyHints,
+    OpenTrackSearch,
+    CloseTrackSearch,
+    /// Send typing to the history search
+    OpenHistorySearch,
+    /// Give the keys back, keeping whatever search was typed
+    CloseHistorySearch,
+    OpenLanguagePicker,
+    CloseLanguagePicker,
+    OpenStats,
+    CloseStats,
+    /// Stations by time listened, longest first, for the stats popup
+    SetListeningStats(Vec<crate::listening_stats::StationTime>),
+    /// Shows in the schedule feed, as last downloaded
+    UpdateSchedule(Vec<crate::schedule::Show>),
+    /// The songs a SomaFM channel played last, newest first
+    RecentSongs {
+        station_id: String,
+        songs: Vec<crate::providers::RecentSong>,
+    },
+    /// Show the interface in this locale from now on
+    SetLocale(String),
     Help,
+    DismissSplash,
 
     // Metadata
-    MetadataUpdate { station: String, title: String },
+    MetadataUpdate {
+        station: String,
+        title: String,
+    },
+    AudioLevel(f32),
+    /// Decoded audio waiting to be played, sent about once a second
+    Buffered(std::time::Duration),
+
+    // Station catalog
+    RefreshStations,
+    SetOffline(bool),
+    /// A newer release than the running one is out
+    UpdateAvailable {
+        version: String,
+        url: String,
+    },
+    /// Latest `lastPlaying` and listener count per station ID
+    UpdateLiveStatus(std::collections::HashMap<String, crate::station::LiveStatus>),
+    CycleStationSort,
+    /// Return to the station played before the current one
+    TuneBack,
+    TuneRandom(RandomScope),
+    /// The selection has rested on a station long enough to prefetch it
+    HoverStation(String),
+    /// The station's stream URL is gone; look the station up again
+    StreamGone(String),
+    /// Add the playing track to the favorites
+    LikeTrack,
+    /// The alarm went off
+    RingAlarm {
+        station: String,
+        fade_in_seconds: u64,
+    },
+    GenreNext,
+    GenrePrev,
+    /// Add or remove the genre chip with this key from the genre filter
+    ToggleGenreChip(char),
+    /// Tune the station in preset slot 1-9
+    TunePreset(u8),
+    /// Store the selected station in preset slot 1-9
+    AssignPreset(u8),
 
     // State update (for components)
     UpdateStations(Vec<crate::station::Station>),
     SetActiveStation(Option<usize>),
+    SetStationSort(crate::station::StationSort),
+    SetGenreFilter(Vec<String>),
+    SetGenreChips(Vec<crate::station::GenreChip>),
+    /// IDs of stations to badge as new
+    SetNewStations(Vec<String>),
     SetPlaybackState(crate::PlaybackState),
+    /// The paused stream was disconnected and reconnects on resume
+    SetPauseDisconnected(bool),
+    /// Progress of the recording, `None` when not recording
+    SetRecording(Option<crate::audio::recorder::RecordingStatus>),
+    /// How far playback is behind the live stream
+    SetTimeShift(std::time::Duration),
+    /// Bitrate, codec and server of the playing stream
+    SetStreamInfo(Option<crate::audio::StreamInfo>),
     SetSelectedStation(Option<crate::station::Station>),
     SetTotalPlayed(std::time::Duration),
+    SetUnderruns(u32),
+    /// Reported by the player task
+    Player(crate::audio::player::PlayerEvent),
+    /// Download rate in bytes per second, bytes downloaded since start and
+    /// today, and the daily cap if one is set
+    SetThroughput {
+        rate: u64,
+        total: u64,
+        today: u64,
+        cap: Option<u64>,
+    },
     StartTrackingPlayTime,
     StopTrackingPlayTime,
     AddHistoryMessage(crate::HistoryMessage),
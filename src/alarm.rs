@@ -0,0 +1,153 @@
+//! Alarm clock
+//!
+//! `--alarm HH:MM` or the `[alarm]` config table starts a station at the next
+//! occurrence of that time, optionally fading the volume in from silence.
+//! The time is on the [`clock`](crate::clock), in the configured time zone.
+
+use crate::action::Action;
+use crate::clock::{self, Clock};
+use crate::config::AlarmConfig;
+use crate::i18n::t;
+use crate::{HistoryMessage, MessageType};
+use chrono::{DateTime, NaiveTime, Utc};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::info;
+
+/// Longest single sleep, so the alarm still rings on time after the machine
+/// wakes from suspend or the clock is changed
+const MAX_SLEEP: Duration = Duration::from_secs(30);
+/// Volume steps per second while fading in
+const FADE_STEPS_PER_SECOND: u64 = 4;
+
+/// An armed alarm
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alarm {
+    pub time: NaiveTime,
+    /// Station ID or name to play
+    pub station: String,
+    /// Fade the volume in over this long, zero to start at full volume
+    pub fade_in: Duration,
+}
+
+impl Alarm {
+    /// The alarm described by `config`, `None` when no time is set
+    ///
+    /// Without a configured station the alarm plays `fallback_station`.
+    pub fn from_config(
+        config: &AlarmConfig,
+        fallback_station: Option<&str>,
+    ) -> Result<Option<Self>, String> {
+        let Some(time) = config.time.as_deref() else {
+            return Ok(None);
+        };
+        let time = parse_time(time)
+            .ok_or_else(|| format!("Invalid alarm time {:?}, expected HH:MM", time))?;
+        let station = config
+            .station
+            .as_deref()
+            .or(fallback_station)
+            .ok_or("The alarm needs a station: pass --station or set [alarm] station")?;
+        Ok(Some(Self {
+            time,
+            station: station.to_string(),
+            fade_in: Duration::from_secs(config.fade_in_seconds),
+        }))
+    }
+
+    /// The first time after `now` the alarm rings on `clock`
+    pub fn next_ring(&self, clock: &Clock, now: DateTime<Utc>) -> DateTime<Utc> {
+        clock.next(self.time, now)
+    }
+
+    /// Wait for the alarm time in the background, then ring once
+    pub fn spawn(self, action_tx: UnboundedSender<Action>) {
+        tokio::spawn(async move {
+            let clock = clock::current();
+            let ring = self.next_ring(&clock, Utc::now());
+            info!("Alarm set for {}", ring);
+            let _ = action_tx.send(Action::AddHistoryMessage(HistoryMessage {
+                message: t("alarm-set")
+                    .replace("{$time}", &clock.time(ring))
+                    .replace("{$station}", &self.station),
+                message_type: MessageType::Info,
+                timestamp: clock::timestamp(),
+            }));
+            loop {
+                let remaining = (ring - Utc::now()).to_std().unwrap_or_default();
+                if remaining.is_zero() {
+                    break;
+                }
+                tokio::time::sleep(remaining.min(MAX_SLEEP)).await;
+            }
+            let _ = action_tx.send(Action::RingAlarm {
+                station: self.station,
+                fade_in_seconds: self.fade_in.as_secs(),
+            });
+        });
+    }
+}
+
+/// Raise the volume from silence to `target` over `fade_in`
+pub fn spawn_fade(target: f32, fade_in: Duration, action_tx: UnboundedSender<Action>) {
+    let steps = (fade_in.as_secs() * FADE_STEPS_PER_SECOND).max(1);
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(1000 / FADE_STEPS_PER_SECOND));
+        for step in 1..=steps {
+            interval.tick().await;
+            let volume = target * step as f32 / steps as f32;
+            if action_tx.send(Action::SetVolume(volume)).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+/// `HH:MM` on a 24-hour clock
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn rings_at_the_next_occurrence() {
+        let config = AlarmConfig {
+            time: Some("07:30".to_string()),
+            station: None,
+            fade_in_seconds: 60,
+        };
+        let alarm = Alarm::from_config(&config, Some("groovesalad"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(alarm.station, "groovesalad");
+        assert_eq!(alarm.fade_in, Duration::from_secs(60));
+
+        let clock = Clock::default();
+        let evening = Local.with_ymd_and_hms(2024, 3, 1, 22, 0, 0).unwrap();
+        assert_eq!(
+            alarm.next_ring(&clock, evening.to_utc()),
+            Local.with_ymd_and_hms(2024, 3, 2, 7, 30, 0).unwrap()
+        );
+        let early = Local.with_ymd_and_hms(2024, 3, 1, 6, 0, 0).unwrap();
+        assert_eq!(
+            alarm.next_ring(&clock, early.to_utc()),
+            Local.with_ymd_and_hms(2024, 3, 1, 7, 30, 0).unwrap()
+        );
+
+        assert!(Alarm::from_config(&config, None).is_err());
+        let config = AlarmConfig {
+            time: Some("25:00".to_string()),
+            ..config
+        };
+        assert!(Alarm::from_config(&config, Some("groovesalad")).is_err());
+        assert_eq!(
+            Alarm::from_config(&AlarmConfig::default(), Some("groovesalad")),
+            Ok(None)
+        );
+    }
+}
@@ -3,24 +3,53 @@
 //! The App manages the component collection, event loop, and application state.
 
 use crate::{
-    action::Action,
-    audio,
-    components::{BottomControls, Component, Help, History, NowPlaying, StationList},
+    action::{Action, RandomScope},
+    alarm, artwork,
+    audio::{
+        self,
+        effects::{Effect, SoundEffects},
+        player::{PlayRequest, PlayerCommand, PlayerEvent},
+    },
+    components::{
+        splash::{self, Splash},
+        BottomControls, Component, Help, History, KeyHints, LanguagePicker, NowPlaying,
+        RecentSongs, StationList, Stats, TrackSearch, UpcomingShows,
+    },
     config::Config,
+    control::Permissions,
+    data_usage::DataUsage,
     event::Event,
+    experimental::{self, Feature},
+    favorites::Favorites,
+    history_export,
+    hooks::Hooks,
+    idle::Activity,
     media_session::MediaSessionHandle,
-    station::Station,
+    mqtt::MqttHandle,
+    prefetch::HoverPrefetch,
+    presets::PresetKey,
+    providers::{self, ProviderRegistry},
+    schedule::{self, Reminders},
+    scrobble_log::ScrobbleLog,
+    state::{AppEvent, AppState, Resume, Underrun, UnderrunWatch},
+    station::{self, Station, StreamQuality, URL_STATION_ID},
+    station_changes::{self, KnownStations},
+    status_file::StatusFile,
+    status_server::StatusServerHandle,
+    track_log::TrackLog,
     tui::Tui,
+    utils, voice,
+    webhook::WebhookHandle,
+    websocket::WebSocketHandle,
     MessageType, PlaybackState,
 };
 use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
 use ratatui::layout::{Constraint, Direction, Layout as RatatuiLayout, Rect};
-use rodio::Sink;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::time::Instant;
 use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 // Component indices - must match order in App::new()
 const COMPONENT_STATION_LIST: usize = 0;
@@ -28,6 +57,21 @@ const COMPONENT_NOW_PLAYING: usize = 1;
 const COMPONENT_HISTORY: usize = 2;
 const COMPONENT_HELP: usize = 3;
 const COMPONENT_BOTTOM_CONTROLS: usize = 4;
+const COMPONENT_TRACK_SEARCH: usize = 5;
+const COMPONENT_LANGUAGE_PICKER: usize = 6;
+const COMPONENT_STATS: usize = 7;
+const COMPONENT_KEY_HINTS: usize = 8;
+
+/// Volume change for one press of the volume keys
+const VOLUME_STEP: f32 = 0.05;
+/// How often playback, the output and the recorder are looked after,
+/// whether or not anything is animating
+const HOUSEKEEPING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often to try reopening the output while no device is available
+const OUTPUT_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+/// A station whose stream is still missing this soon after being looked up
+/// again is reported instead of looked up once more
+const RETUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
 
 /// History message type alias - use the one from main.rs
 pub type HistoryMessage = crate::HistoryMessage;
@@ -39,35 +83,67 @@ pub struct App {
 
     // Shared state
     pub config: Config,
+    /// Stations shown in the list: the catalog filtered by genre and sorted
     pub stations: Vec<Station>,
+    /// Every known station in catalog order, including a stream played by URL
+    catalog: Vec<Station>,
+    /// Only list stations of one of these genres, every station when empty
+    genre_filter: BTreeSet<String>,
     pub active_station: Option<usize>,
     pub selected_station: usize,
+    /// IDs of the station played last and the one before it, for `tune back`
+    last_played: Option<String>,
+    previous_station: Option<String>,
+    /// Station to tune once the catalog fetched after its stream went missing arrives
+    retune_pending: Option<String>,
+    /// Station last looked up again after its stream went missing, and when
+    last_retune: Option<(String, Instant)>,
+    /// SomaFM channels seen before, to point out the ones added since
+    known_stations: KnownStations,
+    /// Channels added since they were last seen and not played yet, badged in the list
+    new_stations: BTreeSet<String>,
 
     // Playback state
-    pub playback_state: PlaybackState,
-    pub volume: f32,
+    /// Playback, listening time, volume and whether the screen needs drawing
+    pub state: AppState,
     media_session: MediaSessionHandle,
+    mqtt: MqttHandle,
+    status_server: StatusServerHandle,
+    status_file: StatusFile,
+    websocket: WebSocketHandle,
+    webhook: WebhookHandle,
+    scrobble_log: ScrobbleLog,
+    track_log: TrackLog,
+    hooks: Hooks,
+    favorites: Favorites,
+    /// Station and title of the track playing, for liking it
+    current_track: Option<(String, String)>,
+    sound_effects: SoundEffects,
+    splash: Splash,
+    /// Songs the selected SomaFM channel played last
+    recent_songs: RecentSongs,
+    /// Scheduled shows on the selected station
+    upcoming_shows: UpcomingShows,
+    /// Shows reminded of ahead of their start
+    show_reminders: Reminders,
+    /// The station fetch running, retrying while offline
+    station_fetch: Option<tokio::task::AbortHandle>,
 
     // Audio
     #[allow(dead_code)]
     pub audio_manager: audio::AudioManager,
-    pub sink: Option<Arc<Mutex<Sink>>>,
-    pub metadata_tx: mpsc::Sender<audio::MetadataEvent>,
-    pub log_tx: mpsc::Sender<HistoryMessage>,
+    pub output: audio::output::Output,
+    /// When the lost output was last reopened, while the device is missing
+    output_retry: Option<Instant>,
+    pub player: audio::player::PlayerHandle,
+    /// Sources queued in the sink, as last reported by the player
+    queued: usize,
 
     // Playback timing
-    pub playback_start_time: Option<Instant>,
-    pub total_played: std::time::Duration,
-    pub last_pause_time: Option<Instant>,
-    pub playback_start_time_for_underrun: Option<Instant>,
     #[allow(dead_code)]
     pub last_position: std::time::Duration,
-    #[allow(dead_code)]
-    pub last_underrun_check: Option<Instant>,
-    pub last_restart_time: Option<Instant>,
-    pub restart_attempts: u32,
-    #[allow(dead_code)]
-    pub underrun_detected: bool,
+    /// Restarts playback when the output stays empty
+    underrun: UnderrunWatch,
     pub station_loading: bool,
 
     // Channels
@@ -75,11 +151,54 @@ pub struct App {
     action_rx: UnboundedReceiver<Action>,
 
     // State
-    pub should_quit: bool,
     pub loading: bool,
+    /// The station catalog could not be downloaded and comes from the cache
+    pub offline: bool,
+    /// The track search popup has the keyboard
+    track_search_open: bool,
+    /// Whether typing goes to the history search
+    history_search_open: bool,
+    /// The language popup has the keyboard
+    language_picker_open: bool,
+    /// The listening stats popup has the keyboard
+    stats_open: bool,
+    /// Focus and input tracking for the animation tick rate
+    activity: Activity,
+    /// Volume ramps when playback starts, stops or switches stations
+    fader: audio::transition::Fader,
+    /// Bands of the equalizer preset, shared with the playing stream
+    equalizer: audio::equalizer::EqualizerHandle,
+    /// Records the playing stream on request
+    recorder: audio::recorder::Recorder,
+    /// Recorded seconds last shown, `None` when not recording
+    recording_shown: Option<u64>,
+    /// Rewinds the playing stream into what it played
+    time_shift: audio::time_shift::TimeShiftHandle,
+    /// Seconds behind live last shown
+    time_shift_shown: u64,
+    /// Counts the bytes downloaded for the playing stream
+    throughput: audio::throughput::Throughput,
+    throughput_rate: audio::throughput::RateMeter,
+    /// When the download rate was last shown
+    throughput_sampled: Option<Instant>,
+    /// Rate and totals last shown
+    throughput_shown: Option<(u64, u64, u64)>,
+    /// Bytes downloaded per day, for the daily total and cap
+    data_usage: DataUsage,
+    /// Decoded audio waiting to be played, as last reported by the playback task
+    buffered: std::time::Duration,
+    /// Buffer underruns since the station started
+    underruns: u32,
+    /// When progress was last sent to WebSocket clients
+    progress_sent: Option<Instant>,
+    /// Waits for the selection to rest before prefetching the station and
+    /// fetching its recent songs
+    hover_prefetch: HoverPrefetch,
+    /// Store with the track log, `None` when it couldn't be opened
+    store: Option<crate::storage::SharedStore>,
 
     // UI state
-    pub history_messages: Vec<HistoryMessage>,
+    pub history_messages: VecDeque<HistoryMessage>,
     pub log_level: u8,
 
     // UDP control state
@@ -94,16 +213,9 @@ pub struct App {
 }
 
 impl App {
-    fn abort_playback_task(&mut self) {
-        if let Some(handle) = self.audio_manager.take_handle() {
-            handle.abort();
-        }
-        self.audio_manager.clear_current_station();
-    }
-
     /// Create a new application instance
     pub fn new(
-        sink: Arc<Mutex<Sink>>,
+        output: audio::output::Output,
         metadata_tx: mpsc::Sender<audio::MetadataEvent>,
         log_tx: mpsc::Sender<HistoryMessage>,
         config: Config,
@@ -116,44 +228,129 @@ impl App {
         let udp_enabled = config.udp_enabled;
         let udp_port = config.udp_port;
         let media_session = MediaSessionHandle::start(action_tx.clone(), volume);
+        let permissions = Permissions::from_config(&config.remote);
+        let mqtt = MqttHandle::start(&config.mqtt, permissions.clone(), action_tx.clone());
+        let websocket = WebSocketHandle::start(&config.websocket, permissions.clone());
+        let status_server = StatusServerHandle::start(&config.status_server, permissions, volume);
+        let webhook = WebhookHandle::start(&config.webhook);
+        let status_file = StatusFile::start(&config.status_file, volume);
+        let scrobble_log = ScrobbleLog::new(&config.scrobbler_log);
+        let store = crate::storage::open(&config)
+            .inspect_err(|error| warn!("Failed to open the store: {}", error))
+            .ok()
+            .map(crate::storage::SharedStore::new);
+        let track_log = TrackLog::new(&config.track_log, store.clone());
+        let hooks = Hooks::new(&config.hooks);
+        let favorites = Favorites::new(&config.favorites, store.clone());
+        let data_usage = DataUsage::load(&config.data_usage);
+        let recent_songs = RecentSongs::new(config.recent_songs);
+        let known_stations =
+            KnownStations::load(config.station_changes.then(station_changes::path).flatten());
+        let splash = Splash::new(&config.splash);
+        let activity = Activity::new(config.idle_after_seconds);
+        let fader =
+            audio::transition::Fader::new(std::time::Duration::from_millis(config.audio_fade_ms));
+        let equalizer =
+            audio::equalizer::EqualizerHandle::new(if experimental::enabled(Feature::Dsp) {
+                config.equalizer.bands()
+            } else {
+                Vec::new()
+            });
+        // Nothing is kept for saving what just played while recording is off
+        let recorder =
+            audio::recorder::Recorder::new(if experimental::enabled(Feature::Recording) {
+                config.recording.replay()
+            } else {
+                std::time::Duration::ZERO
+            });
+        let player = audio::player::spawn(
+            output.mixer(),
+            fader.clone(),
+            metadata_tx,
+            log_tx,
+            action_tx.clone(),
+        );
+        let sound_effects = SoundEffects::new(output.mixer(), &config.sound_effects);
 
         // Create components
         let components: Vec<Box<dyn Component>> = vec![
             Box::new(StationList::new()),
             Box::new(NowPlaying::new()),
-            Box::new(History::new()),
+            Box::new(History::new(config.history_max_entries)),
             Box::new(Help::new()),
             Box::new(BottomControls::new()),
+            Box::new(TrackSearch::new(store.clone())),
+            Box::new(LanguagePicker::new()),
+            Box::new(Stats::new()),
+            Box::new(KeyHints::new()),
         ];
 
         Self {
             components,
             config,
             stations: Vec::new(),
+            catalog: Vec::new(),
+            genre_filter: BTreeSet::new(),
             active_station: None,
             selected_station: 0,
-            playback_state: PlaybackState::Stopped,
-            volume,
+            last_played: None,
+            previous_station: None,
+            retune_pending: None,
+            last_retune: None,
+            known_stations,
+            new_stations: BTreeSet::new(),
+            state: AppState::new(volume),
             media_session,
+            mqtt,
+            status_server,
+            status_file,
+            websocket,
+            webhook,
+            scrobble_log,
+            track_log,
+            hooks,
+            favorites,
+            current_track: None,
+            sound_effects,
+            splash,
+            recent_songs,
+            upcoming_shows: UpcomingShows::new(),
+            show_reminders: Reminders::default(),
+            station_fetch: None,
             audio_manager: audio::AudioManager::new(),
-            sink: Some(sink),
-            metadata_tx,
-            log_tx,
-            playback_start_time: None,
-            total_played: std::time::Duration::default(),
-            last_pause_time: None,
-            playback_start_time_for_underrun: None,
+            output,
+            output_retry: None,
+            player,
+            queued: 0,
             last_position: std::time::Duration::default(),
-            last_underrun_check: None,
-            last_restart_time: None,
-            restart_attempts: 0,
-            underrun_detected: false,
+            underrun: UnderrunWatch::default(),
             station_loading: false,
             action_tx,
             action_rx,
-            should_quit: false,
             loading: true,
-            history_messages: Vec::new(),
+            offline: false,
+            track_search_open: false,
+            history_search_open: false,
+            language_picker_open: false,
+            stats_open: false,
+            activity,
+            fader,
+            equalizer,
+            recorder,
+            recording_shown: None,
+            time_shift: audio::time_shift::TimeShiftHandle::default(),
+            time_shift_shown: 0,
+            throughput: audio::throughput::Throughput::default(),
+            throughput_rate: audio::throughput::RateMeter::default(),
+            throughput_sampled: None,
+            throughput_shown: None,
+            data_usage,
+            buffered: std::time::Duration::default(),
+            underruns: 0,
+            progress_sent: None,
+            hover_prefetch: HoverPrefetch::new(),
+            store: store.clone(),
+            history_messages: VecDeque::new(),
             log_level,
             udp_enabled,
             udp_port,
@@ -162,18 +359,28 @@ impl App {
         }
     }
 
+    /// Whether `feature` is on, telling how to turn it on when it isn't
+    fn feature_enabled(&mut self, feature: Feature) -> bool {
+        if experimental::enabled(feature) {
+            return true;
+        }
+        self.add_history_message(
+            crate::i18n::t("experimental-off").replace("{$feature}", feature.as_str()),
+            MessageType::Info,
+        );
+        false
+    }
+
     /// Helper to add a history message
     fn add_history_message(&mut self, message: String, message_type: MessageType) {
         let history_msg = HistoryMessage {
             message,
             message_type,
-            timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+            timestamp: crate::clock::timestamp(),
         };
-        self.history_messages.push(history_msg.clone());
-
-        // Keep only last 1000 messages
-        while self.history_messages.len() > 1000 {
-            self.history_messages.remove(0);
+        self.history_messages.push_back(history_msg.clone());
+        while self.history_messages.len() > self.config.history_max_entries.max(1) {
+            self.history_messages.pop_front();
         }
 
         // Forward to the History component
@@ -182,9 +389,11 @@ impl App {
 
     /// Run the application
     pub async fn run(&mut self) -> Result<()> {
-        let mut tui = Tui::new()?;
-        let mut animation_interval = tokio::time::interval(Duration::from_millis(250));
+        let mut tui = Tui::new()?.title(self.config.terminal.window_title());
+        let mut animation_interval = tokio::time::interval(self.activity.tick_period());
         animation_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut housekeeping_interval = tokio::time::interval(HOUSEKEEPING_INTERVAL);
+        housekeeping_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         tui.enter()?;
 
@@ -200,9 +409,35 @@ impl App {
             let _ = history.update(Action::SetLogLevel(self.log_level));
         }
 
-        // Main event loop
+        if self.config.update.check {
+            let action_tx = self.action_tx.clone();
+            tokio::spawn(async move {
+                match crate::update::latest_release().await {
+                    Ok(release) if release.is_newer() => {
+                        let _ = action_tx.send(Action::UpdateAvailable {
+                            version: release.version().to_string(),
+                            url: release.html_url,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(error) => debug!("Update check failed: {}", error),
+                }
+            });
+        }
+
+        // Main event loop: the screen is only drawn when an event or action
+        // changed something on it
         loop {
-            let animation_active = self.loading || self.playback_state == PlaybackState::Playing;
+            // Slow down while nobody is watching; the first tick after
+            // speeding up again fires immediately
+            let tick_period = self.activity.tick_period();
+            if animation_interval.period() != tick_period {
+                animation_interval = tokio::time::interval(tick_period);
+                animation_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            }
+            let animation_active = self.loading
+                || self.splash.is_visible()
+                || self.state.playback_state() == PlaybackState::Playing;
             tokio::select! {
                 event = tui.next_event() => {
                     if let Some(event) = event {
@@ -218,19 +453,38 @@ impl App {
                 _ = animation_interval.tick(), if animation_active => {
                     self.handle_actions(&mut tui, Some(Action::Tick))?;
                 }
+                _ = housekeeping_interval.tick() => {
+                    self.housekeeping()?;
+                    self.handle_actions(&mut tui, None)?;
+                }
             }
 
-            if self.should_quit {
+            if self.state.should_quit() {
                 break;
             }
+            if self.state.take_dirty() {
+                self.render(&mut tui)?;
+            }
         }
 
+        self.scrobble_log.finish();
+        self.track_log.finish();
+        self.hooks.quit();
+        self.status_file.quit();
+        self.data_usage.save();
         tui.exit()?;
         Ok(())
     }
 
     /// Handle events from the TUI
     fn handle_event(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::Key(_) | Event::Mouse(_) | Event::Paste(_) => self.activity.input(),
+            Event::FocusGained => self.activity.set_focused(true),
+            Event::FocusLost => self.activity.set_focused(false),
+            _ => {}
+        }
+
         // Convert events to actions
         match event {
             Event::Init => {
@@ -242,6 +496,37 @@ impl App {
             Event::Resize(w, h) => {
                 self.action_tx.send(Action::Resize(w, h))?;
             }
+            Event::Key(_) if self.splash.is_visible() => {
+                // Any key skips the splash without acting on it
+                self.action_tx.send(Action::DismissSplash)?;
+                return Ok(());
+            }
+            Event::Key(key) if key.code == crate::keymap::HINTS_KEY => {
+                // Works in every context, so popups don't get it
+                self.action_tx.send(Action::ToggleKeyHints)?;
+                return Ok(());
+            }
+            Event::Key(key) if self.track_search_open => {
+                // Typing goes to the search box, not to the shortcuts
+                self.handle_popup_key(COMPONENT_TRACK_SEARCH, key)?;
+                self.action_tx.send(Action::Render)?;
+                return Ok(());
+            }
+            Event::Key(key) if self.history_search_open => {
+                self.handle_popup_key(COMPONENT_HISTORY, key)?;
+                self.action_tx.send(Action::Render)?;
+                return Ok(());
+            }
+            Event::Key(key) if self.language_picker_open => {
+                self.handle_popup_key(COMPONENT_LANGUAGE_PICKER, key)?;
+                self.action_tx.send(Action::Render)?;
+                return Ok(());
+            }
+            Event::Key(key) if self.stats_open => {
+                self.handle_popup_key(COMPONENT_STATS, key)?;
+                self.action_tx.send(Action::Render)?;
+                return Ok(());
+            }
             Event::Key(key) => {
                 self.handle_key_event(key)?;
                 // Some components mutate their state directly on key events.
@@ -260,6 +545,28 @@ impl App {
         Ok(())
     }
 
+    /// Handle keyboard events while a popup or search box takes the typing
+    ///
+    /// Only Ctrl+C still quits; every other key goes to the component at
+    /// `component_index`.
+    fn handle_popup_key(&mut self, component_index: usize, key: KeyEvent) -> Result<()> {
+        if key.code == crossterm::event::KeyCode::Char('c')
+            && key
+                .modifiers
+                .contains(crossterm::event::KeyModifiers::CONTROL)
+        {
+            info!("Ctrl+C detected, initiating graceful shutdown");
+            self.state.reduce(AppEvent::Quit);
+            return Ok(());
+        }
+        if let Some(component) = self.components.get_mut(component_index) {
+            if let Some(action) = component.handle_key_event(key)? {
+                self.action_tx.send(action)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Handle keyboard events
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
         use crossterm::event::KeyCode;
@@ -271,10 +578,27 @@ impl App {
                 .contains(crossterm::event::KeyModifiers::CONTROL)
         {
             info!("Ctrl+C detected, initiating graceful shutdown");
-            self.should_quit = true;
+            self.state.reduce(AppEvent::Quit);
+            return Ok(());
+        }
+
+        if let Some(preset) = PresetKey::from_key(&key) {
+            self.action_tx.send(match preset {
+                PresetKey::Tune(slot) => Action::TunePreset(slot),
+                PresetKey::Assign(slot) => Action::AssignPreset(slot),
+            })?;
             return Ok(());
         }
 
+        // Alt and a chip's key toggles that genre in the filter
+        if let KeyCode::Char(chip) = key.code {
+            if key.modifiers.contains(crossterm::event::KeyModifiers::ALT) {
+                self.action_tx
+                    .send(Action::ToggleGenreChip(chip.to_ascii_lowercase()))?;
+                return Ok(());
+            }
+        }
+
         // Handle global keyboard shortcuts
         match key.code {
             KeyCode::Char('q') => {
@@ -293,6 +617,86 @@ impl App {
                 self.action_tx.send(Action::VolumeDown)?;
                 return Ok(());
             }
+            KeyCode::Char('b') => {
+                self.action_tx.send(Action::CycleStreamQuality)?;
+                return Ok(());
+            }
+            KeyCode::Char('e') => {
+                self.action_tx.send(Action::CycleEqualizer)?;
+                return Ok(());
+            }
+            KeyCode::Char('R') => {
+                self.action_tx.send(Action::ToggleRecording)?;
+                return Ok(());
+            }
+            KeyCode::Char('S') => {
+                self.action_tx.send(Action::SaveReplay)?;
+                return Ok(());
+            }
+            KeyCode::Char('E') => {
+                self.action_tx.send(Action::ExportHistory)?;
+                return Ok(());
+            }
+            KeyCode::Left => {
+                self.action_tx.send(Action::TimeShift(-10))?;
+                return Ok(());
+            }
+            KeyCode::Char('[') => {
+                self.action_tx.send(Action::TimeShift(-30))?;
+                return Ok(());
+            }
+            KeyCode::Char('{') => {
+                self.action_tx.send(Action::TimeShift(-60))?;
+                return Ok(());
+            }
+            KeyCode::Right => {
+                self.action_tx.send(Action::TimeShift(10))?;
+                return Ok(());
+            }
+            KeyCode::Char(']') => {
+                self.action_tx.send(Action::GoLive)?;
+                return Ok(());
+            }
+            KeyCode::Char('r') => {
+                self.action_tx.send(Action::RefreshStations)?;
+                return Ok(());
+            }
+            KeyCode::Char('s') => {
+                self.action_tx.send(Action::CycleStationSort)?;
+                return Ok(());
+            }
+            KeyCode::Char('g') => {
+                self.action_tx.send(Action::GenreNext)?;
+                return Ok(());
+            }
+            KeyCode::Char('G') => {
+                self.action_tx.send(Action::GenrePrev)?;
+                return Ok(());
+            }
+            KeyCode::Char('x') => {
+                self.action_tx.send(Action::TuneRandom(RandomScope::List))?;
+                return Ok(());
+            }
+            KeyCode::Backspace => {
+                self.action_tx.send(Action::TuneBack)?;
+                return Ok(());
+            }
+            KeyCode::Char('f') => {
+                self.action_tx.send(Action::OpenTrackSearch)?;
+                return Ok(());
+            }
+            KeyCode::Char('l') => {
+                self.action_tx.send(Action::LikeTrack)?;
+                return Ok(());
+            }
+            KeyCode::Char('L') => {
+                self.action_tx.send(Action::OpenLanguagePicker)?;
+                return Ok(());
+            }
+            KeyCode::Char('T') => {
+                self.action_tx.send(Action::OpenStats)?;
+                return Ok(());
+            }
             _ => {
                 // For other keys, don't process them here - let components handle them via handle_events
                 // This prevents double processing of key events
@@ -304,7 +708,6 @@ impl App {
 
     /// Handle actions from components
     fn handle_actions(&mut self, tui: &mut Tui, first_action: Option<Action>) -> Result<()> {
-        let mut needs_render = false;
         let mut next_action = first_action;
         loop {
             let action = match next_action.take() {
@@ -314,44 +717,118 @@ impl App {
                     Err(_) => break,
                 },
             };
-            if action != Action::Tick && action != Action::Render {
+            if !matches!(
+                action,
+                Action::Tick | Action::Render | Action::AudioLevel(_) | Action::Buffered(_)
+            ) {
                 debug!(?action);
             }
 
             // Handle app-level actions
             match &action {
                 Action::Quit => {
-                    self.should_quit = true;
+                    self.state.reduce(AppEvent::Quit);
                 }
                 Action::Render => {
-                    needs_render = true;
+                    self.state.mark_dirty();
                 }
                 Action::Resize(w, h) => {
                     tui.resize(Rect::new(0, 0, *w, *h))?;
-                    needs_render = true;
+                    self.splash.invalidate();
+                    self.state.mark_dirty();
                 }
                 Action::UpdateStations(stations) => {
-                    self.stations = stations.clone();
+                    let refreshed = !self.loading;
+                    let (added, removed) = self.set_catalog(stations.clone());
+                    if refreshed {
+                        self.add_history_message(
+                            format!(
+                                "{} (+{} / -{})",
+                                crate::i18n::t("stations-updated"),
+                                added,
+                                removed
+                            ),
+                            MessageType::System,
+                        );
+                    }
+                    if self.config.station_changes {
+                        self.note_station_changes(stations);
+                    }
+                    self.mqtt.set_voice_names(voice::voice_names(
+                        &self.catalog,
+                        &self.config.voice_aliases,
+                    ));
                     self.loading = false;
+                    self.splash.stations_loaded();
+                    self.fetch_recent_songs();
+                    if let Some(station_id) = self.retune_pending.take() {
+                        self.action_tx.send(Action::TuneStation(station_id))?;
+                    }
+                    if Splash::wants_artwork(&self.config.splash) {
+                        prefetch_artwork(stations);
+                    }
                 }
                 Action::SetActiveStation(idx) => {
+                    if self.active_station != *idx {
+                        self.scrobble_log.finish();
+                        self.track_log.end_session();
+                        self.current_track = None;
+                    }
                     self.active_station = *idx;
                     if let Some(station) = idx.and_then(|idx| self.stations.get(idx)) {
+                        if self.new_stations.remove(&station.id) {
+                            if let Some(station_list) =
+                                self.components.get_mut(COMPONENT_STATION_LIST)
+                            {
+                                let _ = station_list.update(Action::SetNewStations(
+                                    self.new_stations.iter().cloned().collect(),
+                                ));
+                            }
+                        }
                         self.media_session.set_station(station.clone());
+                        self.mqtt.set_station(station.clone());
+                        self.status_server.set_station(station.clone());
+                        self.websocket.set_station(station.clone());
+                        self.webhook.set_station(station.clone());
+                        self.status_file.set_station(station);
                     }
                 }
                 Action::SetPlaybackState(state) => {
-                    self.playback_state = state.clone();
+                    // Usually already applied by whoever sent it; the
+                    // playback task also reports a stream that ended
+                    self.reduce_playback(AppEvent::PlaybackChanged(state.clone(), Instant::now()));
+                    if *state != PlaybackState::Playing {
+                        self.scrobble_log.finish();
+                        self.current_track = None;
+                    }
+                    match state {
+                        PlaybackState::Playing => {}
+                        PlaybackState::Stopped => self.track_log.end_session(),
+                        _ => self.track_log.finish(),
+                    }
                     self.media_session.set_playback_state(state.clone());
+                    self.mqtt.set_playback_state(state.clone());
+                    self.status_server.set_playback_state(state.clone());
+                    self.websocket.set_playback_state(state.clone());
+                    self.webhook.set_playback_state(state.clone());
+                    self.status_file.set_playback_state(state.clone());
+                    self.hooks.playback_changed(
+                        state,
+                        self.active_station
+                            .and_then(|index| self.stations.get(index)),
+                    );
                 }
                 Action::SetVolume(level) => {
-                    self.volume = level.clamp(0.0, 2.0);
-                    if let Some(ref sink) = self.sink {
-                        if let Ok(sink) = sink.lock() {
-                            sink.set_volume(self.volume);
-                        }
-                    }
-                    self.media_session.set_volume(self.volume);
+                    self.state.reduce(AppEvent::SetVolume(*level));
+                    let volume = self.state.volume();
+                    self.player.send(PlayerCommand::SetVolume(
+                        self.config.volume_curve.gain(volume),
+                    ));
+                    self.media_session.set_volume(volume);
+                    self.mqtt.set_volume(volume);
+                    self.status_server.set_volume(volume);
+                    self.websocket.set_volume(volume);
+                    self.status_file.set_volume(volume);
                 }
                 Action::MetadataUpdate { station, title } => {
                     if let Some(active_station) = self
@@ -361,10 +838,49 @@ impl App {
                     {
                         self.media_session
                             .set_track_title(active_station.clone(), title.clone());
+                        self.mqtt.set_track_title(title.clone());
+                        self.status_server.set_track_title(title.clone());
+                        self.webhook.set_track_title(title.clone());
+                        self.status_file.set_track_title(title.clone());
+                        self.scrobble_log.track_started(title);
+                        self.track_log.track_started(station, title);
+                        self.current_track = Some((station.clone(), title.clone()));
+                        self.websocket
+                            .set_track_title(station.clone(), title.clone());
+                        self.hooks.track_changed(active_station, title);
                     }
                 }
                 Action::Error(msg) => {
+                    self.sound_effects.play(Effect::Error);
                     self.add_history_message(msg.clone(), MessageType::Error);
+                    self.websocket.error(msg.clone());
+                }
+                Action::Buffered(buffered) => {
+                    self.buffered = *buffered;
+                }
+                Action::SetUnderruns(count) => {
+                    self.underruns = *count;
+                }
+                Action::Player(PlayerEvent::Queued(queued)) => {
+                    self.queued = *queued;
+                }
+                Action::Player(PlayerEvent::Ended { station, error }) => {
+                    debug!(%station, ?error, "Playback task ended");
+                    if self.audio_manager.current_station() == Some(station.as_str()) {
+                        self.audio_manager.clear_current_station();
+                    }
+                }
+                Action::Tick => {
+                    if self.splash.tick() {
+                        self.dismiss_splash(tui)?;
+                    }
+                    if self.splash.is_visible() {
+                        self.state.mark_dirty();
+                    }
+                }
+                Action::DismissSplash => {
+                    self.dismiss_splash(tui)?;
+                    self.state.mark_dirty();
                 }
                 Action::ToggleHelp => {
                     // Show help visibility synchronously in components
@@ -372,7 +888,62 @@ impl App {
                         let _ = component.update(Action::ToggleHelp);
                     }
                     // Mark that we need to render immediately
-                    needs_render = true;
+                    self.state.mark_dirty();
+                }
+                Action::OpenTrackSearch => {
+                    self.track_search_open = true;
+                    self.state.mark_dirty();
+                }
+                Action::CloseTrackSearch => {
+                    self.track_search_open = false;
+                    self.state.mark_dirty();
+                }
+                Action::OpenHistorySearch => {
+                    self.history_search_open = true;
+                    self.state.mark_dirty();
+                }
+                Action::CloseHistorySearch => {
+                    self.history_search_open = false;
+                    self.state.mark_dirty();
+                }
+                Action::OpenLanguagePicker => {
+                    self.language_picker_open = true;
+                    self.state.mark_dirty();
+                }
+                Action::CloseLanguagePicker => {
+                    self.language_picker_open = false;
+                    self.state.mark_dirty();
+                }
+                Action::OpenStats => {
+                    let ranking = self
+                        .store
+                        .as_ref()
+                        .map_or(Ok(Vec::new()), |store| store.lock().station_times())
+                        .unwrap_or_else(|error| {
+                            warn!("Failed to read listening stats: {}", error);
+                            Vec::new()
+                        });
+                    if let Some(stats) = self.components.get_mut(COMPONENT_STATS) {
+                        let _ = stats.update(Action::SetListeningStats(ranking));
+                    }
+                    self.stats_open = true;
+                    self.state.mark_dirty();
+                }
+                Action::CloseStats => {
+                    self.stats_open = false;
+                    self.state.mark_dirty();
+                }
+                Action::SetLocale(locale) => {
+                    self.language_picker_open = false;
+                    if crate::i18n::set_locale(locale) {
+                        self.config.locale = Some(locale.clone());
+                        self.add_history_message(
+                            crate::i18n::t("language-changed")
+                                .replace("{$language}", &crate::i18n::t("language-name")),
+                            MessageType::System,
+                        );
+                    }
+                    self.state.mark_dirty();
                 }
                 Action::Play => {
                     self.play_station()?;
@@ -385,7 +956,7 @@ impl App {
                     self.action_tx.send(Action::Render)?;
                 }
                 Action::TogglePlayStop => {
-                    match self.playback_state {
+                    match self.state.playback_state() {
                         PlaybackState::Stopped => self.play_station()?,
                         PlaybackState::Playing | PlaybackState::Paused => self.stop_playback(),
                     }
@@ -407,21 +978,44 @@ impl App {
                     self.action_tx.send(Action::Render)?;
                 }
                 Action::VolumeUp => {
-                    self.volume_up();
-                    // Sync volume to components
-                    self.action_tx.send(Action::SetVolume(self.volume))?;
-                    // Trigger render to show volume
-                    self.action_tx.send(Action::Render)?;
+                    self.state
+                        .reduce(AppEvent::SetVolume(self.state.volume() + VOLUME_STEP));
+                    // Sets the player and components
+                    self.action_tx
+                        .send(Action::SetVolume(self.state.volume()))?;
                 }
                 Action::VolumeDown => {
-                    self.volume_down();
-                    // Sync volume to components
-                    self.action_tx.send(Action::SetVolume(self.volume))?;
-                    // Trigger render to show volume
-                    self.action_tx.send(Action::Render)?;
+                    self.state
+                        .reduce(AppEvent::SetVolume(self.state.volume() - VOLUME_STEP));
+                    self.action_tx
+                        .send(Action::SetVolume(self.state.volume()))?;
+                }
+                Action::AdjustVolume(delta) => {
+                    self.state.reduce(AppEvent::AdjustVolume(*delta));
+                    self.action_tx
+                        .send(Action::SetVolume(self.state.volume()))?;
+                }
+                Action::Mute if self.state.reduce(AppEvent::Mute) => {
+                    self.action_tx.send(Action::SetVolume(0.0))?;
+                }
+                Action::Unmute if self.state.reduce(AppEvent::Unmute) => {
+                    self.action_tx
+                        .send(Action::SetVolume(self.state.volume()))?;
                 }
                 Action::TuneStation(station_id) => {
-                    if let Some(index) = self.stations.iter().position(|s| s.id == *station_id) {
+                    // A station hidden by the genre filter can still be tuned
+                    let aliases = &self.config.voice_aliases;
+                    if !self.genre_filter.is_empty()
+                        && voice::find_station(&self.stations, station_id, aliases).is_none()
+                        && voice::find_station(&self.catalog, station_id, aliases).is_some()
+                    {
+                        self.genre_filter.clear();
+                        self.update_view();
+                        self.sync_station_list();
+                    }
+                    if let Some(index) =
+                        voice::find_station(&self.stations, station_id, &self.config.voice_aliases)
+                    {
                         self.selected_station = index;
                         // Update NowPlaying component with the selected station details
                         if let Some(now_playing) = self.components.get_mut(COMPONENT_NOW_PLAYING) {
@@ -436,6 +1030,271 @@ impl App {
                         self.play_station()?;
                     }
                 }
+                Action::TuneUrl(url) => {
+                    self.tune_url(url)?;
+                }
+                Action::StreamGone(station_id) => {
+                    self.retune(station_id)?;
+                }
+                Action::TuneBack => {
+                    if let Some(station_id) = self.previous_station.clone() {
+                        self.action_tx.send(Action::TuneStation(station_id))?;
+                    }
+                }
+                Action::RingAlarm {
+                    station,
+                    fade_in_seconds,
+                } => {
+                    self.add_history_message(
+                        crate::i18n::t("alarm-ringing").replace("{$station}", station),
+                        MessageType::System,
+                    );
+                    // An alarm nobody can hear is no use; wake up at full volume
+                    self.state.reduce(AppEvent::Unmute);
+                    let mut target = self.state.volume();
+                    if target <= 0.0 {
+                        target = 1.0;
+                    }
+                    if *fade_in_seconds > 0 {
+                        self.action_tx.send(Action::SetVolume(0.0))?;
+                        alarm::spawn_fade(
+                            target,
+                            std::time::Duration::from_secs(*fade_in_seconds),
+                            self.action_tx.clone(),
+                        );
+                    } else {
+                        self.action_tx.send(Action::SetVolume(target))?;
+                    }
+                    self.action_tx.send(Action::TuneStation(station.clone()))?;
+                }
+                Action::LikeTrack => match &self.current_track {
+                    Some((station, title)) => match self.favorites.like(station, title) {
+                        Ok(Some(favorite)) => self.add_history_message(
+                            crate::i18n::t("favorite-added").replace("{$track}", &favorite.track()),
+                            MessageType::System,
+                        ),
+                        Ok(None) => self.add_history_message(
+                            crate::i18n::t("favorite-exists").replace("{$track}", title),
+                            MessageType::Info,
+                        ),
+                        Err(error) => {
+                            self.action_tx.send(Action::Error(format!(
+                                "{}: {}",
+                                crate::i18n::t("favorite-failed"),
+                                error
+                            )))?;
+                        }
+                    },
+                    None => self.add_history_message(
+                        crate::i18n::t("favorite-no-track"),
+                        MessageType::Info,
+                    ),
+                },
+                Action::HoverStation(station_id) => {
+                    let hovered = self
+                        .stations
+                        .get(self.selected_station)
+                        .filter(|station| station.id == *station_id);
+                    if let Some(station) = hovered {
+                        if self.hover_prefetch.start(&station.id) {
+                            prefetch_station(station);
+                        }
+                    }
+                    self.fetch_recent_songs();
+                }
+                Action::RecentSongs { station_id, songs } => {
+                    self.recent_songs.set(station_id, songs.clone());
+                    self.state.mark_dirty();
+                }
+                Action::UpdateSchedule(shows) => {
+                    self.upcoming_shows.set(shows.clone());
+                    self.state.mark_dirty();
+                }
+                Action::TuneRandom(scope) => {
+                    let candidates: Vec<&Station> = match scope {
+                        RandomScope::List => self.stations.iter().collect(),
+                        RandomScope::All => self.catalog.iter().collect(),
+                        RandomScope::Presets => self
+                            .catalog
+                            .iter()
+                            .filter(|station| {
+                                self.config.presets.values().any(|id| *id == station.id)
+                            })
+                            .collect(),
+                    };
+                    // Something other than what's playing, unless there's no choice
+                    let mut candidates: Vec<&Station> = candidates
+                        .into_iter()
+                        .filter(|station| station.id != URL_STATION_ID)
+                        .collect();
+                    if candidates.len() > 1 {
+                        candidates.retain(|station| self.last_played.as_ref() != Some(&station.id));
+                    }
+                    if !candidates.is_empty() {
+                        let station = candidates[utils::random_index(candidates.len())];
+                        self.action_tx
+                            .send(Action::TuneStation(station.id.clone()))?;
+                    }
+                }
+                Action::TunePreset(slot) => match self.config.presets.get(&slot.to_string()) {
+                    Some(station_id) => {
+                        self.action_tx
+                            .send(Action::TuneStation(station_id.clone()))?;
+                    }
+                    None => self.add_history_message(
+                        format!(
+                            "{} {}: {}",
+                            crate::i18n::t("preset"),
+                            slot,
+                            crate::i18n::t("preset-empty")
+                        ),
+                        MessageType::System,
+                    ),
+                },
+                Action::AssignPreset(slot) => {
+                    // A stream played by URL has no ID to come back to
+                    if let Some(station) = self
+                        .stations
+                        .get(self.selected_station)
+                        .filter(|station| station.id != URL_STATION_ID)
+                        .cloned()
+                    {
+                        self.config.presets.insert(slot.to_string(), station.id);
+                        self.add_history_message(
+                            format!("{} {}: {}", crate::i18n::t("preset"), slot, station.title),
+                            MessageType::System,
+                        );
+                    }
+                }
+                Action::SetStreamQuality(quality) => {
+                    self.set_stream_quality(*quality)?;
+                }
+                Action::SetOffline(offline) => {
+                    if self.offline != *offline {
+                        let message = if *offline {
+                            "catalog-offline"
+                        } else {
+                            "catalog-online"
+                        };
+                        self.add_history_message(crate::i18n::t(message), MessageType::System);
+                    }
+                    self.offline = *offline;
+                }
+                Action::UpdateAvailable { version, url } => {
+                    self.add_history_message(
+                        crate::i18n::t("update-available")
+                            .replace("{$version}", version)
+                            .replace("{$url}", url),
+                        MessageType::Info,
+                    );
+                }
+                Action::UpdateLiveStatus(live) => {
+                    // A channel was added, renamed or removed since the catalog was loaded
+                    if self.config.station_changes
+                        && !self.loading
+                        && station_changes::live_differs(&self.catalog, live)
+                    {
+                        info!("SomaFM channels changed, fetching the catalog again");
+                        self.fetch_stations(ProviderRegistry::from_config(&self.config, true));
+                    }
+                    for station in &mut self.catalog {
+                        if let Some(status) = live.get(&station.id) {
+                            station.last_playing.clone_from(&status.last_playing);
+                            station.listeners = status.listeners;
+                        }
+                    }
+                    self.update_view();
+                    self.sync_station_list();
+                }
+                Action::CycleStationSort => {
+                    self.config.station_sort = self.config.station_sort.next();
+                    self.add_history_message(
+                        format!(
+                            "{}: {}",
+                            crate::i18n::t("station-sort"),
+                            crate::i18n::t(&format!("sort-{}", self.config.station_sort))
+                        ),
+                        MessageType::System,
+                    );
+                    self.update_view();
+                    self.sync_station_list();
+                }
+                Action::GenreNext | Action::GenrePrev => {
+                    let genres = station::genres(&self.catalog);
+                    // Stepping starts over from the first genre when several are chosen
+                    let current = match self.genre_filter.len() {
+                        1 => self
+                            .genre_filter
+                            .first()
+                            .and_then(|genre| genres.iter().position(|g| g == genre)),
+                        _ => None,
+                    };
+                    // "All genres" sits between the last genre and the first
+                    let next = if matches!(action, Action::GenreNext) {
+                        match current {
+                            None => genres.first(),
+                            Some(index) => genres.get(index + 1),
+                        }
+                    } else {
+                        match current {
+                            None => genres.last(),
+                            Some(index) => index.checked_sub(1).and_then(|i| genres.get(i)),
+                        }
+                    };
+                    self.genre_filter = next.cloned().into_iter().collect();
+                    self.genre_filter_changed();
+                }
+                Action::ToggleGenreChip(key) => {
+                    if let Some(chip) = station::genre_chips(&self.catalog)
+                        .into_iter()
+                        .find(|chip| chip.key == *key)
+                    {
+                        if !self.genre_filter.remove(&chip.genre) {
+                            self.genre_filter.insert(chip.genre);
+                        }
+                        self.genre_filter_changed();
+                    }
+                }
+                Action::RefreshStations => {
+                    self.add_history_message(
+                        crate::i18n::t("refreshing-stations"),
+                        MessageType::System,
+                    );
+                    self.fetch_stations(ProviderRegistry::from_config(&self.config, true));
+                }
+                Action::CycleStreamQuality => {
+                    self.set_stream_quality(self.config.stream_quality.next())?;
+                }
+                Action::ToggleRecording if self.feature_enabled(Feature::Recording) => {
+                    self.toggle_recording();
+                }
+                Action::SaveReplay if self.feature_enabled(Feature::Recording) => {
+                    self.save_replay();
+                }
+                Action::ReplaySaved(saved) => {
+                    self.replay_saved(saved.clone());
+                }
+                Action::ExportHistory => {
+                    self.export_history();
+                }
+                Action::TimeShift(seconds) => {
+                    self.jump_time_shift(*seconds);
+                }
+                Action::GoLive => {
+                    self.time_shift.go_live();
+                }
+                Action::CycleEqualizer if self.feature_enabled(Feature::Dsp) => {
+                    self.config.equalizer.preset = self.config.equalizer.preset.next();
+                    self.equalizer.set(self.config.equalizer.bands());
+                    self.add_history_message(
+                        format!(
+                            "{}: {}",
+                            crate::i18n::t("equalizer"),
+                            crate::i18n::t(&format!("eq-{}", self.config.equalizer.preset))
+                        ),
+                        MessageType::System,
+                    );
+                }
                 Action::TuneNext => {
                     if !self.stations.is_empty() {
                         let current = self.selected_station;
@@ -494,6 +1353,7 @@ impl App {
                             let _ = now_playing.update(Action::SetSelectedStation(None));
                         }
                     }
+                    self.schedule_prefetch();
                     // Trigger render to update selection highlight
                     self.action_tx.send(Action::Render)?;
                 }
@@ -513,6 +1373,7 @@ impl App {
                             let _ = now_playing.update(Action::SetSelectedStation(None));
                         }
                     }
+                    self.schedule_prefetch();
                     // Trigger render to update selection highlight
                     self.action_tx.send(Action::Render)?;
                 }
@@ -532,6 +1393,7 @@ impl App {
                             let _ = now_playing.update(Action::SetSelectedStation(None));
                         }
                     }
+                    self.schedule_prefetch();
                 }
                 _ => {}
             }
@@ -548,12 +1410,43 @@ impl App {
                 | Action::ResumePlayback
                 | Action::VolumeUp
                 | Action::VolumeDown
+                | Action::AdjustVolume(_)
+                | Action::Mute
+                | Action::Unmute
                 | Action::SetVolume(_)
                 | Action::TuneStation(_)
+                | Action::TuneUrl(_)
                 | Action::TuneNext
                 | Action::TunePrev
+                | Action::SetStreamQuality(_)
+                | Action::CycleStreamQuality
+                | Action::CycleEqualizer
+                | Action::ToggleRecording
+                | Action::SaveReplay
+                | Action::ReplaySaved(_)
+                | Action::ExportHistory
+                | Action::TimeShift(_)
+                | Action::GoLive
+                | Action::RefreshStations
+                | Action::UpdateLiveStatus(_)
+                | Action::CycleStationSort
+                | Action::GenreNext
+                | Action::GenrePrev
+                | Action::ToggleGenreChip(_)
+                | Action::TuneBack
+                | Action::StreamGone(_)
+                | Action::TuneRandom(_)
+                | Action::HoverStation(_)
+                | Action::RecentSongs { .. }
+                | Action::UpdateSchedule(_)
+                | Action::LikeTrack
+                | Action::RingAlarm { .. }
+                | Action::TunePreset(_)
+                | Action::AssignPreset(_)
                 | Action::StationUp
                 | Action::StationDown
+                | Action::DismissSplash
+                | Action::Buffered(_)
                 | Action::Tick
                 | Action::Render
                 | Action::Quit => false,
@@ -571,12 +1464,10 @@ impl App {
             // Always update components with state changes (but not as actions)
             // This ensures components reflect the current app state
             match &action {
-                Action::UpdateStations(ref stations) => {
-                    self.stations = stations.clone();
-                    // Update StationList component with new stations
-                    if let Some(station_list) = self.components.get_mut(COMPONENT_STATION_LIST) {
-                        let _ = station_list.update(action.clone());
-                    }
+                Action::UpdateStations(_) => {
+                    // Update StationList component with new stations, including
+                    // any stream played by URL
+                    self.sync_station_list();
                     // Update NowPlaying component with the selected station if it exists
                     if let Some(now_playing) = self.components.get_mut(COMPONENT_NOW_PLAYING) {
                         if let Some(station) = self.stations.get(self.selected_station).cloned() {
@@ -589,7 +1480,11 @@ impl App {
                     // Handle initial station selection (from CLI or config)
                     if !self.auto_played {
                         if let Some(ref station_id) = self.initial_station {
-                            if let Some(idx) = stations.iter().position(|s| s.id == *station_id) {
+                            if let Some(idx) = voice::find_station(
+                                &self.stations,
+                                station_id,
+                                &self.config.voice_aliases,
+                            ) {
                                 self.selected_station = idx;
                                 // Update components with the selection
                                 if let Some(station_list) =
@@ -597,7 +1492,7 @@ impl App {
                                 {
                                     let _ = station_list.update(Action::SelectStation(idx));
                                 }
-                                if let Some(station) = stations.get(idx).cloned() {
+                                if let Some(station) = self.stations.get(idx).cloned() {
                                     if let Some(now_playing) =
                                         self.components.get_mut(COMPONENT_NOW_PLAYING)
                                     {
@@ -623,9 +1518,7 @@ impl App {
                     }
                 }
                 Action::SetPlaybackState(state) => {
-                    // Update the app's playback state first
-                    let old_state = self.playback_state.clone();
-                    self.playback_state = state.clone();
+                    let old_state = self.state.playback_state();
 
                     // Check if we need to start/stop tracking play time
                     if matches!(old_state, PlaybackState::Stopped | PlaybackState::Paused)
@@ -633,7 +1526,8 @@ impl App {
                     {
                         // Starting playback - start tracking play time
                         if let Some(history) = self.components.get_mut(COMPONENT_HISTORY) {
-                            let _ = history.update(Action::SetTotalPlayed(self.total_played));
+                            let _ =
+                                history.update(Action::SetTotalPlayed(self.state.total_played()));
                             let _ = history.update(Action::StartTrackingPlayTime);
                         }
                     } else if matches!(old_state, PlaybackState::Playing)
@@ -643,12 +1537,14 @@ impl App {
                         if let Some(history) = self.components.get_mut(COMPONENT_HISTORY) {
                             let _ = history.update(Action::StopTrackingPlayTime);
                             // Update with the accumulated time
-                            let _ = history.update(Action::SetTotalPlayed(self.total_played));
+                            let _ =
+                                history.update(Action::SetTotalPlayed(self.state.total_played()));
                         }
                     } else {
                         // Just update the timing information normally
                         if let Some(history) = self.components.get_mut(COMPONENT_HISTORY) {
-                            let _ = history.update(Action::SetTotalPlayed(self.total_played));
+                            let _ =
+                                history.update(Action::SetTotalPlayed(self.state.total_played()));
                         }
                     }
 
@@ -674,113 +1570,405 @@ impl App {
                     }
                 }
                 Action::Tick => {
-                    // Update all components with tick
+                    // Components answer the tick with Render when it moved
+                    // something they show
                     for component in self.components.iter_mut() {
-                        let _ = component.update(action.clone());
+                        if let Ok(Some(Action::Render)) = component.update(action.clone()) {
+                            self.state.mark_dirty();
+                        }
                     }
                     // Update History component with latest timing information
+                    let listened = self.listened();
                     if let Some(history) = self.components.get_mut(COMPONENT_HISTORY) {
-                        // Calculate current played time including current session
-                        let current_played = if self.playback_state == PlaybackState::Playing {
-                            if let Some(start) = self.playback_start_time {
-                                self.total_played + start.elapsed()
-                            } else {
-                                self.total_played
-                            }
-                        } else {
-                            self.total_played
-                        };
-                        let _ = history.update(Action::SetTotalPlayed(current_played));
                         // Update the playback state as well to ensure it's in sync
                         let _ =
-                            history.update(Action::SetPlaybackState(self.playback_state.clone()));
+                            history.update(Action::SetPlaybackState(self.state.playback_state()));
+                        if let Ok(Some(Action::Render)) =
+                            history.update(Action::SetTotalPlayed(listened))
+                        {
+                            self.state.mark_dirty();
+                        }
                     }
-                    // Animate only while visible state changes. Idle stays event-driven.
-                    needs_render |= self.loading || self.playback_state == PlaybackState::Playing;
                 }
                 _ => {}
             }
 
+            // Level samples arrive several times a second; the animation tick draws them.
             if !matches!(
                 action,
-                Action::Tick | Action::Render | Action::MetadataUpdate { .. } | Action::Quit
+                Action::Tick
+                    | Action::Render
+                    | Action::MetadataUpdate { .. }
+                    | Action::AudioLevel(_)
+                    | Action::Buffered(_)
+                    | Action::Quit
             ) {
-                needs_render = true;
+                self.state.mark_dirty();
             }
         }
 
-        // Render immediately if needed (for UI actions like ToggleHelp)
-        if needs_render {
-            self.render(tui)?;
+        Ok(())
+    }
+
+    /// Replace the station list with the catalog
+    ///
+    /// The selected and playing stations are matched by ID so a refresh that
+    /// adds, drops or reorders stations doesn't move them. Returns the number
+    /// of stations added and removed.
+    fn set_catalog(&mut self, stations: Vec<Station>) -> (usize, usize) {
+        let diff = catalog_diff(&self.catalog, &stations);
+        let active = self
+            .active_station
+            .and_then(|index| self.stations.get(index))
+            .cloned();
+        let url_station = self
+            .catalog
+            .iter()
+            .find(|station| station.id == URL_STATION_ID)
+            .cloned();
+
+        self.catalog = stations;
+        // Keep the stream played by URL and the playing station, even if it
+        // was dropped from the catalog
+        for station in url_station.into_iter().chain(active) {
+            if !self.catalog.iter().any(|s| s.id == station.id) {
+                self.catalog.push(station);
+            }
         }
+        self.update_view();
+        diff
+    }
+
+    /// Rebuild the station list from the catalog with the genre filter and
+    /// sort order, keeping the selected and playing stations
+    ///
+    /// The playing station stays listed even if the filter excludes it.
+    fn update_view(&mut self) {
+        let selected_id = self
+            .stations
+            .get(self.selected_station)
+            .map(|station| station.id.clone());
+        let active_id = self
+            .active_station
+            .and_then(|index| self.stations.get(index))
+            .map(|station| station.id.clone());
+
+        let mut stations: Vec<Station> = self
+            .catalog
+            .iter()
+            .filter(|station| {
+                self.genre_filter.is_empty()
+                    || self
+                        .genre_filter
+                        .iter()
+                        .any(|genre| station.has_genre(genre))
+                    || active_id.as_ref() == Some(&station.id)
+            })
+            .cloned()
+            .collect();
+        self.config.station_sort.sort(&mut stations);
+        self.stations = stations;
+
+        let position = |id: String| self.stations.iter().position(|s| s.id == id);
+        self.active_station = active_id.and_then(position);
+        self.selected_station = selected_id.and_then(position).unwrap_or(0);
+    }
+
+    /// Report the genre filter and show the stations it lets through
+    fn genre_filter_changed(&mut self) {
+        let label = if self.genre_filter.is_empty() {
+            crate::i18n::t("genre-all")
+        } else {
+            Vec::from_iter(self.genre_filter.iter().cloned()).join(", ")
+        };
+        self.add_history_message(
+            format!("{}: {}", crate::i18n::t("genre"), label),
+            MessageType::System,
+        );
+        self.update_view();
+        self.sync_station_list();
+    }
+
+    /// Push the station list, selection and active station to the StationList component
+    fn sync_station_list(&mut self) {
+        if let Some(station_list) = self.components.get_mut(COMPONENT_STATION_LIST) {
+            let _ = station_list.update(Action::UpdateStations(self.stations.clone()));
+            let _ = station_list.update(Action::SelectStation(self.selected_station));
+            let _ = station_list.update(Action::SetActiveStation(self.active_station));
+            let _ = station_list.update(Action::SetStationSort(self.config.station_sort));
+            let _ = station_list.update(Action::SetGenreFilter(
+                self.genre_filter.iter().cloned().collect(),
+            ));
+            let _ = station_list.update(Action::SetGenreChips(station::genre_chips(&self.catalog)));
+            let _ = station_list.update(Action::SetNewStations(
+                self.new_stations.iter().cloned().collect(),
+            ));
+        }
+    }
+
+    /// Point out SomaFM channels added, renamed or removed since the list
+    /// was last seen, and badge the new ones until they are played
+    ///
+    /// The list is badged when the station list is synced after the update.
+    fn note_station_changes(&mut self, stations: &[Station]) {
+        let changes = self.known_stations.update(stations);
+        for (id, title) in changes.added {
+            self.add_history_message(
+                crate::i18n::t("station-added").replace("{$station}", &title),
+                MessageType::Notice,
+            );
+            self.new_stations.insert(id);
+        }
+        for (_, old, new) in changes.renamed {
+            self.add_history_message(
+                crate::i18n::t("station-renamed")
+                    .replace("{$old}", &old)
+                    .replace("{$new}", &new),
+                MessageType::Notice,
+            );
+        }
+        for (id, title) in changes.removed {
+            self.add_history_message(
+                crate::i18n::t("station-removed").replace("{$station}", &title),
+                MessageType::Notice,
+            );
+            self.new_stations.remove(&id);
+        }
+    }
 
+    /// Switch stream quality, restarting the current station on the new stream
+    fn set_stream_quality(&mut self, quality: StreamQuality) -> Result<()> {
+        if self.config.stream_quality == quality {
+            return Ok(());
+        }
+        self.config.stream_quality = quality;
+        self.add_history_message(
+            format!("{}: {}", crate::i18n::t("stream-quality"), quality),
+            MessageType::System,
+        );
+
+        for station in &mut self.catalog {
+            station.select_quality(quality);
+        }
+        self.update_view();
+        self.sync_station_list();
+
+        if self.state.playback_state() == PlaybackState::Playing {
+            if let Some(station) = self
+                .active_station
+                .and_then(|index| self.stations.get(index))
+            {
+                self.action_tx
+                    .send(Action::TuneStation(station.id.clone()))?;
+            }
+        }
         Ok(())
     }
 
+    /// Play a stream URL directly, bypassing the SomaFM catalog
+    fn tune_url(&mut self, url: &str) -> Result<()> {
+        let station = Station::from_url(url);
+        self.catalog.retain(|station| station.id != URL_STATION_ID);
+        self.catalog.push(station.clone());
+        self.stations.retain(|station| station.id != URL_STATION_ID);
+        self.stations.push(station.clone());
+        self.selected_station = self.stations.len() - 1;
+
+        if let Some(station_list) = self.components.get_mut(COMPONENT_STATION_LIST) {
+            let _ = station_list.update(Action::UpdateStations(self.stations.clone()));
+            let _ = station_list.update(Action::SelectStation(self.selected_station));
+        }
+        if let Some(now_playing) = self.components.get_mut(COMPONENT_NOW_PLAYING) {
+            let _ = now_playing.update(Action::SetSelectedStation(Some(station)));
+        }
+        self.play_station()
+    }
+
+    /// Prefetch the selected station once the selection has rested on it
+    fn schedule_prefetch(&mut self) {
+        let station_id = self
+            .stations
+            .get(self.selected_station)
+            .map(|station| station.id.as_str());
+        let wants_songs = station_id.is_some_and(|id| self.recent_songs.wants(id, Instant::now()));
+        self.hover_prefetch
+            .selected(station_id, wants_songs, &self.action_tx);
+    }
+
+    /// Fetch the songs the selected SomaFM channel played last, unless
+    /// they were fetched a moment ago
+    fn fetch_recent_songs(&mut self) {
+        let Some(station) = self.stations.get(self.selected_station) else {
+            return;
+        };
+        let now = Instant::now();
+        if station.source != providers::SOMAFM || !self.recent_songs.wants(&station.id, now) {
+            return;
+        }
+        self.recent_songs.requested(&station.id, now);
+        let api = self.config.api.clone();
+        let station_id = station.id.clone();
+        let action_tx = self.action_tx.clone();
+        tokio::spawn(async move {
+            match providers::fetch_recent_songs(&api, &station_id).await {
+                Ok(songs) => {
+                    let _ = action_tx.send(Action::RecentSongs { station_id, songs });
+                }
+                Err(error) => debug!(%station_id, "Failed to fetch recent songs: {}", error),
+            }
+        });
+    }
+
+    /// Keep the recent songs of the selected channel current while it stays
+    /// selected
+    fn sync_recent_songs(&mut self) {
+        let shown = self
+            .stations
+            .get(self.selected_station)
+            .is_some_and(|station| self.recent_songs.known(&station.id));
+        if shown {
+            self.fetch_recent_songs();
+        }
+    }
+
+    /// Say when a scheduled show is about to start, `remind_minutes` ahead
+    fn remind_of_shows(&mut self) {
+        let lead = self.config.schedule.remind_minutes;
+        if lead == 0 {
+            return;
+        }
+        let now = chrono::Utc::now();
+        let due: Vec<schedule::Show> = self
+            .show_reminders
+            .due(
+                self.upcoming_shows.shows(),
+                now.timestamp(),
+                std::time::Duration::from_secs(lead * 60),
+            )
+            .into_iter()
+            .cloned()
+            .collect();
+        let clock = crate::clock::current();
+        for show in due {
+            let station = schedule::station_of(&show, &self.catalog).cloned();
+            let start = chrono::DateTime::from_timestamp(show.start, 0)
+                .map(|time| clock.time(time))
+                .unwrap_or_default();
+            self.add_history_message(
+                crate::i18n::t("show-reminder")
+                    .replace("{$show}", &show.title)
+                    .replace(
+                        "{$station}",
+                        station.as_ref().map_or("", |station| &station.title),
+                    )
+                    .replace("{$time}", &start),
+                MessageType::Notice,
+            );
+            self.hooks.show_reminder(station.as_ref(), &show.title);
+        }
+    }
+
     /// Play the currently selected station
     fn play_station(&mut self) -> Result<()> {
         debug!("play_station called");
         if let Some(station) = self.stations.get(self.selected_station) {
             let station = station.clone();
             info!(station_id = %station.id, station_title = %station.title, "Starting playback");
+            self.retune_pending = None;
 
-            if let Some(sink) = self.sink.clone() {
-                self.active_station = Some(self.selected_station);
-                let current_time = Instant::now();
-                self.playback_start_time = Some(current_time);
-                self.playback_start_time_for_underrun = Some(current_time);
-                self.station_loading = true;
-
-                if let Some(pause_time) = self.last_pause_time.take() {
-                    if let Some(start) = self.playback_start_time {
-                        self.total_played += pause_time.duration_since(start);
-                    }
-                }
+            self.sound_effects.play(Effect::Tune);
+            if self.last_played.as_ref() != Some(&station.id) {
+                self.stop_recording();
+                self.recorder.clear_replay();
+                self.previous_station = self.last_played.replace(station.id.clone());
+            }
+            self.active_station = Some(self.selected_station);
+            let current_time = Instant::now();
+            self.underrun.tuned(current_time);
+            self.station_loading = true;
+
+            self.add_history_message(
+                crate::i18n::t("starting-playback").replace("{$station}", &station.title),
+                MessageType::System,
+            );
+            self.add_history_message(crate::i18n::t("connecting-to-stream"), MessageType::System);
+
+            let volume = self.config.volume_curve.gain(self.state.volume());
+            let stream_config = audio::stream::StreamConfig {
+                metadata_encoding: audio::encoding::for_station(
+                    &self.config.metadata_encoding,
+                    &station,
+                ),
+                title_rules: audio::title_rules::TitleRules::for_station(
+                    &self.config.title_cleanup,
+                    &station,
+                ),
+                fader: self.fader.clone(),
+                equalizer: self.equalizer.clone(),
+                recorder: self.recorder.clone(),
+                time_shift: self.time_shift.clone(),
+                throughput: self.throughput.clone(),
+                ..audio::stream::StreamConfig::from_app_config(&self.config)
+            };
 
-                self.abort_playback_task();
+            // The player fades out what plays before streaming the station
+            self.player.send(PlayerCommand::Play(Box::new(PlayRequest {
+                station: station.clone(),
+                volume,
+                config: stream_config,
+            })));
+            self.audio_manager.set_current_station(station.id.clone());
 
-                // Stop any existing playback
-                if let Ok(locked_sink) = sink.lock() {
-                    locked_sink.stop();
-                }
+            self.reduce_playback(AppEvent::Tuned(current_time));
 
-                self.add_history_message(
-                    crate::i18n::t("starting-playback").replace("{$station}", &station.title),
-                    MessageType::System,
-                );
-                self.add_history_message(
-                    crate::i18n::t("connecting-to-stream"),
-                    MessageType::System,
-                );
+            // Sync state to components
+            let _ = self
+                .action_tx
+                .send(Action::SetActiveStation(self.active_station));
+            let _ = self
+                .action_tx
+                .send(Action::SetPlaybackState(self.state.playback_state()));
+        }
+        Ok(())
+    }
 
-                let log_tx = self.log_tx.clone();
-                let metadata_tx = self.metadata_tx.clone();
-                let volume = self.volume;
-                let action_tx = self.action_tx.clone();
-                let stream_config = audio::stream::StreamConfig::from_app_config(&self.config);
-
-                let handle = audio::start_playback(
-                    station.clone(),
-                    sink,
-                    metadata_tx,
-                    log_tx,
-                    action_tx,
-                    volume,
-                    stream_config,
-                );
-                self.audio_manager.set_handle(handle);
-                self.audio_manager.set_current_station(station.id.clone());
+    /// Move playback to the default device after the output device went away
+    fn recover_output(&mut self) -> Result<()> {
+        if !self.output.is_lost()
+            || self
+                .output_retry
+                .is_some_and(|tried| tried.elapsed() < OUTPUT_RETRY_INTERVAL)
+        {
+            return Ok(());
+        }
+        if self.output_retry.is_none() {
+            self.add_history_message(crate::i18n::t("output-lost"), MessageType::Error);
+        }
+        self.output_retry = Some(Instant::now());
+        if let Err(error) = self.output.reopen() {
+            debug!("No audio output yet: {}", error);
+            return Ok(());
+        }
+        self.output_retry = None;
 
-                self.playback_state = PlaybackState::Playing;
+        self.player
+            .send(PlayerCommand::SetOutput(self.output.mixer().clone()));
+        self.sound_effects = SoundEffects::new(self.output.mixer(), &self.config.sound_effects);
+        self.add_history_message(crate::i18n::t("output-restored"), MessageType::System);
 
-                // Sync state to components
-                let _ = self
-                    .action_tx
-                    .send(Action::SetActiveStation(self.active_station));
-                let _ = self
-                    .action_tx
-                    .send(Action::SetPlaybackState(self.playback_state.clone()));
+        match self.state.playback_state() {
+            PlaybackState::Playing => {
+                if let Some(station) = self
+                    .active_station
+                    .and_then(|index| self.stations.get(index))
+                {
+                    self.action_tx
+                        .send(Action::TuneStation(station.id.clone()))?;
+                }
             }
+            // The paused stream is gone with the old sink
+            PlaybackState::Paused => self.stop_playback(),
+            PlaybackState::Stopped => {}
         }
         Ok(())
     }
@@ -788,42 +1976,26 @@ impl App {
     /// Stop playback
     fn stop_playback(&mut self) {
         debug!("stop_playback called");
-        let old_state = self.playback_state.clone();
-        self.abort_playback_task();
-        if let Some(ref sink) = self.sink {
-            if let Ok(sink) = sink.lock() {
-                match self.playback_state {
-                    PlaybackState::Playing => {
-                        sink.stop();
-                        self.playback_state = PlaybackState::Stopped;
-                        if let Some(start) = self.playback_start_time.take() {
-                            self.total_played += start.elapsed();
-                        }
-                        self.last_pause_time = None;
-                    }
-                    PlaybackState::Paused => {
-                        sink.stop();
-                        self.playback_state = PlaybackState::Stopped;
-                        self.last_pause_time = None;
-                    }
-                    PlaybackState::Stopped => {}
-                }
-            }
-        }
-        self.restart_attempts = 0;
-        self.last_restart_time = None;
+        self.player.send(PlayerCommand::Stop);
+        self.audio_manager.clear_current_station();
+        let stopped = self.reduce_playback(AppEvent::PlaybackChanged(
+            PlaybackState::Stopped,
+            Instant::now(),
+        ));
+        self.underrun.reset();
+        self.stop_recording();
 
         // Sync state to components if it changed
-        if old_state != PlaybackState::Stopped {
+        if stopped {
             let _ = self
                 .action_tx
-                .send(Action::SetPlaybackState(self.playback_state.clone()));
+                .send(Action::SetPlaybackState(PlaybackState::Stopped));
         }
     }
 
     /// Toggle pause/resume
     fn toggle_pause(&mut self) -> Result<()> {
-        match self.playback_state {
+        match self.state.playback_state() {
             PlaybackState::Playing => {
                 self.pause_playback();
             }
@@ -840,74 +2012,452 @@ impl App {
     /// Pause playback
     fn pause_playback(&mut self) {
         debug!("pause_playback called");
-        if let Some(ref sink) = self.sink {
-            if let Ok(sink) = sink.lock() {
-                if matches!(self.playback_state, PlaybackState::Playing) {
-                    sink.pause();
-                    self.playback_state = PlaybackState::Paused;
-                    if let Some(start) = self.playback_start_time.take() {
-                        self.total_played += start.elapsed();
-                    }
-                    self.last_pause_time = Some(Instant::now());
-
-                    // Sync state to components
-                    let _ = self
-                        .action_tx
-                        .send(Action::SetPlaybackState(self.playback_state.clone()));
-                }
-            }
+        if matches!(self.state.playback_state(), PlaybackState::Playing) {
+            self.player.send(PlayerCommand::Pause);
+            self.state.reduce(AppEvent::PlaybackChanged(
+                PlaybackState::Paused,
+                Instant::now(),
+            ));
+
+            // Sync state to components
+            let _ = self
+                .action_tx
+                .send(Action::SetPlaybackState(self.state.playback_state()));
         }
-        self.restart_attempts = 0;
-        self.last_restart_time = None;
+        self.underrun.reset();
     }
 
     /// Resume playback
     fn resume_playback(&mut self) -> Result<()> {
         debug!("resume_playback called");
-        if matches!(self.playback_state, PlaybackState::Paused) {
-            if let Some(ref sink) = self.sink {
-                if let Ok(sink) = sink.lock() {
-                    sink.play();
-                    self.playback_state = PlaybackState::Playing;
-                    self.playback_start_time = Some(Instant::now());
-                    self.last_pause_time = None;
-
-                    // Sync state to components
-                    let _ = self
-                        .action_tx
-                        .send(Action::SetPlaybackState(self.playback_state.clone()));
+        match self.state.resume() {
+            Some(Resume::Reconnect) => {
+                // Connect again from the live edge
+                if let Some(station) = self
+                    .active_station
+                    .and_then(|index| self.stations.get(index))
+                {
+                    self.action_tx
+                        .send(Action::TuneStation(station.id.clone()))?;
                 }
             }
-        } else if matches!(self.playback_state, PlaybackState::Stopped) {
-            self.play_station()?;
+            Some(Resume::Unpause) => {
+                self.player.send(PlayerCommand::Resume);
+                self.reduce_playback(AppEvent::PlaybackChanged(
+                    PlaybackState::Playing,
+                    Instant::now(),
+                ));
+
+                // Sync state to components
+                let _ = self
+                    .action_tx
+                    .send(Action::SetPlaybackState(self.state.playback_state()));
+            }
+            Some(Resume::Start) => self.play_station()?,
+            None => {}
         }
         Ok(())
     }
 
-    /// Increase volume
-    fn volume_up(&mut self) {
-        self.volume = (self.volume + 0.05).min(2.0);
-        if let Some(ref sink) = self.sink {
-            if let Ok(sink) = sink.lock() {
-                sink.set_volume(self.volume);
+    /// Checks that run every second, whether or not anything animates
+    ///
+    /// Whatever they change on screen arrives as actions, so they don't draw.
+    fn housekeeping(&mut self) -> Result<()> {
+        self.recover_output()?;
+        self.disconnect_paused_stream();
+        self.check_underrun()?;
+        self.sync_recording();
+        self.sync_time_shift();
+        self.sync_throughput();
+        self.sync_recent_songs();
+        self.remind_of_shows();
+        self.sync_progress();
+        Ok(())
+    }
+
+    /// Disconnect a stream paused for longer than the pause timeout, so it
+    /// stops downloading
+    fn disconnect_paused_stream(&mut self) {
+        let timeout = std::time::Duration::from_secs(self.config.pause_disconnect_seconds);
+        if !self.state.pause_expired(Instant::now(), timeout) {
+            return;
+        }
+        info!("Disconnecting the stream after a long pause");
+        self.player.send(PlayerCommand::Disconnect);
+        self.audio_manager.clear_current_station();
+        self.reduce_playback(AppEvent::Disconnected);
+    }
+
+    /// Restart playback when the output has had nothing to play for a while
+    fn check_underrun(&mut self) -> Result<()> {
+        let timeout = std::time::Duration::from_secs(self.config.audio_underrun_restart_seconds);
+        if timeout.is_zero()
+            || self.state.playback_state() != PlaybackState::Playing
+            || self.retune_pending.is_some()
+        {
+            self.underrun.idle();
+            return Ok(());
+        }
+        match self.underrun.check(Instant::now(), self.queued, timeout) {
+            Some(Underrun::Restart(empty_for)) => {
+                let Some(station_id) = self
+                    .active_station
+                    .and_then(|index| self.stations.get(index))
+                    .map(|station| station.id.clone())
+                else {
+                    return Ok(());
+                };
+                self.add_history_message(
+                    crate::i18n::t("output-underrun")
+                        .replace("{$seconds}", &empty_for.as_secs().to_string()),
+                    MessageType::Error,
+                );
+                self.action_tx.send(Action::TuneStation(station_id))?;
             }
+            Some(Underrun::GiveUp) => {
+                self.stop_playback();
+                self.action_tx
+                    .send(Action::Error(crate::i18n::t("output-underrun-gave-up")))?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// Start recording the playing station, or stop the running recording
+    fn toggle_recording(&mut self) {
+        if self.recorder.status().is_some() {
+            self.stop_recording();
+            return;
+        }
+        let station = self
+            .active_station
+            .and_then(|index| self.stations.get(index))
+            .filter(|_| self.state.playback_state() == PlaybackState::Playing);
+        let Some(station) = station else {
+            self.add_history_message(
+                crate::i18n::t("recording-needs-playback"),
+                MessageType::Info,
+            );
+            return;
+        };
+        let id = station.id.clone();
+        let started = self
+            .config
+            .recording
+            .dir()
+            .ok_or_else(|| "no music or home folder".to_string())
+            .and_then(|dir| {
+                self.recorder
+                    .start(&dir, &id)
+                    .map_err(|error| error.to_string())
+            });
+        match started {
+            Ok(path) => self.add_history_message(
+                crate::i18n::t("recording-started").replace("{$path}", &path.display().to_string()),
+                MessageType::System,
+            ),
+            Err(error) => self.add_history_message(
+                crate::i18n::t("recording-failed").replace("{$error}", &error),
+                MessageType::Error,
+            ),
+        }
+        self.sync_recording();
+    }
+
+    fn stop_recording(&mut self) {
+        if let Some((path, status)) = self.recorder.stop() {
+            self.add_history_message(
+                crate::i18n::t("recording-saved")
+                    .replace("{$path}", &path.display().to_string())
+                    .replace(
+                        "{$length}",
+                        &utils::format_duration(std::time::Duration::from_secs(status.seconds)),
+                    )
+                    .replace("{$size}", &utils::format_size(status.bytes)),
+                MessageType::System,
+            );
+        }
+        self.sync_recording();
+    }
+
+    /// Look a station up again after the server stopped finding its stream,
+    /// as when SomaFM moves a mount, and tune it once the fresh catalog arrives
+    ///
+    /// A station that is still missing right after that is reported instead.
+    fn retune(&mut self, station_id: &str) -> Result<()> {
+        let Some(station) = self
+            .catalog
+            .iter()
+            .find(|station| station.id == station_id)
+            .cloned()
+        else {
+            return Ok(());
+        };
+        let recently = self
+            .last_retune
+            .as_ref()
+            .is_some_and(|(id, at)| id == station_id && at.elapsed() < RETUNE_INTERVAL);
+        if recently {
+            self.action_tx
+                .send(Action::SetPlaybackState(PlaybackState::Stopped))?;
+            self.action_tx.send(Action::Error(
+                crate::i18n::t("stream-not-found").replace("{$station}", &station.title),
+            ))?;
+            return Ok(());
         }
+
+        for playlist in &station.playlists {
+            Station::forget_stream(&playlist.url);
+        }
+        Station::forget_stream(&station.playlist);
+        info!(station_id = %station.id, "Stream gone, fetching the catalog again");
+        self.last_retune = Some((station.id.clone(), Instant::now()));
+        self.retune_pending = Some(station.id);
+        self.add_history_message(
+            crate::i18n::t("stream-moved").replace("{$station}", &station.title),
+            MessageType::System,
+        );
+        self.fetch_stations(ProviderRegistry::from_config(&self.config, true));
+        Ok(())
     }
 
-    /// Decrease volume
-    fn volume_down(&mut self) {
-        self.volume = (self.volume - 0.05).max(0.0);
-        if let Some(ref sink) = self.sink {
-            if let Ok(sink) = sink.lock() {
-                sink.set_volume(self.volume);
+    /// Fetch the stations from `registry`, replacing any fetch still running
+    /// so only one retries while offline
+    pub fn fetch_stations(&mut self, registry: ProviderRegistry) {
+        if let Some(fetch) = self.station_fetch.take() {
+            fetch.abort();
+        }
+        self.station_fetch = Some(registry.spawn_fetch(self.action_tx.clone()));
+    }
+
+    /// Save what the playing station just played, off the UI thread
+    fn save_replay(&mut self) {
+        let (Some(station), Some(replay)) = (self.last_played.clone(), self.recorder.replay())
+        else {
+            self.add_history_message(crate::i18n::t("replay-empty"), MessageType::Info);
+            return;
+        };
+        let Some(dir) = self.config.recording.dir() else {
+            self.replay_saved(Err("no music or home folder".to_string()));
+            return;
+        };
+        let action_tx = self.action_tx.clone();
+        tokio::spawn(async move {
+            let saved = tokio::task::spawn_blocking(move || replay.save(&dir, &station))
+                .await
+                .map_err(|error| error.to_string())
+                .and_then(|saved| saved.map_err(|error| error.to_string()));
+            let _ = action_tx.send(Action::ReplaySaved(saved));
+        });
+    }
+
+    /// Say where the replay was saved, or why it wasn't
+    fn replay_saved(
+        &mut self,
+        saved: Result<(std::path::PathBuf, audio::recorder::RecordingStatus), String>,
+    ) {
+        match saved {
+            Ok((path, status)) => self.add_history_message(
+                crate::i18n::t("replay-saved")
+                    .replace("{$path}", &path.display().to_string())
+                    .replace(
+                        "{$length}",
+                        &utils::format_duration(std::time::Duration::from_secs(status.seconds)),
+                    )
+                    .replace("{$size}", &utils::format_size(status.bytes)),
+                MessageType::System,
+            ),
+            Err(error) => self.add_history_message(
+                crate::i18n::t("replay-failed").replace("{$error}", &error),
+                MessageType::Error,
+            ),
+        }
+    }
+
+    /// Write the track log to a dated file in the export folder
+    fn export_history(&mut self) {
+        let format = self.config.track_log.export_format;
+        let exported = self
+            .store
+            .clone()
+            .ok_or_else(|| crate::i18n::t("history-export-no-log"))
+            .and_then(|store| {
+                let dir = self
+                    .config
+                    .track_log
+                    .export_dir()
+                    .ok_or_else(|| "no documents or home folder".to_string())?;
+                let plays = store.lock().plays().map_err(|error| error.to_string())?;
+                let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+                let path = history_export::file_path(&dir, &date, format);
+                history_export::export(&path, &plays, format).map_err(|error| error.to_string())?;
+                Ok((path, plays.len()))
+            });
+        match exported {
+            Ok((path, plays)) => self.add_history_message(
+                crate::i18n::t("history-exported")
+                    .replace("{$count}", &plays.to_string())
+                    .replace("{$path}", &path.display().to_string()),
+                MessageType::System,
+            ),
+            Err(error) => self.add_history_message(
+                crate::i18n::t("history-export-failed").replace("{$error}", &error),
+                MessageType::Error,
+            ),
+        }
+    }
+
+    /// Show the recording's progress, once a second while it runs
+    fn sync_recording(&mut self) {
+        let status = self.recorder.status();
+        let shown = status.as_ref().map(|status| status.seconds);
+        if shown == self.recording_shown {
+            return;
+        }
+        self.recording_shown = shown;
+        self.status_server.set_recording(status.clone());
+        self.websocket.set_recording(status.clone());
+        let _ = self.action_tx.send(Action::SetRecording(status));
+    }
+
+    /// Rewind or skip forward through the time shift of the playing stream
+    fn jump_time_shift(&mut self, seconds: i64) {
+        if self.state.playback_state() != PlaybackState::Playing {
+            return;
+        }
+        if seconds < 0 && self.config.audio_time_shift_seconds == 0 {
+            self.add_history_message(crate::i18n::t("time-shift-off"), MessageType::Info);
+            return;
+        }
+        self.time_shift.jump(seconds);
+    }
+
+    /// Show how far behind live playback is, when it changes
+    fn sync_time_shift(&mut self) {
+        let behind = self.time_shift.behind();
+        if behind.as_secs() != self.time_shift_shown {
+            self.time_shift_shown = behind.as_secs();
+            let _ = self.action_tx.send(Action::SetTimeShift(behind));
+        }
+    }
+
+    /// Show the download rate and the data used, once a second, and stop
+    /// playback once the daily cap is used up
+    fn sync_throughput(&mut self) {
+        let now = Instant::now();
+        if self
+            .throughput_sampled
+            .is_some_and(|sampled| now.duration_since(sampled) < std::time::Duration::from_secs(1))
+        {
+            return;
+        }
+        self.throughput_sampled = Some(now);
+        let total = self.throughput.total();
+        let rate = self.throughput_rate.sample(now, total);
+        let today = chrono::Local::now().date_naive();
+        self.data_usage.record(today, total);
+        let today = self.data_usage.on(today);
+        let cap = self.config.data_usage.daily_cap();
+        if total > 0 && self.throughput_shown != Some((rate, total, today)) {
+            self.throughput_shown = Some((rate, total, today));
+            let _ = self.action_tx.send(Action::SetThroughput {
+                rate,
+                total,
+                today,
+                cap,
+            });
+        }
+        if let Some(cap) = cap.filter(|cap| today >= *cap) {
+            if self.state.playback_state() != PlaybackState::Stopped {
+                self.stop_playback();
+                let _ = self.action_tx.send(Action::Error(
+                    crate::i18n::t("data-cap-reached").replace("{$cap}", &utils::format_size(cap)),
+                ));
             }
         }
     }
 
+    /// Time played this session, including the current stretch
+    fn listened(&self) -> std::time::Duration {
+        self.state.listened(Instant::now())
+    }
+
+    /// Send listening time and buffer health to WebSocket clients every
+    /// `progress_seconds` while playing
+    fn sync_progress(&mut self) {
+        let interval = std::time::Duration::from_secs(self.config.websocket.progress_seconds);
+        if interval.is_zero() || self.state.playback_state() != PlaybackState::Playing {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .progress_sent
+            .is_some_and(|sent| now.duration_since(sent) < interval)
+        {
+            return;
+        }
+        self.progress_sent = Some(now);
+        self.websocket
+            .progress(self.listened(), self.buffered, self.underruns);
+    }
+
+    /// Apply a playback `event`, telling the components when it disconnects
+    /// a paused stream or connects it again
+    fn reduce_playback(&mut self, event: AppEvent) -> bool {
+        let was_disconnected = self.state.pause_disconnected();
+        let changed = self.state.reduce(event);
+        let disconnected = self.state.pause_disconnected();
+        if disconnected != was_disconnected {
+            let _ = self
+                .action_tx
+                .send(Action::SetPauseDisconnected(disconnected));
+        }
+        changed
+    }
+
+    /// Close the splash screen and repaint the whole terminal over its images
+    fn dismiss_splash(&mut self, tui: &mut Tui) -> Result<()> {
+        if self.splash.is_visible() {
+            self.splash.dismiss(&mut std::io::stdout())?;
+            tui.clear()?;
+        }
+        Ok(())
+    }
+
+    /// `somars – Station – Track` while a station plays, `somars` otherwise
+    fn window_title(&self) -> String {
+        let mut parts = vec!["somars"];
+        if self.state.playback_state() != PlaybackState::Stopped {
+            if let Some(station) = self
+                .active_station
+                .and_then(|index| self.stations.get(index))
+            {
+                parts.push(&station.title);
+            }
+            if let Some((_, title)) = &self.current_track {
+                parts.push(title);
+            }
+        }
+        parts.join(" – ")
+    }
+
     /// Render the UI
     fn render(&mut self, tui: &mut Tui) -> Result<()> {
+        tui.set_title(&self.window_title())?;
+        if self.splash.is_visible() {
+            tui.draw(|frame| self.splash.draw(frame, frame.area()))?;
+            self.splash.write_collage(&mut std::io::stdout())?;
+            return Ok(());
+        }
+
+        let selected = self.stations.get(self.selected_station);
+        let selected_id = selected.map(|station| station.id.as_str());
+        let now = chrono::Utc::now().timestamp();
+        let shows_height = self.upcoming_shows.height(selected, &self.catalog, now);
+        let recent_height = self.recent_songs.height(selected_id);
         tui.draw(|frame| {
-            let layout = Self::calculate_layout(frame.area());
+            let layout = Self::calculate_layout(frame.area(), shows_height, recent_height);
 
             // Render each component in its area
             for (i, component) in self.components.iter_mut().enumerate() {
@@ -916,21 +2466,41 @@ impl App {
                     COMPONENT_NOW_PLAYING => layout.right_top,
                     COMPONENT_HISTORY => layout.right_bottom,
                     COMPONENT_BOTTOM_CONTROLS => layout.bottom,
-                    _ => continue, // Help renders on full screen
+                    _ => continue, // Popups render on full screen
                 };
                 let _ = component.draw(frame, area);
             }
+            self.upcoming_shows
+                .draw(frame, layout.shows, selected, &self.catalog, now);
+            self.recent_songs
+                .draw(frame, layout.recent_songs, selected_id);
 
             // Render help on top (overlay) if visible
             if let Some(help_comp) = self.components.get_mut(COMPONENT_HELP) {
                 let _ = help_comp.draw(frame, frame.area());
             }
+            if let Some(track_search) = self.components.get_mut(COMPONENT_TRACK_SEARCH) {
+                let _ = track_search.draw(frame, frame.area());
+            }
+            if let Some(language_picker) = self.components.get_mut(COMPONENT_LANGUAGE_PICKER) {
+                let _ = language_picker.draw(frame, frame.area());
+            }
+            if let Some(stats) = self.components.get_mut(COMPONENT_STATS) {
+                let _ = stats.draw(frame, frame.area());
+            }
+            // In the corner above the bottom controls, over everything else
+            if let Some(key_hints) = self.components.get_mut(COMPONENT_KEY_HINTS) {
+                let mut area = frame.area();
+                area.height = area.height.saturating_sub(layout.bottom.height);
+                let _ = key_hints.draw(frame, area);
+            }
         })?;
         Ok(())
     }
 
-    /// Calculate layout rectangles
-    fn calculate_layout(area: Rect) -> AppLayout {
+    /// Calculate layout rectangles, with `shows_height` rows for the upcoming
+    /// shows and `recent_height` for the recent songs under now playing
+    fn calculate_layout(area: Rect, shows_height: u16, recent_height: u16) -> AppLayout {
         // Main vertical split: content area and bottom controls
         let app_layout = RatatuiLayout::default()
             .direction(Direction::Vertical)
@@ -943,17 +2513,28 @@ impl App {
             .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
             .split(app_layout[0]);
 
-        // Vertical split of right panel: now playing and history
+        // Vertical split of right panel: now playing, upcoming shows, recent
+        // songs and history
         let right_chunks = RatatuiLayout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(10), Constraint::Fill(1)].as_ref())
+            .constraints(
+                [
+                    Constraint::Length(10),
+                    Constraint::Length(shows_height),
+                    Constraint::Length(recent_height),
+                    Constraint::Fill(1),
+                ]
+                .as_ref(),
+            )
             .split(chunks[1]);
 
         AppLayout {
             bottom: app_layout[1],
             left_panel: chunks[0],
             right_top: right_chunks[0],
-            right_bottom: right_chunks[1],
+            shows: right_chunks[1],
+            recent_songs: right_chunks[2],
+            right_bottom: right_chunks[3],
         }
     }
 
@@ -965,11 +2546,65 @@ impl App {
     }
 }
 
+/// Cache station artwork in the background for future splash screens
+/// Stations added to and removed from `old` by `new`, by ID
+fn catalog_diff(old: &[Station], new: &[Station]) -> (usize, usize) {
+    let catalog = |stations: &[Station]| -> HashSet<String> {
+        stations
+            .iter()
+            .filter(|station| station.id != URL_STATION_ID)
+            .map(|station| station.id.clone())
+            .collect()
+    };
+    let (old, new) = (catalog(old), catalog(new));
+    (new.difference(&old).count(), old.difference(&new).count())
+}
+
+fn prefetch_artwork(stations: &[Station]) {
+    let urls: Vec<String> = stations
+        .iter()
+        .map(|station| station.image.trim().to_string())
+        .filter(|url| !url.is_empty() && artwork::cached(url).is_none())
+        .take(splash::MAX_TILES)
+        .collect();
+    if urls.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        for url in urls {
+            if let Err(error) = artwork::download(&url).await {
+                debug!(%url, "Failed to cache artwork: {:#}", error);
+            }
+        }
+    });
+}
+
+/// Download a station's artwork and resolve its playlist in the background,
+/// so both are ready when it is tuned
+fn prefetch_station(station: &Station) {
+    let image = station.image.trim().to_string();
+    let playlist = station.url.is_empty().then(|| station.playlist.clone());
+    tokio::spawn(async move {
+        if !image.is_empty() {
+            if let Err(error) = artwork::download(&image).await {
+                debug!(url = %image, "Failed to prefetch artwork: {:#}", error);
+            }
+        }
+        if let Some(playlist) = playlist.filter(|playlist| !playlist.is_empty()) {
+            if let Err(error) = Station::resolve_playlist(&playlist).await {
+                debug!(%playlist, "Failed to prefetch playlist: {}", error);
+            }
+        }
+    });
+}
+
 /// Layout areas for the application
 #[derive(Debug, Clone)]
 pub struct AppLayout {
     pub bottom: Rect,
     pub left_panel: Rect,
     pub right_top: Rect,
+    pub shows: Rect,
+    pub recent_songs: Rect,
     pub right_bottom: Rect,
 }
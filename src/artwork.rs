@@ -0,0 +1,99 @@
+//! Station artwork disk cache
+//!
+//! Station images are downloaded once into the user cache directory and shared
+//! by the macOS Now Playing integration and the startup splash.
+
+use anyhow::{bail, Context};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+
+const MAX_ARTWORK_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Path of the cached image for `url`, if it has been downloaded
+pub fn cached(url: &str) -> Option<PathBuf> {
+    if url.is_empty() {
+        return None;
+    }
+    let path = cache_path(url);
+    std::fs::metadata(&path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0)
+        .then_some(path)
+}
+
+fn cache_path(url: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    cache_dir().join(format!("{:016x}", hasher.finish()))
+}
+
+fn cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("somars")
+        .join("artwork")
+}
+
+/// Download the image at `url` into the cache unless it is already there
+pub async fn download(url: &str) -> anyhow::Result<PathBuf> {
+    let path = cache_path(url);
+    if std::fs::metadata(&path).is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0) {
+        return Ok(path);
+    }
+
+    let response = crate::http::client()
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("request failed: {url}"))?
+        .error_for_status()
+        .with_context(|| format!("server rejected artwork: {url}"))?;
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_ARTWORK_BYTES)
+    {
+        bail!("artwork exceeds {MAX_ARTWORK_BYTES} bytes");
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .with_context(|| format!("failed to read artwork: {url}"))?;
+    if bytes.is_empty() {
+        bail!("artwork response is empty");
+    }
+    if bytes.len() as u64 > MAX_ARTWORK_BYTES {
+        bail!("artwork exceeds {MAX_ARTWORK_BYTES} bytes");
+    }
+
+    let parent = path.parent().context("artwork cache has no parent")?;
+    tokio::fs::create_dir_all(parent)
+        .await
+        .context("failed to create artwork cache")?;
+    let temporary_path = path.with_extension(format!("tmp-{}", std::process::id()));
+    tokio::fs::write(&temporary_path, &bytes)
+        .await
+        .context("failed to write artwork cache")?;
+    tokio::fs::rename(&temporary_path, &path)
+        .await
+        .context("failed to commit artwork cache")?;
+    Ok(path)
+}
+
+/// Up to `limit` images from the cache, in a stable order
+pub fn cached_files(limit: usize) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(cache_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        // Skip partial downloads
+        .filter(|path| path.extension().is_none())
+        .filter(|path| std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() > 0))
+        .collect();
+    files.sort();
+    files.truncate(limit);
+    files
+}
@@ -0,0 +1,83 @@
+//! UI sound effects
+//!
+//! Short synthesized tones for UI feedback, for setups where the screen is not
+//! watched. They play through their own sink on the output mixer so the
+//! effect volume is independent of the radio volume.
+
+use rodio::mixer::Mixer;
+use rodio::source::{SineWave, Source};
+use rodio::Sink;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Sound effect settings, read from the `[sound_effects]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoundEffectsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Effect volume (0.0-1.0), independent of the playback volume
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+fn default_volume() -> f32 {
+    0.2
+}
+
+impl Default for SoundEffectsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: default_volume(),
+        }
+    }
+}
+
+/// Available effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Tuning to a station
+    Tune,
+    /// An error was reported
+    Error,
+}
+
+impl Effect {
+    /// Tones making up the effect as (frequency in Hz, duration in ms)
+    fn tones(self) -> &'static [(f32, u64)] {
+        match self {
+            Effect::Tune => &[(1500.0, 25)],
+            Effect::Error => &[(440.0, 80), (330.0, 120)],
+        }
+    }
+}
+
+/// Plays effects on a secondary sink
+pub struct SoundEffects {
+    /// `None` when effects are disabled
+    sink: Option<Sink>,
+}
+
+impl SoundEffects {
+    pub fn new(mixer: &Mixer, config: &SoundEffectsConfig) -> Self {
+        let sink = config.enabled.then(|| {
+            let sink = Sink::connect_new(mixer);
+            sink.set_volume(config.volume.clamp(0.0, 1.0));
+            sink
+        });
+        Self { sink }
+    }
+
+    /// Play `effect` unless another effect is still sounding
+    pub fn play(&self, effect: Effect) {
+        let Some(sink) = self.sink.as_ref().filter(|sink| sink.empty()) else {
+            return;
+        };
+        for &(frequency, millis) in effect.tones() {
+            let mut tone = SineWave::new(frequency).take_duration(Duration::from_millis(millis));
+            // Decay to silence so the tone ends without a click
+            tone.set_filter_fadeout();
+            sink.append(tone.amplify(0.5));
+        }
+    }
+}
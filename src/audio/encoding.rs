@@ -0,0 +1,115 @@
+//! ICY metadata character encodings
+//!
+//! Stream titles should be UTF-8, but plenty of Icecast and Shoutcast servers
+//! pass through whatever the source client sent, typically Windows-1252
+//! (Latin-1) or Windows-1251 (Cyrillic). Titles that aren't valid UTF-8 are
+//! decoded with the station's configured encoding, or a guess between those
+//! two.
+
+use crate::config::MetadataEncodingConfig;
+use crate::station::Station;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1251, WINDOWS_1252};
+use tracing::warn;
+
+/// The encoding configured for `station`, `None` to detect it
+///
+/// Stations are looked up by ID, then by stream URL.
+pub fn for_station(
+    config: &MetadataEncodingConfig,
+    station: &Station,
+) -> Option<&'static Encoding> {
+    let label = config
+        .stations
+        .get(&station.id)
+        .or_else(|| config.stations.get(&station.url))
+        .unwrap_or(&config.default);
+    match lookup(label) {
+        Some(encoding) => encoding,
+        None => {
+            warn!(
+                "Unknown metadata encoding {:?}, detecting it instead",
+                label
+            );
+            None
+        }
+    }
+}
+
+/// Encoding for a WHATWG label such as `latin1` or `windows-1251`
+///
+/// `auto` gives `Some(None)`; unknown labels give `None`.
+pub fn lookup(label: &str) -> Option<Option<&'static Encoding>> {
+    if label.trim().eq_ignore_ascii_case("auto") {
+        return Some(None);
+    }
+    Encoding::for_label(label.trim().as_bytes()).map(Some)
+}
+
+/// Decode raw metadata with `encoding`, or detect it when `None`
+pub fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> String {
+    let encoding = encoding.unwrap_or_else(|| detect(bytes));
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+/// UTF-8 if the bytes are valid UTF-8, otherwise Windows-1251 when most
+/// letters are non-ASCII (as in Cyrillic text) and Windows-1252 when they
+/// are mostly ASCII with a few accents
+fn detect(bytes: &[u8]) -> &'static Encoding {
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    // Letters live at 0xC0-0xFF in both code pages
+    let high_letters = bytes.iter().filter(|byte| **byte >= 0xC0).count();
+    let ascii_letters = bytes
+        .iter()
+        .filter(|byte| byte.is_ascii_alphabetic())
+        .count();
+    if high_letters * 4 > ascii_letters {
+        WINDOWS_1251
+    } else {
+        WINDOWS_1252
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_legacy_encodings() {
+        let (cyrillic, _, _) = WINDOWS_1251.encode("Кино - Группа крови");
+        assert_eq!(decode(&cyrillic, None), "Кино - Группа крови");
+
+        let (latin, _, _) = WINDOWS_1252.encode("Émilie Simon - Désert");
+        assert_eq!(decode(&latin, None), "Émilie Simon - Désert");
+
+        assert_eq!(decode("Björk - Jóga".as_bytes(), None), "Björk - Jóga");
+    }
+
+    #[test]
+    fn configured_encoding_wins() {
+        let mut config = MetadataEncodingConfig::default();
+        config
+            .stations
+            .insert("russian".to_string(), "windows-1251".to_string());
+        let station = |id: &str| Station {
+            id: id.to_string(),
+            ..Station::from_url("http://example.com/stream")
+        };
+
+        assert_eq!(
+            for_station(&config, &station("russian")),
+            Some(WINDOWS_1251)
+        );
+        assert_eq!(for_station(&config, &station("other")), None);
+        config.default = "latin1".to_string();
+        assert_eq!(for_station(&config, &station("other")), Some(WINDOWS_1252));
+        config.default = "klingon".to_string();
+        assert_eq!(for_station(&config, &station("other")), None);
+
+        // A mostly-ASCII title forced to Windows-1251
+        let (title, _, _) = WINDOWS_1251.encode("DJ Groove - Привет");
+        assert_eq!(decode(&title, Some(WINDOWS_1251)), "DJ Groove - Привет");
+    }
+}
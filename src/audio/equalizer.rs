@@ -0,0 +1,272 @@
+//! Equalizer
+//!
+//! A few peaking filters applied to the decoded samples on their way to the
+//! sink. The preset can change while a station plays: the [`Equalizer`]
+//! source picks up the bands set through its [`EqualizerHandle`].
+
+use super::filter::Biquad;
+use rodio::Source;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Width of each band
+const Q: f32 = 1.0;
+/// Lowest and highest centre of the custom bands, in Hz
+const CUSTOM_RANGE: (f32, f32) = (60.0, 12_000.0);
+/// Custom band count limits
+const MIN_BANDS: usize = 3;
+const MAX_BANDS: usize = 10;
+
+/// One band: a boost or cut around a frequency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Band {
+    pub frequency: f32,
+    pub gain_db: f32,
+}
+
+/// Equalizer presets, cycled with `e`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EqPreset {
+    /// No equalization
+    #[default]
+    Flat,
+    BassBoost,
+    /// Clearer speech for talk stations
+    Voice,
+    /// The gains in `[equalizer] custom`
+    Custom,
+}
+
+impl EqPreset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Flat => "flat",
+            Self::BassBoost => "bass-boost",
+            Self::Voice => "voice",
+            Self::Custom => "custom",
+        }
+    }
+
+    /// The next preset, wrapping back to flat
+    pub fn next(self) -> Self {
+        match self {
+            Self::Flat => Self::BassBoost,
+            Self::BassBoost => Self::Voice,
+            Self::Voice => Self::Custom,
+            Self::Custom => Self::Flat,
+        }
+    }
+}
+
+impl fmt::Display for EqPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Equalizer settings, read from the `[equalizer]` table
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct EqualizerConfig {
+    #[serde(default)]
+    pub preset: EqPreset,
+    /// Gains in dB for 3 to 10 bands spread from 60 Hz to 12 kHz, used by the
+    /// custom preset
+    #[serde(default)]
+    pub custom: Vec<f32>,
+}
+
+impl EqualizerConfig {
+    /// Bands of the selected preset; empty when flat
+    pub fn bands(&self) -> Vec<Band> {
+        let band = |frequency, gain_db| Band { frequency, gain_db };
+        match self.preset {
+            EqPreset::Flat => Vec::new(),
+            EqPreset::BassBoost => vec![band(60.0, 6.0), band(150.0, 4.0), band(400.0, 1.0)],
+            EqPreset::Voice => vec![
+                band(100.0, -6.0),
+                band(300.0, -2.0),
+                band(2_500.0, 4.0),
+                band(5_000.0, 2.0),
+            ],
+            EqPreset::Custom => {
+                let gains = &self.custom[..self.custom.len().min(MAX_BANDS)];
+                if gains.len() < MIN_BANDS {
+                    return Vec::new();
+                }
+                // Spaced evenly on a log scale across the custom range
+                let (low, high) = CUSTOM_RANGE;
+                let step = (high / low).powf(1.0 / (gains.len() - 1) as f32);
+                gains
+                    .iter()
+                    .enumerate()
+                    .map(|(index, gain)| {
+                        band(low * step.powi(index as i32), gain.clamp(-12.0, 12.0))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Changes the bands of every [`Equalizer`] made from it, including one playing
+#[derive(Debug, Clone, Default)]
+pub struct EqualizerHandle {
+    bands: Arc<RwLock<Vec<Band>>>,
+    /// Bumped by every change so the sources know to rebuild their filters
+    generation: Arc<AtomicU64>,
+}
+
+impl EqualizerHandle {
+    pub fn new(bands: Vec<Band>) -> Self {
+        let handle = Self::default();
+        handle.set(bands);
+        handle
+    }
+
+    pub fn set(&self, bands: Vec<Band>) {
+        if let Ok(mut current) = self.bands.write() {
+            *current = bands;
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn bands(&self) -> Vec<Band> {
+        self.bands
+            .read()
+            .map(|bands| bands.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Source applying the handle's bands to interleaved samples
+pub struct Equalizer<S> {
+    source: S,
+    handle: EqualizerHandle,
+    /// Generation the filters were built for
+    generation: u64,
+    /// Filters for each channel, one per band
+    filters: Vec<Vec<Biquad>>,
+    /// Channel of the next sample
+    channel: usize,
+}
+
+impl<S: Source> Equalizer<S> {
+    pub fn new(source: S, handle: EqualizerHandle) -> Self {
+        let mut equalizer = Self {
+            source,
+            handle,
+            generation: 0,
+            filters: Vec::new(),
+            channel: 0,
+        };
+        equalizer.rebuild();
+        equalizer
+    }
+
+    fn rebuild(&mut self) {
+        self.generation = self.handle.generation.load(Ordering::SeqCst);
+        let sample_rate = self.source.sample_rate();
+        let bands: Vec<Biquad> = self
+            .handle
+            .bands()
+            .iter()
+            .filter(|band| band.gain_db != 0.0)
+            .map(|band| Biquad::peaking(sample_rate, band.frequency, Q, band.gain_db))
+            .collect();
+        self.filters = vec![bands; usize::from(self.source.channels().max(1))];
+    }
+}
+
+impl<S: Source> Iterator for Equalizer<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Switch presets between frames so the channels stay in step
+        if self.channel == 0 && self.handle.generation.load(Ordering::Relaxed) != self.generation {
+            self.rebuild();
+        }
+        let sample = self.source.next()?;
+        let channels = self.filters.len();
+        let filters = &mut self.filters[self.channel.min(channels - 1)];
+        self.channel = (self.channel + 1) % channels;
+        if filters.is_empty() {
+            return Some(sample);
+        }
+        let filtered = filters
+            .iter_mut()
+            .fold(sample, |sample, filter| filter.process(sample));
+        Some(filtered.clamp(-1.0, 1.0))
+    }
+}
+
+impl<S: Source> Source for Equalizer<S> {
+    fn current_span_len(&self) -> Option<usize> {
+        self.source.current_span_len()
+    }
+
+    fn channels(&self) -> rodio::ChannelCount {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> rodio::SampleRate {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::source::SineWave;
+
+    /// Peak level of a sine at `frequency` after the equalizer settles
+    fn peak(handle: &EqualizerHandle, frequency: f32) -> f32 {
+        Equalizer::new(SineWave::new(frequency).amplify(0.25), handle.clone())
+            .skip(48_000)
+            .take(4_800)
+            .fold(0.0, |peak: f32, sample| peak.max(sample.abs()))
+    }
+
+    #[test]
+    fn boosts_bands_and_follows_the_handle() {
+        let handle = EqualizerHandle::new(Vec::new());
+        assert!((peak(&handle, 60.0) - 0.25).abs() < 0.01);
+
+        let bass = EqualizerConfig {
+            preset: EqPreset::BassBoost,
+            ..Default::default()
+        };
+        handle.set(bass.bands());
+        // +6 dB and a little from the neighbouring band
+        assert!(peak(&handle, 60.0) > 0.5);
+        assert!((peak(&handle, 6_000.0) - 0.25).abs() < 0.02);
+
+        // A playing source picks up the change
+        let mut playing = Equalizer::new(SineWave::new(60.0).amplify(0.25), handle.clone());
+        handle.set(Vec::new());
+        let samples: Vec<f32> = playing.by_ref().take(4_800).collect();
+        assert!(samples.iter().all(|sample| sample.abs() <= 0.25 + 1e-4));
+    }
+
+    #[test]
+    fn spreads_custom_bands() {
+        let custom = |gains: &[f32]| EqualizerConfig {
+            preset: EqPreset::Custom,
+            custom: gains.to_vec(),
+        };
+        let bands = custom(&[3.0, 0.0, -20.0]).bands();
+        let frequencies: Vec<_> = bands.iter().map(|band| band.frequency.round()).collect();
+        assert_eq!(frequencies, [60.0, 849.0, 12_000.0]);
+        assert_eq!(bands[2].gain_db, -12.0);
+        assert!(custom(&[3.0, 3.0]).bands().is_empty());
+        assert_eq!(custom(&[1.0; 12]).bands().len(), 10);
+        assert_eq!(EqPreset::Custom.next(), EqPreset::Flat);
+    }
+}
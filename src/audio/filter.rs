@@ -0,0 +1,55 @@
+//! Biquad filters
+//!
+//! Second-order IIR sections shared by loudness measurement and the equalizer.
+
+/// Second-order IIR filter section
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b: [f32; 3],
+    a: [f32; 2],
+    /// Previous inputs and outputs
+    x: [f32; 2],
+    y: [f32; 2],
+}
+
+impl Biquad {
+    /// A section with coefficients normalized so that `a0` is 1
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Self {
+            b: b.map(|b| b as f32),
+            a: a.map(|a| a as f32),
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// Peaking filter raising or cutting `gain_db` around `frequency`
+    ///
+    /// From the Audio EQ Cookbook by Robert Bristow-Johnson.
+    pub fn peaking(sample_rate: u32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let rate = f64::from(sample_rate.max(1));
+        // Keep the centre below Nyquist so the filter stays stable
+        let frequency = f64::from(frequency).clamp(1.0, rate * 0.45);
+        let a = 10f64.powf(f64::from(gain_db) / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * frequency / rate;
+        let alpha = w0.sin() / (2.0 * f64::from(q.max(0.1)));
+        let a0 = 1.0 + alpha / a;
+        Self::new(
+            [
+                (1.0 + alpha * a) / a0,
+                -2.0 * w0.cos() / a0,
+                (1.0 - alpha * a) / a0,
+            ],
+            [-2.0 * w0.cos() / a0, (1.0 - alpha / a) / a0],
+        )
+    }
+
+    pub fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
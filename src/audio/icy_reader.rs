@@ -1,3 +1,6 @@
+use super::encoding;
+use super::recorder::Recorder;
+use encoding_rs::Encoding;
 use icy_metadata::{error::MetadataParseError, IcyMetadata};
 use std::collections::VecDeque;
 use std::fmt::Debug;
@@ -13,6 +16,12 @@ pub struct IcyMetadataReader<T> {
     next_metadata: usize,
     metadata_sizes: MetadataSizeQueue,
     current_pos: u64,
+    /// Encoding of the metadata, `None` to detect it
+    encoding: Option<&'static Encoding>,
+    /// Receives the audio bytes as they are first read
+    recorder: Option<Recorder>,
+    /// Audio position up to which bytes went to the recorder
+    recorded_until: u64,
     on_metadata_read: Box<dyn Fn(Result<IcyMetadata, MetadataParseError>) + Send + Sync>,
 }
 
@@ -25,13 +34,20 @@ impl<T> Debug for IcyMetadataReader<T> {
             .field("next_metadata", &self.next_metadata)
             .field("metadata_sizes", &self.metadata_sizes)
             .field("current_pos", &self.current_pos)
+            .field("encoding", &self.encoding)
+            .field("recorder", &self.recorder)
             .field("on_metadata_read", &"<on_metadata_read>")
             .finish()
     }
 }
 
 impl<T> IcyMetadataReader<T> {
-    pub fn new<F>(inner: T, metadata_interval: Option<NonZeroUsize>, on_metadata_read: F) -> Self
+    pub fn new<F>(
+        inner: T,
+        metadata_interval: Option<NonZeroUsize>,
+        encoding: Option<&'static Encoding>,
+        on_metadata_read: F,
+    ) -> Self
     where
         F: Fn(Result<IcyMetadata, MetadataParseError>) + Send + Sync + 'static,
     {
@@ -42,10 +58,32 @@ impl<T> IcyMetadataReader<T> {
             next_metadata: metadata_interval.unwrap_or(0),
             metadata_sizes: MetadataSizeQueue::default(),
             current_pos: 0,
+            encoding,
+            recorder: None,
+            recorded_until: 0,
             on_metadata_read: Box::new(on_metadata_read),
         }
     }
 
+    /// Copy the audio, without metadata, to `recorder`
+    pub fn with_recorder(mut self, recorder: Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Pass `bytes`, read at the current position, to the recorder. Bytes read
+    /// again after seeking back were recorded the first time.
+    fn record(&mut self, bytes: &[u8]) {
+        let end = self.current_pos + bytes.len() as u64;
+        if let Some(recorder) = &self.recorder {
+            if end > self.recorded_until {
+                let skip = self.recorded_until.saturating_sub(self.current_pos) as usize;
+                recorder.write(&bytes[skip..]);
+            }
+        }
+        self.recorded_until = self.recorded_until.max(end);
+    }
+
     fn read_metadata(&mut self) -> io::Result<()>
     where
         T: Read,
@@ -61,14 +99,10 @@ impl<T> IcyMetadataReader<T> {
 
         let mut metadata = vec![0_u8; length];
         self.inner.read_exact(&mut metadata)?;
-        let parsed = String::from_utf8(metadata)
-            .map_err(MetadataParseError::InvalidUtf8)
-            .and_then(|value| {
-                value
-                    .trim_end_matches(char::from(0))
-                    .parse::<IcyMetadata>()
-                    .map_err(MetadataParseError::Empty)
-            });
+        let parsed = encoding::decode(&metadata, self.encoding)
+            .trim_end_matches(char::from(0))
+            .parse::<IcyMetadata>()
+            .map_err(MetadataParseError::Empty);
         (self.on_metadata_read)(parsed);
         Ok(())
     }
@@ -81,7 +115,10 @@ impl<T: Read> Read for IcyMetadataReader<T> {
         }
 
         let Some(metadata_interval) = self.metadata_interval else {
-            return self.inner.read(buffer);
+            let read = self.inner.read(buffer)?;
+            self.record(&buffer[..read]);
+            self.current_pos += read as u64;
+            return Ok(read);
         };
 
         if self.next_metadata == 0 {
@@ -91,6 +128,7 @@ impl<T: Read> Read for IcyMetadataReader<T> {
 
         let limit = buffer.len().min(self.next_metadata);
         let read = self.inner.read(&mut buffer[..limit])?;
+        self.record(&buffer[..read]);
         self.next_metadata -= read;
         self.current_pos += read as u64;
         Ok(read)
@@ -100,7 +138,8 @@ impl<T: Read> Read for IcyMetadataReader<T> {
 impl<T: Read + Seek> Seek for IcyMetadataReader<T> {
     fn seek(&mut self, seek_from: SeekFrom) -> io::Result<u64> {
         let Some(metadata_interval) = self.metadata_interval else {
-            return self.inner.seek(seek_from);
+            self.current_pos = self.inner.seek(seek_from)?;
+            return Ok(self.current_pos);
         };
 
         let (requested_change, requested_pos) = match seek_from {
@@ -257,7 +296,7 @@ mod tests {
             max_read: 7_000,
         };
         let mut reader =
-            IcyMetadataReader::new(inner, NonZeroUsize::new(METAINT), move |metadata| {
+            IcyMetadataReader::new(inner, NonZeroUsize::new(METAINT), None, move |metadata| {
                 if let Ok(metadata) = metadata {
                     captured_titles
                         .lock()
@@ -274,4 +313,40 @@ mod tests {
         assert!(audio[METAINT..].iter().all(|byte| *byte == 2));
         assert_eq!(*titles.lock().unwrap(), vec!["Deep ambient"]);
     }
+
+    #[test]
+    fn passes_streams_without_metadata_through() {
+        let stream: Vec<u8> = (0..=255).cycle().take(40_000).collect();
+        let inner = ShortReader {
+            inner: Cursor::new(stream.clone()),
+            max_read: 7_000,
+        };
+        let mut reader = IcyMetadataReader::new(inner, None, None, |_| {
+            panic!("a stream without an interval has no metadata")
+        });
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+        assert_eq!(audio, stream);
+    }
+
+    #[test]
+    fn records_audio_once_across_seeks() {
+        let dir = std::env::temp_dir().join(format!("somars-icy-record-{}", std::process::id()));
+        let recorder = Recorder::default();
+        let path = recorder.start(&dir, "test").unwrap();
+
+        let stream: Vec<u8> = (0..=255).collect();
+        let mut reader = IcyMetadataReader::new(Cursor::new(stream.clone()), None, None, |_| {})
+            .with_recorder(recorder.clone());
+        let mut head = [0; 64];
+        reader.read_exact(&mut head).unwrap();
+        // Probing rewinds to the start before decoding
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+        recorder.stop();
+
+        assert_eq!(std::fs::read(&path).unwrap(), stream);
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,231 @@
+//! Loudness normalization
+//!
+//! Stations are mastered at very different levels, so switching from a quiet
+//! ambient channel to a loud one can be startling. The normalizer measures the
+//! short-term loudness of the decoded stream the way ITU-R BS.1770 does (a
+//! K-weighting filter, then mean square over a window of a few seconds) and
+//! moves the gain toward a target loudness, ramping it across each chunk.
+
+use super::filter::Biquad;
+use serde::{Deserialize, Serialize};
+
+/// Loudness normalization settings, read from the `[loudness]` table
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Loudness to aim for, in LUFS
+    #[serde(default = "default_target_lufs")]
+    pub target_lufs: f32,
+    /// Seconds of audio the loudness is measured over
+    #[serde(default = "default_window_seconds")]
+    pub window_seconds: f32,
+    /// Most the gain may rise or fall, in decibels
+    #[serde(default = "default_max_gain_db")]
+    pub max_gain_db: f32,
+}
+
+fn default_target_lufs() -> f32 {
+    -18.0
+}
+
+fn default_window_seconds() -> f32 {
+    3.0
+}
+
+fn default_max_gain_db() -> f32 {
+    12.0
+}
+
+impl Default for LoudnessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_lufs: default_target_lufs(),
+            window_seconds: default_window_seconds(),
+            max_gain_db: default_max_gain_db(),
+        }
+    }
+}
+
+/// Below this the stream is treated as silence and the gain is left alone
+const SILENCE_LUFS: f32 = -70.0;
+
+/// K-weighting for one channel: a high shelf for the head, then a high pass
+///
+/// Coefficients are derived for any sample rate as in libebur128.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate.max(1));
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad::new(
+        [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad::new(
+        [1.0, -2.0, 1.0],
+        [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    );
+
+    [shelf, high_pass]
+}
+
+/// Adjusts decoded samples toward the target loudness
+#[derive(Debug)]
+pub struct Normalizer {
+    config: LoudnessConfig,
+    sample_rate: u32,
+    /// Filters per channel
+    filters: Vec<[Biquad; 2]>,
+    /// Smoothed K-weighted mean square summed over channels, `None` until measured
+    mean_square: Option<f32>,
+    /// Linear gain at the end of the last chunk
+    gain: f32,
+}
+
+impl Normalizer {
+    /// A normalizer for interleaved samples, `None` when normalization is off
+    pub fn new(config: LoudnessConfig, sample_rate: u32, channels: u16) -> Option<Self> {
+        config.enabled.then(|| Self {
+            config,
+            sample_rate,
+            filters: vec![k_weighting(sample_rate); usize::from(channels.max(1))],
+            mean_square: None,
+            gain: 1.0,
+        })
+    }
+
+    /// Short-term loudness measured so far, in LUFS
+    #[cfg(test)]
+    fn loudness(&self) -> Option<f32> {
+        self.mean_square.map(lufs)
+    }
+
+    /// Measure `chunk` and apply the gain, ramping from the previous chunk's
+    pub fn process(&mut self, chunk: &mut [f32]) {
+        let channels = self.filters.len();
+        let frames = chunk.len() / channels;
+        if frames == 0 {
+            return;
+        }
+
+        let mut sum = 0.0;
+        let mut unweighted = 0.0;
+        let mut peak: f32 = 0.0;
+        for frame in chunk.chunks_exact(channels) {
+            for (sample, [shelf, high_pass]) in frame.iter().zip(self.filters.iter_mut()) {
+                let weighted = high_pass.process(shelf.process(*sample));
+                sum += weighted * weighted;
+                unweighted += sample * sample;
+                peak = peak.max(sample.abs());
+            }
+        }
+        let chunk_mean_square = sum / frames as f32;
+
+        // Silence between tracks would otherwise pull the gain up. It's judged
+        // on the samples themselves, as the filters ring on after the music stops.
+        let mut target = self.gain;
+        if lufs(unweighted / frames as f32) > SILENCE_LUFS {
+            // Exponential smoothing with the window as its time constant
+            let seconds = frames as f32 / self.sample_rate.max(1) as f32;
+            let smoothing = 1.0 - (-seconds / self.config.window_seconds.max(0.1)).exp();
+            let mean_square = match self.mean_square {
+                Some(previous) => previous + smoothing * (chunk_mean_square - previous),
+                None => chunk_mean_square,
+            };
+            self.mean_square = Some(mean_square);
+
+            let limit = self.config.max_gain_db.abs();
+            let db = (self.config.target_lufs - lufs(mean_square)).clamp(-limit, limit);
+            target = 10f32.powf(db / 20.0);
+        }
+        // Never push the chunk's peak past full scale
+        if peak > 0.0 {
+            target = target.min(1.0 / peak);
+        }
+
+        let start = self.gain;
+        for (index, frame) in chunk.chunks_exact_mut(channels).enumerate() {
+            let gain = start + (target - start) * (index + 1) as f32 / frames as f32;
+            for sample in frame {
+                *sample *= gain;
+            }
+        }
+        self.gain = target;
+    }
+}
+
+/// Loudness in LUFS of a K-weighted mean square summed over channels
+fn lufs(mean_square: f32) -> f32 {
+    -0.691 + 10.0 * mean_square.max(f32::MIN_POSITIVE).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Interleaved stereo sine at `amplitude`
+    fn sine(amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|n| {
+                let sample =
+                    amplitude * (2.0 * std::f32::consts::PI * 1000.0 * n as f32 / 48_000.0).sin();
+                [sample, sample]
+            })
+            .collect()
+    }
+
+    fn normalizer() -> Normalizer {
+        let config = LoudnessConfig {
+            enabled: true,
+            ..LoudnessConfig::default()
+        };
+        Normalizer::new(config, 48_000, 2).unwrap()
+    }
+
+    #[test]
+    fn measures_bs1770_loudness() {
+        // A full-scale 1 kHz sine in both channels reads 0 LUFS, so one at
+        // -20 dBFS reads -20
+
+        let mut normalizer = normalizer();
+        normalizer.process(&mut sine(0.1, 48_000));
+        let loudness = normalizer.loudness().unwrap();
+        assert!((loudness + 20.0).abs() < 0.2, "{loudness}");
+    }
+
+    #[test]
+    fn moves_loud_and_quiet_streams_toward_the_target() {
+        let mut loud = normalizer();
+        let mut quiet = normalizer();
+        for _ in 0..10 {
+            loud.process(&mut sine(0.5, 8_192));
+            quiet.process(&mut sine(0.05, 8_192));
+        }
+        assert!(loud.gain < 1.0);
+        assert!(quiet.gain > 1.0);
+        let mut chunk = sine(0.5, 48_000);
+        loud.process(&mut chunk);
+        let mut check = normalizer();
+        check.process(&mut chunk);
+        assert!((check.loudness().unwrap() + 18.0).abs() < 0.5);
+
+        // Silence leaves the gain where it was
+        let gain = quiet.gain;
+        quiet.process(&mut vec![0.0; 8_192]);
+        assert_eq!(quiet.gain, gain);
+        assert!(Normalizer::new(LoudnessConfig::default(), 48_000, 2).is_none());
+    }
+}
@@ -1,25 +1,28 @@
 //! Audio manager - High-level audio API
 //!
-//! Provides a centralized interface for audio playback control.
+//! Provides a centralized interface for audio playback control. The sink and
+//! the streaming task live in the [`super::player`] task.
 
 #![allow(dead_code)]
 
 use super::types::{AudioResult, AudioState};
-use rodio::Sink;
-use std::sync::{Arc, Mutex};
 
 /// Audio manager - Centralized control for audio playback
 pub struct AudioManager {
-    handle: Option<tokio::task::JoinHandle<AudioResult<()>>>,
     state: AudioState,
     pub(crate) current_station: Option<String>,
 }
 
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AudioManager {
-    /// Create a new AudioManager (for internal use)
-    pub(crate) fn new() -> Self {
+    /// Create a new AudioManager
+    pub fn new() -> Self {
         Self {
-            handle: None,
             state: AudioState::Stopped,
             current_station: None,
         }
@@ -36,41 +39,28 @@ impl AudioManager {
     }
 
     /// Set the audio state
-    pub(crate) fn set_state(&mut self, state: AudioState) {
+    pub fn set_state(&mut self, state: AudioState) {
         self.state = state;
     }
 
     /// Set the current station
-    pub(crate) fn set_current_station(&mut self, station: String) {
+    pub fn set_current_station(&mut self, station: String) {
         self.current_station = Some(station);
     }
 
     /// Clear the current station
-    pub(crate) fn clear_current_station(&mut self) {
+    pub fn clear_current_station(&mut self) {
         self.current_station = None;
     }
 
-    /// Set the playback handle
-    pub(crate) fn set_handle(&mut self, handle: tokio::task::JoinHandle<AudioResult<()>>) {
-        self.handle = Some(handle);
-    }
-
-    /// Take the playback handle (consuming it)
-    pub(crate) fn take_handle(&mut self) -> Option<tokio::task::JoinHandle<AudioResult<()>>> {
-        self.handle.take()
-    }
-
     /// Check if there's an active playback
     pub fn is_active(&self) -> bool {
-        self.handle.is_some()
+        self.current_station.is_some()
     }
 }
 
 /// Extension trait for integrating AudioManager with existing App
 pub trait AudioApp {
-    /// Get the audio sink
-    fn sink(&self) -> Option<Arc<Mutex<Sink>>>;
-
     /// Get the audio manager
     fn audio_manager(&self) -> &AudioManager;
 
@@ -5,23 +5,93 @@
 
 #![allow(dead_code)]
 
+use super::probe::Format;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
 /// Metadata event extracted from audio stream
 #[derive(Debug, Clone)]
 pub enum MetadataEvent {
     /// New track information (title - artist format)
     Track { station: String, title: String },
-    /// Bitrate information
-    Bitrate(u64),
+    /// What the server says about the stream, once connected
+    StreamInfo(StreamInfo),
     /// Stream started
     StreamStarted(String),
     /// Stream error
     Error(String),
 }
 
+/// Stream details from the response headers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StreamInfo {
+    /// Title of the station playing the stream
+    pub station: String,
+    /// `icy-br`, in kbps
+    pub bitrate: Option<u32>,
+    /// `Content-Type`, such as `audio/mpeg`
+    pub content_type: Option<String>,
+    /// `icy-name`, the name the server gives the stream
+    pub name: Option<String>,
+    /// `icy-url`, the station's website
+    pub url: Option<String>,
+    /// `Server`, such as `Icecast 2.4.4`
+    pub server: Option<String>,
+}
+
+impl StreamInfo {
+    pub fn from_headers(station: &str, headers: &HeaderMap, bitrate: Option<u32>) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        Self {
+            station: station.to_string(),
+            bitrate,
+            content_type: header("content-type"),
+            name: header("icy-name"),
+            url: header("icy-url"),
+            server: header("server"),
+        }
+    }
+
+    /// Codec name the content type stands for, such as `MP3`
+    pub fn codec(&self) -> Option<&'static str> {
+        self.content_type
+            .as_deref()
+            .and_then(Format::from_claim)
+            .map(Format::name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn reads_stream_info_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", "audio/aacp".parse().unwrap());
+        headers.insert(
+            "icy-name",
+            "Groove Salad: a nicely chilled plate".parse().unwrap(),
+        );
+        headers.insert("icy-url", " ".parse().unwrap());
+        let info = StreamInfo::from_headers("Groove Salad", &headers, Some(64));
+        assert_eq!(info.codec(), Some("AAC"));
+        assert_eq!(info.bitrate, Some(64));
+        assert_eq!(
+            info.name.as_deref(),
+            Some("Groove Salad: a nicely chilled plate")
+        );
+        assert_eq!(info.url, None);
+        assert_eq!(info.server, None);
+    }
+
     #[test]
     fn test_metadata_event_creation() {
         let event = MetadataEvent::Track {
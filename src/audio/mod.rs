@@ -2,24 +2,45 @@
 //!
 //! This module handles all audio playback functionality including:
 //! - Stream downloading and buffering
-//! - Audio playback control
-//! - ICY metadata extraction
-//! - Volume control
+//! - Audio playback control, run by the player task
+//! - ICY metadata extraction, decoding and title cleanup
+//! - Volume control, the perceptual volume curve and loudness normalization
+//! - The equalizer
+//! - Output backend selection
+//! - Recording the stream to a file
+//! - Rewinding through the time shift of what played
+//! - Measuring the download rate and data used
+//! - UI sound effects
 //! - Error recovery and retry logic
 //!
 //! The main entry point is the [`AudioManager`] struct which provides
 //! a high-level API for audio operations.
 
+pub mod effects;
+pub mod encoding;
+pub mod equalizer;
+pub mod filter;
 mod icy_reader;
+pub mod loudness;
 pub mod manager;
 pub mod metadata;
+pub mod output;
 pub mod playback;
+pub mod player;
+pub mod probe;
+pub mod quality;
+pub mod recorder;
 pub mod recovery;
+mod silence;
 pub mod stream;
+pub mod throughput;
+pub mod time_shift;
+pub mod title_rules;
+pub mod transition;
 pub mod types;
+pub mod volume;
 
 pub use manager::AudioManager;
 
 // Re-export common types and functions
-pub use metadata::MetadataEvent;
-pub use playback::start_playback;
+pub use metadata::{MetadataEvent, StreamInfo};
@@ -0,0 +1,379 @@
+//! Audio output backends
+//!
+//! Playback always feeds a rodio mixer; the backend decides how the device
+//! stream underneath it is opened. When the device goes away (headphones
+//! unplugged, Bluetooth dropping) the stream reports errors and falls silent;
+//! [`Output`] notices and can open a new stream on the new default device.
+
+use crate::error::AppError;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::{self, BufferSize, SampleFormat};
+use rodio::mixer::Mixer;
+use rodio::{OutputStream, OutputStreamBuilder};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+/// How the audio output device is opened
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioBackend {
+    /// Let rodio pick the default device and stream format (portable default)
+    #[default]
+    Rodio,
+    /// Open the default device directly through cpal in its native format
+    ///
+    /// `pipewire` is read as this for configs from before `audio_hosts`,
+    /// which [`crate::config::Config`] turns into the `pipewire` host.
+    #[serde(alias = "pipewire")]
+    Cpal,
+}
+
+/// Sound system to play through
+///
+/// PulseAudio and PipeWire are reached through their ALSA plugins, which show
+/// up as ALSA devices named `pulse` and `pipewire`; cpal has no native client
+/// for either, so the sound server sees an ALSA client named by
+/// [`set_stream_properties`]. JACK needs a build with the `jack` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioHost {
+    /// The ALSA default device
+    Alsa,
+    /// PulseAudio's ALSA plugin
+    Pulse,
+    /// PipeWire's ALSA plugin, not a native PipeWire stream
+    Pipewire,
+    Jack,
+}
+
+impl AudioHost {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Alsa => "alsa",
+            Self::Pulse => "pulse",
+            Self::Pipewire => "pipewire",
+            Self::Jack => "jack",
+        }
+    }
+}
+
+impl fmt::Display for AudioHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AudioHost {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "alsa" => Ok(Self::Alsa),
+            "pulse" | "pulseaudio" => Ok(Self::Pulse),
+            "pipewire" => Ok(Self::Pipewire),
+            "jack" => Ok(Self::Jack),
+            _ => Err(format!(
+                "Unknown audio host: {} (expected alsa, pulse, pipewire or jack)",
+                value
+            )),
+        }
+    }
+}
+
+/// The output stream playback goes through
+pub struct Output {
+    stream: OutputStream,
+    backend: AudioBackend,
+    /// Hosts to try in order; empty leaves the choice to the backend
+    hosts: Vec<AudioHost>,
+    buffer_frames: u32,
+    /// Set by the stream's error callback
+    lost: Arc<AtomicBool>,
+}
+
+impl Output {
+    /// Open the output stream for `backend` on the first of `hosts` that works
+    pub fn open(
+        backend: AudioBackend,
+        hosts: &[AudioHost],
+        buffer_frames: u32,
+    ) -> Result<Self, AppError> {
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = open(backend, hosts, buffer_frames, lost.clone())?;
+        Ok(Self {
+            stream,
+            backend,
+            hosts: hosts.to_vec(),
+            buffer_frames,
+            lost,
+        })
+    }
+
+    pub fn mixer(&self) -> &Mixer {
+        self.stream.mixer()
+    }
+
+    /// Whether the stream has failed, usually because its device went away
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    /// Replace the stream with one on the current default device
+    ///
+    /// Sinks connected to the old stream's mixer stay silent; connect new ones
+    /// to [`Output::mixer`].
+    pub fn reopen(&mut self) -> Result<(), AppError> {
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = open(self.backend, &self.hosts, self.buffer_frames, lost.clone())?;
+        let mut old = std::mem::replace(&mut self.stream, stream);
+        old.log_on_drop(false);
+        drop(old);
+        self.lost = lost;
+        Ok(())
+    }
+}
+
+fn open(
+    backend: AudioBackend,
+    hosts: &[AudioHost],
+    buffer_frames: u32,
+    lost: Arc<AtomicBool>,
+) -> Result<OutputStream, AppError> {
+    if !hosts.is_empty() {
+        return open_hosts(backend, hosts, buffer_frames, lost);
+    }
+
+    let builder = match backend {
+        AudioBackend::Rodio => OutputStreamBuilder::from_default_device()
+            .map(|builder| builder.with_buffer_size(BufferSize::Fixed(buffer_frames))),
+        AudioBackend::Cpal => cpal_builder(buffer_frames),
+    };
+
+    builder
+        .and_then(|builder| open_stream(builder, lost))
+        .map_err(|e| {
+            AppError::Audio(format!("Failed to initialize audio output stream: {}. This could be due to:\n\
+                                     - No audio output device available\n\
+                                     - Audio device is busy or locked by another application\n\
+                                     - Missing audio system dependencies (e.g., ALSA on Linux)\n\
+                                     Try checking your system's audio settings or restarting your audio service.", e))
+        })
+}
+
+/// Try `hosts` in order, saying why each one that failed did
+fn open_hosts(
+    backend: AudioBackend,
+    hosts: &[AudioHost],
+    buffer_frames: u32,
+    lost: Arc<AtomicBool>,
+) -> Result<OutputStream, AppError> {
+    let mut failures = Vec::new();
+    for &host in hosts {
+        let opened = host_device(host).and_then(|device| {
+            let builder = match backend {
+                AudioBackend::Rodio => OutputStreamBuilder::from_device(device)
+                    .map(|builder| builder.with_buffer_size(BufferSize::Fixed(buffer_frames))),
+                AudioBackend::Cpal => device_builder(device, buffer_frames),
+            };
+            builder
+                .and_then(|builder| open_stream(builder, lost.clone()))
+                .map_err(|e| e.to_string())
+        });
+        match opened {
+            Ok(stream) => {
+                info!("Playing through {}", host);
+                return Ok(stream);
+            }
+            Err(reason) => {
+                warn!("Audio host {} is not usable: {}", host, reason);
+                failures.push(format!("- {}: {}", host, reason));
+            }
+        }
+    }
+    Err(AppError::Audio(format!(
+        "None of the audio hosts in audio_hosts could be opened:\n{}",
+        failures.join("\n")
+    )))
+}
+
+/// The output device `host` plays to
+fn host_device(host: AudioHost) -> Result<cpal::Device, String> {
+    match host {
+        AudioHost::Jack => {
+            let id = cpal::available_hosts()
+                .into_iter()
+                .find(|id| id.name() == "JACK")
+                .ok_or(
+                    "this build of somars has no JACK support; build it with `--features jack`",
+                )?;
+            cpal::host_from_id(id)
+                .map_err(|_| "the JACK server is not running".to_string())?
+                .default_output_device()
+                .ok_or_else(|| "JACK has no output ports".to_string())
+        }
+        _ if !cfg!(target_os = "linux") => Err("only available on Linux".to_string()),
+        AudioHost::Alsa => cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "ALSA has no default output device".to_string()),
+        AudioHost::Pulse | AudioHost::Pipewire => alsa_device(host.as_str()).ok_or_else(|| {
+            format!(
+                "no `{}` ALSA device; is the server running with its ALSA plugin installed?",
+                host
+            )
+        }),
+    }
+}
+
+/// Open `builder`'s stream, flagging `lost` when it fails later
+fn open_stream(
+    builder: OutputStreamBuilder,
+    lost: Arc<AtomicBool>,
+) -> Result<OutputStream, rodio::StreamError> {
+    builder
+        .with_error_callback(move |err| {
+            // A failed stream doesn't recover by itself. Most backends say the
+            // device is gone; ALSA reports the failed writes instead, over and
+            // over, so only the first error is logged.
+            if !lost.swap(true, Ordering::Relaxed) {
+                error!("Audio output stream error: {}", err);
+            }
+        })
+        .open_stream()
+}
+
+fn cpal_builder(buffer_frames: u32) -> Result<OutputStreamBuilder, rodio::StreamError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or(rodio::StreamError::NoDevice)?;
+    device_builder(device, buffer_frames)
+}
+
+/// Builder for `device` in its native format, preferring f32 samples at the
+/// default rate so cpal does no conversion of its own
+fn device_builder(
+    device: cpal::Device,
+    buffer_frames: u32,
+) -> Result<OutputStreamBuilder, rodio::StreamError> {
+    let default = device
+        .default_output_config()
+        .map_err(rodio::StreamError::DefaultStreamConfigError)?;
+    let native = device
+        .supported_output_configs()
+        .ok()
+        .and_then(|mut configs| {
+            configs.find(|config| {
+                config.sample_format() == SampleFormat::F32
+                    && config.channels() == default.channels()
+                    && config.min_sample_rate() <= default.sample_rate()
+                    && config.max_sample_rate() >= default.sample_rate()
+            })
+        })
+        .map(|config| config.with_sample_rate(default.sample_rate()))
+        .unwrap_or(default);
+    info!(
+        "Opening audio device {} at {} Hz, {} channels, {:?}",
+        device.name().unwrap_or_default(),
+        native.sample_rate().0,
+        native.channels(),
+        native.sample_format()
+    );
+
+    Ok(OutputStreamBuilder::default()
+        .with_device(device)
+        .with_supported_config(&native)
+        .with_buffer_size(BufferSize::Fixed(buffer_frames)))
+}
+
+/// The ALSA output device called `name`, such as a sound server's plugin
+#[cfg(target_os = "linux")]
+fn alsa_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn alsa_device(_name: &str) -> Option<cpal::Device> {
+    None
+}
+
+/// Stream properties shown by desktop mixers and used for routing
+const APP_NAME: &str = "somars";
+const ICON_NAME: &str = "multimedia-player";
+const MEDIA_NAME: &str = "SomaFM radio";
+
+/// Describe the stream to PulseAudio and PipeWire
+///
+/// ALSA output goes through the PulseAudio or PipeWire ALSA plugin on most
+/// desktops. Both read client properties from the environment when the PCM is
+/// opened, so setting them names the stream in mixers and tags it with the
+/// music role. Variables already set by the user are left alone.
+///
+/// Call it at the start of `main`, before the async runtime or any other
+/// thread is started, as changing the environment races with threads reading
+/// it.
+#[cfg(target_os = "linux")]
+pub fn set_stream_properties() {
+    let pulse = format!(
+        "application.name='{}' application.id='{}' application.icon_name='{}' media.name='{}' media.role='music'",
+        APP_NAME, APP_NAME, ICON_NAME, MEDIA_NAME
+    );
+    let pipewire = format!(
+        "{{ application.name = \"{}\" application.id = \"{}\" application.icon-name = \"{}\" media.name = \"{}\" media.role = \"Music\" media.category = \"Playback\" }}",
+        APP_NAME, APP_NAME, ICON_NAME, MEDIA_NAME
+    );
+    for (key, value) in [("PULSE_PROP", pulse), ("PIPEWIRE_PROPS", pipewire)] {
+        if std::env::var_os(key).is_none() {
+            std::env::set_var(key, value);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_stream_properties() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Wrapper {
+        backend: AudioBackend,
+    }
+
+    #[test]
+    fn parses_backend_names() {
+        for (name, backend) in [
+            ("rodio", AudioBackend::Rodio),
+            ("cpal", AudioBackend::Cpal),
+            ("pipewire", AudioBackend::Cpal),
+        ] {
+            let parsed: Wrapper = toml::from_str(&format!("backend = \"{}\"", name)).unwrap();
+            assert_eq!(parsed.backend, backend);
+        }
+        assert!(toml::from_str::<Wrapper>("backend = \"jack\"").is_err());
+    }
+
+    #[test]
+    fn parses_host_names() {
+        assert_eq!("PipeWire".parse(), Ok(AudioHost::Pipewire));
+        assert_eq!("pulseaudio".parse(), Ok(AudioHost::Pulse));
+        assert!("coreaudio".parse::<AudioHost>().is_err());
+
+        #[derive(Deserialize)]
+        struct Hosts {
+            audio_hosts: Vec<AudioHost>,
+        }
+        let parsed: Hosts =
+            toml::from_str(r#"audio_hosts = ["jack", "pipewire", "alsa"]"#).unwrap();
+        assert_eq!(
+            parsed.audio_hosts,
+            [AudioHost::Jack, AudioHost::Pipewire, AudioHost::Alsa]
+        );
+    }
+}
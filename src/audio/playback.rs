@@ -5,20 +5,27 @@
 
 #![allow(dead_code)]
 
+use super::equalizer::Equalizer;
 use super::icy_reader::IcyMetadataReader;
-use super::metadata::MetadataEvent;
+use super::loudness::Normalizer;
+use super::metadata::{MetadataEvent, StreamInfo};
+use super::probe::{self, Format, Probe};
+use super::quality::{QualityChange, QualityMonitor};
 use super::recovery::{retry_with_backoff, RecoveryConfig};
+use super::silence::SilenceDetector;
 use super::stream::{
     calculate_prefetch_bytes, create_icy_client, parse_bitrate_with_fallback, parse_url,
     StreamConfig,
 };
+use super::time_shift::TimeShift;
 use super::types::{AudioError, AudioResult};
 use crate::action::Action;
 use crate::i18n::t;
+use crate::station::Station;
 use crate::{HistoryMessage, MessageType, PlaybackState};
 use rodio::{Decoder, Sink, Source};
 use std::num::NonZeroUsize;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -29,6 +36,9 @@ use stream_download::storage::memory::MemoryStorageProvider;
 use stream_download::{Settings, StreamDownload};
 use tokio::sync::mpsc;
 
+/// Sound played by an attempt after which earlier restarts are forgotten
+const STABLE_PLAYBACK: Duration = Duration::from_secs(60);
+
 /// Handle for managing an active audio playback
 pub struct PlaybackHandle {
     pub sink: Arc<Mutex<Sink>>,
@@ -105,6 +115,12 @@ struct PlaybackBufferStats {
     queued_samples: AtomicUsize,
     starving: AtomicBool,
     finished: AtomicBool,
+    /// Level of the chunk currently being played (`f32` bits, 0.0 to 1.0)
+    level: AtomicU32,
+    /// Number of times the output ran out of decoded samples
+    underruns: AtomicU32,
+    /// How long the decoded stream has been silent, in milliseconds
+    silent_ms: AtomicU64,
 }
 
 impl PlaybackBufferStats {
@@ -113,8 +129,19 @@ impl PlaybackBufferStats {
             queued_samples: AtomicUsize::new(0),
             starving: AtomicBool::new(false),
             finished: AtomicBool::new(false),
+            level: AtomicU32::new(0),
+            underruns: AtomicU32::new(0),
+            silent_ms: AtomicU64::new(0),
         }
     }
+
+    fn level(&self) -> f32 {
+        f32::from_bits(self.level.load(Ordering::SeqCst))
+    }
+
+    fn silence(&self) -> Duration {
+        Duration::from_millis(self.silent_ms.load(Ordering::SeqCst))
+    }
 }
 
 struct BufferedStreamSource {
@@ -124,6 +151,7 @@ struct BufferedStreamSource {
     channels: rodio::ChannelCount,
     sample_rate: rodio::SampleRate,
     stats: Arc<PlaybackBufferStats>,
+    time_shift: TimeShift,
 }
 
 impl BufferedStreamSource {
@@ -137,8 +165,19 @@ impl BufferedStreamSource {
         let sample_rate = source.sample_rate();
         let stats_for_thread = stats.clone();
         let chunk_size = config.pcm_chunk_samples.max(channels as usize * 1024);
-
-        thread::spawn(move || produce_samples(source, tx, stats_for_thread, chunk_size));
+        let normalizer = Normalizer::new(config.loudness, sample_rate, channels);
+        let silence = SilenceDetector::new(sample_rate, channels);
+
+        thread::spawn(move || {
+            produce_samples(
+                source,
+                tx,
+                stats_for_thread,
+                chunk_size,
+                normalizer,
+                silence,
+            )
+        });
 
         (
             Self {
@@ -148,10 +187,25 @@ impl BufferedStreamSource {
                 channels,
                 sample_rate,
                 stats: stats.clone(),
+                time_shift: TimeShift::new(
+                    config.time_shift.clone(),
+                    config.time_shift_seconds,
+                    sample_rate,
+                    channels,
+                ),
             },
             stats,
         )
     }
+
+    /// Take the next decoded chunk off the channel
+    fn receive(&mut self) -> Result<Vec<f32>, TryRecvError> {
+        let chunk = self.rx.try_recv()?;
+        self.stats
+            .queued_samples
+            .fetch_sub(chunk.len(), Ordering::SeqCst);
+        Ok(chunk)
+    }
 }
 
 impl Iterator for BufferedStreamSource {
@@ -159,11 +213,25 @@ impl Iterator for BufferedStreamSource {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current_index >= self.current_chunk.len() {
-            match self.rx.try_recv() {
+            let played = std::mem::take(&mut self.current_chunk);
+            self.time_shift.played(played);
+            self.time_shift.apply_jump();
+            // Behind live, the stream keeps arriving into the queue so the
+            // decoder and the connection don't stall
+            if self.time_shift.is_behind() {
+                while let Ok(chunk) = self.receive() {
+                    self.time_shift.queue(chunk);
+                }
+            }
+            let next = match self.time_shift.next() {
+                Some(chunk) => Ok(chunk),
+                None => self.receive(),
+            };
+            match next {
                 Ok(chunk) => {
                     self.stats
-                        .queued_samples
-                        .fetch_sub(chunk.len(), Ordering::SeqCst);
+                        .level
+                        .store(chunk_level(&chunk).to_bits(), Ordering::SeqCst);
                     self.current_chunk = chunk;
                     self.current_index = 0;
                     self.stats.starving.store(false, Ordering::SeqCst);
@@ -173,7 +241,9 @@ impl Iterator for BufferedStreamSource {
                         self.stats.starving.store(false, Ordering::SeqCst);
                         return None;
                     }
-                    self.stats.starving.store(true, Ordering::SeqCst);
+                    if !self.stats.starving.swap(true, Ordering::SeqCst) {
+                        self.stats.underruns.fetch_add(1, Ordering::SeqCst);
+                    }
                     return Some(0.0);
                 }
                 Err(TryRecvError::Disconnected) => {
@@ -214,6 +284,9 @@ enum RestartReason {
     Reconnect,
     Stall,
     StreamEnded,
+    /// The stream went on without sound for `silence_timeout`
+    Silence,
+    Quality(QualityChange),
 }
 
 struct PreparedAttempt {
@@ -224,7 +297,7 @@ struct PreparedAttempt {
 
 /// Start playback of a station in a supervised task.
 pub fn start_playback(
-    station: crate::station::Station,
+    station: Station,
     sink: Arc<Mutex<Sink>>,
     metadata_tx: mpsc::Sender<MetadataEvent>,
     log_tx: mpsc::Sender<HistoryMessage>,
@@ -233,14 +306,24 @@ pub fn start_playback(
     config: StreamConfig,
 ) -> tokio::task::JoinHandle<AudioResult<()>> {
     tokio::spawn(async move {
-        let station_url = station.url.clone();
+        let primary_url = station.stream_url().await;
+        let mut station_url = primary_url.clone();
         let station_title = station.title.clone();
         let mut restart_attempts = 0;
+        let mut silence_restarts = 0;
+        let mut quality = QualityMonitor::new(&config, !station.low_bitrate_playlist.is_empty());
+        let playlist_format = station
+            .playlists
+            .iter()
+            .find(|playlist| playlist.url == station.playlist)
+            .map(|playlist| playlist.format.clone());
+        let _ = action_tx.send(Action::SetUnderruns(0));
 
         loop {
             let prepared = match prepare_attempt(
                 &station_url,
                 &station_title,
+                playlist_format.as_deref(),
                 metadata_tx.clone(),
                 log_tx.clone(),
                 config.clone(),
@@ -248,6 +331,12 @@ pub fn start_playback(
             .await
             {
                 Ok(prepared) => prepared,
+                Err(AudioError::StreamNotFound(_)) if !station.playlist.is_empty() => {
+                    // The mount may have moved; the app fetches the catalog
+                    // and playlist again and retunes
+                    let _ = action_tx.send(Action::StreamGone(station.id.clone()));
+                    return Ok(());
+                }
                 Err(error) => {
                     if try_restart_after_error(
                         &log_tx,
@@ -266,6 +355,8 @@ pub fn start_playback(
 
             let startup_samples = config
                 .startup_buffer_samples(prepared.source.sample_rate(), prepared.source.channels());
+            let samples_per_second = u64::from(prepared.source.sample_rate())
+                * u64::from(prepared.source.channels().max(1));
 
             if let Err(error) = wait_for_startup_buffer(
                 &prepared.stats,
@@ -288,25 +379,47 @@ pub fn start_playback(
                 return Err(error);
             }
 
+            // Another station was picked while this one connected
+            if !config.generation.is_current() {
+                return Ok(());
+            }
+            config.fader.fade_in(&sink, volume);
             {
                 let sink = sink.lock().map_err(|_| AudioError::SinkPoisoned)?;
+                // Checked again under the lock the player takes to switch
+                if !config.generation.is_current() {
+                    return Ok(());
+                }
                 sink.stop();
-                sink.append(prepared.source);
-                sink.set_volume(volume);
+                sink.append(Equalizer::new(prepared.source, config.equalizer.clone()));
                 sink.play();
             }
 
             let _ = send_log(&log_tx, t("playback-started"), MessageType::System).await;
             let _ = log_tx.send(clear_station_loading_message()).await;
 
-            let reason = match monitor_playback_attempt(
+            let attempt_started = std::time::Instant::now();
+            let result = monitor_playback_attempt(
                 &sink,
                 &prepared.stats,
                 &prepared.reconnect_requested,
+                &action_tx,
+                &mut quality,
                 &config,
+                samples_per_second,
             )
-            .await
-            {
+            .await;
+            // Sound for a while means the stream recovered; a relay that goes
+            // quiet now and then over a long session shouldn't run out of
+            // restarts
+            let played = attempt_started
+                .elapsed()
+                .saturating_sub(prepared.stats.silence());
+            if played >= STABLE_PLAYBACK {
+                restart_attempts = 0;
+                silence_restarts = 0;
+            }
+            let reason = match result {
                 Ok(reason) => reason,
                 Err(error) => {
                     if try_restart_after_error(
@@ -325,23 +438,46 @@ pub fn start_playback(
             };
 
             match reason {
+                Some(RestartReason::Quality(change)) => {
+                    station_url =
+                        switch_quality(change, &station, &primary_url, &log_tx, &mut quality).await;
+                }
                 Some(reason) => {
-                    restart_attempts += 1;
-                    if restart_attempts > config.max_restart_attempts {
-                        let error = AudioError::AudioUnderrun;
+                    let (attempts, error) = match reason {
+                        RestartReason::Silence => (&mut silence_restarts, AudioError::StreamSilent),
+                        _ => (&mut restart_attempts, AudioError::AudioUnderrun),
+                    };
+                    *attempts += 1;
+                    if *attempts > config.max_restart_attempts {
                         let _ = action_tx.send(Action::SetPlaybackState(PlaybackState::Stopped));
                         let _ = action_tx.send(Action::Error(error.to_string()));
                         return Err(error);
                     }
 
-                    let message = match reason {
-                        RestartReason::Reconnect => {
-                            "Stream reconnected; rebuilding decoder and rebuffering..."
-                        }
-                        RestartReason::Stall => "Playback buffer starved; rebuffering stream...",
-                        RestartReason::StreamEnded => "Stream ended unexpectedly; reconnecting...",
+                    let (message, message_type) = match reason {
+                        RestartReason::Reconnect => (
+                            "Stream reconnected; rebuilding decoder and rebuffering...".to_string(),
+                            MessageType::Background,
+                        ),
+                        RestartReason::Stall => (
+                            "Playback buffer starved; rebuffering stream...".to_string(),
+                            MessageType::Background,
+                        ),
+                        RestartReason::StreamEnded => (
+                            "Stream ended unexpectedly; reconnecting...".to_string(),
+                            MessageType::Background,
+                        ),
+                        // Worth seeing: the relay is broken, not the network
+                        RestartReason::Silence => (
+                            t("stream-silent").replace(
+                                "{$seconds}",
+                                &config.silence_timeout.as_secs().to_string(),
+                            ),
+                            MessageType::Notice,
+                        ),
+                        RestartReason::Quality(_) => unreachable!("handled above"),
                     };
-                    let _ = send_log(&log_tx, message.to_string(), MessageType::Background).await;
+                    let _ = send_log(&log_tx, message, message_type).await;
                     tokio::time::sleep(config.restart_backoff).await;
                 }
                 None => {
@@ -353,6 +489,54 @@ pub fn start_playback(
     })
 }
 
+/// Resolve the stream URL to use after a quality change
+async fn switch_quality(
+    change: QualityChange,
+    station: &Station,
+    primary_url: &str,
+    log_tx: &mpsc::Sender<HistoryMessage>,
+    quality: &mut QualityMonitor,
+) -> String {
+    match change {
+        QualityChange::Downgrade => {
+            match Station::resolve_playlist(&station.low_bitrate_playlist).await {
+                Ok(url) => {
+                    let _ = send_log(log_tx, t("quality-downgraded"), MessageType::System).await;
+                    url
+                }
+                Err(error) => {
+                    quality.disable_downgrade();
+                    let _ = send_log(
+                        log_tx,
+                        format!("Low-bitrate stream unavailable: {}", error),
+                        MessageType::Background,
+                    )
+                    .await;
+                    primary_url.to_string()
+                }
+            }
+        }
+        QualityChange::Upgrade => {
+            let _ = send_log(log_tx, t("quality-restored"), MessageType::System).await;
+            primary_url.to_string()
+        }
+    }
+}
+
+/// Whether the server answers `url` with 404 Not Found or 410 Gone
+async fn stream_gone(url: &str) -> bool {
+    crate::http::client()
+        .get(url)
+        .send()
+        .await
+        .is_ok_and(|response| {
+            matches!(
+                response.status(),
+                reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE
+            )
+        })
+}
+
 async fn try_restart_after_error(
     log_tx: &mpsc::Sender<HistoryMessage>,
     action_tx: &mpsc::UnboundedSender<Action>,
@@ -383,38 +567,43 @@ async fn try_restart_after_error(
 async fn prepare_attempt(
     station_url: &str,
     station_title: &str,
+    playlist_format: Option<&str>,
     metadata_tx: mpsc::Sender<MetadataEvent>,
     log_tx: mpsc::Sender<HistoryMessage>,
     config: StreamConfig,
 ) -> AudioResult<PreparedAttempt> {
     let station_url = station_url.to_string();
     let station_title = station_title.to_string();
-    let log_tx_for_stream = log_tx.clone();
     let config_for_connect = config.clone();
 
-    let (stream, icy_headers, bitrate) = retry_with_backoff(
+    let (stream, icy_headers, bitrate, info) = retry_with_backoff(
         || {
             let station_url = station_url.clone();
-            let log_tx = log_tx_for_stream.clone();
+            let station_title = station_title.clone();
             let config = config_for_connect.clone();
             async move {
                 let client = create_icy_client()?;
                 let url = parse_url(&station_url)?;
-                let stream = HttpStream::new(client, url).await.map_err(|e| {
-                    AudioError::StreamRetryable(format!("Failed to create HTTP stream: {}", e))
-                })?;
+                let stream = match HttpStream::new(client, url).await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        if stream_gone(&station_url).await {
+                            return Err(AudioError::StreamNotFound(station_url));
+                        }
+                        return Err(AudioError::StreamRetryable(format!(
+                            "Failed to create HTTP stream: {}",
+                            e
+                        )));
+                    }
+                };
                 let icy_headers = icy_metadata::IcyHeaders::parse_from_headers(stream.headers());
                 let bitrate = parse_bitrate_with_fallback(icy_headers.bitrate(), &config);
-
-                let _ = log_tx
-                    .send(HistoryMessage {
-                        message: t("bit-rate").replace("{$rate}", &format!("{:?}", bitrate)),
-                        message_type: MessageType::System,
-                        timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
-                    })
-                    .await;
-
-                Ok((stream, icy_headers, bitrate))
+                let info = StreamInfo::from_headers(
+                    &station_title,
+                    stream.headers(),
+                    icy_headers.bitrate(),
+                );
+                Ok((stream, icy_headers, bitrate, info))
             }
         },
         RecoveryConfig {
@@ -425,6 +614,16 @@ async fn prepare_attempt(
         },
     )
     .await?;
+    let content_type = info.content_type.clone();
+    if info.bitrate.is_none() {
+        let _ = send_log(
+            &log_tx,
+            t("no-bitrate").replace("{$kbps}", &bitrate.to_string()),
+            MessageType::Background,
+        )
+        .await;
+    }
+    let _ = metadata_tx.send(MetadataEvent::StreamInfo(info)).await;
 
     let prefetch_bytes = calculate_prefetch_bytes(bitrate, config.startup_prefetch_seconds);
     let storage_size = config
@@ -433,6 +632,8 @@ async fn prepare_attempt(
         .max(512 * 1024);
     let reconnect_requested = Arc::new(AtomicBool::new(false));
     let reconnect_signal = reconnect_requested.clone();
+    let throughput = config.throughput.clone();
+    let mut downloaded = 0;
 
     let reader = StreamDownload::from_stream(
         stream,
@@ -446,6 +647,11 @@ async fn prepare_attempt(
             .on_reconnect(move |_stream, cancellation_token| {
                 reconnect_signal.store(true, Ordering::SeqCst);
                 cancellation_token.cancel();
+            })
+            .on_progress(move |_stream, state, _cancellation_token| {
+                // The position counts from the start of this connection
+                throughput.add(state.current_position.saturating_sub(downloaded));
+                downloaded = state.current_position;
             }),
     )
     .await
@@ -468,36 +674,94 @@ async fn prepare_attempt(
                 .send(HistoryMessage {
                     message: format!("{} :: {}", station_title_for_metadata, title),
                     message_type: MessageType::Playback,
-                    timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+                    timestamp: crate::clock::timestamp(),
                 })
                 .await;
         }
     });
 
+    // What the server and playlist say the stream is
+    let claimed = content_type
+        .as_deref()
+        .and_then(Format::from_claim)
+        .or_else(|| playlist_format.and_then(Format::from_claim));
+    let probe_bytes = icy_headers
+        .metadata_interval()
+        .map_or(probe::PROBE_BYTES, |interval| {
+            interval.get().min(probe::PROBE_BYTES)
+        });
+    // Without an interval the reader below passes the stream through as is,
+    // still copying it to the recorder
+    if icy_headers.metadata_interval().is_none() {
+        let _ = send_log(&log_tx, t("no-metadata"), MessageType::Background).await;
+    }
+
     let config_for_decoder = config.clone();
+    let title_rules = config.title_rules.clone();
     let source_result = tokio::task::spawn_blocking(move || {
-        let decoder = Decoder::new(IcyMetadataReader::new(
-            reader,
-            icy_headers.metadata_interval(),
-            move |metadata| {
-                if let Ok(metadata) = metadata {
-                    if let Some(title) = metadata.stream_title() {
-                        let _ = inner_metadata_tx.blocking_send(title.to_string());
+        let mut reader = reader;
+        let bytes = probe::peek(&mut reader, probe_bytes)
+            .map_err(|e| AudioError::StreamRetryable(format!("Failed to read stream: {}", e)))?;
+        let format = match probe::sniff(&bytes) {
+            Probe::NotAudio(what) => {
+                return Err(AudioError::StreamPermanent(format!(
+                    "the station sent {} instead of audio",
+                    what
+                )))
+            }
+            Probe::Audio(format) => Some(format),
+            Probe::Unknown => claimed,
+        };
+
+        if let Some(format) = format {
+            config_for_decoder.recorder.set_format(format.hint());
+        }
+        let mut builder = Decoder::builder().with_data(
+            IcyMetadataReader::new(
+                reader,
+                icy_headers.metadata_interval(),
+                config_for_decoder.metadata_encoding,
+                move |metadata| {
+                    if let Ok(metadata) = metadata {
+                        if let Some(title) = metadata.stream_title() {
+                            let title = title_rules.apply(title);
+                            if !title.is_empty() {
+                                let _ = inner_metadata_tx.blocking_send(title);
+                            }
+                        }
                     }
-                }
-            },
-        ))
-        .map_err(|e| AudioError::DecodeError(format!("Failed to construct decoder: {}", e)))?;
+                },
+            )
+            .with_recorder(config_for_decoder.recorder.clone()),
+        );
+        if let Some(format) = format {
+            builder = builder.with_hint(format.hint());
+        }
+        let decoder = builder
+            .build()
+            .map_err(|e| AudioError::DecodeError(format!("Failed to construct decoder: {}", e)))?;
 
-        Ok::<_, AudioError>(BufferedStreamSource::from_source(
-            decoder,
-            &config_for_decoder,
+        Ok::<_, AudioError>((
+            BufferedStreamSource::from_source(decoder, &config_for_decoder),
+            format,
         ))
     })
     .await
     .map_err(|e| AudioError::DecodeError(e.to_string()))?;
 
-    let (source, stats) = source_result?;
+    let ((source, stats), format) = source_result?;
+    if let (Some(claimed), Some(format)) = (claimed, format) {
+        if claimed != format {
+            let _ = send_log(
+                &log_tx,
+                t("format-mismatch")
+                    .replace("{$claimed}", claimed.name())
+                    .replace("{$actual}", format.name()),
+                MessageType::Info,
+            )
+            .await;
+        }
+    }
 
     Ok(PreparedAttempt {
         source,
@@ -530,9 +794,14 @@ async fn monitor_playback_attempt(
     sink: &Arc<Mutex<Sink>>,
     stats: &PlaybackBufferStats,
     reconnect_requested: &AtomicBool,
+    action_tx: &mpsc::UnboundedSender<Action>,
+    quality: &mut QualityMonitor,
     config: &StreamConfig,
+    samples_per_second: u64,
 ) -> AudioResult<Option<RestartReason>> {
     let mut starving_since = None;
+    let mut seen_underruns = 0;
+    let mut buffered_sent = None;
 
     loop {
         let paused = {
@@ -545,6 +814,12 @@ async fn monitor_playback_attempt(
             return Ok(Some(RestartReason::Reconnect));
         }
 
+        if !paused && !config.silence_timeout.is_zero() && stats.silence() >= config.silence_timeout
+        {
+            reset_sink(sink)?;
+            return Ok(Some(RestartReason::Silence));
+        }
+
         if !paused && stats.starving.load(Ordering::SeqCst) {
             let started = starving_since.get_or_insert_with(std::time::Instant::now);
             if started.elapsed() >= config.stall_grace_period {
@@ -555,7 +830,33 @@ async fn monitor_playback_attempt(
             starving_since = None;
         }
 
+        let level = if paused || stats.starving.load(Ordering::SeqCst) {
+            0.0
+        } else {
+            stats.level()
+        };
+        let _ = action_tx.send(Action::AudioLevel(level));
+
+        let now = std::time::Instant::now();
+        let underruns = stats.underruns.load(Ordering::SeqCst);
+        if underruns > seen_underruns {
+            for _ in seen_underruns..underruns {
+                quality.record_underrun(now);
+            }
+            seen_underruns = underruns;
+            let _ = action_tx.send(Action::SetUnderruns(quality.total()));
+        }
+        if let Some(change) = quality.check(now) {
+            reset_sink(sink)?;
+            return Ok(Some(RestartReason::Quality(change)));
+        }
+
         let queued = stats.queued_samples.load(Ordering::SeqCst);
+        if buffered_sent.is_none_or(|sent| now.duration_since(sent) >= Duration::from_secs(1)) {
+            buffered_sent = Some(now);
+            let buffered = Duration::from_millis(queued as u64 * 1000 / samples_per_second.max(1));
+            let _ = action_tx.send(Action::Buffered(buffered));
+        }
         let finished = stats.finished.load(Ordering::SeqCst);
         if finished && queued == 0 {
             reset_sink(sink)?;
@@ -571,6 +872,8 @@ fn produce_samples<S>(
     tx: SyncSender<Vec<f32>>,
     stats: Arc<PlaybackBufferStats>,
     chunk_size: usize,
+    mut normalizer: Option<Normalizer>,
+    mut silence: SilenceDetector,
 ) where
     S: Source + Send + 'static,
 {
@@ -578,12 +881,25 @@ fn produce_samples<S>(
 
     for sample in source {
         chunk.push(sample);
-        if chunk.len() >= chunk_size && !send_chunk(&tx, &stats, &mut chunk, chunk_size) {
-            return;
+        if chunk.len() >= chunk_size {
+            // Measured before normalizing, which would lift quiet noise
+            let silent = silence.feed(&chunk);
+            stats
+                .silent_ms
+                .store(silent.as_millis() as u64, Ordering::SeqCst);
+            if let Some(normalizer) = normalizer.as_mut() {
+                normalizer.process(&mut chunk);
+            }
+            if !send_chunk(&tx, &stats, &mut chunk, chunk_size) {
+                return;
+            }
         }
     }
 
     if !chunk.is_empty() {
+        if let Some(normalizer) = normalizer.as_mut() {
+            normalizer.process(&mut chunk);
+        }
         let len = chunk.len();
         stats.queued_samples.fetch_add(len, Ordering::SeqCst);
         if tx.send(std::mem::take(&mut chunk)).is_err() {
@@ -612,6 +928,16 @@ fn send_chunk(
     true
 }
 
+/// Loudness of a PCM chunk mapped from -60..0 dBFS RMS onto 0.0..1.0
+fn chunk_level(chunk: &[f32]) -> f32 {
+    if chunk.is_empty() {
+        return 0.0;
+    }
+    let mean_square = chunk.iter().map(|sample| sample * sample).sum::<f32>() / chunk.len() as f32;
+    let db = 10.0 * mean_square.max(1e-12).log10();
+    ((db + 60.0) / 60.0).clamp(0.0, 1.0)
+}
+
 fn reset_sink(sink: &Arc<Mutex<Sink>>) -> AudioResult<()> {
     let sink = sink.lock().map_err(|_| AudioError::SinkPoisoned)?;
     sink.stop();
@@ -627,7 +953,7 @@ async fn send_log(
         .send(HistoryMessage {
             message,
             message_type,
-            timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+            timestamp: crate::clock::timestamp(),
         })
         .await
 }
@@ -636,6 +962,46 @@ fn clear_station_loading_message() -> HistoryMessage {
     HistoryMessage {
         message: "CLEAR_STATION_LOADING".to_string(),
         message_type: MessageType::Background,
-        timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+        timestamp: crate::clock::timestamp(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_level_maps_rms_to_unit_range() {
+        assert_eq!(chunk_level(&[]), 0.0);
+        assert_eq!(chunk_level(&[0.0; 64]), 0.0);
+        assert_eq!(chunk_level(&[1.0, -1.0]), 1.0);
+        let quiet = chunk_level(&[0.01, -0.01]);
+        assert!(quiet > 0.0 && quiet < 0.5);
+    }
+
+    #[tokio::test]
+    async fn only_a_missing_mount_counts_as_gone() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let read = socket.read(&mut request).await.unwrap_or(0);
+                let status = if request[..read].starts_with(b"GET /moved ") {
+                    "404 Not Found"
+                } else {
+                    "503 Service Unavailable"
+                };
+                let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n");
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        assert!(stream_gone(&format!("http://127.0.0.1:{port}/moved")).await);
+        assert!(!stream_gone(&format!("http://127.0.0.1:{port}/busy")).await);
     }
 }
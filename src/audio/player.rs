@@ -0,0 +1,356 @@
+//! Playback actor
+//!
+//! The sink and the task streaming a station into it belong to the player,
+//! which runs as its own task. The app tells it what to do with
+//! [`PlayerCommand`]s and hears back through [`PlayerEvent`]s on the action
+//! channel, so nothing outside the player locks the sink or aborts its task.
+
+use super::metadata::MetadataEvent;
+use super::playback::start_playback;
+use super::stream::StreamConfig;
+use super::transition::Fader;
+use super::types::AudioResult;
+use crate::action::Action;
+use crate::station::Station;
+use crate::HistoryMessage;
+use rodio::mixer::Mixer;
+use rodio::queue::SourcesQueueOutput;
+use rodio::Sink;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// How often the player looks at the sink and its streaming task
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Which play request a streaming task belongs to
+///
+/// Every request to play or stop supersedes the ones before it, so a task
+/// still connecting to a station that was switched away from can tell
+/// before it touches the sink.
+#[derive(Debug, Clone, Default)]
+pub struct Generation {
+    current: Arc<AtomicU64>,
+    issued: u64,
+}
+
+impl Generation {
+    /// Start a new generation, superseding this one and every earlier one
+    fn next(&self) -> Self {
+        Self {
+            current: self.current.clone(),
+            issued: self.current.fetch_add(1, Ordering::SeqCst) + 1,
+        }
+    }
+
+    /// Whether nothing was asked of the player since this generation began
+    pub fn is_current(&self) -> bool {
+        self.current.load(Ordering::SeqCst) == self.issued
+    }
+}
+
+/// A station to start playing
+pub struct PlayRequest {
+    pub station: Station,
+    /// Sink gain to fade in to
+    pub volume: f32,
+    pub config: StreamConfig,
+}
+
+/// What the player is asked to do
+pub enum PlayerCommand {
+    /// Fade out whatever plays and stream the station instead
+    Play(Box<PlayRequest>),
+    /// Fade out and stop streaming
+    Stop,
+    Pause,
+    Resume,
+    /// Set the sink gain
+    SetVolume(f32),
+    /// Stop streaming and drop what is queued, staying paused
+    Disconnect,
+    /// Play through a new output's mixer
+    SetOutput(Mixer),
+    /// Stop everything and answer once the sink is gone
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// What the player reports back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlayerEvent {
+    /// Sources queued in the sink; 0 while the output has nothing to play
+    Queued(usize),
+    /// The streaming task for a station ended on its own
+    Ended {
+        station: String,
+        error: Option<String>,
+    },
+}
+
+/// Sends commands to the player task
+#[derive(Debug, Clone)]
+pub struct PlayerHandle {
+    commands: mpsc::UnboundedSender<PlayerCommand>,
+}
+
+impl PlayerHandle {
+    pub fn send(&self, command: PlayerCommand) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Stop playback and wait for the player to let go of the output
+    pub async fn shutdown(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        self.send(PlayerCommand::Shutdown(done_tx));
+        let _ = done_rx.await;
+    }
+}
+
+/// Start the player on `mixer`
+pub fn spawn(
+    mixer: &Mixer,
+    fader: Fader,
+    metadata_tx: mpsc::Sender<MetadataEvent>,
+    log_tx: mpsc::Sender<HistoryMessage>,
+    action_tx: mpsc::UnboundedSender<Action>,
+) -> PlayerHandle {
+    let sink = Arc::new(Mutex::new(Sink::connect_new(mixer)));
+    spawn_with(sink, fader, metadata_tx, log_tx, action_tx)
+}
+
+/// Start the player on a sink with no audio device behind it
+///
+/// What it plays comes out of the returned source instead, for tests and
+/// frontends that mix the audio themselves. The station only plays as fast
+/// as the source is read.
+pub fn spawn_detached(
+    fader: Fader,
+    metadata_tx: mpsc::Sender<MetadataEvent>,
+    log_tx: mpsc::Sender<HistoryMessage>,
+    action_tx: mpsc::UnboundedSender<Action>,
+) -> (PlayerHandle, SourcesQueueOutput) {
+    let (sink, output) = Sink::new();
+    let player = spawn_with(
+        Arc::new(Mutex::new(sink)),
+        fader,
+        metadata_tx,
+        log_tx,
+        action_tx,
+    );
+    (player, output)
+}
+
+fn spawn_with(
+    sink: Arc<Mutex<Sink>>,
+    fader: Fader,
+    metadata_tx: mpsc::Sender<MetadataEvent>,
+    log_tx: mpsc::Sender<HistoryMessage>,
+    action_tx: mpsc::UnboundedSender<Action>,
+) -> PlayerHandle {
+    let (commands, command_rx) = mpsc::unbounded_channel();
+    let player = Player {
+        sink,
+        fader,
+        volume: 1.0,
+        generation: Generation::default(),
+        task: None,
+        queued: None,
+        metadata_tx,
+        log_tx,
+        action_tx,
+    };
+    tokio::spawn(player.run(command_rx));
+    PlayerHandle { commands }
+}
+
+struct Player {
+    sink: Arc<Mutex<Sink>>,
+    fader: Fader,
+    /// Gain last asked for, kept for a new output
+    volume: f32,
+    generation: Generation,
+    /// Station streaming and the task doing it
+    task: Option<(String, JoinHandle<AudioResult<()>>)>,
+    /// Queue length last reported
+    queued: Option<usize>,
+    metadata_tx: mpsc::Sender<MetadataEvent>,
+    log_tx: mpsc::Sender<HistoryMessage>,
+    action_tx: mpsc::UnboundedSender<Action>,
+}
+
+impl Player {
+    async fn run(mut self, mut commands: mpsc::UnboundedReceiver<PlayerCommand>) {
+        let mut poll = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                biased;
+                command = commands.recv() => match command {
+                    Some(PlayerCommand::Shutdown(done)) => {
+                        self.shutdown();
+                        let _ = done.send(());
+                        return;
+                    }
+                    Some(command) => self.handle(command),
+                    None => {
+                        self.shutdown();
+                        return;
+                    }
+                },
+                _ = poll.tick() => self.poll().await,
+            }
+        }
+    }
+
+    fn handle(&mut self, command: PlayerCommand) {
+        match command {
+            PlayerCommand::Play(request) => {
+                let PlayRequest {
+                    station,
+                    volume,
+                    mut config,
+                } = *request;
+                self.abort();
+                self.fader.fade_out(&self.sink);
+                self.volume = volume;
+                config.generation = self.generation.clone();
+                let task = start_playback(
+                    station.clone(),
+                    self.sink.clone(),
+                    self.metadata_tx.clone(),
+                    self.log_tx.clone(),
+                    self.action_tx.clone(),
+                    volume,
+                    config,
+                );
+                self.task = Some((station.id, task));
+            }
+            PlayerCommand::Stop => {
+                self.abort();
+                self.fader.fade_out(&self.sink);
+            }
+            PlayerCommand::Pause => self.with_sink(Sink::pause),
+            PlayerCommand::Resume => self.with_sink(Sink::play),
+            PlayerCommand::SetVolume(volume) => {
+                self.volume = volume;
+                self.with_sink(|sink| sink.set_volume(volume));
+            }
+            PlayerCommand::Disconnect => {
+                self.abort();
+                self.with_sink(Sink::clear);
+            }
+            PlayerCommand::SetOutput(mixer) => {
+                // Everything playing went to the old device's mixer
+                if let Ok(mut sink) = self.sink.lock() {
+                    *sink = Sink::connect_new(&mixer);
+                    sink.set_volume(self.volume);
+                }
+            }
+            PlayerCommand::Shutdown(_) => unreachable!("handled by the run loop"),
+        }
+    }
+
+    fn with_sink(&self, f: impl FnOnce(&Sink)) {
+        if let Ok(sink) = self.sink.lock() {
+            f(&sink);
+        }
+    }
+
+    /// Report a changed queue length and a streaming task that ended
+    async fn poll(&mut self) {
+        let queued = self.sink.lock().map(|sink| sink.len()).unwrap_or(0);
+        if self.queued != Some(queued) {
+            self.queued = Some(queued);
+            self.report(PlayerEvent::Queued(queued));
+        }
+        if !self
+            .task
+            .as_ref()
+            .is_some_and(|(_, task)| task.is_finished())
+        {
+            return;
+        }
+        if let Some((station, task)) = self.task.take() {
+            let error = match task.await {
+                Ok(Ok(())) => None,
+                Ok(Err(error)) => Some(error.to_string()),
+                Err(error) => Some(error.to_string()),
+            };
+            self.report(PlayerEvent::Ended { station, error });
+        }
+    }
+
+    fn report(&self, event: PlayerEvent) {
+        let _ = self.action_tx.send(Action::Player(event));
+    }
+
+    /// Stop the streaming task, making sure one caught between awaits
+    /// doesn't go on to fill the sink
+    fn abort(&mut self) {
+        self.generation = self.generation.next();
+        if let Some((_, task)) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.abort();
+        // Stop audio before the output stream is dropped
+        self.with_sink(Sink::stop);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::source::SineWave;
+
+    #[tokio::test]
+    async fn follows_commands_in_order() {
+        let (sink, _output) = Sink::new();
+        sink.append(SineWave::new(440.0));
+        let sink = Arc::new(Mutex::new(sink));
+        let (metadata_tx, _metadata_rx) = mpsc::channel(1);
+        let (log_tx, _log_rx) = mpsc::channel(1);
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let player = spawn_with(
+            sink.clone(),
+            Fader::default(),
+            metadata_tx,
+            log_tx,
+            action_tx,
+        );
+
+        player.send(PlayerCommand::SetVolume(0.5));
+        player.send(PlayerCommand::Pause);
+        assert_eq!(
+            action_rx.recv().await,
+            Some(Action::Player(PlayerEvent::Queued(1)))
+        );
+        {
+            let sink = sink.lock().unwrap();
+            assert_eq!(sink.volume(), 0.5);
+            assert!(sink.is_paused());
+        }
+
+        player.send(PlayerCommand::Resume);
+        player.send(PlayerCommand::SetVolume(0.8));
+        player.shutdown().await;
+        let sink = sink.lock().unwrap();
+        assert_eq!(sink.volume(), 0.8);
+        assert!(!sink.is_paused());
+    }
+
+    #[test]
+    fn later_requests_supersede_earlier_ones() {
+        let first = Generation::default().next();
+        assert!(first.is_current());
+        let second = first.next();
+        assert!(!first.is_current());
+        assert!(second.is_current());
+        second.next();
+        assert!(!second.is_current());
+    }
+}
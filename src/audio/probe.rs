@@ -0,0 +1,202 @@
+//! Pre-flight stream format probing
+//!
+//! Playlists and `Content-Type` headers aren't always honest: some "MP3"
+//! playlists serve AAC, and a dead mount often answers with an HTML error
+//! page. The first bytes of the stream are sniffed before the decoder is
+//! built, so the decoder gets the right format hint and pages that aren't
+//! audio fail with a clear error instead of a decoder one.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Bytes read to sniff the format
+pub const PROBE_BYTES: usize = 2048;
+
+/// Audio container or codec a stream can carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Mp3,
+    Aac,
+    Ogg,
+    Flac,
+    Wav,
+    Mp4,
+}
+
+impl Format {
+    /// File extension hint for the decoder
+    pub fn hint(self) -> &'static str {
+        match self {
+            Format::Mp3 => "mp3",
+            Format::Aac => "aac",
+            Format::Ogg => "ogg",
+            Format::Flac => "flac",
+            Format::Wav => "wav",
+            Format::Mp4 => "mp4",
+        }
+    }
+
+    /// Name shown to the user
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Mp3 => "MP3",
+            Format::Aac => "AAC",
+            Format::Ogg => "Ogg",
+            Format::Flac => "FLAC",
+            Format::Wav => "WAV",
+            Format::Mp4 => "MP4",
+        }
+    }
+
+    /// The format a MIME type or playlist format label such as `aacp` stands for
+    pub fn from_claim(claim: &str) -> Option<Self> {
+        let claim = claim.split(';').next().unwrap_or("").trim().to_lowercase();
+        let claim = claim.strip_prefix("audio/").unwrap_or(&claim);
+        match claim {
+            "mpeg" | "mp3" | "mpeg3" | "x-mpeg" => Some(Format::Mp3),
+            "aac" | "aacp" | "x-aac" | "x-aacp" => Some(Format::Aac),
+            "ogg" | "application/ogg" | "vorbis" | "opus" => Some(Format::Ogg),
+            "flac" | "x-flac" => Some(Format::Flac),
+            "wav" | "x-wav" | "vnd.wav" | "wave" => Some(Format::Wav),
+            "mp4" | "m4a" | "x-m4a" => Some(Format::Mp4),
+            _ => None,
+        }
+    }
+}
+
+/// What the first bytes of a stream look like
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    Audio(Format),
+    /// Not audio at all; says what it looks like instead
+    NotAudio(&'static str),
+    /// Nothing recognisable, left to the decoder
+    Unknown,
+}
+
+/// Sniff the format from the first bytes of a stream
+pub fn sniff(bytes: &[u8]) -> Probe {
+    if bytes.starts_with(b"ID3") {
+        return Probe::Audio(Format::Mp3);
+    }
+    if bytes.starts_with(b"OggS") {
+        return Probe::Audio(Format::Ogg);
+    }
+    if bytes.starts_with(b"fLaC") {
+        return Probe::Audio(Format::Flac);
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        return Probe::Audio(Format::Wav);
+    }
+    if bytes.get(4..8) == Some(b"ftyp") {
+        return Probe::Audio(Format::Mp4);
+    }
+
+    let text = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let text = &text[text
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(text.len())..];
+    let lowercase = text[..text.len().min(16)].to_ascii_lowercase();
+    if lowercase.starts_with(b"<!doctype html") || lowercase.starts_with(b"<html") {
+        return Probe::NotAudio("an HTML page");
+    }
+    if lowercase.starts_with(b"<") {
+        return Probe::NotAudio("an XML document");
+    }
+    if lowercase.starts_with(b"{") || lowercase.starts_with(b"[{") {
+        return Probe::NotAudio("JSON");
+    }
+
+    // Streams may start mid-frame, so look for the first frame header
+    match bytes.windows(3).find_map(frame_header) {
+        Some(format) => Probe::Audio(format),
+        None if !text.is_empty()
+            && text
+                .iter()
+                .all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace()) =>
+        {
+            Probe::NotAudio("text")
+        }
+        None => Probe::Unknown,
+    }
+}
+
+/// The format of the MPEG audio or ADTS frame header starting `bytes`
+fn frame_header(bytes: &[u8]) -> Option<Format> {
+    let &[first, second, third, ..] = bytes else {
+        return None;
+    };
+    if first != 0xFF || second & 0xE0 != 0xE0 {
+        return None;
+    }
+    if second & 0x06 == 0 {
+        // ADTS uses the layer bits MPEG audio reserves; check its 12-bit sync
+        // and sampling rate index
+        (second & 0xF0 == 0xF0 && (third >> 2) & 0x0F < 13).then_some(Format::Aac)
+    } else {
+        // Valid version, bitrate and sampling rate
+        (second & 0x18 != 0x08 && third >> 4 != 0x0F && (third >> 2) & 0x03 != 0x03)
+            .then_some(Format::Mp3)
+    }
+}
+
+/// Read up to `limit` bytes from the start of `reader` and rewind it
+pub fn peek<R: Read + Seek>(reader: &mut R, limit: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; limit];
+    let mut filled = 0;
+    while filled < limit {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    bytes.truncate(filled);
+    reader.seek(SeekFrom::Start(0))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_formats_and_error_pages() {
+        assert_eq!(sniff(b"ID3\x04\0\0"), Probe::Audio(Format::Mp3));
+        // An MPEG-1 Layer III frame header, after some junk
+        assert_eq!(
+            sniff(&[0x12, 0x34, 0xFF, 0xFB, 0x90, 0x64]),
+            Probe::Audio(Format::Mp3)
+        );
+        // ADTS, as served by "MP3" playlists that are really AAC
+        assert_eq!(sniff(&[0xFF, 0xF1, 0x50, 0x80]), Probe::Audio(Format::Aac));
+        assert_eq!(sniff(b"OggS\0\x02"), Probe::Audio(Format::Ogg));
+        assert_eq!(
+            sniff(b"\n  <!DOCTYPE html><html><body>404</body></html>"),
+            Probe::NotAudio("an HTML page")
+        );
+        assert_eq!(sniff(b"{\"error\": \"gone\"}"), Probe::NotAudio("JSON"));
+        assert_eq!(sniff(b"Mount point not found"), Probe::NotAudio("text"));
+        assert_eq!(sniff(&[0x00, 0x01, 0x02]), Probe::Unknown);
+    }
+
+    #[test]
+    fn reads_claims() {
+        assert_eq!(Format::from_claim("audio/mpeg"), Some(Format::Mp3));
+        assert_eq!(
+            Format::from_claim("audio/aacp; charset=x"),
+            Some(Format::Aac)
+        );
+        assert_eq!(Format::from_claim("aac"), Some(Format::Aac));
+        assert_eq!(Format::from_claim("application/ogg"), Some(Format::Ogg));
+        assert_eq!(Format::from_claim("text/html"), None);
+    }
+
+    #[test]
+    fn peeks_and_rewinds() {
+        let mut reader = io::Cursor::new(b"OggS and more".to_vec());
+        assert_eq!(peek(&mut reader, 4).unwrap(), b"OggS");
+        assert_eq!(reader.position(), 0);
+    }
+}
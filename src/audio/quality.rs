@@ -0,0 +1,154 @@
+//! Adaptive stream quality
+//!
+//! Counts playback buffer underruns and decides when to drop to a station's
+//! low-bitrate stream, and when the connection has been stable long enough to
+//! go back to the full-quality one.
+
+use super::stream::StreamConfig;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Quality change requested by the [`QualityMonitor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityChange {
+    Downgrade,
+    Upgrade,
+}
+
+/// Tracks underruns over a playback session
+#[derive(Debug)]
+pub struct QualityMonitor {
+    /// Underruns within `window` that trigger a downgrade (0 disables)
+    threshold: u32,
+    window: Duration,
+    upgrade_after: Duration,
+    /// Whether the station has a low-bitrate stream to fall back to
+    can_downgrade: bool,
+    recent: VecDeque<Instant>,
+    total: u32,
+    /// Start of the current stable period while on the low-bitrate stream
+    downgraded_since: Option<Instant>,
+}
+
+impl QualityMonitor {
+    pub fn new(config: &StreamConfig, can_downgrade: bool) -> Self {
+        Self {
+            threshold: config.downgrade_underruns,
+            window: config.downgrade_window,
+            upgrade_after: config.upgrade_after,
+            can_downgrade,
+            recent: VecDeque::new(),
+            total: 0,
+            downgraded_since: None,
+        }
+    }
+
+    /// Total underruns this session
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Record an underrun at `now`
+    pub fn record_underrun(&mut self, now: Instant) {
+        self.total += 1;
+        self.recent.push_back(now);
+        if let Some(since) = self.downgraded_since.as_mut() {
+            *since = now;
+        }
+    }
+
+    /// Decide whether the stream quality should change at `now`
+    pub fn check(&mut self, now: Instant) -> Option<QualityChange> {
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > self.window)
+        {
+            self.recent.pop_front();
+        }
+
+        match self.downgraded_since {
+            None if self.can_downgrade
+                && self.threshold > 0
+                && self.recent.len() >= self.threshold as usize =>
+            {
+                self.recent.clear();
+                self.downgraded_since = Some(now);
+                Some(QualityChange::Downgrade)
+            }
+            Some(since) if now.duration_since(since) >= self.upgrade_after => {
+                self.recent.clear();
+                self.downgraded_since = None;
+                Some(QualityChange::Upgrade)
+            }
+            _ => None,
+        }
+    }
+
+    /// Stop downgrading, e.g. because the low-bitrate stream is unavailable
+    pub fn disable_downgrade(&mut self) {
+        self.can_downgrade = false;
+        self.downgraded_since = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> StreamConfig {
+        StreamConfig {
+            downgrade_underruns: 3,
+            downgrade_window: Duration::from_secs(60),
+            upgrade_after: Duration::from_secs(600),
+            ..StreamConfig::default()
+        }
+    }
+
+    #[test]
+    fn downgrades_after_threshold_within_window() {
+        let mut monitor = QualityMonitor::new(&config(), true);
+        let start = Instant::now();
+        monitor.record_underrun(start);
+        monitor.record_underrun(start + Duration::from_secs(90));
+        monitor.record_underrun(start + Duration::from_secs(100));
+        // The first underrun has left the window
+        assert_eq!(monitor.check(start + Duration::from_secs(100)), None);
+        monitor.record_underrun(start + Duration::from_secs(110));
+        assert_eq!(
+            monitor.check(start + Duration::from_secs(110)),
+            Some(QualityChange::Downgrade)
+        );
+        assert!(monitor.downgraded_since.is_some());
+        assert_eq!(monitor.total(), 4);
+    }
+
+    #[test]
+    fn upgrades_after_stable_period() {
+        let mut monitor = QualityMonitor::new(&config(), true);
+        let start = Instant::now();
+        for _ in 0..3 {
+            monitor.record_underrun(start);
+        }
+        assert_eq!(monitor.check(start), Some(QualityChange::Downgrade));
+
+        // An underrun on the low-bitrate stream restarts the stable period
+        monitor.record_underrun(start + Duration::from_secs(300));
+        assert_eq!(monitor.check(start + Duration::from_secs(700)), None);
+        assert_eq!(
+            monitor.check(start + Duration::from_secs(900)),
+            Some(QualityChange::Upgrade)
+        );
+        assert!(monitor.downgraded_since.is_none());
+    }
+
+    #[test]
+    fn never_downgrades_without_fallback() {
+        let mut monitor = QualityMonitor::new(&config(), false);
+        let start = Instant::now();
+        for _ in 0..10 {
+            monitor.record_underrun(start);
+        }
+        assert_eq!(monitor.check(start), None);
+    }
+}
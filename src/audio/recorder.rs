@@ -0,0 +1,323 @@
+//! Stream recording
+//!
+//! Recording copies the stream as it arrives, before it is decoded, so the
+//! file holds the station's own MP3 or AAC frames with the ICY metadata taken
+//! out. A [`Recorder`] is shared by the app, which starts and stops it, and
+//! the playing stream, which feeds it.
+//!
+//! The last few minutes of the stream are also kept in memory whether or not
+//! a recording runs, so what just played can be saved after the fact.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Recording settings, read from the `[recording]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingConfig {
+    /// Folder recordings are saved in; defaults to `somars` in the music folder
+    #[serde(default)]
+    pub dir: Option<PathBuf>,
+    /// Minutes of the stream kept for saving what just played; 0 keeps none
+    #[serde(default = "default_replay_minutes")]
+    pub replay_minutes: u64,
+}
+
+fn default_replay_minutes() -> u64 {
+    5
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            replay_minutes: default_replay_minutes(),
+        }
+    }
+}
+
+impl RecordingConfig {
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir.clone().or_else(|| {
+            dirs::audio_dir()
+                .or_else(dirs::home_dir)
+                .map(|dir| dir.join("somars"))
+        })
+    }
+
+    /// How much of the stream is kept for saving what just played
+    pub fn replay(&self) -> Duration {
+        Duration::from_secs(self.replay_minutes.saturating_mul(60))
+    }
+}
+
+/// Progress of the recording in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingStatus {
+    /// File name, without the folder
+    pub file: String,
+    /// Seconds since the recording started
+    pub seconds: u64,
+    /// Bytes written so far
+    pub bytes: u64,
+}
+
+struct Recording {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    bytes: u64,
+}
+
+impl Recording {
+    fn status(&self) -> RecordingStatus {
+        RecordingStatus {
+            file: self
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            seconds: self.started.elapsed().as_secs(),
+            bytes: self.bytes,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// File extension for the stream's format, once it is known
+    extension: Option<&'static str>,
+    recording: Option<Recording>,
+    /// How long stream bytes are kept in `replay`
+    replay_window: Duration,
+    /// Recent stream bytes and when they arrived, oldest first
+    replay: VecDeque<(Instant, Vec<u8>)>,
+}
+
+/// The last minutes of the stream, taken from a [`Recorder`] to be saved
+#[derive(Debug)]
+pub struct Replay {
+    extension: &'static str,
+    /// When the oldest chunk arrived
+    oldest: Instant,
+    chunks: Vec<Vec<u8>>,
+}
+
+impl Replay {
+    /// Save to a new file in `dir`; this blocks, so the app runs it on a
+    /// blocking thread
+    pub fn save(&self, dir: &Path, station: &str) -> io::Result<(PathBuf, RecordingStatus)> {
+        std::fs::create_dir_all(dir)?;
+        let name = format!(
+            "{}-{}-replay.{}",
+            station,
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            self.extension
+        );
+        let path = dir.join(&name);
+        let mut writer = BufWriter::new(File::create(&path)?);
+        let mut bytes = 0;
+        for chunk in &self.chunks {
+            writer.write_all(chunk)?;
+            bytes += chunk.len() as u64;
+        }
+        writer.flush()?;
+        let status = RecordingStatus {
+            file: name,
+            seconds: self.oldest.elapsed().as_secs(),
+            bytes,
+        };
+        Ok((path, status))
+    }
+}
+
+/// Starts, stops and feeds a recording of the playing stream
+#[derive(Clone, Default)]
+pub struct Recorder {
+    state: Arc<Mutex<State>>,
+}
+
+impl std::fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Recorder")
+            .field("status", &self.status())
+            .finish()
+    }
+}
+
+impl Recorder {
+    /// A recorder keeping the last `replay_window` of the stream
+    pub fn new(replay_window: Duration) -> Self {
+        let recorder = Self::default();
+        recorder.lock().replay_window = replay_window;
+        recorder
+    }
+
+    /// Start recording `station` into a new file in `dir`
+    pub fn start(&self, dir: &Path, station: &str) -> io::Result<PathBuf> {
+        let mut state = self.lock();
+        std::fs::create_dir_all(dir)?;
+        let name = format!(
+            "{}-{}.{}",
+            station,
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            state.extension.unwrap_or("mp3")
+        );
+        let path = dir.join(name);
+        let file = File::create(&path)?;
+        state.recording = Some(Recording {
+            writer: BufWriter::new(file),
+            path: path.clone(),
+            started: Instant::now(),
+            bytes: 0,
+        });
+        Ok(path)
+    }
+
+    /// Finish the recording, returning how it ended up
+    pub fn stop(&self) -> Option<(PathBuf, RecordingStatus)> {
+        let mut recording = self.lock().recording.take()?;
+        if let Err(error) = recording.writer.flush() {
+            warn!(
+                "Failed to finish recording {}: {}",
+                recording.path.display(),
+                error
+            );
+        }
+        let status = recording.status();
+        Some((recording.path, status))
+    }
+
+    pub fn status(&self) -> Option<RecordingStatus> {
+        self.lock().recording.as_ref().map(Recording::status)
+    }
+
+    /// A copy of the stream kept in memory, `None` when nothing has played
+    /// yet
+    ///
+    /// Only the copy is made under the lock, so the stream keeps being fed
+    /// while the copy is saved.
+    pub fn replay(&self) -> Option<Replay> {
+        let state = self.lock();
+        let (oldest, _) = state.replay.front()?;
+        Some(Replay {
+            extension: state.extension.unwrap_or("mp3"),
+            oldest: *oldest,
+            chunks: state
+                .replay
+                .iter()
+                .map(|(_, chunk)| chunk.clone())
+                .collect(),
+        })
+    }
+
+    /// Forget the kept stream, when another station starts
+    pub fn clear_replay(&self) {
+        self.lock().replay.clear();
+    }
+
+    /// Name the format of the stream being fed, for the next file's extension
+    pub(super) fn set_format(&self, extension: &'static str) {
+        self.lock().extension = Some(extension);
+    }
+
+    /// Keep stream bytes and append them to the recording, if one runs; a
+    /// failed write ends the recording
+    pub(super) fn write(&self, bytes: &[u8]) {
+        let mut state = self.lock();
+        if !state.replay_window.is_zero() && !bytes.is_empty() {
+            let now = Instant::now();
+            let window = state.replay_window;
+            while state
+                .replay
+                .front()
+                .is_some_and(|(arrived, _)| now.duration_since(*arrived) > window)
+            {
+                state.replay.pop_front();
+            }
+            state.replay.push_back((now, bytes.to_vec()));
+        }
+        let Some(recording) = state.recording.as_mut() else {
+            return;
+        };
+        match recording.writer.write_all(bytes) {
+            Ok(()) => recording.bytes += bytes.len() as u64,
+            Err(error) => {
+                warn!(
+                    "Recording to {} failed: {}",
+                    recording.path.display(),
+                    error
+                );
+                state.recording = None;
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        // A panic mid-write leaves nothing inconsistent worth refusing
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_while_started() {
+        let dir = std::env::temp_dir().join(format!("somars-recorder-{}", std::process::id()));
+        let recorder = Recorder::default();
+        recorder.write(b"before");
+        assert!(recorder.status().is_none());
+
+        recorder.set_format("aac");
+        let path = recorder.start(&dir, "groovesalad").unwrap();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("groovesalad-"));
+        assert_eq!(path.extension().unwrap(), "aac");
+        recorder.write(b"frames");
+        recorder.clone().write(b"!");
+        assert_eq!(recorder.status().unwrap().bytes, 7);
+
+        let (saved, status) = recorder.stop().unwrap();
+        recorder.write(b"after");
+        assert_eq!(saved, path);
+        assert_eq!(status.bytes, 7);
+        assert_eq!(std::fs::read(&path).unwrap(), b"frames!");
+        assert!(recorder.stop().is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn saves_what_just_played() {
+        let dir = std::env::temp_dir().join(format!("somars-replay-{}", std::process::id()));
+        let recorder = Recorder::new(Duration::from_secs(60));
+        assert!(recorder.replay().is_none());
+
+        recorder.write(b"heard ");
+        recorder.write(b"it");
+        let replay = recorder.replay().unwrap();
+        // What arrives after the copy was taken isn't saved
+        recorder.write(b" later");
+        let (path, status) = replay.save(&dir, "dronezone").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"heard it");
+        assert_eq!(status.bytes, 8);
+        // Saving leaves the recording state alone
+        assert!(recorder.status().is_none());
+
+        recorder.clear_replay();
+        assert!(recorder.replay().is_none());
+        assert!(Recorder::default().lock().replay_window.is_zero());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -98,10 +98,10 @@ impl RecoveryState {
 ///
 /// # Example
 /// ```no_run
-/// # use somars::audio::recovery::retry_with_backoff;
+/// # use somars_core::audio::recovery::retry_with_backoff;
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let result = retry_with_backoff(
-///     || async { Ok::<(), somars::audio::AudioError>(()) },
+///     || async { Ok::<(), somars_core::audio::types::AudioError>(()) },
 ///     Default::default(),
 /// ).await?;
 /// # Ok(())
@@ -0,0 +1,60 @@
+//! Silence detection
+//!
+//! Some Icecast relays keep the connection open while sending nothing but
+//! silence, so nothing looks wrong to the downloader. The decoded stream is
+//! measured as it arrives, and playback restarts once it has been silent for
+//! too long, which reconnects to the relay.
+
+use std::time::Duration;
+
+/// Peak amplitude below which a chunk counts as silent, about -66 dBFS
+const SILENCE_PEAK: f32 = 0.0005;
+
+/// Measures how long the decoded stream has been silent
+#[derive(Debug)]
+pub(super) struct SilenceDetector {
+    /// Interleaved samples in a second of audio
+    samples_per_second: u64,
+    /// Samples since the last one that wasn't silent
+    silent_samples: u64,
+}
+
+impl SilenceDetector {
+    pub(super) fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            samples_per_second: u64::from(sample_rate) * u64::from(channels.max(1)),
+            silent_samples: 0,
+        }
+    }
+
+    /// Take in the next chunk, returning how long the stream has been silent
+    pub(super) fn feed(&mut self, chunk: &[f32]) -> Duration {
+        let peak = chunk
+            .iter()
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+        if peak < SILENCE_PEAK {
+            self.silent_samples += chunk.len() as u64;
+        } else {
+            self.silent_samples = 0;
+        }
+        Duration::from_millis(self.silent_samples * 1000 / self.samples_per_second.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_silence_until_sound_returns() {
+        let mut silence = SilenceDetector::new(10, 2);
+        assert_eq!(silence.feed(&[0.0; 20]), Duration::from_secs(1));
+        // Dither and line noise still count as silence
+        assert_eq!(
+            silence.feed(&[0.0001, -0.0002].repeat(20)),
+            Duration::from_secs(3)
+        );
+        assert_eq!(silence.feed(&[0.0, 0.2, 0.0, 0.0]), Duration::ZERO);
+        assert_eq!(silence.feed(&[0.0; 10]), Duration::from_millis(500));
+    }
+}
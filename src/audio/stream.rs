@@ -5,6 +5,7 @@
 #![allow(dead_code)]
 
 use super::types::{AudioError, AudioResult};
+use crate::experimental::{self, Feature};
 use icy_metadata::RequestIcyMetadata;
 use reqwest::Url;
 use std::time::Duration;
@@ -27,6 +28,8 @@ pub struct StreamConfig {
     pub startup_buffer_seconds: u64,
     /// How long decoded PCM starvation may last before we rebuild the pipeline.
     pub stall_grace_period: Duration,
+    /// How long the stream may stay silent before it is restarted; zero disables.
+    pub silence_timeout: Duration,
     /// Delay before rebuilding after reconnect or starvation.
     pub restart_backoff: Duration,
     /// Maximum number of restarts before giving up.
@@ -35,6 +38,33 @@ pub struct StreamConfig {
     pub pcm_chunk_samples: usize,
     /// Number of PCM chunks buffered between decoder and output.
     pub pcm_buffer_chunks: usize,
+    /// Underruns within `downgrade_window` that switch to the low-bitrate stream (0 disables).
+    pub downgrade_underruns: u32,
+    /// Window over which underruns are counted for a downgrade.
+    pub downgrade_window: Duration,
+    /// Time without underruns before returning to the full-quality stream.
+    pub upgrade_after: Duration,
+    /// Encoding of the stream titles, `None` to detect it.
+    pub metadata_encoding: Option<&'static encoding_rs::Encoding>,
+    /// Cleanup applied to stream titles as they arrive.
+    pub title_rules: super::title_rules::TitleRules,
+    /// Fades the new stream in once it starts playing.
+    pub fader: super::transition::Fader,
+    /// The play request this stream belongs to; a superseded one stays out
+    /// of the sink.
+    pub generation: super::player::Generation,
+    /// Evens out the loudness of the decoded stream.
+    pub loudness: super::loudness::LoudnessConfig,
+    /// Equalizer bands, which can change while the stream plays.
+    pub equalizer: super::equalizer::EqualizerHandle,
+    /// Records the stream while a recording is running.
+    pub recorder: super::recorder::Recorder,
+    /// Seconds of played audio kept for rewinding; 0 disables the time shift.
+    pub time_shift_seconds: u64,
+    /// Rewinds and reports the time shift of the playing stream.
+    pub time_shift: super::time_shift::TimeShiftHandle,
+    /// Counts the bytes downloaded.
+    pub throughput: super::throughput::Throughput,
 }
 
 impl Default for StreamConfig {
@@ -47,10 +77,24 @@ impl Default for StreamConfig {
             retry_timeout: Duration::from_secs(15),
             startup_buffer_seconds: 1,
             stall_grace_period: Duration::from_secs(2),
+            silence_timeout: Duration::from_secs(30),
             restart_backoff: Duration::from_secs(1),
             max_restart_attempts: 10,
             pcm_chunk_samples: 8192,
             pcm_buffer_chunks: 64,
+            downgrade_underruns: 3,
+            downgrade_window: Duration::from_secs(60),
+            upgrade_after: Duration::from_secs(600),
+            metadata_encoding: None,
+            title_rules: Default::default(),
+            fader: Default::default(),
+            generation: Default::default(),
+            loudness: Default::default(),
+            equalizer: Default::default(),
+            recorder: Default::default(),
+            time_shift_seconds: 60,
+            time_shift: Default::default(),
+            throughput: Default::default(),
         }
     }
 }
@@ -61,6 +105,16 @@ impl StreamConfig {
             buffer_size: config.audio_buffer_size_bytes,
             prefetch_seconds: config.audio_prefetch_seconds,
             startup_prefetch_seconds: config.audio_startup_prefetch_seconds,
+            default_bitrate: config.audio_default_bitrate_kbps.max(1),
+            downgrade_underruns: config.audio_downgrade_underruns,
+            downgrade_window: Duration::from_secs(config.audio_downgrade_window_seconds),
+            upgrade_after: Duration::from_secs(config.audio_upgrade_after_seconds),
+            loudness: crate::audio::loudness::LoudnessConfig {
+                enabled: config.loudness.enabled && experimental::enabled(Feature::Dsp),
+                ..config.loudness
+            },
+            time_shift_seconds: config.audio_time_shift_seconds,
+            silence_timeout: Duration::from_secs(config.audio_silence_restart_seconds),
             ..Self::default()
         }
     }
@@ -72,9 +126,12 @@ impl StreamConfig {
 }
 
 /// Creates an HTTP client with ICY metadata support
+///
+/// Connecting and reading time out and go through a proxy as the
+/// `[network]` table says.
 pub fn create_icy_client() -> AudioResult<Client> {
-    Client::builder()
-        .request_icy_metadata()
+    crate::http::network()
+        .apply(Client::builder().request_icy_metadata())
         .build()
         .map_err(|e| {
             AudioError::InitializationFailed(format!("Failed to create HTTP client: {}", e))
@@ -82,8 +139,13 @@ pub fn create_icy_client() -> AudioResult<Client> {
 }
 
 /// Parses bitrate from ICY headers with fallback
+///
+/// Streams that leave out `icy-br`, or send 0, get the configured default.
 pub fn parse_bitrate_with_fallback(bitrate: Option<u32>, config: &StreamConfig) -> u64 {
-    bitrate.map(|b| b as u64).unwrap_or(config.default_bitrate)
+    bitrate
+        .filter(|&b| b > 0)
+        .map(|b| b as u64)
+        .unwrap_or(config.default_bitrate)
 }
 
 /// Calculates prefetch bytes based on bitrate
@@ -120,6 +182,12 @@ mod tests {
         let config = StreamConfig::default();
         assert_eq!(parse_bitrate_with_fallback(Some(256), &config), 256);
         assert_eq!(parse_bitrate_with_fallback(None, &config), 128);
+        assert_eq!(parse_bitrate_with_fallback(Some(0), &config), 128);
+        let config = StreamConfig::from_app_config(&crate::config::Config {
+            audio_default_bitrate_kbps: 64,
+            ..Default::default()
+        });
+        assert_eq!(parse_bitrate_with_fallback(None, &config), 64);
     }
 
     #[test]
@@ -0,0 +1,93 @@
+//! Network throughput
+//!
+//! The downloader counts every byte it receives for the playing stream, across
+//! reconnects and station changes. The app turns the count into a download
+//! rate for the bottom bar, next to the data used since somars started, which
+//! matters to anyone on a metered connection.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How far back the download rate is averaged
+const RATE_WINDOW: Duration = Duration::from_secs(3);
+
+/// Counts the bytes downloaded, shared by the app and the downloader
+#[derive(Debug, Clone, Default)]
+pub struct Throughput {
+    downloaded: Arc<AtomicU64>,
+}
+
+impl Throughput {
+    pub(super) fn add(&self, bytes: u64) {
+        self.downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Bytes downloaded since somars started
+    pub fn total(&self) -> u64 {
+        self.downloaded.load(Ordering::Relaxed)
+    }
+}
+
+/// Turns the growing byte count into bytes per second
+#[derive(Debug, Default)]
+pub struct RateMeter {
+    /// Recent counts and when they were taken, oldest first
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateMeter {
+    /// Take in the count at `now`, returning the average rate over the last
+    /// few seconds
+    pub fn sample(&mut self, now: Instant, total: u64) -> u64 {
+        while self
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, total));
+        let (oldest, from) = self.samples[0];
+        let elapsed = now.duration_since(oldest).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        (total.saturating_sub(from) as f64 / elapsed) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_the_rate_over_the_window() {
+        let throughput = Throughput::default();
+        let mut meter = RateMeter::default();
+        let start = Instant::now();
+        assert_eq!(meter.sample(start, throughput.total()), 0);
+
+        throughput.add(16_000);
+        assert_eq!(
+            meter.sample(start + Duration::from_secs(1), throughput.total()),
+            16_000
+        );
+        throughput.add(16_000);
+        assert_eq!(
+            meter.sample(start + Duration::from_secs(2), throughput.total()),
+            16_000
+        );
+        // Once the stream stops, the rate falls to nothing as the window moves on
+        assert_eq!(
+            meter.sample(start + Duration::from_secs(4), throughput.total()),
+            5_333
+        );
+        assert_eq!(
+            meter.sample(start + Duration::from_secs(9), throughput.total()),
+            0
+        );
+        assert_eq!(throughput.total(), 32_000);
+    }
+}
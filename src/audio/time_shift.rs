@@ -0,0 +1,204 @@
+//! Time shift
+//!
+//! The decoded stream is kept for a while after it plays so it can be heard
+//! again. Rewinding moves playback back into what played, and the stream that
+//! keeps arriving queues up behind it, so the connection never stalls and
+//! nothing is skipped until playback jumps forward or back to live.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Jumps through the time shift of the playing stream and reports how far
+/// behind live it is
+#[derive(Debug, Clone, Default)]
+pub struct TimeShiftHandle {
+    /// Seconds to move by at the next chunk; negative rewinds
+    pending: Arc<AtomicI64>,
+    /// Milliseconds of stream queued behind playback
+    behind_ms: Arc<AtomicU64>,
+}
+
+impl TimeShiftHandle {
+    /// Move playback by `seconds`: back into what played when negative,
+    /// toward live when positive
+    pub fn jump(&self, seconds: i64) {
+        let _ = self
+            .pending
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pending| {
+                Some(pending.saturating_add(seconds))
+            });
+    }
+
+    /// Skip whatever is queued and play the live stream again
+    pub fn go_live(&self) {
+        self.pending.store(i64::MAX, Ordering::SeqCst);
+    }
+
+    /// How far playback is behind the live stream
+    pub fn behind(&self) -> Duration {
+        Duration::from_millis(self.behind_ms.load(Ordering::SeqCst))
+    }
+
+    /// Forget the last stream's position, for a new one
+    fn reset(&self) {
+        self.pending.store(0, Ordering::SeqCst);
+        self.behind_ms.store(0, Ordering::SeqCst);
+    }
+}
+
+/// The played and queued chunks of one stream
+#[derive(Debug)]
+pub(super) struct TimeShift {
+    handle: TimeShiftHandle,
+    /// Interleaved samples in a second of audio
+    samples_per_second: usize,
+    /// Most samples of played audio kept
+    capacity: usize,
+    played: VecDeque<Vec<f32>>,
+    played_samples: usize,
+    /// Chunks to play before taking new ones from the stream
+    queued: VecDeque<Vec<f32>>,
+    queued_samples: usize,
+}
+
+impl TimeShift {
+    pub(super) fn new(
+        handle: TimeShiftHandle,
+        seconds: u64,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Self {
+        handle.reset();
+        let samples_per_second = sample_rate as usize * usize::from(channels.max(1));
+        Self {
+            handle,
+            samples_per_second,
+            capacity: samples_per_second.saturating_mul(seconds as usize),
+            played: VecDeque::new(),
+            played_samples: 0,
+            queued: VecDeque::new(),
+            queued_samples: 0,
+        }
+    }
+
+    /// Whether playback is behind live, so new chunks must queue
+    pub(super) fn is_behind(&self) -> bool {
+        !self.queued.is_empty()
+    }
+
+    /// Keep a chunk that finished playing
+    pub(super) fn played(&mut self, chunk: Vec<f32>) {
+        if self.capacity == 0 || chunk.is_empty() {
+            return;
+        }
+        self.played_samples += chunk.len();
+        self.played.push_back(chunk);
+        while self.played_samples > self.capacity {
+            let Some(oldest) = self.played.pop_front() else {
+                break;
+            };
+            self.played_samples -= oldest.len();
+        }
+    }
+
+    /// Queue a chunk that arrived from the stream while behind live
+    pub(super) fn queue(&mut self, chunk: Vec<f32>) {
+        self.queued_samples += chunk.len();
+        self.queued.push_back(chunk);
+        self.report();
+    }
+
+    /// The next chunk to play, if playback is behind live
+    pub(super) fn next(&mut self) -> Option<Vec<f32>> {
+        let chunk = self.queued.pop_front()?;
+        self.queued_samples -= chunk.len();
+        self.report();
+        Some(chunk)
+    }
+
+    /// Carry out a jump asked for through the handle
+    pub(super) fn apply_jump(&mut self) {
+        let seconds = self.handle.pending.swap(0, Ordering::SeqCst);
+        let samples = (seconds.unsigned_abs() as usize).saturating_mul(self.samples_per_second);
+        if seconds < 0 {
+            self.rewind(samples);
+        } else if seconds > 0 {
+            self.forward(samples);
+        }
+    }
+
+    /// Move whole chunks of played audio back into the queue
+    fn rewind(&mut self, samples: usize) {
+        let mut moved = 0;
+        while moved < samples {
+            let Some(chunk) = self.played.pop_back() else {
+                break;
+            };
+            self.played_samples -= chunk.len();
+            moved += chunk.len();
+            self.queued_samples += chunk.len();
+            self.queued.push_front(chunk);
+        }
+        self.report();
+    }
+
+    /// Skip queued chunks, keeping them as played
+    fn forward(&mut self, samples: usize) {
+        let mut moved = 0;
+        while moved < samples {
+            let Some(chunk) = self.queued.pop_front() else {
+                break;
+            };
+            self.queued_samples -= chunk.len();
+            moved += chunk.len();
+            self.played(chunk);
+        }
+        self.report();
+    }
+
+    fn report(&self) {
+        let ms = self.queued_samples as u64 * 1000 / self.samples_per_second.max(1) as u64;
+        self.handle.behind_ms.store(ms, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One second of mono audio at 10 Hz, filled with `value`
+    fn second(value: f32) -> Vec<f32> {
+        vec![value; 10]
+    }
+
+    #[test]
+    fn rewinds_queues_and_returns_to_live() {
+        let handle = TimeShiftHandle::default();
+        let mut shift = TimeShift::new(handle.clone(), 3, 10, 1);
+        for value in 0..5 {
+            shift.played(second(value as f32));
+        }
+        // Only the last three seconds are kept
+        handle.jump(-10);
+        shift.apply_jump();
+        assert_eq!(handle.behind(), Duration::from_secs(3));
+        assert!(shift.is_behind());
+
+        // The stream keeps arriving behind the replay
+        shift.queue(second(5.0));
+        assert_eq!(shift.next().unwrap()[0], 2.0);
+        shift.played(second(2.0));
+        assert_eq!(handle.behind(), Duration::from_secs(3));
+
+        handle.jump(1);
+        shift.apply_jump();
+        assert_eq!(shift.next().unwrap()[0], 4.0);
+        handle.go_live();
+        shift.apply_jump();
+        assert!(!shift.is_behind());
+        assert_eq!(handle.behind(), Duration::ZERO);
+        assert!(shift.next().is_none());
+    }
+}
@@ -0,0 +1,131 @@
+//! Stream title cleanup
+//!
+//! Stations pad their ICY titles with things that aren't part of the track:
+//! `[SomaFM]` tags, promo URLs, runs of spaces. Titles are cleaned with regex
+//! rules as they arrive, so the display, history, scrobbler log and track log
+//! all see the same tidy `Artist - Title`.
+
+use crate::config::TitleCleanupConfig;
+use crate::station::Station;
+use regex::Regex;
+use tracing::warn;
+
+/// Rules for every station
+const BUILTIN: &[(&str, &str)] = &[
+    // "Now playing on ... visit www.example.com" and bare promo links
+    (r"(?i)\s*[-|~*]*\s*(https?://|www\.)\S+", ""),
+    (r"\s{2,}", " "),
+];
+
+/// Rules for stations by provider name
+const BUILTIN_BY_SOURCE: &[(&str, &[(&str, &str)])] = &[(
+    "SomaFM",
+    &[(r"(?i)\s*[-|]?\s*[\[(]\s*soma\s*fm(\.com)?\s*[\])]\s*$", "")],
+)];
+
+/// Compiled cleanup rules for one station
+#[derive(Debug, Clone, Default)]
+pub struct TitleRules {
+    rules: Vec<(Regex, String)>,
+}
+
+impl TitleRules {
+    /// Built-in rules for the station's provider, then configured rules for
+    /// every station, then those for the station's ID, stream URL or provider
+    ///
+    /// Patterns that don't compile are skipped with a warning.
+    pub fn for_station(config: &TitleCleanupConfig, station: &Station) -> Self {
+        let mut rules = TitleRules::default();
+        if !config.enabled {
+            return rules;
+        }
+        if config.builtin {
+            for (pattern, replace) in BUILTIN_BY_SOURCE
+                .iter()
+                .filter(|(source, _)| *source == station.source)
+                .flat_map(|(_, rules)| rules.iter())
+                .chain(BUILTIN)
+            {
+                rules.push(pattern, replace);
+            }
+        }
+        let station_rules = [&station.id, &station.url, &station.source]
+            .into_iter()
+            .filter_map(|key| config.stations.get(key))
+            .flatten();
+        for rule in config.rules.iter().chain(station_rules) {
+            rules.push(&rule.pattern, &rule.replace);
+        }
+        rules
+    }
+
+    /// `title` with every rule applied in order and the ends trimmed
+    pub fn apply(&self, title: &str) -> String {
+        let mut title = title.to_string();
+        for (regex, replace) in &self.rules {
+            title = regex.replace_all(&title, replace.as_str()).into_owned();
+        }
+        title.trim().to_string()
+    }
+
+    fn push(&mut self, pattern: &str, replace: &str) {
+        match Regex::new(pattern) {
+            Ok(regex) => self.rules.push((regex, replace.to_string())),
+            Err(error) => warn!("Skipping title cleanup rule {:?}: {}", pattern, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TitleRule;
+
+    fn rule(pattern: &str, replace: &str) -> TitleRule {
+        TitleRule {
+            pattern: pattern.to_string(),
+            replace: replace.to_string(),
+        }
+    }
+
+    fn station(id: &str, source: &str) -> Station {
+        Station {
+            id: id.to_string(),
+            source: source.to_string(),
+            ..Station::from_url("http://example.com/stream")
+        }
+    }
+
+    #[test]
+    fn cleans_titles_with_builtin_and_configured_rules() {
+        let mut config = TitleCleanupConfig::default();
+        let somafm = TitleRules::for_station(&config, &station("groovesalad", "SomaFM"));
+        assert_eq!(somafm.apply("Tycho  -  Awake [SomaFM]"), "Tycho - Awake");
+        assert_eq!(
+            somafm.apply("Bonobo - Kerala - www.example.com/promo"),
+            "Bonobo - Kerala"
+        );
+        // The SomaFM tag is only stripped on SomaFM stations
+        let other = TitleRules::for_station(&config, &station("other", "Radio Browser"));
+        assert_eq!(
+            other.apply("Tycho - Awake [SomaFM]"),
+            "Tycho - Awake [SomaFM]"
+        );
+
+        config.rules.push(rule(r"^\d+\.\s*", ""));
+        config
+            .stations
+            .insert("other".to_string(), vec![rule(r"\s*\(Live\)$", "")]);
+        config
+            .stations
+            .insert("unrelated".to_string(), vec![rule("Awake", "Asleep")]);
+        // Broken patterns are skipped
+        config.rules.push(rule("([", ""));
+        let other = TitleRules::for_station(&config, &station("other", "Radio Browser"));
+        assert_eq!(other.apply("07. Tycho - Awake (Live)"), "Tycho - Awake");
+
+        config.enabled = false;
+        let disabled = TitleRules::for_station(&config, &station("groovesalad", "SomaFM"));
+        assert_eq!(disabled.apply(" A  [SomaFM] "), "A  [SomaFM]");
+    }
+}
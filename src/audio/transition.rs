@@ -0,0 +1,130 @@
+//! Playback transitions
+//!
+//! Ramps the sink volume when playback starts, stops or switches stations so
+//! the audio doesn't cut in and out abruptly.
+
+use rodio::Sink;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Time between volume steps
+const STEP: Duration = Duration::from_millis(10);
+
+/// Fades the sink in and out
+///
+/// Clones share their state: starting a fade cancels the one in progress, so
+/// a station that starts playing during a fade-out isn't stopped by it.
+#[derive(Debug, Clone, Default)]
+pub struct Fader {
+    duration: Duration,
+    /// Bumped by every fade; a fade stops when it no longer matches
+    generation: Arc<AtomicU64>,
+}
+
+impl Fader {
+    /// Fades lasting `duration`; zero switches immediately
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            generation: Arc::default(),
+        }
+    }
+
+    /// Silence the sink and ramp it up to `volume`
+    ///
+    /// Call before appending the new source so it starts silent.
+    pub fn fade_in(&self, sink: &Arc<Mutex<Sink>>, volume: f32) {
+        let generation = self.next_generation();
+        if self.duration.is_zero() {
+            set_volume(sink, volume);
+            return;
+        }
+        set_volume(sink, 0.0);
+        self.spawn_ramp(sink.clone(), generation, 0.0, volume, false);
+    }
+
+    /// Ramp the sink down to silence, then stop it
+    pub fn fade_out(&self, sink: &Arc<Mutex<Sink>>) {
+        let generation = self.next_generation();
+        let playing = sink
+            .lock()
+            .map(|sink| !sink.empty() && !sink.is_paused())
+            .unwrap_or(false);
+        if self.duration.is_zero() || !playing {
+            if let Ok(sink) = sink.lock() {
+                sink.stop();
+            }
+            return;
+        }
+        let from = sink.lock().map(|sink| sink.volume()).unwrap_or(0.0);
+        self.spawn_ramp(sink.clone(), generation, from, 0.0, true);
+    }
+
+    fn next_generation(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn spawn_ramp(&self, sink: Arc<Mutex<Sink>>, generation: u64, from: f32, to: f32, stop: bool) {
+        let current = self.generation.clone();
+        let steps = (self.duration.as_millis() / STEP.as_millis()).max(1) as u32;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STEP);
+            for step in 1..=steps {
+                interval.tick().await;
+                if current.load(Ordering::SeqCst) != generation {
+                    return;
+                }
+                set_volume(&sink, from + (to - from) * step as f32 / steps as f32);
+            }
+            if stop && current.load(Ordering::SeqCst) == generation {
+                if let Ok(sink) = sink.lock() {
+                    sink.stop();
+                }
+            }
+        });
+    }
+}
+
+fn set_volume(sink: &Arc<Mutex<Sink>>, volume: f32) {
+    if let Ok(sink) = sink.lock() {
+        sink.set_volume(volume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::source::SineWave;
+
+    #[tokio::test]
+    async fn fades_out_unless_playback_restarts() {
+        let (sink, mut output) = Sink::new();
+        let sink = Arc::new(Mutex::new(sink));
+        let fader = Fader::new(Duration::from_millis(50));
+        // Pulling samples is what makes the sink act on a stop
+        let mut play = || {
+            output.by_ref().take(10_000).for_each(drop);
+        };
+
+        sink.lock().unwrap().append(SineWave::new(440.0));
+        play();
+        fader.fade_out(&sink);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        play();
+        assert_eq!(sink.lock().unwrap().volume(), 0.0);
+        assert!(sink.lock().unwrap().empty());
+
+        // A new station starting mid-fade cancels the fade-out
+        fader.fade_in(&sink, 0.8);
+        sink.lock().unwrap().append(SineWave::new(440.0));
+        play();
+        fader.clone().fade_out(&sink);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        fader.fade_in(&sink, 0.8);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        play();
+        assert_eq!(sink.lock().unwrap().volume(), 0.8);
+        assert!(!sink.lock().unwrap().empty());
+    }
+}
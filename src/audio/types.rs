@@ -35,6 +35,10 @@ pub enum AudioError {
     StreamRetryable(String),
     /// Permanent stream error - should not retry
     StreamPermanent(String),
+    /// The server has nothing at the stream URL, as when a mount moved
+    StreamNotFound(String),
+    /// The stream stayed silent however often it was restarted
+    StreamSilent,
     /// Generic error
     Other(String),
 }
@@ -57,6 +61,8 @@ impl fmt::Display for AudioError {
                 write!(f, "Stream temporarily unavailable: {}", msg)
             }
             AudioError::StreamPermanent(msg) => write!(f, "Permanent stream error: {}", msg),
+            AudioError::StreamNotFound(url) => write!(f, "Stream not found: {}", url),
+            AudioError::StreamSilent => write!(f, "Stream stays silent after restarting"),
             AudioError::Other(msg) => write!(f, "Audio error: {}", msg),
         }
     }
@@ -74,6 +80,8 @@ impl AudioError {
             AudioError::StreamConnectionFailed(_) => true,
             // These are permanent errors
             AudioError::StreamPermanent(_) => false,
+            AudioError::StreamNotFound(_) => false,
+            AudioError::StreamSilent => false,
             AudioError::SinkPoisoned => false,
             AudioError::InvalidUrl(_) => false,
             AudioError::InitializationFailed(_) => false,
@@ -0,0 +1,70 @@
+//! Volume curve
+//!
+//! Loudness is perceived logarithmically, so with a linear sink volume most of
+//! the audible change happens in the bottom tenth of the range. The UI volume
+//! is mapped through a decibel curve before it reaches the sink instead.
+
+use serde::{Deserialize, Serialize};
+
+/// Attenuation at the bottom of the 0–100% range
+const RANGE_DB: f32 = 50.0;
+/// Below this the curve fades linearly to silence, so 0% is really silent
+const FADE_TO_SILENCE: f32 = 0.1;
+
+/// How the UI volume maps to the sink gain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeCurve {
+    /// Even steps in decibels
+    #[default]
+    Logarithmic,
+    /// The UI volume is the gain
+    Linear,
+}
+
+impl VolumeCurve {
+    /// Sink gain for a UI volume between 0.0 and 2.0
+    ///
+    /// Both curves agree at 0%, 100% and 200%, the loudest boost.
+    pub fn gain(self, volume: f32) -> f32 {
+        let volume = volume.clamp(0.0, 2.0);
+        match self {
+            VolumeCurve::Linear => volume,
+            VolumeCurve::Logarithmic if volume <= 1.0 => {
+                let gain = db_to_gain((volume.max(FADE_TO_SILENCE) - 1.0) * RANGE_DB);
+                gain * (volume / FADE_TO_SILENCE).min(1.0)
+            }
+            // The boost above 100% stays linear, up to twice the gain
+            VolumeCurve::Logarithmic => volume,
+        }
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_volume_to_decibels() {
+        let log = VolumeCurve::Logarithmic;
+        assert_eq!(log.gain(0.0), 0.0);
+        assert_eq!(log.gain(1.0), 1.0);
+        assert_eq!(log.gain(2.0), 2.0);
+        // Half volume is 25 dB down rather than 6 dB
+        assert!((log.gain(0.5) - db_to_gain(-25.0)).abs() < 1e-6);
+        // Steps sound even: each 10% is the same ratio
+        assert!((log.gain(0.8) / log.gain(0.7) - log.gain(0.4) / log.gain(0.3)).abs() < 1e-4);
+        let mut last = 0.0;
+        for step in 1..=40 {
+            let gain = log.gain(step as f32 * 0.05);
+            assert!(gain > last);
+            last = gain;
+        }
+        assert_eq!(VolumeCurve::Linear.gain(0.5), 0.5);
+        assert_eq!(VolumeCurve::Linear.gain(3.0), 2.0);
+    }
+}
@@ -0,0 +1,253 @@
+//! SomaFM catalog disk cache
+//!
+//! The `channels.json` response and the stream URLs resolved from its
+//! playlists are kept in the user cache directory. Within the TTL a restart
+//! shows the stations without touching the network, and when SomaFM cannot be
+//! reached an older copy is used instead of an empty list.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Held while the cache file is changed, so a remembered stream can't bring
+/// back the catalog a fresh download just replaced
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Resolved streams on their way to the writer thread, `None` if it couldn't
+/// be started
+static STREAMS: Lazy<Option<mpsc::Sender<(String, String)>>> = Lazy::new(|| {
+    let (sender, streams) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("somars-catalog-cache".to_string())
+        .spawn(move || write_streams(&path(), streams))
+        .inspect_err(|error| warn!("Failed to start the catalog cache writer: {}", error))
+        .ok()
+        .map(|_| sender)
+});
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// Unix time the catalog was downloaded
+    fetched_at: u64,
+    /// Raw `channels.json` body
+    channels: String,
+    /// Stream URLs resolved from playlists, keyed by playlist URL
+    #[serde(default)]
+    streams: HashMap<String, String>,
+}
+
+/// On-disk copy of the SomaFM catalog
+#[derive(Debug, Clone)]
+pub struct CatalogCache {
+    path: PathBuf,
+    /// How long a download stays fresh, `None` when caching is disabled
+    ttl: Option<Duration>,
+}
+
+impl CatalogCache {
+    /// Cache with a TTL of `ttl_minutes`; zero disables it
+    ///
+    /// With `refresh`, the cached catalog is never fresh, so it is downloaded
+    /// again and the old copy is only used if that fails.
+    pub fn new(ttl_minutes: u64, refresh: bool) -> Self {
+        let ttl = (ttl_minutes > 0).then(|| {
+            if refresh {
+                Duration::ZERO
+            } else {
+                Duration::from_secs(ttl_minutes * 60)
+            }
+        });
+        Self { path: path(), ttl }
+    }
+
+    /// Cached `channels.json` body, if it is younger than the TTL
+    pub fn fresh(&self) -> Option<String> {
+        let ttl = self.ttl?;
+        let file = read(&self.path)?;
+        let age = now().saturating_sub(file.fetched_at);
+        (age < ttl.as_secs()).then_some(file.channels)
+    }
+
+    /// Cached `channels.json` body of any age
+    pub fn stale(&self) -> Option<String> {
+        self.ttl?;
+        read(&self.path).map(|file| file.channels)
+    }
+
+    /// Stream URLs resolved while the cached catalog was current
+    pub fn streams(&self) -> HashMap<String, String> {
+        read(&self.path)
+            .map(|file| file.streams)
+            .unwrap_or_default()
+    }
+
+    /// Replace the cached catalog with a fresh download
+    pub fn store(&self, channels: &str) {
+        if self.ttl.is_none() {
+            return;
+        }
+        let _writing = lock();
+        write(
+            &self.path,
+            &CacheFile {
+                fetched_at: now(),
+                channels: channels.to_string(),
+                streams: HashMap::new(),
+            },
+        );
+    }
+}
+
+/// Remember a resolved stream URL alongside the cached catalog
+///
+/// The cache file is written on its own thread, so this doesn't block the
+/// caller. Nothing is written when there is no cached catalog.
+pub fn remember_stream(playlist_url: &str, stream_url: &str) {
+    if let Some(streams) = STREAMS.as_ref() {
+        let _ = streams.send((playlist_url.to_string(), stream_url.to_string()));
+    }
+}
+
+/// Add the streams arriving on `streams` to the cache file at `path`, until
+/// the sender is gone
+///
+/// Streams resolved together, as when several playlists are prefetched, are
+/// written at once.
+fn write_streams(path: &Path, streams: mpsc::Receiver<(String, String)>) {
+    while let Ok(first) = streams.recv() {
+        let batch: Vec<(String, String)> =
+            std::iter::once(first).chain(streams.try_iter()).collect();
+        let _writing = lock();
+        let Some(mut file) = read(path) else {
+            continue;
+        };
+        let mut changed = false;
+        for (playlist_url, stream_url) in batch {
+            if file.streams.get(&playlist_url) != Some(&stream_url) {
+                file.streams.insert(playlist_url, stream_url);
+                changed = true;
+            }
+        }
+        if changed {
+            write(path, &file);
+        }
+    }
+}
+
+fn lock() -> MutexGuard<'static, ()> {
+    WRITING
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn path() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("somars")
+        .join("catalog.json")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn read(path: &Path) -> Option<CacheFile> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write(path: &Path, file: &CacheFile) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let contents = serde_json::to_string(file).map_err(std::io::Error::other)?;
+            std::fs::write(path, contents)
+        });
+    if let Err(error) = result {
+        warn!(
+            "Failed to write catalog cache {}: {}",
+            path.display(),
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_fresh_and_stale_copies() {
+        let path = std::env::temp_dir().join(format!("somars-catalog-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let cache = |ttl| CatalogCache {
+            path: path.clone(),
+            ttl,
+        };
+
+        let hour = Some(Duration::from_secs(3600));
+        assert_eq!(cache(hour).fresh(), None);
+        cache(hour).store(r#"{"channels":[]}"#);
+        assert_eq!(cache(hour).fresh().as_deref(), Some(r#"{"channels":[]}"#));
+
+        // A forced refresh treats the copy as expired but keeps it as a fallback
+        let refresh = cache(Some(Duration::ZERO));
+        assert_eq!(refresh.fresh(), None);
+        assert!(refresh.stale().is_some());
+        // Disabled caching reads nothing
+        assert_eq!(cache(None).stale(), None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn writes_remembered_streams_in_one_go() {
+        let path = std::env::temp_dir().join(format!(
+            "somars-catalog-streams-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let cache = CatalogCache {
+            path: path.clone(),
+            ttl: Some(Duration::from_secs(3600)),
+        };
+        let stream = |playlist: &str, url: &str| (playlist.to_string(), url.to_string());
+        let remember = |remembered: &[(String, String)]| {
+            let (sender, streams) = mpsc::channel();
+            for stream in remembered {
+                sender.send(stream.clone()).unwrap();
+            }
+            drop(sender);
+            write_streams(&path, streams);
+        };
+
+        // Nothing is written without a cached catalog
+        remember(&[stream("a.pls", "https://a/1")]);
+        assert!(!path.exists());
+
+        cache.store(r#"{"channels":[]}"#);
+        remember(&[
+            stream("a.pls", "https://a/1"),
+            stream("b.pls", "https://b/1"),
+        ]);
+        remember(&[stream("a.pls", "https://a/2")]);
+        assert_eq!(
+            cache.streams(),
+            HashMap::from([
+                stream("a.pls", "https://a/2"),
+                stream("b.pls", "https://b/1")
+            ])
+        );
+        // The catalog is kept
+        assert!(cache.fresh().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,192 @@
+//! Time display
+//!
+//! Timestamps in the history, the alarm and the track log are shown on a 12
+//! or 24-hour clock in the system's time zone, or in a zone named in the
+//! `[time]` config table. Naming one matters when somars runs on a server far
+//! from whoever listens to it.
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+/// Time display settings, read from the `[time]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimeConfig {
+    #[serde(default)]
+    pub clock: HourCycle,
+    /// IANA zone name such as `Europe/Berlin`, or `UTC`; the system's zone
+    /// when unset or `local`
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// How hours are counted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HourCycle {
+    #[default]
+    #[serde(rename = "24h")]
+    H24,
+    #[serde(rename = "12h")]
+    H12,
+}
+
+/// Where times are shown for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+/// Formats times the way the config asks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Clock {
+    pub cycle: HourCycle,
+    pub zone: Zone,
+}
+
+static CLOCK: OnceCell<Clock> = OnceCell::new();
+
+/// Set up the clock from `config`, failing on a zone name that isn't known
+///
+/// Only the first call has an effect; later calls are ignored.
+pub fn init(config: &TimeConfig) -> Result<(), String> {
+    let _ = CLOCK.set(Clock::from_config(config)?);
+    Ok(())
+}
+
+/// Get the active clock
+///
+/// Falls back to a 24-hour clock in the system's zone if [`init`] has not
+/// been called.
+pub fn current() -> Clock {
+    *CLOCK.get_or_init(Clock::default)
+}
+
+/// The time now, for a history message
+pub fn timestamp() -> String {
+    current().timestamp(Utc::now())
+}
+
+impl Clock {
+    pub fn from_config(config: &TimeConfig) -> Result<Self, String> {
+        let zone = match config.timezone.as_deref().map(str::trim) {
+            None | Some("") => Zone::Local,
+            Some(name) if name.eq_ignore_ascii_case("local") => Zone::Local,
+            Some(name) => Zone::Named(name.parse::<Tz>().map_err(|_| {
+                format!(
+                    "Unknown time zone {:?}, expected a name such as \"Europe/Berlin\" or \"UTC\"",
+                    name
+                )
+            })?),
+        };
+        Ok(Self {
+            cycle: config.clock,
+            zone,
+        })
+    }
+
+    /// `14:05:09` or `02:05:09 PM`
+    pub fn timestamp(&self, time: DateTime<Utc>) -> String {
+        self.format(time, "%H:%M:%S", "%I:%M:%S %p")
+    }
+
+    /// `14:05` or `02:05 PM`
+    pub fn time(&self, time: DateTime<Utc>) -> String {
+        self.format(time, "%H:%M", "%I:%M %p")
+    }
+
+    /// `2024-03-01 14:05` or `2024-03-01 02:05 PM`
+    pub fn date_time(&self, time: DateTime<Utc>) -> String {
+        self.format(time, "%Y-%m-%d %H:%M", "%Y-%m-%d %I:%M %p")
+    }
+
+    /// `2024-03-01T14:05:09+01:00`, for files other programs read
+    pub fn rfc3339(&self, time: DateTime<Utc>) -> String {
+        let format = chrono::SecondsFormat::Secs;
+        match self.zone {
+            Zone::Local => time.with_timezone(&Local).to_rfc3339_opts(format, true),
+            Zone::Named(tz) => time.with_timezone(&tz).to_rfc3339_opts(format, true),
+        }
+    }
+
+    /// The first time after `now` the clock shows `time` of day
+    pub fn next(&self, time: NaiveTime, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self.zone {
+            Zone::Local => next_in(time, now.with_timezone(&Local)).with_timezone(&Utc),
+            Zone::Named(tz) => next_in(time, now.with_timezone(&tz)).with_timezone(&Utc),
+        }
+    }
+
+    fn format(&self, time: DateTime<Utc>, h24: &str, h12: &str) -> String {
+        let format = match self.cycle {
+            HourCycle::H24 => h24,
+            HourCycle::H12 => h12,
+        };
+        match self.zone {
+            Zone::Local => time.with_timezone(&Local).format(format).to_string(),
+            Zone::Named(tz) => time.with_timezone(&tz).format(format).to_string(),
+        }
+    }
+}
+
+/// The first time after `now` that is `time` of day in `now`'s zone
+pub fn next_in<Z: TimeZone>(time: NaiveTime, now: DateTime<Z>) -> DateTime<Z> {
+    let zone = now.timezone();
+    let mut day = now.date_naive();
+    loop {
+        // Skip times that don't exist on a daylight saving change
+        if let Some(at) = day
+            .and_time(time)
+            .and_local_timezone(zone.clone())
+            .earliest()
+        {
+            if at > now {
+                return at;
+            }
+        }
+        day = day.succ_opt().unwrap_or(day);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(cycle: HourCycle, timezone: &str) -> Clock {
+        Clock::from_config(&TimeConfig {
+            clock: cycle,
+            timezone: Some(timezone.to_string()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn formats_in_the_configured_zone_and_cycle() {
+        let time = Utc.with_ymd_and_hms(2024, 3, 1, 21, 5, 9).unwrap();
+        let utc = clock(HourCycle::H24, "UTC");
+        assert_eq!(utc.timestamp(time), "21:05:09");
+        assert_eq!(utc.date_time(time), "2024-03-01 21:05");
+
+        let tokyo = clock(HourCycle::H12, "Asia/Tokyo");
+        assert_eq!(tokyo.timestamp(time), "06:05:09 AM");
+        assert_eq!(tokyo.date_time(time), "2024-03-02 06:05 AM");
+        assert_eq!(tokyo.rfc3339(time), "2024-03-02T06:05:09+09:00");
+        assert_eq!(utc.rfc3339(time), "2024-03-01T21:05:09Z");
+        assert_eq!(
+            tokyo.next(NaiveTime::from_hms_opt(7, 30, 0).unwrap(), time),
+            Utc.with_ymd_and_hms(2024, 3, 1, 22, 30, 0).unwrap()
+        );
+
+        assert_eq!(clock(HourCycle::H24, "local").zone, Zone::Local);
+        assert_eq!(clock(HourCycle::H24, " ").zone, Zone::Local);
+        assert!(Clock::from_config(&TimeConfig {
+            timezone: Some("Mars/Olympus_Mons".to_string()),
+            ..TimeConfig::default()
+        })
+        .is_err());
+        let config: TimeConfig = toml::from_str("clock = \"12h\"").unwrap();
+        assert_eq!(config.clock, HourCycle::H12);
+    }
+}
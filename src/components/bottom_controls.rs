@@ -2,25 +2,51 @@
 //!
 //! Displays keyboard shortcuts and debug information at the bottom of the screen.
 
-use crate::{action::Action, components, i18n::t};
+use crate::{
+    action::Action, audio::player::PlayerEvent, components, i18n::t, terminal_caps, theme, utils,
+    PlaybackState,
+};
 
 use color_eyre::eyre::Result;
 use components::Component;
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Padding, Paragraph},
     Frame,
 };
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::UnboundedSender;
 
+/// Number of characters used by the level sparkline
+const SPARKLINE_WIDTH: usize = 16;
+/// How long the new version notice stays up
+const UPDATE_NOTICE: Duration = Duration::from_secs(30);
+
 /// Bottom controls bar component
 pub struct BottomControls {
     /// Log level (affects debug display)
     log_level: u8,
     /// Audio sink length (for debug display)
     sink_len: usize,
+    /// Recent audio levels (0.0 to 1.0), oldest first
+    levels: VecDeque<f32>,
+    /// Levels arrived since the last tick drew them
+    levels_changed: bool,
+    /// Buffer underruns in the current playback session
+    underruns: u32,
+    /// Download rate in bytes per second and bytes downloaded since start
+    throughput: Option<(u64, u64)>,
+    /// Bytes downloaded today and the daily cap
+    data_today: (u64, Option<u64>),
+    /// Stations come from the cache because the catalog is unreachable
+    offline: bool,
+    /// A newer release and when it was announced
+    update: Option<(String, Instant)>,
+    /// A recording is running
+    recording: bool,
     /// Action sender
     action_tx: Option<UnboundedSender<Action>>,
 }
@@ -31,6 +57,14 @@ impl BottomControls {
         Self {
             log_level: 1,
             sink_len: 0,
+            levels: VecDeque::with_capacity(SPARKLINE_WIDTH * 2),
+            levels_changed: false,
+            underruns: 0,
+            throughput: None,
+            data_today: (0, None),
+            offline: false,
+            update: None,
+            recording: false,
             action_tx: None,
         }
     }
@@ -42,10 +76,49 @@ impl BottomControls {
     }
 
     /// Set the sink length (for debug display)
-    #[allow(dead_code)]
     pub fn set_sink_len(&mut self, len: usize) {
         self.sink_len = len;
     }
+
+    /// Record an audio level sample for the sparkline
+    pub fn push_level(&mut self, level: f32) {
+        self.levels.push_back(level.clamp(0.0, 1.0));
+        self.levels_changed = true;
+        while self.levels.len() > SPARKLINE_WIDTH * 2 {
+            self.levels.pop_front();
+        }
+    }
+}
+
+/// Render levels as a braille sparkline, two samples per character
+fn braille_sparkline(levels: &VecDeque<f32>) -> String {
+    // Dot bits for each column, from the bottom row up
+    const LEFT: [u32; 4] = [0x40, 0x04, 0x02, 0x01];
+    const RIGHT: [u32; 4] = [0x80, 0x20, 0x10, 0x08];
+    let height = |level: f32| (level * 4.0).round() as usize;
+    let column = |dots: &[u32; 4], level: f32| dots.iter().take(height(level)).sum::<u32>();
+
+    let padding = (SPARKLINE_WIDTH * 2).saturating_sub(levels.len());
+    let samples: Vec<f32> = std::iter::repeat_n(0.0, padding)
+        .chain(levels.iter().copied())
+        .collect();
+    samples
+        .chunks(2)
+        .map(|pair| {
+            let dots = column(&LEFT, pair[0]) + column(&RIGHT, pair.get(1).copied().unwrap_or(0.0));
+            char::from_u32(0x2800 + dots).unwrap_or(' ')
+        })
+        .collect()
+}
+
+/// Render levels as an ASCII sparkline, one sample per character
+fn ascii_sparkline(levels: &VecDeque<f32>) -> String {
+    const BARS: [char; 5] = [' ', '.', ':', '|', '#'];
+    levels
+        .iter()
+        .skip(levels.len().saturating_sub(SPARKLINE_WIDTH))
+        .map(|level| BARS[(level * 4.0).round() as usize])
+        .collect()
 }
 
 impl Component for BottomControls {
@@ -54,50 +127,168 @@ impl Component for BottomControls {
         Ok(())
     }
 
-    fn update(&mut self, _action: Action) -> Result<Option<Action>> {
-        // BottomControls doesn't need to react to actions
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::AudioLevel(level) => self.push_level(level),
+            // Levels arrive several times a second and are drawn on the tick
+            Action::Tick if std::mem::take(&mut self.levels_changed) => {
+                return Ok(Some(Action::Render))
+            }
+            Action::SetUnderruns(count) => self.underruns = count,
+            Action::Player(PlayerEvent::Queued(len)) => self.set_sink_len(len),
+            Action::SetThroughput {
+                rate,
+                total,
+                today,
+                cap,
+            } => {
+                self.throughput = Some((rate, total));
+                self.data_today = (today, cap);
+            }
+            Action::SetOffline(offline) => self.offline = offline,
+            Action::SetRecording(status) => self.recording = status.is_some(),
+            Action::UpdateAvailable { version, .. } => {
+                self.update = Some((version, Instant::now()));
+            }
+            Action::SetPlaybackState(PlaybackState::Stopped) => self.levels.clear(),
+            _ => {}
+        }
         Ok(None)
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let theme = theme::current();
+
         // Build control key spans
-        let mut bottom_controls_spans = vec![
+        let mut bottom_controls_spans = Vec::new();
+        if self.recording {
+            bottom_controls_spans.extend([
+                Span::styled(
+                    format!("{} {}", terminal_caps::current().symbol("●", "*"), t("rec")),
+                    Style::default()
+                        .fg(theme.error)
+                        .add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(" "),
+            ]);
+        }
+        if self.offline {
+            bottom_controls_spans.extend([
+                Span::styled(
+                    format!(" {} ", t("offline")),
+                    Style::default()
+                        .fg(theme.error)
+                        .add_modifier(ratatui::style::Modifier::BOLD)
+                        .add_modifier(ratatui::style::Modifier::REVERSED),
+                ),
+                Span::raw(" "),
+            ]);
+        }
+        if let Some((version, _)) = self
+            .update
+            .as_ref()
+            .filter(|(_, announced)| announced.elapsed() < UPDATE_NOTICE)
+        {
+            bottom_controls_spans.extend([
+                Span::styled(
+                    format!(" {} ", t("update-badge").replace("{$version}", version)),
+                    Style::default()
+                        .fg(theme.success)
+                        .add_modifier(ratatui::style::Modifier::BOLD)
+                        .add_modifier(ratatui::style::Modifier::REVERSED),
+                ),
+                Span::raw(" "),
+            ]);
+        }
+        bottom_controls_spans.extend([
             Span::styled(
                 "q",
                 Style::default()
-                    .fg(Color::Yellow)
+                    .fg(theme.warning)
                     .add_modifier(ratatui::style::Modifier::BOLD),
             ),
             Span::raw(format!(":{} ", t("controls-quit"))),
             Span::styled(
-                "↵",
+                terminal_caps::current().symbol("↵", "Enter"),
                 Style::default()
-                    .fg(Color::Green)
+                    .fg(theme.success)
                     .add_modifier(ratatui::style::Modifier::BOLD),
             ),
             Span::raw(format!(":{} ", t("controls-play"))),
             Span::styled(
                 "Space",
                 Style::default()
-                    .fg(Color::Blue)
+                    .fg(theme.info)
                     .add_modifier(ratatui::style::Modifier::BOLD),
             ),
             Span::raw(format!(":{}/{} ", t("controls-stop"), t("controls-start"))),
             Span::styled(
                 "+/-",
                 Style::default()
-                    .fg(Color::Cyan)
+                    .fg(theme.secondary)
                     .add_modifier(ratatui::style::Modifier::BOLD),
             ),
             Span::raw(format!(":{} ", t("controls-volume"))),
             Span::styled(
                 "?",
                 Style::default()
-                    .fg(Color::Magenta)
+                    .fg(theme.accent)
                     .add_modifier(ratatui::style::Modifier::BOLD),
             ),
             Span::raw(format!(":{} ", t("controls-help"))),
-        ];
+        ]);
+
+        // Show whether audio is actually flowing
+        if !self.levels.is_empty() {
+            let sparkline = if terminal_caps::current().unicode {
+                braille_sparkline(&self.levels)
+            } else {
+                ascii_sparkline(&self.levels)
+            };
+            bottom_controls_spans.extend(vec![
+                Span::raw(" "),
+                Span::styled(sparkline, Style::default().fg(theme.success)),
+            ]);
+        }
+
+        if let Some((rate, total)) = self.throughput {
+            let (today, cap) = self.data_today;
+            let today_text = match cap {
+                Some(cap) => t("data-today-cap")
+                    .replace("{$size}", &utils::format_size(today))
+                    .replace("{$cap}", &utils::format_size(cap)),
+                None => t("data-today").replace("{$size}", &utils::format_size(today)),
+            };
+            // Nearing the cap
+            let color = if cap.is_some_and(|cap| today >= cap / 10 * 9) {
+                theme.warning
+            } else {
+                theme.muted
+            };
+            bottom_controls_spans.extend(vec![
+                Span::raw(" "),
+                Span::styled(
+                    format!(
+                        "{} {}/s ({}, {})",
+                        terminal_caps::current().symbol("↓", "v"),
+                        utils::format_size(rate),
+                        t("data-used").replace("{$size}", &utils::format_size(total)),
+                        today_text
+                    ),
+                    Style::default().fg(color),
+                ),
+            ]);
+        }
+
+        if self.underruns > 0 {
+            bottom_controls_spans.extend(vec![
+                Span::raw(" "),
+                Span::styled(
+                    format!("{}: {}", t("underruns"), self.underruns),
+                    Style::default().fg(theme.warning),
+                ),
+            ]);
+        }
 
         // Add debug info if log level is high
         if self.log_level > 1 {
@@ -105,7 +296,7 @@ impl Component for BottomControls {
                 Span::raw("  "),
                 Span::styled(
                     format!("Sink: {}", self.sink_len),
-                    Style::default().fg(Color::Cyan),
+                    Style::default().fg(theme.secondary),
                 ),
             ]);
         }
@@ -125,3 +316,43 @@ impl Default for BottomControls {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braille_sparkline_encodes_two_samples_per_cell() {
+        let levels = VecDeque::from(vec![1.0, 0.0, 0.25, 1.0]);
+        let sparkline: Vec<char> = braille_sparkline(&levels).chars().collect();
+        assert_eq!(sparkline.len(), SPARKLINE_WIDTH);
+        assert_eq!(sparkline[0], '\u{2800}');
+        // Full left column, empty right column
+        assert_eq!(sparkline[SPARKLINE_WIDTH - 2], '\u{2847}');
+        // One dot on the left, full right column
+        assert_eq!(sparkline[SPARKLINE_WIDTH - 1], '\u{28F8}');
+    }
+
+    #[test]
+    fn level_history_is_bounded() {
+        let mut controls = BottomControls::new();
+        for _ in 0..100 {
+            controls.push_level(0.5);
+        }
+        assert_eq!(controls.levels.len(), SPARKLINE_WIDTH * 2);
+        assert_eq!(
+            ascii_sparkline(&controls.levels),
+            ":".repeat(SPARKLINE_WIDTH)
+        );
+    }
+
+    #[test]
+    fn redraws_on_the_tick_after_new_levels() {
+        let mut controls = BottomControls::new();
+        assert_eq!(controls.update(Action::Tick).unwrap(), None);
+        controls.update(Action::AudioLevel(0.3)).unwrap();
+        controls.update(Action::AudioLevel(0.6)).unwrap();
+        assert_eq!(controls.update(Action::Tick).unwrap(), Some(Action::Render));
+        assert_eq!(controls.update(Action::Tick).unwrap(), None);
+    }
+}
@@ -2,13 +2,19 @@
 //!
 //! Displays keyboard shortcuts and usage information.
 
-use crate::{action::Action, components, i18n::t};
+use crate::{
+    action::Action,
+    components,
+    experimental::{self, Feature},
+    i18n::t,
+    terminal_caps, theme,
+};
 
 use color_eyre::eyre::Result;
 use components::Component;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::{Constraint, Flex, Layout, Rect},
+    layout::Rect,
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Padding, Paragraph, Wrap},
     Frame,
@@ -50,17 +56,9 @@ impl Help {
         self.visible
     }
 
-    /// Calculate the popup area
-    fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-        let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
-        let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
-        let [area] = vertical.areas(area);
-        let [area] = horizontal.areas(area);
-        area
-    }
-
     /// Build the help text content
     fn build_help_text() -> Vec<Line<'static>> {
+        let caps = terminal_caps::current();
         vec![
             Line::from(vec![Span::styled(
                 format!("{} - {}", env!("CARGO_PKG_NAME"), t("app-description")),
@@ -71,7 +69,7 @@ impl Help {
             Line::from(""),
             Line::from(vec![
                 Span::styled(
-                    "↵ (Enter)",
+                    caps.symbol("↵ (Enter)", "Enter"),
                     ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
                 ),
                 Span::raw(format!(" - {}", t("help-enter"))),
@@ -97,7 +95,166 @@ impl Help {
             ]),
             Line::from(vec![
                 Span::styled(
-                    "↑/↓",
+                    caps.symbol("←/→ [ {", "Left/Right [ {"),
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-time-shift"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "]",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-live"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "b",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-quality"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "e",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " - {}{}",
+                    t("help-equalizer"),
+                    experimental::label(Feature::Dsp)
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "R",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " - {}{}",
+                    t("help-record"),
+                    experimental::label(Feature::Recording)
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "S",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(
+                    " - {}{}",
+                    t("help-save-replay"),
+                    experimental::label(Feature::Recording)
+                )),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "E",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-export-history"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "r",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-refresh"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "s",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-sort"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "g/G",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-genre"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "Alt+key",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-genre-chip"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "1-9",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-presets"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    caps.symbol("⌫ (Backspace)", "Backspace"),
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-back"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "x",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-random"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "l",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-like"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "c",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-sessions"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "m",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-history-filter"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "F",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-history-search"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "f",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-track-search"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "L",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-language"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "T",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-stats"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    caps.symbol("↑/↓", "Up/Down"),
                     ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
                 ),
                 Span::raw(format!(" - {}", t("help-arrows"))),
@@ -116,6 +273,13 @@ impl Help {
                 ),
                 Span::raw(format!(" - {}", t("help-toggle-help"))),
             ]),
+            Line::from(vec![
+                Span::styled(
+                    "F1",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-hints"))),
+            ]),
             Line::from(""),
             Line::from(t("help-cli")),
             Line::from(""),
@@ -133,11 +297,25 @@ impl Help {
                 ),
                 Span::raw(format!(" - {}", t("help-station"))),
             ]),
+            Line::from(vec![
+                Span::styled(
+                    "--url <URL>",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-url"))),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "--alarm <HH:MM>",
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::raw(format!(" - {}", t("help-alarm"))),
+            ]),
             Line::from(""),
             Line::from(t("help-homepage")),
             Line::from(vec![Span::styled(
                 env!("CARGO_PKG_HOMEPAGE"),
-                ratatui::style::Style::default().fg(ratatui::style::Color::Blue),
+                ratatui::style::Style::default().fg(theme::current().info),
             )]),
         ]
     }
@@ -192,7 +370,7 @@ impl Component for Help {
         info!("Drawing help overlay (visible={})", self.visible);
 
         let help_text = Self::build_help_text();
-        let popup_area = Self::popup_area(area, 60, 60);
+        let popup_area = components::popup_area(area, 60, 60);
         let help_widget = Paragraph::new(help_text)
             .block(
                 Block::default()
@@ -1,34 +1,108 @@
 //! History log component
 //!
 //! Displays the history of events and messages with text wrapping and caching.
+//! Messages are grouped into listening sessions, from play to stop, under
+//! headers that can be collapsed. `m` narrows the list to errors or playback
+//! messages and `F` searches it as you type.
 
 use crate::{
-    action::Action, components, i18n::t, utils::format_duration, HistoryMessage, MessageType,
-    PlaybackState,
+    action::Action, components, i18n::t, terminal_caps, theme, track_log::session_summary,
+    utils::format_duration, HistoryMessage, MessageType, PlaybackState,
 };
 
 use color_eyre::eyre::Result;
 use components::Component;
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::{Modifier, Style},
     text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListDirection, ListItem, ListState},
     Frame,
 };
 use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
 use tokio::sync::mpsc::UnboundedSender;
 
+/// Messages from one play to the next stop or station change
+#[derive(Debug)]
+struct Session {
+    id: u64,
+    /// Known once the first track title arrives
+    station: Option<String>,
+    tracks: usize,
+    last_title: Option<String>,
+    started: Instant,
+    /// `None` while the session is playing
+    ended: Option<Instant>,
+    collapsed: bool,
+}
+
+impl Session {
+    fn summary(&self) -> String {
+        let length = self.ended.unwrap_or_else(Instant::now) - self.started;
+        session_summary(self.station.as_deref().unwrap_or("…"), length, self.tracks)
+    }
+}
+
+/// Kinds of messages shown, cycled with `m`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum MessageFilter {
+    #[default]
+    All,
+    Errors,
+    Playback,
+}
+
+impl MessageFilter {
+    fn next(self) -> Self {
+        match self {
+            Self::All => Self::Errors,
+            Self::Errors => Self::Playback,
+            Self::Playback => Self::All,
+        }
+    }
+
+    fn shows(self, message_type: &MessageType) -> bool {
+        match self {
+            Self::All => true,
+            Self::Errors => *message_type == MessageType::Error,
+            Self::Playback => *message_type == MessageType::Playback,
+        }
+    }
+
+    /// Message ID of the name shown in the title, none when nothing is left out
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Self::All => None,
+            Self::Errors => Some("history-filter-errors"),
+            Self::Playback => Some("history-filter-playback"),
+        }
+    }
+}
+
+/// A line of the history list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    /// Header of the session with this id
+    Header(u64),
+    /// Message at this index
+    Message(usize),
+}
+
 /// History component
 pub struct History {
-    /// History messages
+    /// History messages, the newest `max_entries` of them
     messages: VecDeque<HistoryMessage>,
+    max_entries: usize,
+    /// Messages dropped off the front, so the number of `messages[idx]` is
+    /// `dropped + idx` for as long as it is kept
+    dropped: u64,
     /// Scroll state
     scroll_state: ListState,
     /// Log level filter
     log_level: u8,
-    /// Cache for wrapped text
-    wrapped_cache: HashMap<usize, Vec<String>>,
+    /// Wrapped text by message number
+    wrapped_cache: HashMap<u64, Vec<String>>,
     /// Whether the cache is valid
     cache_valid: bool,
     /// Last known width
@@ -39,15 +113,31 @@ pub struct History {
     total_played: std::time::Duration,
     /// Playback start time
     playback_start_time: Option<std::time::Instant>,
+    /// Whole seconds of played time last shown
+    played_shown: u64,
     /// Action sender
     action_tx: Option<UnboundedSender<Action>>,
+    /// Listening sessions that still have messages, oldest first
+    sessions: Vec<Session>,
+    /// Session of each message, parallel to `messages`
+    message_sessions: VecDeque<Option<u64>>,
+    next_session_id: u64,
+    active_station: Option<usize>,
+    filter: MessageFilter,
+    /// Words a message must all contain to be shown
+    search: String,
+    /// Whether typing goes to `search`
+    searching: bool,
 }
 
 impl History {
-    /// Create a new history component
-    pub fn new() -> Self {
+    /// Create a new history component keeping up to `max_entries` messages
+    pub fn new(max_entries: usize) -> Self {
+        let max_entries = max_entries.max(1);
         Self {
-            messages: VecDeque::with_capacity(1000),
+            messages: VecDeque::with_capacity(max_entries),
+            max_entries,
+            dropped: 0,
             scroll_state: ListState::default(),
             log_level: 1,
             wrapped_cache: HashMap::new(),
@@ -56,30 +146,48 @@ impl History {
             playback_state: PlaybackState::Stopped,
             total_played: std::time::Duration::default(),
             playback_start_time: None,
+            played_shown: 0,
             action_tx: None,
+            sessions: Vec::new(),
+            message_sessions: VecDeque::with_capacity(max_entries),
+            next_session_id: 0,
+            active_station: None,
+            filter: MessageFilter::default(),
+            search: String::new(),
+            searching: false,
         }
     }
 
     /// Add a message to the history
     pub fn add_message(&mut self, message: HistoryMessage) {
-        let preserve_position =
-            self.scroll_state.selected().is_some() && self.message_is_visible(&message);
-
         self.messages.push_back(message);
-        self.cache_valid = false;
+        self.message_sessions
+            .push_back(self.open_session().map(|session| session.id));
+
+        // Rows added on top, a message and perhaps its session header
+        let added = self.rows_added_by_newest();
 
-        while self.messages.len() > 1000 {
+        // Rows are counted from the newest, so dropping the oldest leaves
+        // the selection where it is
+        while self.messages.len() > self.max_entries {
             self.messages.pop_front();
+            self.message_sessions.pop_front();
+            self.wrapped_cache.remove(&self.dropped);
+            self.dropped += 1;
         }
+        let oldest = self.message_sessions.iter().flatten().next().copied();
+        self.sessions.retain(|session| {
+            session.ended.is_none() || oldest.is_some_and(|oldest| session.id >= oldest)
+        });
 
-        if preserve_position {
+        if added > 0 {
             if let Some(selected) = self.scroll_state.selected() {
-                self.scroll_state.select(Some(selected + 1));
-                *self.scroll_state.offset_mut() += 1;
+                self.scroll_state.select(Some(selected + added));
+                *self.scroll_state.offset_mut() += added;
             }
         }
 
-        let visible_count = self.visible_messages().len();
+        let visible_count = self.rows().len();
         if visible_count == 0 {
             self.scroll_state = ListState::default();
         } else {
@@ -94,7 +202,10 @@ impl History {
     /// Clear all messages
     #[allow(dead_code)]
     pub fn clear(&mut self) {
+        self.dropped += self.messages.len() as u64;
         self.messages.clear();
+        self.message_sessions.clear();
+        self.sessions.retain(|session| session.ended.is_none());
         self.cache_valid = false;
         self.scroll_state.select(Some(0));
     }
@@ -106,9 +217,112 @@ impl History {
 
     /// Set the playback state
     pub fn set_playback_state(&mut self, state: PlaybackState) {
+        match state {
+            PlaybackState::Playing => self.start_session(),
+            PlaybackState::Stopped => self.end_session(),
+            // A pause doesn't end the session
+            _ => {}
+        }
         self.playback_state = state;
     }
 
+    /// Playback moved to another station, which starts a new session
+    fn set_active_station(&mut self, station: Option<usize>) {
+        if self.active_station == station {
+            return;
+        }
+        self.active_station = station;
+        self.end_session();
+        if self.playback_state == PlaybackState::Playing {
+            self.start_session();
+        }
+    }
+
+    /// A track title arrived; counts it in the open session
+    fn track_started(&mut self, station: &str, title: &str) {
+        let Some(session) = self.open_session_mut() else {
+            return;
+        };
+        if session
+            .station
+            .as_deref()
+            .is_some_and(|known| known != station)
+        {
+            return;
+        }
+        session.station = Some(station.to_string());
+        if !title.trim().is_empty() && session.last_title.as_deref() != Some(title) {
+            session.last_title = Some(title.to_string());
+            session.tracks += 1;
+        }
+    }
+
+    fn start_session(&mut self) {
+        if self.open_session().is_some() {
+            return;
+        }
+        self.sessions.push(Session {
+            id: self.next_session_id,
+            station: None,
+            tracks: 0,
+            last_title: None,
+            started: Instant::now(),
+            ended: None,
+            collapsed: false,
+        });
+        self.next_session_id += 1;
+    }
+
+    fn end_session(&mut self) {
+        if let Some(session) = self.open_session_mut() {
+            session.ended = Some(Instant::now());
+        }
+        let referenced: Vec<u64> = self.message_sessions.iter().flatten().copied().collect();
+        self.sessions
+            .retain(|session| session.ended.is_none() || referenced.contains(&session.id));
+    }
+
+    fn open_session(&self) -> Option<&Session> {
+        self.sessions
+            .last()
+            .filter(|session| session.ended.is_none())
+    }
+
+    fn open_session_mut(&mut self) -> Option<&mut Session> {
+        self.sessions
+            .last_mut()
+            .filter(|session| session.ended.is_none())
+    }
+
+    fn session(&self, id: u64) -> Option<&Session> {
+        self.sessions.iter().find(|session| session.id == id)
+    }
+
+    /// Collapse or expand the session of the selected row, or the newest one
+    fn toggle_session(&mut self) {
+        let rows = self.rows();
+        let id = match self
+            .scroll_state
+            .selected()
+            .and_then(|selected| rows.get(selected))
+        {
+            Some(Row::Header(id)) => Some(*id),
+            Some(Row::Message(idx)) => self.message_sessions.get(*idx).copied().flatten(),
+            None => self.message_sessions.iter().rev().flatten().next().copied(),
+        };
+        let Some(session) = id.and_then(|id| self.sessions.iter_mut().find(|s| s.id == id)) else {
+            return;
+        };
+        session.collapsed = !session.collapsed;
+        let header = Row::Header(session.id);
+
+        // Keep the selection on the session that was toggled
+        if self.scroll_state.selected().is_some() {
+            let position = self.rows().iter().position(|row| *row == header);
+            self.scroll_state.select(position);
+        }
+    }
+
     /// Set the total played time
     pub fn set_total_played(&mut self, duration: std::time::Duration) {
         self.total_played = duration;
@@ -150,7 +364,7 @@ impl History {
 
     /// Scroll toward newer messages.
     fn scroll_up(&mut self) {
-        let visible_count = self.visible_messages().len();
+        let visible_count = self.rows().len();
         if visible_count == 0 {
             return;
         }
@@ -166,7 +380,7 @@ impl History {
 
     /// Scroll toward older messages.
     fn scroll_down(&mut self) {
-        let visible_count = self.visible_messages().len();
+        let visible_count = self.rows().len();
         if visible_count == 0 {
             return;
         }
@@ -187,44 +401,165 @@ impl History {
         self.cache_valid = false;
     }
 
-    /// Ensure cache is valid
+    /// Ensure cache is valid, wrapping only messages added since the last
+    /// call unless the width changed
     fn ensure_cache_valid(&mut self, width: u16) {
-        if self.cache_valid && self.last_width == width {
-            return;
+        if !self.cache_valid || self.last_width != width {
+            self.wrapped_cache.clear();
+            self.cache_valid = true;
+            self.last_width = width;
         }
 
-        self.wrapped_cache.clear();
-        let message_width = width.saturating_sub(10) as usize;
-
         for (idx, msg) in self.messages.iter().enumerate() {
-            let wrapped: Vec<String> = textwrap::wrap(&msg.message, message_width)
-                .into_iter()
-                .map(|s| s.to_string())
-                .collect();
-            self.wrapped_cache.insert(idx, wrapped);
+            self.wrapped_cache
+                .entry(self.dropped + idx as u64)
+                .or_insert_with(|| {
+                    // Room left after the timestamp, which is longer on a 12-hour clock
+                    let message_width = width.saturating_sub(indent(msg) as u16) as usize;
+                    textwrap::wrap(&msg.message, message_width)
+                        .into_iter()
+                        .map(|s| s.to_string())
+                        .collect()
+                });
         }
-
-        self.cache_valid = true;
-        self.last_width = width;
     }
 
     fn message_is_visible(&self, message: &HistoryMessage) -> bool {
-        self.log_level > 1
+        let shown_at_level = self.log_level > 1
             || matches!(
                 message.message_type,
-                MessageType::Error | MessageType::Info | MessageType::Playback
-            )
+                MessageType::Error
+                    | MessageType::Info
+                    | MessageType::Playback
+                    | MessageType::Notice
+            );
+        shown_at_level && self.filter.shows(&message.message_type) && self.matches_search(message)
+    }
+
+    fn matches_search(&self, message: &HistoryMessage) -> bool {
+        if self.search.trim().is_empty() {
+            return true;
+        }
+        let text = message.message.to_lowercase();
+        self.search
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| text.contains(word))
+    }
+
+    /// Show the next kind of messages, from the newest
+    fn cycle_filter(&mut self) {
+        self.filter = self.filter.next();
+        self.scroll_state = ListState::default();
+    }
+
+    /// Search for `search` instead, from the newest match
+    fn set_search(&mut self, search: String) {
+        self.search = search;
+        self.scroll_state = ListState::default();
+    }
+
+    /// Keys typed into the search
+    fn handle_search_key(&mut self, key: crossterm::event::KeyEvent) -> Option<Action> {
+        use crossterm::event::KeyCode;
+
+        match key.code {
+            KeyCode::Esc => {
+                self.set_search(String::new());
+                return Some(Action::CloseHistorySearch);
+            }
+            KeyCode::Enter => return Some(Action::CloseHistorySearch),
+            KeyCode::Backspace => {
+                let mut search = std::mem::take(&mut self.search);
+                search.pop();
+                self.set_search(search);
+            }
+            KeyCode::Char(c) => {
+                let search = format!("{}{}", self.search, c);
+                self.set_search(search);
+            }
+            KeyCode::Up => self.scroll_up(),
+            KeyCode::Down => self.scroll_down(),
+            _ => {}
+        }
+        None
+    }
+
+    /// The panel title, with the filter and search in effect
+    fn title(&self) -> Line<'static> {
+        let theme = theme::current();
+        let mut spans = vec![Span::raw(t("history"))];
+        if let Some(label) = self.filter.label() {
+            spans.push(Span::styled(
+                format!(" · {}", t(label)),
+                Style::default().fg(theme.accent),
+            ));
+        }
+        if self.searching || !self.search.is_empty() {
+            spans.push(Span::styled(
+                format!(" / {}", self.search),
+                Style::default().fg(theme.label),
+            ));
+        }
+        if self.searching {
+            spans.push(Span::styled(
+                terminal_caps::current().symbol("▏", "_"),
+                Style::default().fg(theme.muted),
+            ));
+        }
+        Line::from(spans)
     }
 
     /// Return visible messages from newest to oldest.
-    fn visible_messages(&self) -> Vec<(usize, HistoryMessage)> {
+    fn visible_messages(&self) -> impl Iterator<Item = (usize, &HistoryMessage)> {
         self.messages
             .iter()
             .enumerate()
             .rev()
             .filter(|(_, msg)| self.message_is_visible(msg))
-            .map(|(idx, msg)| (idx, msg.clone()))
-            .collect()
+    }
+
+    /// Rows the newest message added on top of the list: none when it is
+    /// hidden, its own unless its session is collapsed, and its session's
+    /// header when the message before it wasn't in that session
+    fn rows_added_by_newest(&self) -> usize {
+        let mut visible = self.visible_messages();
+        let Some((idx, _)) = visible
+            .next()
+            .filter(|(idx, _)| idx + 1 == self.messages.len())
+        else {
+            return 0;
+        };
+        let session = self.message_sessions.get(idx).copied().flatten();
+        let previous = visible
+            .next()
+            .and_then(|(idx, _)| self.message_sessions.get(idx).copied().flatten());
+        let header = session.is_some() && session != previous;
+        let collapsed = session
+            .and_then(|id| self.session(id))
+            .is_some_and(|session| session.collapsed);
+        usize::from(header) + usize::from(!collapsed)
+    }
+
+    /// Return the list rows from newest to oldest, with a header above the
+    /// messages of each session and collapsed sessions' messages left out.
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut current = None;
+        for (idx, _) in self.visible_messages() {
+            let session = self.message_sessions.get(idx).copied().flatten();
+            if session.is_some() && session != current {
+                rows.extend(session.map(Row::Header));
+            }
+            current = session;
+            let collapsed = session
+                .and_then(|id| self.session(id))
+                .is_some_and(|session| session.collapsed);
+            if !collapsed {
+                rows.push(Row::Message(idx));
+            }
+        }
+        rows
     }
 }
 
@@ -237,6 +572,9 @@ impl Component for History {
     fn handle_key_event(&mut self, key: crossterm::event::KeyEvent) -> Result<Option<Action>> {
         use crossterm::event::KeyCode;
 
+        if self.searching {
+            return Ok(self.handle_search_key(key));
+        }
         match key.code {
             KeyCode::Char('j') => {
                 self.scroll_down();
@@ -248,8 +586,18 @@ impl Component for History {
             }
             KeyCode::Esc => {
                 self.scroll_state = ListState::default();
+                self.search.clear();
+                Ok(None)
+            }
+            KeyCode::Char('c') => {
+                self.toggle_session();
+                Ok(None)
+            }
+            KeyCode::Char('m') => {
+                self.cycle_filter();
                 Ok(None)
             }
+            KeyCode::Char('F') => Ok(Some(Action::OpenHistorySearch)),
             _ => Ok(None),
         }
     }
@@ -257,6 +605,7 @@ impl Component for History {
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
             Action::AddHistoryMessage(msg) => {
+                crate::logging::record_history(&msg);
                 self.add_message(msg);
             }
             Action::SetLogLevel(level) => {
@@ -268,11 +617,27 @@ impl Component for History {
             Action::ScrollHistoryDown => {
                 self.scroll_down();
             }
+            Action::OpenHistorySearch => {
+                self.searching = true;
+            }
+            Action::CloseHistorySearch => {
+                self.searching = false;
+            }
             Action::SetPlaybackState(state) => {
                 self.set_playback_state(state);
             }
+            Action::SetActiveStation(station) => {
+                self.set_active_station(station);
+            }
+            Action::MetadataUpdate { station, title } => {
+                self.track_started(&station, &title);
+            }
             Action::SetTotalPlayed(duration) => {
                 self.set_total_played(duration);
+                let played = self.current_played_time().as_secs();
+                if played != std::mem::replace(&mut self.played_shown, played) {
+                    return Ok(Some(Action::Render));
+                }
             }
             Action::StartTrackingPlayTime => {
                 self.start_tracking_play_time();
@@ -292,23 +657,45 @@ impl Component for History {
         // Ensure cache is valid
         self.ensure_cache_valid(area.width);
 
-        let visible_messages = self.visible_messages();
+        let rows = self.rows();
+        let theme = theme::current();
 
-        let history_items: Vec<ListItem> = visible_messages
+        let history_items: Vec<ListItem> = rows
             .iter()
-            .map(|(idx, msg)| {
+            .filter_map(|row| {
+                let idx = match *row {
+                    Row::Header(id) => {
+                        let session = self.session(id)?;
+                        let marker = if session.collapsed { "▸ " } else { "▾ " };
+                        return Some(ListItem::new(Line::from(Span::styled(
+                            format!("{}{}", marker, session.summary()),
+                            Style::default()
+                                .fg(theme.accent)
+                                .add_modifier(Modifier::BOLD),
+                        ))));
+                    }
+                    Row::Message(idx) => idx,
+                };
+                let msg = self.messages.get(idx)?;
                 let style = match msg.message_type {
-                    MessageType::Error => Style::default().fg(Color::Red),
-                    MessageType::Info => Style::default().fg(Color::Green),
-                    MessageType::System => Style::default().fg(Color::Green),
-                    MessageType::Background => Style::default().fg(Color::DarkGray),
-                    MessageType::Playback => Style::default().fg(Color::White),
+                    MessageType::Error => Style::default().fg(theme.error),
+                    MessageType::Info => Style::default().fg(theme.success),
+                    MessageType::System => Style::default().fg(theme.success),
+                    MessageType::Background => Style::default().fg(theme.muted),
+                    MessageType::Playback => Style::default().fg(theme.text),
+                    MessageType::Notice => Style::default()
+                        .fg(theme.accent)
+                        .add_modifier(Modifier::BOLD),
                 };
 
-                let timestamp_span = Span::styled(&msg.timestamp, style);
+                let timestamp_span = Span::styled(msg.timestamp.clone(), style);
 
                 // Get wrapped text from cache
-                let wrapped_lines = self.wrapped_cache.get(idx).cloned().unwrap_or_default();
+                let wrapped_lines = self
+                    .wrapped_cache
+                    .get(&(self.dropped + idx as u64))
+                    .cloned()
+                    .unwrap_or_default();
 
                 // Create lines with proper alignment
                 let mut lines = Vec::new();
@@ -322,29 +709,29 @@ impl Component for History {
 
                 for line in wrapped_lines.iter().skip(1) {
                     lines.push(Line::from(vec![
-                        Span::styled("          ", style),
+                        Span::styled(" ".repeat(indent(msg)), style),
                         Span::styled(line.clone(), style),
                     ]));
                 }
 
-                ListItem::new(Text::from(lines))
+                Some(ListItem::new(Text::from(lines)))
             })
             .collect();
 
         let selected_pos = self.scroll_state.selected().unwrap_or(0) + 1;
-        let total_history = visible_messages.len();
+        let total_history = rows.len();
 
         let current_time = self.current_played_time();
         let time_str = format_duration(current_time);
 
         let history_list = List::new(history_items)
             .direction(ListDirection::TopToBottom)
-            .highlight_style(Style::default().bg(Color::Blue))
+            .highlight_style(Style::default().bg(theme.selection))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(t("history"))
-                    .title(Line::from("[jk Esc]").right_aligned())
+                    .title(self.title())
+                    .title(Line::from("[jk c m F Esc]").right_aligned())
                     .title_bottom(
                         Line::from(vec![Span::raw(format!(
                             "[{} / {}]",
@@ -365,10 +752,15 @@ impl Component for History {
 
 impl Default for History {
     fn default() -> Self {
-        Self::new()
+        Self::new(1000)
     }
 }
 
+/// Columns before a message's text: its timestamp and a gap
+fn indent(message: &HistoryMessage) -> usize {
+    message.timestamp.chars().count() + 2
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -384,15 +776,14 @@ mod tests {
 
     #[test]
     fn shows_newest_visible_message_first() {
-        let mut history = History::new();
+        let mut history = History::new(1000);
         history.add_message(message("old", MessageType::Info));
         history.add_message(message("hidden", MessageType::Background));
         history.add_message(message("new", MessageType::Playback));
 
         let messages: Vec<_> = history
             .visible_messages()
-            .into_iter()
-            .map(|(_, message)| message.message)
+            .map(|(_, message)| message.message.clone())
             .collect();
 
         assert_eq!(messages, ["new", "old"]);
@@ -400,7 +791,7 @@ mod tests {
 
     #[test]
     fn navigation_moves_down_to_older_and_up_to_newer() {
-        let mut history = History::new();
+        let mut history = History::new(1000);
         history.add_message(message("old", MessageType::Info));
         history.add_message(message("middle", MessageType::Info));
         history.add_message(message("new", MessageType::Info));
@@ -415,7 +806,7 @@ mod tests {
 
     #[test]
     fn new_message_preserves_manually_scrolled_position() {
-        let mut history = History::new();
+        let mut history = History::new(1000);
         history.add_message(message("old", MessageType::Info));
         history.add_message(message("new", MessageType::Info));
         history.scroll_down();
@@ -423,12 +814,12 @@ mod tests {
         history.add_message(message("newest", MessageType::Info));
 
         assert_eq!(history.scroll_state.selected(), Some(2));
-        assert_eq!(history.visible_messages()[2].1.message, "old");
+        assert_eq!(history.visible_messages().nth(2).unwrap().1.message, "old");
     }
 
     #[test]
     fn escape_returns_to_latest_message() {
-        let mut history = History::new();
+        let mut history = History::new(1000);
         history.add_message(message("old", MessageType::Info));
         history.add_message(message("new", MessageType::Info));
         history.scroll_down();
@@ -439,4 +830,174 @@ mod tests {
 
         assert_eq!(history.scroll_state, ListState::default());
     }
+
+    #[test]
+    fn groups_messages_into_collapsible_sessions() {
+        let mut history = History::new(1000);
+        history.add_message(message("before", MessageType::Info));
+        history.update(Action::SetActiveStation(Some(0))).unwrap();
+        history
+            .update(Action::SetPlaybackState(PlaybackState::Playing))
+            .unwrap();
+        for title in ["A - One", "A - One", "A - Two"] {
+            history
+                .update(Action::MetadataUpdate {
+                    station: "Groove Salad".to_string(),
+                    title: title.to_string(),
+                })
+                .unwrap();
+        }
+        history.add_message(message("one", MessageType::Playback));
+        history.add_message(message("two", MessageType::Playback));
+        history.update(Action::SetActiveStation(Some(1))).unwrap();
+        history.add_message(message("other", MessageType::Playback));
+
+        let first = history.sessions[0].id;
+        let second = history.sessions[1].id;
+        assert_eq!(history.sessions[0].tracks, 2);
+        assert_eq!(
+            history.rows(),
+            [
+                Row::Header(second),
+                Row::Message(3),
+                Row::Header(first),
+                Row::Message(2),
+                Row::Message(1),
+                Row::Message(0),
+            ]
+        );
+
+        // Collapsing keeps the header selected and hides its messages
+        history.scroll_state.select(Some(3));
+        history
+            .handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE))
+            .unwrap();
+        assert!(history.sessions[0].collapsed);
+        assert_eq!(history.scroll_state.selected(), Some(2));
+        assert_eq!(
+            history.rows(),
+            [
+                Row::Header(second),
+                Row::Message(3),
+                Row::Header(first),
+                Row::Message(0),
+            ]
+        );
+
+        // Stopping ends the session; later messages have none
+        history
+            .update(Action::SetPlaybackState(PlaybackState::Stopped))
+            .unwrap();
+        history.add_message(message("after", MessageType::Info));
+        assert_eq!(history.rows()[0], Row::Message(4));
+        assert!(history
+            .sessions
+            .iter()
+            .all(|session| session.ended.is_some()));
+    }
+
+    #[test]
+    fn keeps_the_selected_row_as_sessions_grow() {
+        let mut history = History::new(1000);
+        history.add_message(message("before", MessageType::Info));
+        history.scroll_state.select(Some(0));
+        let selected = |history: &History| history.rows()[history.scroll_state.selected().unwrap()];
+
+        // A new session adds its header and message above
+        history.update(Action::SetActiveStation(Some(0))).unwrap();
+        history
+            .update(Action::SetPlaybackState(PlaybackState::Playing))
+            .unwrap();
+        history.add_message(message("one", MessageType::Playback));
+        assert_eq!(selected(&history), Row::Message(0));
+        // The header moves up over the next message of the same session
+        history.add_message(message("two", MessageType::Playback));
+        assert_eq!(selected(&history), Row::Message(0));
+        // Hidden messages add nothing
+        history.add_message(message("hidden", MessageType::Background));
+        assert_eq!(selected(&history), Row::Message(0));
+
+        history.sessions[0].collapsed = true;
+        history.add_message(message("three", MessageType::Playback));
+        assert_eq!(selected(&history), Row::Message(0));
+    }
+
+    #[test]
+    fn drops_the_oldest_messages_and_keeps_the_selection() {
+        let mut history = History::new(3);
+        for text in ["one", "two", "three"] {
+            history.add_message(message(text, MessageType::Info));
+        }
+        history.ensure_cache_valid(80);
+        history.scroll_down();
+        history.scroll_up();
+        let selected =
+            |history: &History| match history.rows()[history.scroll_state.selected().unwrap()] {
+                Row::Message(idx) => history.messages[idx].message.clone(),
+                Row::Header(_) => unreachable!(),
+            };
+        assert_eq!(selected(&history), "three");
+
+        history.add_message(message("four", MessageType::Info));
+        history.add_message(message("five", MessageType::Info));
+        assert_eq!(history.messages.len(), 3);
+        assert_eq!(selected(&history), "three");
+        // Once its message is dropped, the selection rests on the oldest kept
+        history.add_message(message("six", MessageType::Info));
+        assert_eq!(selected(&history), "four");
+
+        // Only the new messages are wrapped, and the dropped ones let go
+        history.ensure_cache_valid(80);
+        assert_eq!(history.wrapped_cache.len(), 3);
+        assert_eq!(history.wrapped_cache[&history.dropped], ["four"]);
+    }
+
+    #[test]
+    fn filters_by_kind_and_searches_as_you_type() {
+        let mut history = History::new(1000);
+        history.add_message(message("Playing Groove Salad", MessageType::Playback));
+        history.add_message(message("Stream error: timed out", MessageType::Error));
+        history.add_message(message("Playing Drone Zone", MessageType::Playback));
+        let shown = |history: &History| -> Vec<String> {
+            history
+                .visible_messages()
+                .map(|(_, message)| message.message.clone())
+                .collect()
+        };
+        let press = |history: &mut History, code| {
+            history
+                .handle_key_event(KeyEvent::new(code, KeyModifiers::NONE))
+                .unwrap()
+        };
+
+        press(&mut history, KeyCode::Char('m'));
+        assert_eq!(shown(&history), ["Stream error: timed out"]);
+        press(&mut history, KeyCode::Char('m'));
+        assert_eq!(shown(&history).len(), 2);
+        press(&mut history, KeyCode::Char('m'));
+        assert_eq!(shown(&history).len(), 3);
+
+        assert_eq!(
+            press(&mut history, KeyCode::Char('F')),
+            Some(Action::OpenHistorySearch)
+        );
+        history.update(Action::OpenHistorySearch).unwrap();
+        for c in "drone ZONE".chars() {
+            // Typed keys don't reach the shortcuts while searching
+            assert_eq!(press(&mut history, KeyCode::Char(c)), None);
+        }
+        assert_eq!(shown(&history), ["Playing Drone Zone"]);
+        assert_eq!(history.filter, MessageFilter::All);
+
+        // Enter keeps the search, Esc drops it
+        assert_eq!(
+            press(&mut history, KeyCode::Enter),
+            Some(Action::CloseHistorySearch)
+        );
+        history.update(Action::CloseHistorySearch).unwrap();
+        assert_eq!(shown(&history), ["Playing Drone Zone"]);
+        history.update(Action::OpenHistorySearch).unwrap();
+        press(&mut history, KeyCode::Esc);
+        assert_eq!(shown(&history).len(), 3);
+    }
 }
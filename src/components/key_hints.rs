@@ -0,0 +1,220 @@
+//! Key hint overlay
+//!
+//! A small box in the corner listing the keys that work where the keyboard
+//! is, taken from the [`keymap`](crate::keymap). F1 pins it open or closes it,
+//! and it shows for a few seconds by itself when a popup opens or closes.
+
+use crate::{
+    action::Action,
+    components,
+    i18n::t,
+    keymap::{Binding, KeyContext},
+    terminal_caps, theme,
+};
+
+use color_eyre::eyre::Result;
+use components::Component;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// How long the hints show after the context changes
+const BRIEFLY: Duration = Duration::from_secs(3);
+/// Most rows before the hints wrap into another column
+const MAX_ROWS: usize = 8;
+/// Space between columns
+const GAP: usize = 3;
+
+/// Key hint overlay component
+pub struct KeyHints {
+    context: KeyContext,
+    /// Toggled on with F1; stays until toggled off
+    pinned: bool,
+    /// Shown without pinning until then
+    shown_until: Option<Instant>,
+    /// Action sender, to redraw once the hints time out
+    action_tx: Option<UnboundedSender<Action>>,
+}
+
+impl KeyHints {
+    pub fn new() -> Self {
+        Self {
+            context: KeyContext::default(),
+            pinned: false,
+            shown_until: None,
+            action_tx: None,
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        self.pinned || self.shown_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    fn toggle(&mut self) {
+        self.pinned = !self.is_visible();
+        self.shown_until = None;
+    }
+
+    /// Switch to `context`, showing its keys for a moment
+    fn enter(&mut self, context: KeyContext) {
+        if context == self.context {
+            return;
+        }
+        self.context = context;
+        self.shown_until = Some(Instant::now() + BRIEFLY);
+        if let Some(tx) = self.action_tx.clone() {
+            tokio::spawn(async move {
+                tokio::time::sleep(BRIEFLY).await;
+                let _ = tx.send(Action::Render);
+            });
+        }
+    }
+
+    /// Lines of the hints, laid out in columns, and their width
+    fn lines(bindings: &[Binding]) -> (Vec<Line<'static>>, usize) {
+        let theme = theme::current();
+        let caps = terminal_caps::current();
+        let entries: Vec<(&str, String)> = bindings
+            .iter()
+            .map(|binding| {
+                (
+                    caps.symbol(binding.keys, binding.plain),
+                    t(binding.description),
+                )
+            })
+            .collect();
+        let columns: Vec<&[(&str, String)]> = entries.chunks(MAX_ROWS).collect();
+        let widths: Vec<(usize, usize)> = columns
+            .iter()
+            .map(|column| {
+                let key = column.iter().map(|(key, _)| key.chars().count()).max();
+                let text = column.iter().map(|(_, text)| text.chars().count()).max();
+                (key.unwrap_or(0), text.unwrap_or(0))
+            })
+            .collect();
+        let width = widths
+            .iter()
+            .map(|(key, text)| key + 1 + text)
+            .sum::<usize>()
+            + GAP * widths.len().saturating_sub(1);
+
+        let rows = entries.len().min(MAX_ROWS);
+        let lines = (0..rows)
+            .map(|row| {
+                let mut spans = Vec::new();
+                for (index, (column, (key_width, text_width))) in
+                    columns.iter().zip(&widths).enumerate()
+                {
+                    let Some((key, text)) = column.get(row) else {
+                        break;
+                    };
+                    if index > 0 {
+                        spans.push(Span::raw(" ".repeat(GAP)));
+                    }
+                    spans.push(Span::styled(
+                        format!("{key:>key_width$} "),
+                        Style::default().fg(theme.info).add_modifier(Modifier::BOLD),
+                    ));
+                    spans.push(Span::styled(
+                        format!("{text:<text_width$}"),
+                        Style::default().fg(theme.text),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        (lines, width)
+    }
+}
+
+impl Component for KeyHints {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.action_tx = Some(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::ToggleKeyHints => self.toggle(),
+            Action::OpenTrackSearch => self.enter(KeyContext::TrackSearch),
+            Action::OpenHistorySearch => self.enter(KeyContext::HistorySearch),
+            Action::OpenLanguagePicker => self.enter(KeyContext::LanguagePicker),
+            Action::OpenStats => self.enter(KeyContext::Stats),
+            Action::CloseTrackSearch
+            | Action::CloseHistorySearch
+            | Action::CloseLanguagePicker
+            | Action::CloseStats
+            | Action::SetLocale(_) => self.enter(KeyContext::Stations),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// Draw in the bottom right corner of `area`, sized to the context's keys
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.is_visible() {
+            return Ok(());
+        }
+        let (lines, width) = Self::lines(self.context.bindings());
+        // Borders and padding
+        let width = (width as u16 + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let hints_area = Rect {
+            x: area.right().saturating_sub(width),
+            y: area.bottom().saturating_sub(height),
+            width,
+            height,
+        };
+        let hints = Paragraph::new(lines).block(
+            Block::default()
+                .title(format!(" {} ", t(self.context.title())))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .padding(Padding::horizontal(1))
+                .border_style(Style::default().fg(theme::current().muted)),
+        );
+        frame.render_widget(Clear, hints_area);
+        frame.render_widget(hints, hints_area);
+        Ok(())
+    }
+}
+
+impl Default for KeyHints {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_on_toggle_and_briefly_on_context_change() {
+        let mut hints = KeyHints::new();
+        assert!(!hints.is_visible());
+        hints.update(Action::ToggleKeyHints).unwrap();
+        assert!(hints.is_visible());
+        hints.update(Action::ToggleKeyHints).unwrap();
+        assert!(!hints.is_visible());
+
+        hints.update(Action::OpenTrackSearch).unwrap();
+        assert_eq!(hints.context, KeyContext::TrackSearch);
+        assert!(hints.is_visible() && !hints.pinned);
+        // F1 while they show briefly hides them
+        hints.update(Action::ToggleKeyHints).unwrap();
+        assert!(!hints.is_visible());
+
+        // Sized to the context: the popups have a single column
+        let (lines, _) = KeyHints::lines(KeyContext::LanguagePicker.bindings());
+        assert_eq!(lines.len(), KeyContext::LanguagePicker.bindings().len());
+        let (lines, _) = KeyHints::lines(KeyContext::Stations.bindings());
+        assert_eq!(lines.len(), MAX_ROWS);
+    }
+}
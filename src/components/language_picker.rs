@@ -0,0 +1,166 @@
+//! Language popup component
+//!
+//! Lists the available languages with how much of each is translated, and
+//! which messages the highlighted one is missing, so the language can be
+//! picked explicitly.
+
+use crate::{
+    action::Action,
+    components,
+    i18n::{self, t, Completeness},
+    terminal_caps, theme,
+};
+
+use color_eyre::eyre::Result;
+use components::Component;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, Wrap},
+    Frame,
+};
+
+/// Language popup component
+pub struct LanguagePicker {
+    /// Whether the popup is visible
+    visible: bool,
+    languages: Vec<Completeness>,
+    /// Highlighted language
+    selected: usize,
+}
+
+impl LanguagePicker {
+    /// Create a new, hidden language popup
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            languages: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    fn open(&mut self) {
+        self.visible = true;
+        self.languages = i18n::completeness();
+        let current = i18n::current_locale();
+        self.selected = self
+            .languages
+            .iter()
+            .position(|language| language.locale == current)
+            .unwrap_or(0);
+    }
+
+    fn close(&mut self) {
+        self.visible = false;
+    }
+}
+
+impl Component for LanguagePicker {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.visible {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(Some(Action::CloseLanguagePicker)),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.selected + 1 < self.languages.len() => {
+                self.selected += 1;
+            }
+            KeyCode::Enter => {
+                if let Some(language) = self.languages.get(self.selected) {
+                    return Ok(Some(Action::SetLocale(language.locale.clone())));
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenLanguagePicker => self.open(),
+            Action::CloseLanguagePicker | Action::SetLocale(_) => self.close(),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let theme = theme::current();
+        let muted = Style::default().fg(theme.muted);
+        let current = i18n::current_locale();
+        let mut lines: Vec<Line> = self
+            .languages
+            .iter()
+            .enumerate()
+            .map(|(index, language)| {
+                let marker = if index == self.selected {
+                    terminal_caps::current().symbol("▸ ", "> ")
+                } else {
+                    "  "
+                };
+                let mut name = Style::default().fg(theme.text);
+                if index == self.selected {
+                    name = name.bg(theme.selection);
+                }
+                if language.locale == current {
+                    name = name.add_modifier(Modifier::BOLD);
+                }
+                Line::from(vec![
+                    Span::raw(marker),
+                    Span::styled(format!("{} ({})", language.name, language.locale), name),
+                    Span::styled(
+                        format!("  {:>3}%", language.percent()),
+                        Style::default().fg(if language.missing.is_empty() {
+                            theme.success
+                        } else {
+                            theme.info
+                        }),
+                    ),
+                ])
+            })
+            .collect();
+
+        // What the highlighted language still lacks
+        if let Some(language) = self.languages.get(self.selected) {
+            lines.push(Line::from(""));
+            if language.missing.is_empty() {
+                lines.push(Line::styled(t("language-complete"), muted));
+            } else {
+                lines.push(Line::styled(
+                    t("language-missing").replace("{$count}", &language.missing.len().to_string()),
+                    muted,
+                ));
+                lines.push(Line::styled(language.missing.join(", "), muted));
+            }
+        }
+
+        let popup_area = components::popup_area(area, 60, 50);
+        let popup = Paragraph::new(lines).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .title(t("language-title"))
+                .title_bottom(Line::from("[Enter] [Esc]").right_aligned())
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double)
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+        Ok(())
+    }
+}
+
+impl Default for LanguagePicker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -6,7 +6,7 @@
 use color_eyre::eyre::Result;
 use crossterm::event::{KeyEvent, MouseEvent};
 use ratatui::{
-    layout::{Rect, Size},
+    layout::{Constraint, Flex, Layout, Rect, Size},
     Frame,
 };
 use tokio::sync::mpsc::UnboundedSender;
@@ -16,14 +16,27 @@ use crate::{action::Action, config::Config, event::Event};
 pub mod bottom_controls;
 pub mod help;
 pub mod history;
+pub mod key_hints;
+pub mod language_picker;
 pub mod now_playing;
+pub mod recent_songs;
+pub mod splash;
 pub mod station_list;
+pub mod stats;
+pub mod track_search;
+pub mod upcoming_shows;
 
 pub use bottom_controls::BottomControls;
 pub use help::Help;
 pub use history::History;
+pub use key_hints::KeyHints;
+pub use language_picker::LanguagePicker;
 pub use now_playing::NowPlaying;
+pub use recent_songs::RecentSongs;
 pub use station_list::StationList;
+pub use stats::Stats;
+pub use track_search::TrackSearch;
+pub use upcoming_shows::UpcomingShows;
 
 /// Component trait that represents a visual and interactive element of the user interface.
 ///
@@ -80,6 +93,15 @@ pub trait Component {
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()>;
 }
 
+/// A centered area taking the given percentages of `area`, for popups
+pub fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
 /// Layout areas for components
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -2,13 +2,20 @@
 //!
 //! Displays information about the currently selected station and playback state.
 
-use crate::{action::Action, components, i18n::t, station::Station, PlaybackState};
+use crate::{
+    action::Action,
+    audio::{recorder::RecordingStatus, StreamInfo},
+    components,
+    i18n::t,
+    station::Station,
+    terminal_caps, theme, utils, PlaybackState,
+};
 
 use color_eyre::eyre::Result;
 use components::Component;
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
@@ -21,12 +28,22 @@ pub struct NowPlaying {
     selected_station: Option<Station>,
     /// Playback state
     playback_state: PlaybackState,
+    /// The paused stream was disconnected
+    pause_disconnected: bool,
     /// Current volume (0.0 to 2.0)
     volume: f32,
     /// Playback animation frames
     playback_frames: Vec<&'static str>,
     /// Current playback frame index
     playback_frame_index: usize,
+    /// The recording in progress
+    recording: Option<RecordingStatus>,
+    /// Latest audio level, 0 to 1, for the recording meter
+    level: f32,
+    /// How far playback is behind the live stream
+    behind: std::time::Duration,
+    /// What the server says about the playing stream
+    stream_info: Option<StreamInfo>,
     /// Action sender
     action_tx: Option<UnboundedSender<Action>>,
 }
@@ -37,9 +54,18 @@ impl NowPlaying {
         Self {
             selected_station: None,
             playback_state: PlaybackState::Stopped,
+            pause_disconnected: false,
             volume: 1.0,
-            playback_frames: vec!["▮▯▯▯", "▮▮▯▯", "▮▮▮▯", "▮▮▮▮"],
+            playback_frames: if terminal_caps::current().unicode {
+                vec!["▮▯▯▯", "▮▮▯▯", "▮▮▮▯", "▮▮▮▮"]
+            } else {
+                vec!["#...", "##..", "###.", "####"]
+            },
             playback_frame_index: 0,
+            recording: None,
+            level: 0.0,
+            behind: std::time::Duration::ZERO,
+            stream_info: None,
             action_tx: None,
         }
     }
@@ -69,6 +95,89 @@ impl NowPlaying {
     fn advance_frame(&mut self) {
         self.playback_frame_index = (self.playback_frame_index + 1) % self.playback_frames.len();
     }
+
+    /// Codec, bitrate, server name and website of the playing stream, when
+    /// the selected station is the one playing
+    fn stream_lines(&self, station: &Station) -> Vec<Line<'static>> {
+        let Some(info) = self
+            .stream_info
+            .as_ref()
+            .filter(|info| info.station == station.title)
+        else {
+            return Vec::new();
+        };
+        let theme = theme::current();
+        let label =
+            |key: &str| Span::styled(format!("{}: ", t(key)), Style::default().fg(theme.label));
+        let mut format: Vec<String> = Vec::new();
+        format.extend(info.codec().map(str::to_string));
+        format.extend(info.bitrate.map(|bitrate| format!("{} kbps", bitrate)));
+        format.extend(info.content_type.as_ref().map(|mime| format!("({})", mime)));
+
+        let mut lines = Vec::new();
+        if !format.is_empty() {
+            lines.push(Line::from(vec![
+                label("stream-format"),
+                Span::raw(format.join(" ")),
+            ]));
+        }
+        let server = match (&info.name, &info.server) {
+            (Some(name), Some(server)) => Some(format!("{} ({})", name, server)),
+            (Some(name), None) => Some(name.clone()),
+            (None, server) => server.clone(),
+        };
+        if let Some(server) = server {
+            lines.push(Line::from(vec![label("stream-server"), Span::raw(server)]));
+        }
+        if let Some(url) = &info.url {
+            lines.push(Line::from(vec![
+                label("stream-website"),
+                Span::raw(url.clone()),
+            ]));
+        }
+        lines
+    }
+
+    /// Elapsed time, level meter, file name and size of the recording
+    fn recording_lines(&self, recording: &RecordingStatus) -> Vec<Line<'static>> {
+        const METER_WIDTH: usize = 12;
+        let theme = theme::current();
+        let caps = terminal_caps::current();
+        let lit = (self.level * METER_WIDTH as f32).round() as usize;
+        let meter: String = caps.symbol("▮", "#").repeat(lit)
+            + &caps
+                .symbol("▯", ".")
+                .repeat(METER_WIDTH - lit.min(METER_WIDTH));
+        // The top of the meter turns red as the level nears full scale
+        let meter_color = if self.level > 0.9 {
+            theme.error
+        } else {
+            theme.success
+        };
+        vec![
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        "{} {} {}  ",
+                        caps.symbol("●", "*"),
+                        t("rec"),
+                        utils::format_duration(std::time::Duration::from_secs(recording.seconds))
+                    ),
+                    Style::default()
+                        .fg(theme.error)
+                        .add_modifier(ratatui::style::Modifier::BOLD),
+                ),
+                Span::styled(meter, Style::default().fg(meter_color)),
+            ]),
+            Line::from(vec![
+                Span::raw(recording.file.clone()),
+                Span::styled(
+                    format!("  {}", utils::format_size(recording.bytes)),
+                    Style::default().fg(theme.muted),
+                ),
+            ]),
+        ]
+    }
 }
 
 impl Component for NowPlaying {
@@ -79,12 +188,32 @@ impl Component for NowPlaying {
 
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
-            Action::Tick => {
+            // The animation only shows while playing
+            Action::Tick if self.playback_state == PlaybackState::Playing => {
                 self.advance_frame();
+                return Ok(Some(Action::Render));
             }
             Action::SetPlaybackState(state) => {
+                if state == PlaybackState::Stopped {
+                    self.stream_info = None;
+                }
                 self.set_playback_state(state);
             }
+            Action::SetStreamInfo(info) => {
+                self.stream_info = info;
+            }
+            Action::SetPauseDisconnected(disconnected) => {
+                self.pause_disconnected = disconnected;
+            }
+            Action::SetRecording(recording) => {
+                self.recording = recording;
+            }
+            Action::SetTimeShift(behind) => {
+                self.behind = behind;
+            }
+            Action::AudioLevel(level) => {
+                self.level = level.clamp(0.0, 1.0);
+            }
             Action::SetVolume(level) => {
                 self.set_volume(level);
             }
@@ -97,64 +226,96 @@ impl Component for NowPlaying {
     }
 
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
-        let content = if let Some(station) = &self.selected_station {
-            vec![
+        let theme = theme::current();
+        let mut content = if let Some(station) = &self.selected_station {
+            let mut lines = vec![
                 Line::from(vec![
                     Span::styled(
                         format!("{}: ", t("station-id")),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.label),
                     ),
                     Span::raw(&station.id),
                 ]),
                 Line::from(vec![
                     Span::styled(
                         format!("{}: ", t("station-title")),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.label),
                     ),
                     Span::raw(&station.title),
                 ]),
                 Line::from(vec![
                     Span::styled(
                         format!("{}: ", t("station-genre")),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.label),
                     ),
                     Span::raw(&station.genre),
                 ]),
                 Line::from(vec![
                     Span::styled(
                         format!("{}: ", t("station-dj")),
-                        Style::default().fg(Color::Yellow),
+                        Style::default().fg(theme.label),
                     ),
                     Span::raw(&station.dj),
                 ]),
+                Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", t("station-source")),
+                        Style::default().fg(theme.label),
+                    ),
+                    Span::raw(station.source.clone()),
+                ]),
+            ];
+            lines.extend(self.stream_lines(station));
+            lines.extend([
                 Line::from(""),
-                Line::from(Span::raw(&station.description)),
+                Line::from(Span::raw(station.description.clone())),
                 Line::from(""),
-            ]
+            ]);
+            lines
         } else {
             vec![Line::from(t("no-station-selected"))]
         };
+        if let Some(recording) = &self.recording {
+            content.extend(self.recording_lines(recording));
+        }
 
         let playback_state_str = match self.playback_state {
             PlaybackState::Playing => t("playing"),
+            PlaybackState::Paused if self.pause_disconnected => t("paused-disconnected"),
             PlaybackState::Paused => t("paused"),
             PlaybackState::Stopped => t("stopped"),
         };
 
         let playback_state_color = match self.playback_state {
-            PlaybackState::Playing => Color::Green,
-            PlaybackState::Paused => Color::Blue,
-            PlaybackState::Stopped => Color::Red,
+            PlaybackState::Playing => theme.success,
+            PlaybackState::Paused => theme.info,
+            PlaybackState::Stopped => theme.error,
         };
 
         let playback_animation = if matches!(self.playback_state, PlaybackState::Playing) {
             Span::styled(
                 format!(" {}", self.playback_frames[self.playback_frame_index]),
-                Style::default().fg(Color::Green),
+                Style::default().fg(theme.success),
             )
         } else {
             Span::raw("")
         };
+        // Live, or how far back the time shift is
+        let live_indicator = match self.playback_state {
+            PlaybackState::Playing if self.behind.is_zero() => Span::styled(
+                format!(" {}", t("live")),
+                Style::default().fg(theme.success),
+            ),
+            PlaybackState::Playing => Span::styled(
+                format!(
+                    " -{}:{:02}",
+                    self.behind.as_secs() / 60,
+                    self.behind.as_secs() % 60
+                ),
+                Style::default().fg(theme.warning),
+            ),
+            _ => Span::raw(""),
+        };
 
         let now_playing = Paragraph::new(content)
             .wrap(ratatui::widgets::Wrap { trim: true })
@@ -164,7 +325,8 @@ impl Component for NowPlaying {
                     .title(
                         Line::from(vec![Span::styled(
                             format!(
-                                " ♪ {} v{}",
+                                " {} {} v{}",
+                                terminal_caps::current().symbol("♪", "*"),
                                 env!("CARGO_PKG_NAME"),
                                 env!("CARGO_PKG_VERSION")
                             ),
@@ -180,6 +342,7 @@ impl Component for NowPlaying {
                         ),
                         Span::raw("]"),
                         playback_animation,
+                        live_indicator,
                     ]))
                     .title_bottom(
                         Line::from(format!("[{}: {:.0}%]", t("volume"), self.volume * 100.0))
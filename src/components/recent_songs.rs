@@ -0,0 +1,168 @@
+//! Recent songs panel
+//!
+//! Lists the songs the selected SomaFM channel played last, fetched once the
+//! selection rests on it, so a channel can be judged before tuning in.
+
+use crate::{i18n::t, providers::RecentSong, theme};
+
+use chrono::DateTime;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a channel's songs are shown before they are fetched again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A channel's songs and when they were asked for
+#[derive(Debug)]
+struct Fetched {
+    requested: Instant,
+    songs: Vec<RecentSong>,
+}
+
+/// Recent songs panel, by station ID
+#[derive(Debug)]
+pub struct RecentSongs {
+    /// Songs listed at most
+    limit: usize,
+    channels: HashMap<String, Fetched>,
+}
+
+impl RecentSongs {
+    /// A panel listing up to `limit` songs, none when 0
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Whether the songs of `station_id` should be fetched at `now`: never
+    /// asked for, or asked for long enough ago
+    pub fn wants(&self, station_id: &str, now: Instant) -> bool {
+        self.limit > 0
+            && self.channels.get(station_id).is_none_or(|fetched| {
+                now.saturating_duration_since(fetched.requested) >= REFRESH_INTERVAL
+            })
+    }
+
+    /// Note that the songs of `station_id` were asked for at `now`, keeping
+    /// the ones shown until they arrive
+    pub fn requested(&mut self, station_id: &str, now: Instant) {
+        self.channels
+            .entry(station_id.to_string())
+            .and_modify(|fetched| fetched.requested = now)
+            .or_insert(Fetched {
+                requested: now,
+                songs: Vec::new(),
+            });
+    }
+
+    /// Whether songs of `station_id` were asked for before
+    pub fn known(&self, station_id: &str) -> bool {
+        self.channels.contains_key(station_id)
+    }
+
+    pub fn set(&mut self, station_id: &str, mut songs: Vec<RecentSong>) {
+        songs.truncate(self.limit);
+        if let Some(fetched) = self.channels.get_mut(station_id) {
+            fetched.songs = songs;
+        }
+    }
+
+    fn songs(&self, station_id: &str) -> &[RecentSong] {
+        self.channels
+            .get(station_id)
+            .map_or(&[], |fetched| &fetched.songs)
+    }
+
+    /// Rows the panel takes for `station_id`, 0 while it has no songs
+    pub fn height(&self, station_id: Option<&str>) -> u16 {
+        match station_id.map(|id| self.songs(id).len()) {
+            Some(0) | None => 0,
+            Some(count) => u16::try_from(count).unwrap_or(u16::MAX).saturating_add(2),
+        }
+    }
+
+    /// Draw the songs of `station_id` in `area`
+    pub fn draw(&self, frame: &mut Frame, area: Rect, station_id: Option<&str>) {
+        let Some(station_id) = station_id else {
+            return;
+        };
+        if area.height == 0 {
+            return;
+        }
+        let theme = theme::current();
+        let clock = crate::clock::current();
+        let lines: Vec<Line> = self
+            .songs(station_id)
+            .iter()
+            .map(|song| {
+                let played = DateTime::from_timestamp(song.played, 0)
+                    .filter(|_| song.played > 0)
+                    .map(|time| clock.time(time))
+                    .unwrap_or_default();
+                let track = if song.artist.is_empty() {
+                    song.title.clone()
+                } else {
+                    format!("{} - {}", song.artist, song.title)
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:>8}  ", played), Style::default().fg(theme.muted)),
+                    Span::styled(track, Style::default().fg(theme.text)),
+                ])
+            })
+            .collect();
+        let panel = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(t("recent-songs-title"))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(panel, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(title: &str) -> RecentSong {
+        RecentSong {
+            artist: String::new(),
+            title: title.to_string(),
+            album: String::new(),
+            played: 0,
+        }
+    }
+
+    #[test]
+    fn fetches_again_once_stale() {
+        let start = Instant::now();
+        let mut recent = RecentSongs::new(2);
+        assert!(recent.wants("groovesalad", start));
+        recent.requested("groovesalad", start);
+        assert!(!recent.wants("groovesalad", start + Duration::from_secs(5)));
+        // Nothing shows until the songs arrive
+        assert_eq!(recent.height(Some("groovesalad")), 0);
+
+        recent.set("groovesalad", vec![song("a"), song("b"), song("c")]);
+        assert_eq!(recent.height(Some("groovesalad")), 4);
+        assert_eq!(recent.height(Some("dronezone")), 0);
+        assert_eq!(recent.height(None), 0);
+
+        let later = start + REFRESH_INTERVAL;
+        assert!(recent.wants("groovesalad", later));
+        recent.requested("groovesalad", later);
+        // The old songs stay up while the new ones load
+        assert_eq!(recent.height(Some("groovesalad")), 4);
+
+        assert!(!RecentSongs::new(0).wants("groovesalad", start));
+    }
+}
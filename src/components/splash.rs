@@ -0,0 +1,305 @@
+//! Startup splash screen
+//!
+//! Shown while the station list loads: app name, version and, on terminals
+//! with inline graphics, a collage of cached station artwork. Any key skips it.
+
+use crate::{
+    artwork,
+    config::SplashConfig,
+    i18n::t,
+    terminal_caps::{self, GraphicsProtocol},
+    theme,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use crossterm::{cursor::MoveTo, queue};
+use ratatui::{
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph},
+    Frame,
+};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Most artwork tiles in the collage
+pub const MAX_TILES: usize = 16;
+/// Tile size in cells; cells are roughly twice as tall as wide
+const TILE_WIDTH: u16 = 12;
+const TILE_HEIGHT: u16 = 6;
+const TILE_GAP: u16 = 1;
+
+/// Startup splash screen state
+pub struct Splash {
+    visible: bool,
+    /// Earliest time the splash hides by itself
+    hide_after: Instant,
+    loaded: bool,
+    protocol: GraphicsProtocol,
+    images: Vec<PathBuf>,
+    /// Tiles laid out by the last draw, waiting to be painted
+    pending_tiles: Vec<Rect>,
+    collage_drawn: bool,
+    spinner_state: usize,
+    spinner_frames: &'static [&'static str],
+}
+
+impl Splash {
+    pub fn new(config: &SplashConfig) -> Self {
+        let protocol = terminal_caps::current().graphics;
+        let images = if config.enabled && supports_collage(protocol) {
+            artwork::cached_files(MAX_TILES)
+        } else {
+            Vec::new()
+        };
+        Self {
+            visible: config.enabled,
+            hide_after: Instant::now() + Duration::from_millis(config.min_duration_ms),
+            loaded: false,
+            protocol,
+            images,
+            pending_tiles: Vec::new(),
+            collage_drawn: false,
+            spinner_state: 0,
+            spinner_frames: if terminal_caps::current().unicode {
+                &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+            } else {
+                &["|", "/", "-", "\\"]
+            },
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Whether artwork should be cached for future splash screens
+    pub fn wants_artwork(config: &SplashConfig) -> bool {
+        config.enabled && supports_collage(terminal_caps::current().graphics)
+    }
+
+    /// Mark the station list as loaded
+    pub fn stations_loaded(&mut self) {
+        self.loaded = true;
+    }
+
+    /// Advance the spinner; returns true when the splash should close
+    pub fn tick(&mut self) -> bool {
+        self.spinner_state = (self.spinner_state + 1) % self.spinner_frames.len();
+        self.visible && self.loaded && Instant::now() >= self.hide_after
+    }
+
+    /// Hide the splash, removing any images it drew
+    pub fn dismiss(&mut self, out: &mut impl Write) -> io::Result<()> {
+        self.visible = false;
+        if self.collage_drawn && self.protocol == GraphicsProtocol::Kitty {
+            out.write_all(b"\x1b_Ga=d,d=A,q=2\x1b\\")?;
+            out.flush()?;
+        }
+        self.collage_drawn = false;
+        Ok(())
+    }
+
+    /// Repaint the collage on the next draw, e.g. after a resize
+    pub fn invalidate(&mut self) {
+        self.collage_drawn = false;
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        let theme = theme::current();
+        let tiles = collage_tiles(area, self.images.len());
+        let collage_height = tiles
+            .last()
+            .map(|tile| tile.bottom() - tiles[0].y)
+            .unwrap_or(0);
+
+        let [_, title_area, collage_area, status_area, _] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(3),
+            Constraint::Length(collage_height + 1),
+            Constraint::Length(3),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Block::bordered()
+                .border_type(BorderType::Double)
+                .border_style(Style::default().fg(theme.muted)),
+            area,
+        );
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(vec![
+                    Span::styled(
+                        env!("CARGO_PKG_NAME"),
+                        Style::default()
+                            .fg(theme.accent)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" v{}", env!("CARGO_PKG_VERSION")),
+                        Style::default().fg(theme.muted),
+                    ),
+                ]),
+                Line::from(Span::styled(
+                    t("app-description"),
+                    Style::default().fg(theme.text),
+                )),
+            ])
+            .alignment(Alignment::Center),
+            title_area,
+        );
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(Span::styled(
+                    format!(
+                        "{} {}",
+                        self.spinner_frames[self.spinner_state],
+                        t("loading-stations")
+                    ),
+                    Style::default().fg(theme.text),
+                )),
+                Line::from(Span::styled(
+                    t("splash-skip"),
+                    Style::default().fg(theme.muted),
+                )),
+            ])
+            .alignment(Alignment::Center),
+            status_area,
+        );
+
+        // Tiles are positioned relative to the top of the collage band
+        let offset = collage_area
+            .y
+            .saturating_sub(tiles.first().map_or(0, |t| t.y));
+        self.pending_tiles = tiles
+            .into_iter()
+            .map(|tile| Rect {
+                y: tile.y + offset,
+                ..tile
+            })
+            .collect();
+    }
+
+    /// Paint the artwork collage after the frame has been drawn
+    ///
+    /// Images persist in the terminal, so they are only sent once per layout.
+    pub fn write_collage(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if !self.visible || self.collage_drawn || self.pending_tiles.is_empty() {
+            return Ok(());
+        }
+        for (tile, path) in self.pending_tiles.iter().zip(&self.images) {
+            let Ok(data) = std::fs::read(path) else {
+                continue;
+            };
+            queue!(out, MoveTo(tile.x, tile.y))?;
+            match self.protocol {
+                GraphicsProtocol::Kitty => write_kitty_image(out, &data, *tile)?,
+                GraphicsProtocol::Iterm2 => write_iterm2_image(out, &data, *tile)?,
+                GraphicsProtocol::Sixel | GraphicsProtocol::None => {}
+            }
+        }
+        out.flush()?;
+        self.collage_drawn = true;
+        Ok(())
+    }
+}
+
+/// Sixel needs decoded pixels, so only Kitty and iTerm2 get a collage
+fn supports_collage(protocol: GraphicsProtocol) -> bool {
+    matches!(protocol, GraphicsProtocol::Kitty | GraphicsProtocol::Iterm2)
+}
+
+/// Lay out up to `count` tiles in at most two centred rows inside `area`
+fn collage_tiles(area: Rect, count: usize) -> Vec<Rect> {
+    let inner_width = area.width.saturating_sub(4);
+    let per_row = ((inner_width + TILE_GAP) / (TILE_WIDTH + TILE_GAP)) as usize;
+    // Leave room for the title and status lines
+    let rows = if area.height >= 2 * (TILE_HEIGHT + TILE_GAP) + 10 {
+        2
+    } else if area.height >= TILE_HEIGHT + 10 {
+        1
+    } else {
+        0
+    };
+    let count = count.min(per_row * rows);
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let per_row = per_row.min(count);
+    let row_width = per_row as u16 * (TILE_WIDTH + TILE_GAP) - TILE_GAP;
+    let left = area.x + (area.width - row_width) / 2;
+    (0..count)
+        .map(|index| {
+            let (row, column) = ((index / per_row) as u16, (index % per_row) as u16);
+            Rect::new(
+                left + column * (TILE_WIDTH + TILE_GAP),
+                area.y + row * (TILE_HEIGHT + TILE_GAP),
+                TILE_WIDTH,
+                TILE_HEIGHT,
+            )
+        })
+        .collect()
+}
+
+/// Kitty graphics protocol, PNG only, sent in 4 KiB chunks
+fn write_kitty_image(out: &mut impl Write, data: &[u8], tile: Rect) -> io::Result<()> {
+    if !data.starts_with(b"\x89PNG") {
+        return Ok(());
+    }
+    let encoded = BASE64.encode(data);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        if index == 0 {
+            write!(
+                out,
+                "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={};",
+                tile.width, tile.height, more
+            )?;
+        } else {
+            write!(out, "\x1b_Gm={};", more)?;
+        }
+        out.write_all(chunk)?;
+        out.write_all(b"\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// iTerm2 inline image protocol
+fn write_iterm2_image(out: &mut impl Write, data: &[u8], tile: Rect) -> io::Result<()> {
+    write!(
+        out,
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        data.len(),
+        tile.width,
+        tile.height,
+        BASE64.encode(data)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collage_fits_area() {
+        let area = Rect::new(0, 0, 80, 40);
+        let tiles = collage_tiles(area, MAX_TILES);
+        // 76 usable columns fit five 12-wide tiles with gaps, in two rows
+        assert_eq!(tiles.len(), 10);
+        assert!(tiles
+            .iter()
+            .all(|tile| area.contains(tile.as_position()) && tile.right() <= area.right()));
+        assert_eq!(tiles[0].x, tiles[5].x);
+        assert_eq!(tiles[5].y, tiles[0].y + TILE_HEIGHT + TILE_GAP);
+
+        assert_eq!(collage_tiles(area, 3).len(), 3);
+        assert!(collage_tiles(Rect::new(0, 0, 80, 12), 4).is_empty());
+    }
+}
@@ -1,16 +1,23 @@
 //! Station list component
 //!
-//! Displays the list of available SomaFM stations with selection and loading states.
+//! Displays the list of available SomaFM stations with selection and loading states,
+//! under a row of genre chips that filter the list.
 
-use crate::{action::Action, components, i18n::t, station::Station};
+use crate::{
+    action::Action,
+    components,
+    i18n::t,
+    station::{GenreChip, Station, StationSort},
+    terminal_caps, theme,
+};
 
 use color_eyre::eyre::Result;
 use components::Component;
 use crossterm::event::KeyEvent;
 use ratatui::widgets::ListState;
 use ratatui::{
-    layout::Rect,
-    style::{Color, Style},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph},
     Frame,
@@ -33,6 +40,14 @@ pub struct StationList {
     spinner_frames: Vec<&'static str>,
     /// Scroll offset to manage visible portion of list
     scroll_offset: usize,
+    /// Order the App sorted the stations in
+    sort: StationSort,
+    /// Genres the App filtered the stations by
+    genre_filter: Vec<String>,
+    /// Genres offered in the filter row
+    genre_chips: Vec<GenreChip>,
+    /// Stations SomaFM added since they were last seen
+    new_stations: Vec<String>,
     /// Action sender
     action_tx: Option<UnboundedSender<Action>>,
 }
@@ -46,8 +61,16 @@ impl StationList {
             active_station: None,
             loading: true,
             spinner_state: 0,
-            spinner_frames: vec!["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+            spinner_frames: if terminal_caps::current().unicode {
+                vec!["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+            } else {
+                vec!["|", "/", "-", "\\"]
+            },
             scroll_offset: 0,
+            sort: StationSort::default(),
+            genre_filter: Vec::new(),
+            genre_chips: Vec::new(),
+            new_stations: Vec::new(),
             action_tx: None,
         }
     }
@@ -92,6 +115,44 @@ impl StationList {
         Some(Action::StationDown)
     }
 
+    /// Block title with the genre filter and any sort order besides the catalog's
+    fn title(&self) -> String {
+        let mut title = t("stations");
+        if !self.genre_filter.is_empty() {
+            title = format!("{}: {}", title, self.genre_filter.join(", "));
+        }
+        if self.sort != StationSort::Catalog {
+            title = format!("{} ({})", title, t(&format!("sort-{}", self.sort)));
+        }
+        title
+    }
+
+    /// The genre chips, chosen ones highlighted and each key underlined
+    fn chips_line(&self) -> Line<'static> {
+        let theme = theme::current();
+        let mut spans = Vec::new();
+        for chip in &self.genre_chips {
+            let style = if self.genre_filter.contains(&chip.genre) {
+                Style::default().fg(theme.text).bg(theme.selection)
+            } else {
+                Style::default().fg(theme.muted)
+            };
+            if !spans.is_empty() {
+                spans.push(Span::raw(" "));
+            }
+            let key = chip.genre.find(chip.key).unwrap_or(0);
+            let (before, rest) = chip.genre.split_at(key);
+            let (key, after) = rest.split_at(chip.key.len_utf8().min(rest.len()));
+            spans.push(Span::styled(before.to_string(), style));
+            spans.push(Span::styled(
+                key.to_string(),
+                style.add_modifier(Modifier::UNDERLINED),
+            ));
+            spans.push(Span::styled(after.to_string(), style));
+        }
+        Line::from(spans)
+    }
+
     /// Render the loading indicator
     fn render_loading(&self, frame: &mut Frame, area: Rect) -> Result<()> {
         let loading_text = vec![Line::from(vec![
@@ -142,8 +203,21 @@ impl Component for StationList {
             Action::SetActiveStation(idx) => {
                 self.set_active_station(idx);
             }
-            Action::Tick => {
+            Action::SetStationSort(sort) => {
+                self.sort = sort;
+            }
+            Action::SetGenreFilter(genres) => {
+                self.genre_filter = genres;
+            }
+            Action::SetGenreChips(chips) => {
+                self.genre_chips = chips;
+            }
+            Action::SetNewStations(ids) => {
+                self.new_stations = ids;
+            }
+            Action::Tick if self.loading => {
                 self.spinner_state = (self.spinner_state + 1) % self.spinner_frames.len();
+                return Ok(Some(Action::Render));
             }
             _ => {}
         }
@@ -154,9 +228,36 @@ impl Component for StationList {
         if self.loading {
             self.render_loading(frame, area)?;
         } else {
+            let selected_pos = self.selected_index + 1;
+            let total_stations = self.stations.len();
+            let block = Block::bordered()
+                .title(Line::from(self.title()))
+                .title(
+                    Line::from(format!(
+                        "[{}]",
+                        terminal_caps::current().symbol("↓↑", "Up/Dn")
+                    ))
+                    .right_aligned(),
+                )
+                .title_bottom(
+                    Line::from(format!("[{} / {}]", selected_pos, total_stations)).right_aligned(),
+                )
+                .padding(ratatui::widgets::Padding::new(1, 1, 0, 0));
+            let inner = block.inner(area);
+            frame.render_widget(block, area);
+
+            // The genre chips take the first line when there's room for stations too
+            let list_area = if self.genre_chips.is_empty() || inner.height < 2 {
+                inner
+            } else {
+                let [chips_area, list_area] =
+                    Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+                frame.render_widget(Paragraph::new(self.chips_line()), chips_area);
+                list_area
+            };
+
             // Calculate how many items can be displayed in the available area
-            // Account for top and bottom borders (border and title share the same line)
-            let available_height = area.height.saturating_sub(2) as usize; // Account for top and bottom borders
+            let available_height = list_area.height as usize;
 
             // Adjust scroll offset to keep selected item visible
             if self.selected_index < self.scroll_offset {
@@ -181,9 +282,6 @@ impl Component for StationList {
 
             let active_station = self.active_station;
 
-            let selected_pos = self.selected_index + 1;
-            let total_stations = self.stations.len();
-
             // Only take the visible stations based on scroll offset
             let visible_stations: Vec<&Station> = self
                 .stations
@@ -203,25 +301,41 @@ impl Component for StationList {
                     } else {
                         Style::default()
                     };
-                    ListItem::new(Span::styled(s.title.as_str(), style))
+                    let mut spans = vec![Span::styled(s.title.as_str(), style)];
+                    if self.new_stations.contains(&s.id) {
+                        spans.push(Span::styled(
+                            format!(" {}", t("station-new-badge")),
+                            Style::default()
+                                .fg(theme::current().accent)
+                                .add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    if s.listeners > 0 {
+                        spans.push(Span::styled(
+                            format!(" ({})", s.listeners),
+                            Style::default().fg(theme::current().muted),
+                        ));
+                    }
+                    // What the station is playing, dimmed and cut off by the border
+                    if !s.last_playing.is_empty() {
+                        spans.push(Span::styled(
+                            format!(
+                                " {} {}",
+                                terminal_caps::current().symbol("·", "-"),
+                                s.last_playing
+                            ),
+                            Style::default().fg(theme::current().muted),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
                 })
                 .collect();
 
             let stations_list = List::new(station_items)
-                .block(
-                    Block::bordered()
-                        .title(Line::from(t("stations")))
-                        .title(Line::from("[↓↑]").right_aligned())
-                        .title_bottom(
-                            Line::from(format!("[{} / {}]", selected_pos, total_stations))
-                                .right_aligned(),
-                        )
-                        .padding(ratatui::widgets::Padding::new(1, 1, 0, 0)),
-                )
                 .repeat_highlight_symbol(true)
-                .highlight_style(Style::default().bg(Color::Blue));
+                .highlight_style(Style::default().bg(theme::current().selection));
 
-            frame.render_stateful_widget(stations_list, area, &mut list_state);
+            frame.render_stateful_widget(stations_list, list_area, &mut list_state);
         }
         Ok(())
     }
@@ -0,0 +1,160 @@
+//! Listening stats popup component
+//!
+//! Ranks the stations by the time listened to each in the track log, with a
+//! bar for each and the total at the top.
+
+use crate::{
+    action::Action,
+    components,
+    i18n::t,
+    listening_stats::{self, StationTime},
+    terminal_caps, theme, utils,
+};
+
+use color_eyre::eyre::Result;
+use components::Component;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+use std::time::Duration;
+
+/// Listening stats popup component
+pub struct Stats {
+    /// Whether the popup is visible
+    visible: bool,
+    /// Stations listened to, longest first
+    ranking: Vec<StationTime>,
+    /// First station shown
+    scroll: usize,
+}
+
+impl Stats {
+    /// Create a new, hidden stats popup
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            ranking: Vec::new(),
+            scroll: 0,
+        }
+    }
+}
+
+impl Component for Stats {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.visible {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => {
+                return Ok(Some(Action::CloseStats))
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.scroll = self.scroll.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') if self.scroll + 1 < self.ranking.len() => {
+                self.scroll += 1;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::SetListeningStats(ranking) => {
+                self.ranking = ranking;
+                self.scroll = self.scroll.min(self.ranking.len().saturating_sub(1));
+            }
+            Action::OpenStats => {
+                self.visible = true;
+                self.scroll = 0;
+            }
+            Action::CloseStats => self.visible = false,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let theme = theme::current();
+        let muted = Style::default().fg(theme.muted);
+        let popup_area = components::popup_area(area, 70, 70);
+        // Inside the borders and padding
+        let inner_width = usize::from(popup_area.width.saturating_sub(4));
+
+        let total: u64 = self.ranking.iter().map(|station| station.seconds).sum();
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(format!("{} ", t("stats-total")), muted),
+                Span::styled(
+                    utils::format_length(Duration::from_secs(total)),
+                    Style::default().fg(theme.label),
+                ),
+            ]),
+            Line::from(""),
+        ];
+        if self.ranking.is_empty() {
+            lines.push(Line::styled(t("stats-empty"), muted));
+        }
+
+        let max = self.ranking.first().map_or(0, |station| station.seconds);
+        let title_width = self
+            .ranking
+            .iter()
+            .map(|station| station.title.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(inner_width / 2);
+        let bar_width = inner_width.saturating_sub(title_width + 12);
+        let unicode = terminal_caps::current().unicode;
+        for (rank, station) in self.ranking.iter().enumerate().skip(self.scroll) {
+            let title: String = station.title.chars().take(title_width).collect();
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:>2}. ", rank + 1), muted),
+                Span::styled(
+                    format!("{:title_width$}", title),
+                    Style::default().fg(theme.text),
+                ),
+                Span::styled(
+                    format!(
+                        " {:>6} ",
+                        utils::format_length(Duration::from_secs(station.seconds))
+                    ),
+                    muted,
+                ),
+                Span::styled(
+                    listening_stats::bar(station.seconds, max, bar_width, unicode),
+                    Style::default().fg(theme.accent),
+                ),
+            ]));
+        }
+
+        let popup = Paragraph::new(lines).block(
+            Block::default()
+                .title(t("stats-title"))
+                .title_bottom(Line::from("[↑/↓] [Esc]").right_aligned())
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double)
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+        Ok(())
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,159 @@
+//! Track search popup component
+//!
+//! Searches the persistent track log as you type and lists matching tracks
+//! with the station, when they were last heard and how often.
+
+use crate::{
+    action::Action, components, i18n::t, storage::SharedStore, terminal_caps, theme,
+    track_log::SearchHit,
+};
+
+use color_eyre::eyre::Result;
+use components::Component;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph},
+    Frame,
+};
+use tracing::warn;
+
+/// Track search popup component
+pub struct TrackSearch {
+    /// Whether the popup is visible
+    visible: bool,
+    /// Store with the track log, `None` when it couldn't be opened
+    store: Option<SharedStore>,
+    query: String,
+    hits: Vec<SearchHit>,
+    /// First result shown
+    scroll_offset: usize,
+}
+
+impl TrackSearch {
+    /// Create a new track search component searching `store`
+    pub fn new(store: Option<SharedStore>) -> Self {
+        Self {
+            visible: false,
+            store,
+            query: String::new(),
+            hits: Vec::new(),
+            scroll_offset: 0,
+        }
+    }
+
+    fn open(&mut self) {
+        self.visible = true;
+        self.query.clear();
+        self.hits.clear();
+        self.scroll_offset = 0;
+    }
+
+    fn close(&mut self) {
+        self.visible = false;
+        self.hits = Vec::new();
+    }
+
+    fn search(&mut self) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        self.hits = store.lock().search(&self.query).unwrap_or_else(|error| {
+            warn!("Failed to search the track log: {}", error);
+            Vec::new()
+        });
+        self.scroll_offset = 0;
+    }
+}
+
+impl Component for TrackSearch {
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        if !self.visible {
+            return Ok(None);
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(Some(Action::CloseTrackSearch)),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.search();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.search();
+            }
+            KeyCode::Up => {
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
+            }
+            KeyCode::Down if self.scroll_offset + 1 < self.hits.len() => {
+                self.scroll_offset += 1;
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::OpenTrackSearch => self.open(),
+            Action::CloseTrackSearch => self.close(),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let theme = theme::current();
+        let muted = Style::default().fg(theme.muted);
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("> ", Style::default().fg(theme.label)),
+                Span::raw(self.query.as_str()),
+                Span::styled(terminal_caps::current().symbol("▏", "_"), muted),
+            ]),
+            Line::from(""),
+        ];
+        if self.query.trim().is_empty() {
+            lines.push(Line::styled(t("track-search-hint"), muted));
+        } else if self.hits.is_empty() {
+            lines.push(Line::styled(t("track-search-none"), muted));
+        }
+        lines.extend(self.hits.iter().skip(self.scroll_offset).map(|hit| {
+            Line::from(vec![
+                Span::styled(hit.last_heard_local(), muted),
+                Span::styled(
+                    format!(" {:>4}x ", hit.plays),
+                    Style::default().fg(theme.info),
+                ),
+                Span::styled(hit.station.as_str(), Style::default().fg(theme.label)),
+                Span::raw(format!("  {}", hit.track())),
+            ])
+        }));
+
+        let popup_area = components::popup_area(area, 80, 60);
+        let popup = Paragraph::new(lines).block(
+            Block::default()
+                .title(t("track-search-title"))
+                .title_bottom(
+                    Line::from(format!(
+                        "[{} {}] [Esc]",
+                        self.hits.len(),
+                        t("track-search-results")
+                    ))
+                    .right_aligned(),
+                )
+                .borders(Borders::ALL)
+                .border_type(BorderType::Double)
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(popup, popup_area);
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Upcoming shows panel
+//!
+//! Lists the scheduled shows on the selected station that haven't ended,
+//! with when each starts, or that it is on air.
+
+use crate::{
+    i18n::t,
+    schedule::{self, Show},
+    station::Station,
+    theme,
+};
+
+use chrono::DateTime;
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Padding, Paragraph},
+    Frame,
+};
+
+/// Shows listed at most
+const SHOWN: usize = 3;
+
+/// Upcoming shows panel
+#[derive(Debug, Default)]
+pub struct UpcomingShows {
+    /// Every show in the schedule
+    shows: Vec<Show>,
+}
+
+impl UpcomingShows {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, shows: Vec<Show>) {
+        self.shows = shows;
+    }
+
+    pub fn shows(&self) -> &[Show] {
+        &self.shows
+    }
+
+    /// Rows the panel takes for `station` at `now`, 0 without shows
+    pub fn height(&self, station: Option<&Station>, catalog: &[Station], now: i64) -> u16 {
+        match station
+            .map(|station| schedule::upcoming(&self.shows, station, catalog, now, SHOWN).len())
+        {
+            Some(0) | None => 0,
+            Some(count) => count as u16 + 2,
+        }
+    }
+
+    /// Draw the shows on `station` in `area`, leaving shows that name another
+    /// station in `catalog` more closely to it
+    pub fn draw(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        station: Option<&Station>,
+        catalog: &[Station],
+        now: i64,
+    ) {
+        let Some(station) = station else {
+            return;
+        };
+        if area.height == 0 {
+            return;
+        }
+        let theme = theme::current();
+        let clock = crate::clock::current();
+        let lines: Vec<Line> = schedule::upcoming(&self.shows, station, catalog, now, SHOWN)
+            .into_iter()
+            .map(|show| {
+                let when = if show.on_air(now) {
+                    Span::styled(
+                        format!("{:>16}  ", t("show-on-air")),
+                        Style::default().fg(theme.success),
+                    )
+                } else {
+                    let start = DateTime::from_timestamp(show.start, 0)
+                        .map(|time| clock.date_time(time))
+                        .unwrap_or_default();
+                    Span::styled(format!("{:>16}  ", start), Style::default().fg(theme.muted))
+                };
+                Line::from(vec![
+                    when,
+                    Span::styled(show.title.clone(), Style::default().fg(theme.text)),
+                ])
+            })
+            .collect();
+        let panel = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(t("upcoming-shows-title"))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(panel, area);
+    }
+}
@@ -1,8 +1,32 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::audio::effects::SoundEffectsConfig;
+use crate::audio::equalizer::EqualizerConfig;
+use crate::audio::loudness::LoudnessConfig;
+use crate::audio::output::{AudioBackend, AudioHost};
+use crate::audio::recorder::RecordingConfig;
+use crate::audio::volume::VolumeCurve;
+use crate::clock::TimeConfig;
+use crate::control::Permission;
+use crate::data_usage::DataUsageConfig;
+use crate::experimental::ExperimentalConfig;
+use crate::history_export::ExportFormat;
+use crate::hooks::HooksConfig;
+use crate::http::NetworkConfig;
+use crate::logging::LoggingConfig;
+use crate::schedule::ScheduleConfig;
+use crate::station::{StationSort, StreamQuality};
+use crate::status_file::StatusFileConfig;
+use crate::storage::StorageConfig;
+use crate::terminal_caps::TerminalConfig;
+use crate::theme::ThemeConfig;
+use crate::update::UpdateConfig;
+use crate::webhook::PlaybackWebhookConfig;
+
 /// Configuration-specific errors
 #[derive(Debug)]
 pub enum ConfigError {
@@ -86,6 +110,10 @@ pub struct Config {
     pub volume: f32,
     #[serde(default)]
     pub last_station: Option<String>,
+    /// Interface language (`en`, `ru`), picked with `L`; detected from the
+    /// environment when unset
+    #[serde(default)]
+    pub locale: Option<String>,
     #[serde(default = "default_log_level")]
     pub log_level: u8,
     #[serde(default = "default_udp_port")]
@@ -96,10 +124,439 @@ pub struct Config {
     pub audio_prefetch_seconds: u64,
     #[serde(default = "default_audio_startup_prefetch_seconds")]
     pub audio_startup_prefetch_seconds: u64,
+    /// Bitrate the prefetch is sized for when a stream doesn't send `icy-br`
+    #[serde(default = "default_audio_default_bitrate_kbps")]
+    pub audio_default_bitrate_kbps: u64,
     #[serde(default = "default_audio_buffer_size_bytes")]
     pub audio_buffer_size_bytes: usize,
     #[serde(default = "default_audio_output_buffer_frames")]
     pub audio_output_buffer_frames: u32,
+    #[serde(default = "default_audio_downgrade_underruns")]
+    pub audio_downgrade_underruns: u32,
+    #[serde(default = "default_audio_downgrade_window_seconds")]
+    pub audio_downgrade_window_seconds: u64,
+    #[serde(default = "default_audio_upgrade_after_seconds")]
+    pub audio_upgrade_after_seconds: u64,
+    /// Milliseconds to fade in and out when starting, stopping or switching
+    /// stations; 0 cuts straight in and out
+    #[serde(default = "default_audio_fade_ms")]
+    pub audio_fade_ms: u64,
+    /// Seconds of played audio kept for rewinding; 0 disables the time shift
+    #[serde(default = "default_audio_time_shift_seconds")]
+    pub audio_time_shift_seconds: u64,
+    /// Seconds of silence from a playing stream before it is restarted; 0
+    /// never restarts
+    #[serde(default = "default_audio_silence_restart_seconds")]
+    pub audio_silence_restart_seconds: u64,
+    /// Seconds the output may have nothing to play while playing before
+    /// playback is restarted; 0 never restarts
+    #[serde(default = "default_audio_underrun_restart_seconds")]
+    pub audio_underrun_restart_seconds: u64,
+    /// How the volume maps to loudness, `logarithmic` or `linear`
+    #[serde(default)]
+    pub volume_curve: VolumeCurve,
+    #[serde(default)]
+    pub audio_backend: AudioBackend,
+    /// Sound systems to try in order, e.g. `["pipewire", "pulse", "alsa"]`;
+    /// empty lets the backend pick the default device
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_hosts: Vec<AudioHost>,
+    #[serde(default)]
+    pub stream_quality: StreamQuality,
+    /// Order of the station list, cycled with `s`
+    #[serde(default)]
+    pub station_sort: StationSort,
+    /// Minutes the cached SomaFM catalog is used before downloading it again;
+    /// 0 disables the cache
+    #[serde(default = "default_catalog_cache_ttl_minutes")]
+    pub catalog_cache_ttl_minutes: u64,
+    /// Seconds between refreshes of what each station is playing; 0 disables
+    #[serde(default = "default_last_playing_refresh_seconds")]
+    pub last_playing_refresh_seconds: u64,
+    /// Point out SomaFM channels added, renamed or removed since last seen
+    #[serde(default = "default_station_changes")]
+    pub station_changes: bool,
+    /// Songs a SomaFM channel played last to list while it is selected; 0
+    /// doesn't fetch them
+    #[serde(default = "default_recent_songs")]
+    pub recent_songs: usize,
+    /// Seconds without input before animations slow down; 0 only slows them
+    /// while the terminal is unfocused
+    #[serde(default = "default_idle_after_seconds")]
+    pub idle_after_seconds: u64,
+    /// Seconds paused before the stream is disconnected to stop it
+    /// downloading; 0 keeps it connected
+    #[serde(default = "default_pause_disconnect_seconds")]
+    pub pause_disconnect_seconds: u64,
+    /// Messages the history panel keeps; older ones are dropped
+    #[serde(default = "default_history_max_entries")]
+    pub history_max_entries: usize,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub status_server: StatusServerConfig,
+    #[serde(default)]
+    pub status_file: StatusFileConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub custom_stations: Vec<CustomStation>,
+    #[serde(default)]
+    pub radio_browser: RadioBrowserConfig,
+    #[serde(default)]
+    pub scrobbler_log: ScrobblerLogConfig,
+    #[serde(default)]
+    pub track_log: TrackLogConfig,
+    #[serde(default)]
+    pub metadata_encoding: MetadataEncodingConfig,
+    #[serde(default)]
+    pub title_cleanup: TitleCleanupConfig,
+    #[serde(default)]
+    pub sound_effects: SoundEffectsConfig,
+    #[serde(default)]
+    pub loudness: LoudnessConfig,
+    #[serde(default)]
+    pub equalizer: EqualizerConfig,
+    #[serde(default)]
+    pub update: UpdateConfig,
+    #[serde(default)]
+    pub recording: RecordingConfig,
+    #[serde(default)]
+    pub data_usage: DataUsageConfig,
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub experimental: ExperimentalConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub webhook: PlaybackWebhookConfig,
+    #[serde(default)]
+    pub splash: SplashConfig,
+    /// Extra spoken names for `tune`, mapped to station IDs
+    #[serde(default)]
+    pub voice_aliases: BTreeMap<String, String>,
+    /// Station IDs in preset slots `"1"` to `"9"`
+    #[serde(default)]
+    pub presets: BTreeMap<String, String>,
+    #[serde(default)]
+    pub alarm: AlarmConfig,
+    #[serde(default)]
+    pub time: TimeConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub favorites: FavoritesConfig,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+}
+
+/// MQTT client settings, read from the `[mqtt]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_mqtt_host")]
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+    #[serde(default = "default_mqtt_discovery")]
+    pub discovery: bool,
+    #[serde(default = "default_mqtt_discovery_prefix")]
+    pub discovery_prefix: String,
+}
+
+/// WebSocket event stream settings, read from the `[websocket]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebSocketConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_websocket_bind")]
+    pub bind: String,
+    #[serde(default = "default_websocket_port")]
+    pub port: u16,
+    /// Seconds between progress events while playing; 0 sends none
+    #[serde(default = "default_websocket_progress_seconds")]
+    pub progress_seconds: u64,
+}
+
+/// Now Playing HTTP endpoint settings, read from the `[status_server]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StatusServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_websocket_bind")]
+    pub bind: String,
+    #[serde(default = "default_status_server_port")]
+    pub port: u16,
+}
+
+/// SomaFM API endpoints, read from the `[api]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ApiConfig {
+    /// Base URL serving `channels.json`, playlists and images
+    #[serde(default = "default_api_base_url")]
+    pub base_url: String,
+}
+
+/// A station defined in the config file, read from `[[custom_stations]]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CustomStation {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    #[serde(default)]
+    pub genre: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub image: String,
+}
+
+/// Radio Browser directory search, read from the `[radio_browser]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RadioBrowserConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_radio_browser_server")]
+    pub server: String,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub country_code: Option<String>,
+    #[serde(default = "default_radio_browser_limit")]
+    pub limit: u32,
+}
+
+/// Audioscrobbler `.scrobbler.log` export, read from the `[scrobbler_log]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScrobblerLogConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Log file, `.scrobbler.log` next to the config file when unset
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Tracks played for less than this are logged as skipped
+    #[serde(default = "default_scrobbler_log_min_seconds")]
+    pub min_seconds: u64,
+}
+
+/// Persistent track log settings, read from the `[track_log]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TrackLogConfig {
+    #[serde(default = "default_track_log_enabled")]
+    pub enabled: bool,
+    /// Log file of the file storage backend, imported once by the SQLite
+    /// one; `tracks.jsonl` in the user data directory when unset
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Folder `E` exports the log to, `Documents` or the home folder when
+    /// unset
+    #[serde(default)]
+    pub export_dir: Option<PathBuf>,
+    /// Format `E` exports in
+    #[serde(default)]
+    pub export_format: ExportFormat,
+}
+
+impl TrackLogConfig {
+    pub fn export_dir(&self) -> Option<PathBuf> {
+        self.export_dir
+            .clone()
+            .or_else(|| dirs::document_dir().or_else(dirs::home_dir))
+    }
+}
+
+fn default_track_log_enabled() -> bool {
+    true
+}
+
+/// Stream title encodings, read from the `[metadata_encoding]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MetadataEncodingConfig {
+    /// Encoding label such as `windows-1251`, or `auto` to detect it
+    #[serde(default = "default_metadata_encoding")]
+    pub default: String,
+    /// Encoding labels by station ID or stream URL
+    #[serde(default)]
+    pub stations: BTreeMap<String, String>,
+}
+
+/// Alarm clock, read from the `[alarm]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct AlarmConfig {
+    /// `HH:MM` to start playing, no alarm when unset
+    #[serde(default)]
+    pub time: Option<String>,
+    /// Station ID or name, the last played station when unset
+    #[serde(default)]
+    pub station: Option<String>,
+    /// Seconds to fade the volume in over, 0 to start at full volume
+    #[serde(default)]
+    pub fade_in_seconds: u64,
+}
+
+/// Who may use the UDP, MQTT, status and WebSocket APIs, read from the
+/// `[remote]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RemoteConfig {
+    /// Permission of senders without a token
+    #[serde(default)]
+    pub anonymous: Permission,
+    /// Permissions by token
+    #[serde(default)]
+    pub tokens: BTreeMap<String, Permission>,
+}
+
+impl RemoteConfig {
+    /// The token granting the most, if it grants more than no token does
+    ///
+    /// A second instance shares the running one's config, so it sends this
+    /// token to be allowed what the user could do there.
+    pub fn strongest_token(&self) -> Option<&str> {
+        self.tokens
+            .iter()
+            .filter(|(_, permission)| **permission > self.anonymous)
+            .max_by_key(|(_, permission)| **permission)
+            .map(|(token, _)| token.as_str())
+    }
+}
+
+/// Liked tracks, read from the `[favorites]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct FavoritesConfig {
+    /// List file of the file storage backend, imported once by the SQLite
+    /// one; `favorites.jsonl` in the user data directory when unset. Pushes
+    /// still to be made are queued next to it.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    #[serde(default)]
+    pub lastfm: LastFmConfig,
+    #[serde(default)]
+    pub listenbrainz: ListenBrainzConfig,
+    #[serde(default)]
+    pub webhook: WebhookConfig,
+}
+
+/// Pushing likes to Last.fm loved tracks, read from `[favorites.lastfm]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LastFmConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub api_secret: String,
+    /// Session key from Last.fm's desktop authentication flow
+    #[serde(default)]
+    pub session_key: String,
+}
+
+/// Pushing likes to ListenBrainz feedback, read from `[favorites.listenbrainz]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ListenBrainzConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// User token from the ListenBrainz settings page
+    #[serde(default)]
+    pub token: String,
+    #[serde(default = "default_listenbrainz_api_url")]
+    pub api_url: String,
+}
+
+/// POSTing likes as JSON, read from `[favorites.webhook]`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub url: String,
+}
+
+fn default_listenbrainz_api_url() -> String {
+    "https://api.listenbrainz.org".to_string()
+}
+
+/// Stream title cleanup, read from the `[title_cleanup]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TitleCleanupConfig {
+    #[serde(default = "default_title_cleanup_enabled")]
+    pub enabled: bool,
+    /// Apply the built-in rules before the configured ones
+    #[serde(default = "default_title_cleanup_builtin")]
+    pub builtin: bool,
+    /// Rules for every station
+    #[serde(default)]
+    pub rules: Vec<TitleRule>,
+    /// Rules by station ID, stream URL or provider name
+    #[serde(default)]
+    pub stations: BTreeMap<String, Vec<TitleRule>>,
+}
+
+/// Splash screen settings, read from the `[splash]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplashConfig {
+    #[serde(default = "default_splash_enabled")]
+    pub enabled: bool,
+    /// Shortest time the splash stays up, even if stations load sooner
+    #[serde(default = "default_splash_min_duration_ms")]
+    pub min_duration_ms: u64,
+}
+
+impl Default for SplashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_splash_enabled(),
+            min_duration_ms: default_splash_min_duration_ms(),
+        }
+    }
+}
+
+fn default_splash_enabled() -> bool {
+    true
+}
+
+fn default_splash_min_duration_ms() -> u64 {
+    1500
+}
+
+/// A regex and what to replace its matches with
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TitleRule {
+    pub pattern: String,
+    /// Replacement, may refer to groups as `$1`; empty removes the match
+    #[serde(default)]
+    pub replace: String,
+}
+
+fn default_title_cleanup_enabled() -> bool {
+    true
+}
+
+fn default_title_cleanup_builtin() -> bool {
+    true
+}
+
+fn default_metadata_encoding() -> String {
+    "auto".to_string()
 }
 
 fn default_volume() -> f32 {
@@ -122,6 +579,10 @@ fn default_audio_startup_prefetch_seconds() -> u64 {
     3
 }
 
+fn default_audio_default_bitrate_kbps() -> u64 {
+    128
+}
+
 fn default_audio_buffer_size_bytes() -> usize {
     8 * 1024 * 1024
 }
@@ -130,18 +591,328 @@ fn default_audio_output_buffer_frames() -> u32 {
     4096
 }
 
+fn default_audio_downgrade_underruns() -> u32 {
+    3
+}
+
+fn default_audio_downgrade_window_seconds() -> u64 {
+    60
+}
+
+fn default_audio_upgrade_after_seconds() -> u64 {
+    600
+}
+
+fn default_audio_fade_ms() -> u64 {
+    300
+}
+
+fn default_audio_time_shift_seconds() -> u64 {
+    60
+}
+
+fn default_audio_silence_restart_seconds() -> u64 {
+    30
+}
+
+fn default_audio_underrun_restart_seconds() -> u64 {
+    30
+}
+
+fn default_catalog_cache_ttl_minutes() -> u64 {
+    60
+}
+
+fn default_last_playing_refresh_seconds() -> u64 {
+    60
+}
+
+fn default_station_changes() -> bool {
+    true
+}
+
+fn default_recent_songs() -> usize {
+    10
+}
+
+fn default_idle_after_seconds() -> u64 {
+    120
+}
+
+fn default_pause_disconnect_seconds() -> u64 {
+    300
+}
+
+fn default_history_max_entries() -> usize {
+    1000
+}
+
+fn default_mqtt_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "somars".to_string()
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "somars".to_string()
+}
+
+fn default_mqtt_discovery() -> bool {
+    true
+}
+
+fn default_mqtt_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+fn default_websocket_bind() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_websocket_port() -> u16 {
+    8070
+}
+
+fn default_websocket_progress_seconds() -> u64 {
+    5
+}
+
+fn default_status_server_port() -> u16 {
+    8071
+}
+
+fn default_api_base_url() -> String {
+    "https://somafm.com".to_string()
+}
+
+fn default_radio_browser_server() -> String {
+    "https://de1.api.radio-browser.info".to_string()
+}
+
+fn default_radio_browser_limit() -> u32 {
+    50
+}
+
+fn default_scrobbler_log_min_seconds() -> u64 {
+    30
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_mqtt_host(),
+            port: default_mqtt_port(),
+            client_id: default_mqtt_client_id(),
+            username: None,
+            password: None,
+            topic_prefix: default_mqtt_topic_prefix(),
+            discovery: default_mqtt_discovery(),
+            discovery_prefix: default_mqtt_discovery_prefix(),
+        }
+    }
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_websocket_bind(),
+            port: default_websocket_port(),
+            progress_seconds: default_websocket_progress_seconds(),
+        }
+    }
+}
+
+impl Default for StatusServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_websocket_bind(),
+            port: default_status_server_port(),
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_api_base_url(),
+        }
+    }
+}
+
+impl ApiConfig {
+    fn base(&self) -> &str {
+        self.base_url.trim_end_matches('/')
+    }
+
+    /// URL of the channel catalog
+    pub fn channels_url(&self) -> String {
+        format!("{}/channels.json", self.base())
+    }
+
+    /// URL of the songs a channel played last
+    pub fn songs_url(&self, channel: &str) -> String {
+        format!("{}/songs/{}.json", self.base(), channel)
+    }
+
+    /// Rewrite a somafm.com API link (playlist, image) onto the configured base URL
+    ///
+    /// Links to other hosts, including the Icecast stream servers, are left alone.
+    pub fn resolve(&self, link: &str) -> String {
+        if self.base_url == default_api_base_url() {
+            return link.to_string();
+        }
+        match url::Url::parse(link) {
+            Ok(parsed) if matches!(parsed.host_str(), Some("somafm.com" | "api.somafm.com")) => {
+                let mut resolved = format!("{}{}", self.base(), parsed.path());
+                if let Some(query) = parsed.query() {
+                    resolved.push('?');
+                    resolved.push_str(query);
+                }
+                resolved
+            }
+            _ => link.to_string(),
+        }
+    }
+}
+
+impl Default for RadioBrowserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            server: default_radio_browser_server(),
+            tag: None,
+            country_code: None,
+            limit: default_radio_browser_limit(),
+        }
+    }
+}
+
+impl Default for ScrobblerLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            min_seconds: default_scrobbler_log_min_seconds(),
+        }
+    }
+}
+
+impl Default for TrackLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_track_log_enabled(),
+            path: None,
+            export_dir: None,
+            export_format: ExportFormat::default(),
+        }
+    }
+}
+
+impl Default for MetadataEncodingConfig {
+    fn default() -> Self {
+        Self {
+            default: default_metadata_encoding(),
+            stations: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for ListenBrainzConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: String::new(),
+            api_url: default_listenbrainz_api_url(),
+        }
+    }
+}
+
+impl Default for TitleCleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_title_cleanup_enabled(),
+            builtin: default_title_cleanup_builtin(),
+            rules: Vec::new(),
+            stations: BTreeMap::new(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             volume: default_volume(),
             last_station: None,
+            locale: None,
             log_level: default_log_level(),
             udp_port: default_udp_port(),
             udp_enabled: false,
             audio_prefetch_seconds: default_audio_prefetch_seconds(),
             audio_startup_prefetch_seconds: default_audio_startup_prefetch_seconds(),
+            audio_default_bitrate_kbps: default_audio_default_bitrate_kbps(),
             audio_buffer_size_bytes: default_audio_buffer_size_bytes(),
             audio_output_buffer_frames: default_audio_output_buffer_frames(),
+            audio_downgrade_underruns: default_audio_downgrade_underruns(),
+            audio_downgrade_window_seconds: default_audio_downgrade_window_seconds(),
+            audio_upgrade_after_seconds: default_audio_upgrade_after_seconds(),
+            audio_fade_ms: default_audio_fade_ms(),
+            audio_time_shift_seconds: default_audio_time_shift_seconds(),
+            audio_silence_restart_seconds: default_audio_silence_restart_seconds(),
+            audio_underrun_restart_seconds: default_audio_underrun_restart_seconds(),
+            volume_curve: VolumeCurve::default(),
+            loudness: LoudnessConfig::default(),
+            equalizer: EqualizerConfig::default(),
+            update: UpdateConfig::default(),
+            recording: RecordingConfig::default(),
+            data_usage: DataUsageConfig::default(),
+            schedule: ScheduleConfig::default(),
+            experimental: ExperimentalConfig::default(),
+            network: NetworkConfig::default(),
+            logging: LoggingConfig::default(),
+            hooks: HooksConfig::default(),
+            webhook: PlaybackWebhookConfig::default(),
+            audio_backend: AudioBackend::default(),
+            audio_hosts: Vec::new(),
+            stream_quality: StreamQuality::default(),
+            station_sort: StationSort::default(),
+            catalog_cache_ttl_minutes: default_catalog_cache_ttl_minutes(),
+            last_playing_refresh_seconds: default_last_playing_refresh_seconds(),
+            station_changes: default_station_changes(),
+            recent_songs: default_recent_songs(),
+            idle_after_seconds: default_idle_after_seconds(),
+            pause_disconnect_seconds: default_pause_disconnect_seconds(),
+            history_max_entries: default_history_max_entries(),
+            mqtt: MqttConfig::default(),
+            terminal: TerminalConfig::default(),
+            theme: ThemeConfig::default(),
+            websocket: WebSocketConfig::default(),
+            status_server: StatusServerConfig::default(),
+            status_file: StatusFileConfig::default(),
+            api: ApiConfig::default(),
+            custom_stations: Vec::new(),
+            radio_browser: RadioBrowserConfig::default(),
+            scrobbler_log: ScrobblerLogConfig::default(),
+            track_log: TrackLogConfig::default(),
+            metadata_encoding: MetadataEncodingConfig::default(),
+            title_cleanup: TitleCleanupConfig::default(),
+            sound_effects: SoundEffectsConfig::default(),
+            splash: SplashConfig::default(),
+            voice_aliases: BTreeMap::new(),
+            presets: BTreeMap::new(),
+            alarm: AlarmConfig::default(),
+            time: TimeConfig::default(),
+            storage: StorageConfig::default(),
+            favorites: FavoritesConfig::default(),
+            remote: RemoteConfig::default(),
         }
     }
 }
@@ -158,10 +929,10 @@ impl Config {
     /// Load configuration, falling back to defaults on error
     ///
     /// This is a convenience method that always returns a valid config,
-    /// using defaults if loading fails. Errors are printed to stderr.
+    /// using defaults if loading fails. Errors are logged.
     pub fn load_or_default() -> Self {
         Self::load().unwrap_or_else(|e| {
-            eprintln!("Config load failed: {}. Using defaults.", e);
+            tracing::warn!("Config load failed: {}. Using defaults.", e);
             Self::default()
         })
     }
@@ -184,11 +955,12 @@ impl Config {
                     path: config_path.clone(),
                     source,
                 })?;
-            let config: Config =
+            let mut config: Config =
                 toml::from_str(&content).map_err(|source| ConfigError::ParseError {
                     path: config_path.clone(),
                     source,
                 })?;
+            config.migrate_pipewire_backend(&content);
             Ok(config)
         } else {
             // Return default config if file doesn't exist
@@ -196,6 +968,24 @@ impl Config {
         }
     }
 
+    /// Turn `audio_backend = "pipewire"`, from before `audio_hosts`, into the
+    /// `pipewire` host with the default ALSA device to fall back to, which is
+    /// what that backend did
+    fn migrate_pipewire_backend(&mut self, content: &str) {
+        if !self.audio_hosts.is_empty() {
+            return;
+        }
+        let pipewire = content.parse::<toml::Table>().is_ok_and(|table| {
+            table
+                .get("audio_backend")
+                .and_then(toml::Value::as_str)
+                .is_some_and(|backend| backend.eq_ignore_ascii_case("pipewire"))
+        });
+        if pipewire {
+            self.audio_hosts = vec![AudioHost::Pipewire, AudioHost::Alsa];
+        }
+    }
+
     /// Save configuration to default path
     pub fn save(&self) -> ConfigResult<()> {
         let config_path = Self::default_config_path()?;
@@ -260,8 +1050,95 @@ mod tests {
         assert_eq!(config.last_station, None);
         assert_eq!(config.audio_prefetch_seconds, 20);
         assert_eq!(config.audio_startup_prefetch_seconds, 3);
+        assert_eq!(config.audio_default_bitrate_kbps, 128);
         assert_eq!(config.audio_buffer_size_bytes, 8 * 1024 * 1024);
         assert_eq!(config.audio_output_buffer_frames, 4096);
+        assert_eq!(config.audio_downgrade_underruns, 3);
+        assert_eq!(config.audio_backend, AudioBackend::Rodio);
+        assert_eq!(config.mqtt, MqttConfig::default());
+        assert_eq!(config.websocket.port, 8070);
+        assert_eq!(config.status_server.port, 8071);
+    }
+
+    #[test]
+    fn test_api_config_mirror() {
+        let api = ApiConfig {
+            base_url: "http://mirror.local/soma/".to_string(),
+        };
+        assert_eq!(api.channels_url(), "http://mirror.local/soma/channels.json");
+        assert_eq!(
+            api.resolve("https://api.somafm.com/img/groovesalad120.png"),
+            "http://mirror.local/soma/img/groovesalad120.png"
+        );
+        assert_eq!(
+            api.resolve("https://ice1.somafm.com/groovesalad-128-mp3"),
+            "https://ice1.somafm.com/groovesalad-128-mp3"
+        );
+
+        let default = ApiConfig::default();
+        assert_eq!(default.channels_url(), "https://somafm.com/channels.json");
+        assert_eq!(
+            default.songs_url("groovesalad"),
+            "https://somafm.com/songs/groovesalad.json"
+        );
+        assert_eq!(
+            default.resolve("https://api.somafm.com/x.pls"),
+            "https://api.somafm.com/x.pls"
+        );
+    }
+
+    #[test]
+    fn test_config_mqtt_section() {
+        let config: Config = toml::from_str(
+            r#"
+            [mqtt]
+            enabled = true
+            host = "broker.local"
+            "#,
+        )
+        .unwrap();
+        assert!(config.mqtt.enabled);
+        assert_eq!(config.mqtt.host, "broker.local");
+        assert_eq!(config.mqtt.port, 1883);
+        assert_eq!(config.mqtt.topic_prefix, "somars");
+        assert!(config.mqtt.discovery);
+    }
+
+    #[test]
+    fn test_pipewire_backend_becomes_a_host() {
+        let content = r#"audio_backend = "pipewire""#;
+        let mut config: Config = toml::from_str(content).unwrap();
+        config.migrate_pipewire_backend(content);
+        assert_eq!(config.audio_backend, AudioBackend::Cpal);
+        assert_eq!(config.audio_hosts, [AudioHost::Pipewire, AudioHost::Alsa]);
+
+        let content = "audio_backend = \"pipewire\"\naudio_hosts = [\"pulse\"]";
+        let mut config: Config = toml::from_str(content).unwrap();
+        config.migrate_pipewire_backend(content);
+        assert_eq!(config.audio_hosts, [AudioHost::Pulse]);
+    }
+
+    #[test]
+    fn test_remote_strongest_token() {
+        let config: Config = toml::from_str(
+            r#"
+            [remote]
+            anonymous = "status"
+            tokens = { viewer = "status", dj = "playback", owner = "admin" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.remote.strongest_token(), Some("owner"));
+
+        let config: Config = toml::from_str(
+            r#"
+            [remote]
+            tokens = { dj = "playback" }
+            "#,
+        )
+        .unwrap();
+        // Senders without a token are admins already
+        assert_eq!(config.remote.strongest_token(), None);
     }
 
     #[test]
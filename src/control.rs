@@ -1,13 +1,105 @@
+use crate::action::{Action, RandomScope};
+use crate::config::RemoteConfig;
+use crate::station::StreamQuality;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Version of the command syntax, raised when a command changes meaning
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Commands this version understands, as listed by `hello`
+const COMMANDS: &[&str] = &[
+    "hello",
+    "play",
+    "stop",
+    "toggle",
+    "toggle pause",
+    "volume up",
+    "volume down",
+    "volume mute",
+    "volume unmute",
+    "volume <level>",
+    "tune <station>",
+    "tune url",
+    "tune next",
+    "tune prev",
+    "tune back",
+    "tune random",
+    "quality",
+    "preset",
+    "refresh",
+    "like",
+    "select up",
+    "select down",
+];
+
+/// Extensions to the basic syntax, as listed by `hello`
+const FEATURES: &[&str] = &[
+    // `seq <N>` prefix, acknowledged with `ok <N>`
+    "seq",
+    // Sequenced commands that fail are answered with `err <N> <reason>`
+    "seq-errors",
+    // `token <T>` prefix
+    "token",
+    // `volume +<N>` and `volume -<N>`
+    "relative-volume",
+    // Spoken station names in `tune`
+    "spoken-names",
+];
+
+/// Reply to `hello`, so remotes can tell what an instance supports instead of
+/// guessing from silence
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hello {
+    pub app: &'static str,
+    pub version: &'static str,
+    pub protocol: u32,
+    pub commands: &'static [&'static str],
+    pub features: &'static [&'static str],
+}
+
+impl Hello {
+    /// What this build supports
+    pub fn current() -> Self {
+        Self {
+            app: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            protocol: PROTOCOL_VERSION,
+            commands: COMMANDS,
+            features: FEATURES,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ControlCommand {
+    /// Ask for the protocol version and supported commands
+    Hello,
     Play,
     Stop,
     VolumeUp,
     VolumeDown,
     SetVolume(f32),
+    /// Change the volume by a signed amount
+    AdjustVolume(f32),
+    Mute,
+    Unmute,
     Tune(String),
+    TuneUrl(String),
     TuneNext,
     TunePrev,
+    TuneBack,
+    TuneRandom(RandomScope),
+    Quality(StreamQuality),
+    Preset(u8),
+    Refresh,
+    /// Like the playing track
+    Like,
     SelectUp,
     SelectDown,
     Toggle,
@@ -22,3 +114,249 @@ pub enum ControlCommand {
     #[allow(dead_code)]
     Quit,
 }
+
+impl From<ControlCommand> for Action {
+    fn from(command: ControlCommand) -> Self {
+        match command {
+            // Answered by the UDP listener; elsewhere there is nothing to do
+            ControlCommand::Hello => Action::Render,
+            ControlCommand::Play => Action::Play,
+            ControlCommand::Stop => Action::Stop,
+            ControlCommand::TogglePause => Action::TogglePause,
+            ControlCommand::VolumeUp => Action::VolumeUp,
+            ControlCommand::VolumeDown => Action::VolumeDown,
+            ControlCommand::SetVolume(level) => Action::SetVolume(level),
+            ControlCommand::AdjustVolume(delta) => Action::AdjustVolume(delta),
+            ControlCommand::Mute => Action::Mute,
+            ControlCommand::Unmute => Action::Unmute,
+            ControlCommand::Tune(station_id) => Action::TuneStation(station_id),
+            ControlCommand::TuneUrl(url) => Action::TuneUrl(url),
+            ControlCommand::TuneNext => Action::TuneNext,
+            ControlCommand::TunePrev => Action::TunePrev,
+            ControlCommand::TuneBack => Action::TuneBack,
+            ControlCommand::TuneRandom(scope) => Action::TuneRandom(scope),
+            ControlCommand::Quality(quality) => Action::SetStreamQuality(quality),
+            ControlCommand::Preset(slot) => Action::TunePreset(slot),
+            ControlCommand::Refresh => Action::RefreshStations,
+            ControlCommand::Like => Action::LikeTrack,
+            ControlCommand::SelectUp => Action::StationUp,
+            ControlCommand::SelectDown => Action::StationDown,
+            ControlCommand::Toggle => Action::TogglePlayStop,
+            ControlCommand::ToggleHelp => Action::ToggleHelp,
+            ControlCommand::ScrollHistoryUp => Action::ScrollHistoryUp,
+            ControlCommand::ScrollHistoryDown => Action::ScrollHistoryDown,
+            ControlCommand::Quit => Action::Quit,
+        }
+    }
+}
+
+impl ControlCommand {
+    /// The permission a remote sender needs to run this command
+    pub fn permission(&self) -> Permission {
+        match self {
+            // Arbitrary URLs, saved settings and the app itself
+            ControlCommand::TuneUrl(_)
+            | ControlCommand::Quality(_)
+            | ControlCommand::Refresh
+            | ControlCommand::Quit => Permission::Admin,
+            ControlCommand::Hello => Permission::Status,
+            _ => Permission::Playback,
+        }
+    }
+}
+
+/// What a remote API user may do; each level includes the ones before it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Nothing at all
+    None,
+    /// Read the status server and WebSocket events
+    Status,
+    /// Control playback, volume and stations
+    Playback,
+    /// Everything, including settings and quitting
+    #[default]
+    Admin,
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Permission::None => "none",
+            Permission::Status => "status",
+            Permission::Playback => "playback",
+            Permission::Admin => "admin",
+        })
+    }
+}
+
+/// Permissions of remote API users, by token
+///
+/// Every remote command goes through [`Permissions::authorize`], whichever API
+/// it arrived on.
+#[derive(Debug, Clone, Default)]
+pub struct Permissions {
+    /// Permission of senders without a token
+    anonymous: Permission,
+    tokens: HashMap<String, Permission>,
+}
+
+impl Permissions {
+    pub fn from_config(config: &RemoteConfig) -> Self {
+        Self {
+            anonymous: config.anonymous,
+            tokens: config
+                .tokens
+                .iter()
+                .map(|(token, permission)| (token.clone(), *permission))
+                .collect(),
+        }
+    }
+
+    /// The permission `token` grants; unknown tokens grant nothing
+    pub fn permission(&self, token: Option<&str>) -> Permission {
+        match token {
+            Some(token) => self.tokens.get(token).copied().unwrap_or(Permission::None),
+            None => self.anonymous,
+        }
+    }
+
+    /// Whether `token` grants at least `needed`
+    pub fn allows(&self, token: Option<&str>, needed: Permission) -> bool {
+        self.permission(token) >= needed
+    }
+
+    /// Parse a command in the UDP syntax, optionally led by `token <TOKEN>`,
+    /// and check that the sender may run it
+    pub fn authorize(&self, input: &str) -> Result<(Option<u32>, ControlCommand), String> {
+        let (token, input) = split_token(input);
+        let granted = self.permission(token);
+        if granted == Permission::None {
+            return Err(match token {
+                Some(_) => "unknown token".to_string(),
+                None => "a token is required".to_string(),
+            });
+        }
+        let (seq, command) = crate::udp::parse_udp_packet(input)?;
+        let needed = command.permission();
+        if granted < needed {
+            return Err(format!(
+                "{:?} needs the {} permission, the sender has {}",
+                command, needed, granted
+            ));
+        }
+        Ok((seq, command))
+    }
+}
+
+/// Sequence number of a command, even one that doesn't parse, so failures
+/// can be reported to the sender
+pub fn sequence(input: &str) -> Option<u32> {
+    let (_, input) = split_token(input);
+    let mut words = input.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("seq") {
+        return None;
+    }
+    words.next()?.parse().ok()
+}
+
+/// Token from an HTTP `Authorization: Bearer` header or a `token` query
+/// parameter, for the status and WebSocket servers
+pub fn request_token(authorization: Option<&str>, query: &str) -> Option<String> {
+    let bearer = authorization.and_then(|value| {
+        let value = value.trim();
+        value
+            .get(..7)
+            .filter(|scheme| scheme.eq_ignore_ascii_case("bearer "))
+            .map(|_| value[7..].trim().to_string())
+    });
+    bearer.or_else(|| {
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(name, _)| name == "token")
+            .map(|(_, token)| token.into_owned())
+    })
+}
+
+/// Split a leading `token <TOKEN>` off a command; tokens keep their case
+fn split_token(input: &str) -> (Option<&str>, &str) {
+    let input = input.trim_start();
+    let Some((keyword, rest)) = input.split_once(char::is_whitespace) else {
+        return (None, input);
+    };
+    if !keyword.eq_ignore_ascii_case("token") {
+        return (None, input);
+    }
+    let rest = rest.trim_start();
+    let (token, command) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    (Some(token), command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enforces_token_permissions() {
+        let config = RemoteConfig {
+            anonymous: Permission::Status,
+            tokens: [
+                ("Roommate".to_string(), Permission::Playback),
+                ("owner".to_string(), Permission::Admin),
+            ]
+            .into(),
+        };
+        let permissions = Permissions::from_config(&config);
+
+        assert_eq!(
+            permissions.authorize("token Roommate volume 50%"),
+            Ok((None, ControlCommand::SetVolume(0.5)))
+        );
+        assert_eq!(
+            permissions.authorize("TOKEN owner seq 7 refresh"),
+            Ok((Some(7), ControlCommand::Refresh))
+        );
+        assert!(permissions.authorize("token Roommate refresh").is_err());
+        assert!(permissions.authorize("token Roommate quality low").is_err());
+        // Tokens are case-sensitive
+        assert!(permissions.authorize("token roommate play").is_err());
+        assert!(permissions.authorize("play").is_err());
+        assert!(permissions.authorize("token owner").is_err());
+        assert!(permissions.allows(None, Permission::Status));
+        assert_eq!(
+            permissions.authorize("hello"),
+            Ok((None, ControlCommand::Hello))
+        );
+        assert!(!permissions.allows(Some("nope"), Permission::Status));
+
+        // Without configuration everyone may do everything, as before tokens
+        let open = Permissions::default();
+        assert_eq!(
+            open.authorize("tune url http://example.com/stream"),
+            Ok((
+                None,
+                ControlCommand::TuneUrl("http://example.com/stream".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn describes_the_protocol() {
+        let hello: serde_json::Value = serde_json::from_str(&Hello::current().to_json()).unwrap();
+        assert_eq!(hello["protocol"], PROTOCOL_VERSION);
+        assert_eq!(hello["app"], "somars");
+        assert!(hello["commands"]
+            .as_array()
+            .unwrap()
+            .contains(&"tune url".into()));
+        assert!(hello["features"]
+            .as_array()
+            .unwrap()
+            .contains(&"seq".into()));
+
+        assert_eq!(sequence("seq 12 volume loud"), Some(12));
+        assert_eq!(sequence("token abc SEQ 3 frobnicate"), Some(3));
+        assert_eq!(sequence("play"), None);
+        assert_eq!(sequence("seq x play"), None);
+    }
+}
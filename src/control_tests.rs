@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::control::ControlCommand;
-    
+
     #[test]
     fn test_control_command_creation() {
         // Test that all control commands can be created
@@ -22,4 +22,4 @@ mod tests {
         let _scroll_history_down = ControlCommand::ScrollHistoryDown;
         let _quit = ControlCommand::Quit;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,175 @@
+//! Daily data usage
+//!
+//! The bytes counted by [`crate::audio::throughput::Throughput`] are added up
+//! per day and kept in the data folder, so the day's total and an optional
+//! daily cap for a metered connection hold across restarts.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Days of usage kept in the file
+const KEEP_DAYS: usize = 90;
+/// How often the day's usage is saved while streaming
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Data usage settings, read from the `[data_usage]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataUsageConfig {
+    /// Keep daily totals across restarts
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Stop playback once this many MB were downloaded today; 0 for no cap
+    #[serde(default)]
+    pub daily_cap_mb: u64,
+    /// File the daily totals are kept in; defaults to the data folder
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for DataUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            daily_cap_mb: 0,
+            path: None,
+        }
+    }
+}
+
+impl DataUsageConfig {
+    /// Daily cap in bytes, if one is set
+    pub fn daily_cap(&self) -> Option<u64> {
+        (self.daily_cap_mb > 0).then(|| self.daily_cap_mb.saturating_mul(1024 * 1024))
+    }
+}
+
+/// Bytes downloaded per day
+#[derive(Debug)]
+pub struct DataUsage {
+    /// `None` when totals aren't kept across restarts
+    path: Option<PathBuf>,
+    /// Bytes by ISO date, which sorts oldest first
+    days: BTreeMap<String, u64>,
+    /// Session total already added to `days`
+    counted: u64,
+    saved: Option<Instant>,
+}
+
+impl DataUsage {
+    pub fn load(config: &DataUsageConfig) -> Self {
+        let path = config.enabled.then(|| path(config)).flatten();
+        let days = path.as_deref().and_then(read).unwrap_or_default();
+        Self {
+            path,
+            days,
+            counted: 0,
+            saved: None,
+        }
+    }
+
+    /// Add what was downloaded since the last call to `today`, given the
+    /// session's running `total`
+    pub fn record(&mut self, today: NaiveDate, total: u64) {
+        let new = total.saturating_sub(self.counted);
+        self.counted = total;
+        if new == 0 {
+            return;
+        }
+        *self.days.entry(today.to_string()).or_default() += new;
+        if self
+            .saved
+            .is_none_or(|saved| saved.elapsed() >= SAVE_INTERVAL)
+        {
+            self.save();
+        }
+    }
+
+    /// Bytes downloaded on `day`
+    pub fn on(&self, day: NaiveDate) -> u64 {
+        self.days.get(&day.to_string()).copied().unwrap_or_default()
+    }
+
+    /// Write the totals out, dropping the oldest days
+    pub fn save(&mut self) {
+        while self.days.len() > KEEP_DAYS {
+            self.days.pop_first();
+        }
+        self.saved = Some(Instant::now());
+        if let Some(path) = &self.path {
+            write(path, &self.days);
+        }
+    }
+}
+
+pub fn path(config: &DataUsageConfig) -> Option<PathBuf> {
+    config
+        .path
+        .clone()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("somars").join("data_usage.json")))
+}
+
+fn read(path: &Path) -> Option<BTreeMap<String, u64>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write(path: &Path, days: &BTreeMap<String, u64>) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let contents = serde_json::to_string(days).map_err(std::io::Error::other)?;
+            std::fs::write(path, contents)
+        });
+    if let Err(error) = result {
+        warn!("Failed to save data usage {}: {}", path.display(), error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_each_day_across_restarts() {
+        let path =
+            std::env::temp_dir().join(format!("somars-data-usage-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = DataUsageConfig {
+            path: Some(path.clone()),
+            ..DataUsageConfig::default()
+        };
+        let monday = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+
+        let mut usage = DataUsage::load(&config);
+        usage.record(monday, 1_000);
+        usage.record(monday, 1_500);
+        usage.record(tuesday, 2_000);
+        assert_eq!(usage.on(monday), 1_500);
+        assert_eq!(usage.on(tuesday), 500);
+        usage.save();
+
+        // The next session's count starts from zero again
+        let mut usage = DataUsage::load(&config);
+        usage.record(tuesday, 700);
+        assert_eq!(usage.on(tuesday), 1_200);
+        assert_eq!(usage.on(monday), 1_500);
+
+        assert_eq!(config.daily_cap(), None);
+        let capped = DataUsageConfig {
+            daily_cap_mb: 2,
+            ..config
+        };
+        assert_eq!(capped.daily_cap(), Some(2 * 1024 * 1024));
+        let _ = std::fs::remove_file(path);
+    }
+}
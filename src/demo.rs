@@ -0,0 +1,246 @@
+//! Demo mode
+//!
+//! `somars --demo` lists a few made-up stations and plays them from a local
+//! server: each one a short generated melody with stream titles that change
+//! on a fixed schedule. Nothing is fetched from the internet and nothing is
+//! saved, so the interface can be tried offline, UI bugs reproduced the same
+//! way every time, and screenshots taken without anyone's music in them.
+
+use crate::error::AppError;
+use crate::mock_stream::{self, IcyFramer, METAINT, SAMPLE_RATE};
+use crate::providers::StationProvider;
+use crate::station::Station;
+use futures::future::BoxFuture;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+/// How long each stream title lasts
+const TRACK_SECONDS: u64 = 20;
+/// Length of each note of the melodies
+const NOTE_SAMPLES: u64 = SAMPLE_RATE as u64 / 2;
+
+/// A made-up station
+struct DemoStation {
+    id: &'static str,
+    title: &'static str,
+    genre: &'static str,
+    dj: &'static str,
+    description: &'static str,
+    listeners: u32,
+    /// Notes of the melody, in Hz, played in turn
+    notes: &'static [f32],
+    /// Stream titles, played in turn
+    tracks: &'static [&'static str],
+}
+
+const STATIONS: &[DemoStation] = &[
+    DemoStation {
+        id: "sinewave",
+        title: "Sine Wave Lounge",
+        genre: "ambient|chill",
+        dj: "Osc Illator",
+        description: "Pure tones drifting by for unhurried listening.",
+        listeners: 1284,
+        notes: &[220.0, 277.18, 329.63, 440.0, 329.63, 277.18],
+        tracks: &[
+            "Harmonic Drift - Overtones at Dusk",
+            "The Fundamentals - Quiet Carrier",
+            "Nyquist Gardens - Half the Rate",
+            "Phase Locked - Slow Beating",
+        ],
+    },
+    DemoStation {
+        id: "squarebeat",
+        title: "Square Beat Radio",
+        genre: "electronic|dance",
+        dj: "Duty Cycle",
+        description: "Bright arpeggios around the clock.",
+        listeners: 862,
+        notes: &[261.63, 329.63, 392.0, 523.25, 392.0, 329.63, 293.66, 349.23],
+        tracks: &[
+            "Pulse Width - Fifty Percent",
+            "Clock Divider - Every Other Tick",
+            "The Aliasing - Fold Back",
+            "Low Pass Club - Resonance",
+            "Bit Crusher - Eight Bits High",
+        ],
+    },
+    DemoStation {
+        id: "pentatonic",
+        title: "Pentatonic Porch",
+        genre: "folk|acoustic",
+        dj: "Open Strings",
+        description: "Five notes, no wrong ones.",
+        listeners: 431,
+        notes: &[196.0, 220.0, 246.94, 293.66, 329.63, 293.66, 246.94],
+        tracks: &[
+            "The Five Notes - Porch Song",
+            "Rocking Chair Trio - Evening Tune",
+            "Open Tuning - Creek Bed",
+        ],
+    },
+    DemoStation {
+        id: "lowdrone",
+        title: "Low Drone Station",
+        genre: "drone|experimental",
+        dj: "Sub Harmonic",
+        description: "Very slow, very low, very long.",
+        listeners: 97,
+        notes: &[110.0, 110.0, 130.81, 110.0, 98.0, 110.0],
+        tracks: &[
+            "Standing Wave - Part One",
+            "Standing Wave - Part Two",
+            "Infrasound Collective - Below Hearing",
+        ],
+    },
+];
+
+/// The local server playing the demo stations
+pub struct Demo {
+    port: u16,
+}
+
+impl Demo {
+    /// Start serving the demo stations on a local port
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(serve(listener, Instant::now()));
+        info!("Demo stations served on port {}", port);
+        Ok(Self { port })
+    }
+
+    /// Provider for the demo stations
+    pub fn provider(&self) -> DemoProvider {
+        DemoProvider { port: self.port }
+    }
+}
+
+/// Stations on the demo server
+pub struct DemoProvider {
+    port: u16,
+}
+
+impl StationProvider for DemoProvider {
+    fn name(&self) -> &str {
+        "Demo"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        let stations = STATIONS
+            .iter()
+            .map(|station| Station {
+                id: station.id.to_string(),
+                title: station.title.to_string(),
+                description: station.description.to_string(),
+                dj: station.dj.to_string(),
+                genre: station.genre.to_string(),
+                last_playing: station.tracks[0].to_string(),
+                listeners: station.listeners,
+                source: self.name().to_string(),
+                ..Station::from_url(&format!("http://127.0.0.1:{}/{}", self.port, station.id))
+            })
+            .collect();
+        Box::pin(async move { Ok(stations) })
+    }
+}
+
+async fn serve(listener: TcpListener, started: Instant) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                tokio::spawn(async move {
+                    if let Err(error) = stream(socket, started).await {
+                        info!("Demo stream ended: {}", error);
+                    }
+                });
+            }
+            Err(error) => {
+                warn!("Demo server failed: {}", error);
+                return;
+            }
+        }
+    }
+}
+
+/// Serve a station's melody and titles, timed from when the demo started so
+/// reconnecting picks up where the station is
+async fn stream(mut socket: TcpStream, started: Instant) -> io::Result<()> {
+    let Some(path) = mock_stream::read_request_path(&mut socket).await? else {
+        return Ok(());
+    };
+    let Some(station) = STATIONS.iter().find(|station| station.id == path) else {
+        return socket.write_all(b"HTTP/1.0 404 Not Found\r\n\r\n").await;
+    };
+    socket
+        .write_all(mock_stream::response_header(station.title).as_bytes())
+        .await?;
+
+    let mut framer = IcyFramer::new(METAINT);
+    let mut audio = mock_stream::wav_header();
+    let mut sample = started.elapsed().as_secs() * u64::from(SAMPLE_RATE);
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        interval.tick().await;
+        melody(
+            &mut audio,
+            station.notes,
+            &mut sample,
+            SAMPLE_RATE as usize / 10,
+        );
+        let title = track_at(station, sample / u64::from(SAMPLE_RATE));
+        socket
+            .write_all(&framer.frame(&std::mem::take(&mut audio), title))
+            .await?;
+    }
+}
+
+/// The stream title `seconds` into the demo
+fn track_at(station: &DemoStation, seconds: u64) -> &'static str {
+    station.tracks[(seconds / TRACK_SECONDS) as usize % station.tracks.len()]
+}
+
+/// Append `samples` of the melody from `sample` on: plucked notes, each
+/// fading out before the next
+fn melody(audio: &mut Vec<u8>, notes: &[f32], sample: &mut u64, samples: usize) {
+    for _ in 0..samples {
+        let frequency = notes[(*sample / NOTE_SAMPLES) as usize % notes.len()];
+        let within = *sample % NOTE_SAMPLES;
+        let t = within as f32 / SAMPLE_RATE as f32;
+        let progress = within as f32 / NOTE_SAMPLES as f32;
+        let envelope = (progress * 50.0).min(1.0) * (1.0 - progress).powi(2);
+        let wave = (t * frequency * std::f32::consts::TAU).sin()
+            + 0.3 * (t * frequency * 2.0 * std::f32::consts::TAU).sin();
+        let value = (wave * envelope * 4_000.0) as i16;
+        audio.extend_from_slice(&value.to_le_bytes());
+        *sample += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_the_same_melody_and_titles_every_time() {
+        let station = &STATIONS[0];
+        let render = |from: u64| {
+            let mut audio = Vec::new();
+            let mut sample = from;
+            melody(&mut audio, station.notes, &mut sample, 1_000);
+            audio
+        };
+        assert_eq!(render(0), render(0));
+        assert_eq!(render(0).len(), 2_000);
+        // Each note starts from silence
+        assert_eq!(&render(NOTE_SAMPLES)[..2], &[0, 0]);
+
+        assert_eq!(track_at(station, 0), station.tracks[0]);
+        assert_eq!(track_at(station, TRACK_SECONDS), station.tracks[1]);
+        let cycle = TRACK_SECONDS * station.tracks.len() as u64;
+        assert_eq!(track_at(station, cycle + 1), station.tracks[0]);
+    }
+}
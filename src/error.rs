@@ -29,7 +29,6 @@ pub enum AppError {
     #[error("UDP error: {0}")]
     Udp(String),
 
-    #[allow(dead_code)]
     #[error("Parse error: {0}")]
     Parse(String),
 
@@ -14,7 +14,6 @@ pub enum Event {
     /// Error occurred
     Error,
     /// Event stream closed
-    #[allow(dead_code)]
     Closed,
     /// Terminal gained focus
     FocusGained,
@@ -0,0 +1,145 @@
+//! Experimental features
+//!
+//! Subsystems still being worked on ship turned off, each behind a name that
+//! the `[experimental]` config table or `--enable-feature` turns on. What they
+//! add to the interface is labeled experimental while they are on, and says
+//! how to turn them on while they are off.
+
+use crate::i18n::t;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Experimental settings, read from the `[experimental]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentalConfig {
+    /// Features to turn on, such as `recording`
+    ///
+    /// Kept as names so a feature that graduates or goes away doesn't stop
+    /// the config from loading.
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+/// A subsystem that can be turned on while it is being worked on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Feature {
+    /// Recording the stream and saving what just played
+    Recording,
+    /// The equalizer and loudness normalization
+    Dsp,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 2] = [Feature::Recording, Feature::Dsp];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Recording => "recording",
+            Self::Dsp => "dsp",
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Feature {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|feature| feature.as_str().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|feature| feature.as_str()).collect();
+                format!(
+                    "Unknown experimental feature: {} (expected {})",
+                    value,
+                    names.join(" or ")
+                )
+            })
+    }
+}
+
+static ENABLED: OnceCell<BTreeSet<Feature>> = OnceCell::new();
+
+/// Turn on the features named in `config` and on the command line,
+/// returning the config's names that aren't features
+///
+/// Only the first call has an effect; later calls are ignored.
+pub fn init(config: &ExperimentalConfig, cli: &[Feature]) -> Vec<String> {
+    let (features, unknown) = resolve(config, cli);
+    let _ = ENABLED.set(features);
+    unknown
+}
+
+/// Whether `feature` was turned on
+pub fn enabled(feature: Feature) -> bool {
+    ENABLED
+        .get()
+        .is_some_and(|features| features.contains(&feature))
+}
+
+/// The features turned on, in a stable order
+pub fn enabled_features() -> Vec<Feature> {
+    ENABLED
+        .get()
+        .map(|features| features.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Label for what `feature` adds to the interface: experimental while it is
+/// on, how to turn it on while it is off
+pub fn label(feature: Feature) -> String {
+    if enabled(feature) {
+        format!(" ({})", t("experimental"))
+    } else {
+        format!(
+            " ({})",
+            t("experimental-needs").replace("{$feature}", feature.as_str())
+        )
+    }
+}
+
+fn resolve(config: &ExperimentalConfig, cli: &[Feature]) -> (BTreeSet<Feature>, Vec<String>) {
+    let mut features: BTreeSet<Feature> = cli.iter().copied().collect();
+    let mut unknown = Vec::new();
+    for name in &config.features {
+        match name.parse() {
+            Ok(feature) => {
+                features.insert(feature);
+            }
+            Err(_) => unknown.push(name.clone()),
+        }
+    }
+    (features, unknown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combines_config_and_command_line() {
+        let config = ExperimentalConfig {
+            features: vec!["DSP".to_string(), "daemon".to_string()],
+        };
+        let (features, unknown) = resolve(&config, &[Feature::Recording]);
+        assert_eq!(
+            features.into_iter().collect::<Vec<_>>(),
+            vec![Feature::Recording, Feature::Dsp]
+        );
+        assert_eq!(unknown, vec!["daemon".to_string()]);
+
+        assert_eq!("recording".parse::<Feature>(), Ok(Feature::Recording));
+        assert!("dameon".parse::<Feature>().is_err());
+        let config: ExperimentalConfig = toml::from_str("features = [\"dsp\"]").unwrap();
+        assert_eq!(config.features, vec!["dsp".to_string()]);
+    }
+}
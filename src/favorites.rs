@@ -0,0 +1,554 @@
+//! Liked tracks
+//!
+//! `l` or the `like` command adds the playing track to the liked tracks in the
+//! [store](crate::storage). Likes can also be pushed to Last.fm loved tracks,
+//! ListenBrainz feedback and a webhook. Pushes that fail are kept in
+//! `favorites-queue.jsonl` in the user data directory and retried with
+//! backoff, across restarts.
+
+use crate::config::{FavoritesConfig, LastFmConfig, ListenBrainzConfig, WebhookConfig};
+use crate::http;
+use crate::scrobble_log::split_title;
+use crate::storage::file::{read, replace};
+use crate::storage::SharedStore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+const LASTFM_API: &str = "https://ws.audioscrobbler.com/2.0/";
+/// Wait before the first retry; doubles with every failure
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+/// Pushes are dropped after failing this many times
+const MAX_ATTEMPTS: u32 = 24;
+
+/// A liked track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Favorite {
+    /// Unix time the track was liked
+    pub liked: u64,
+    pub station: String,
+    /// Empty when the title has no `Artist - Title` split
+    pub artist: String,
+    pub title: String,
+}
+
+impl Favorite {
+    fn new(station: &str, title: &str, now: SystemTime) -> Self {
+        let (artist, title) = split_title(title).unwrap_or(("", title.trim()));
+        Self {
+            liked: now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+            station: station.to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    /// `Artist - Title`, or just the title when the artist is unknown
+    pub fn track(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artist, self.title)
+        }
+    }
+
+    /// Artist and title ignoring case; a track is liked once
+    pub(crate) fn key(&self) -> String {
+        format!("{}\n{}", self.artist, self.title).to_lowercase()
+    }
+}
+
+/// External service likes are pushed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    LastFm,
+    ListenBrainz,
+    Webhook,
+}
+
+/// A like waiting to be pushed to one backend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Pending {
+    backend: Backend,
+    favorite: Favorite,
+    /// Failed pushes so far
+    attempts: u32,
+    /// Queued pushes are retried right away after a restart
+    #[serde(skip, default = "Instant::now")]
+    next_attempt: Instant,
+}
+
+/// Why a push failed
+#[derive(Debug)]
+enum PushError {
+    /// Worth trying again later, e.g. the network is down
+    Retry(String),
+    /// Will never succeed, e.g. bad credentials or an unknown track
+    Permanent(String),
+}
+
+impl From<reqwest::Error> for PushError {
+    fn from(error: reqwest::Error) -> Self {
+        PushError::Retry(error.to_string())
+    }
+}
+
+/// The local list of liked tracks and the queue pushing them to other services
+#[derive(Debug)]
+pub struct Favorites {
+    /// `None` when the store couldn't be opened
+    store: Option<SharedStore>,
+    liked: HashSet<String>,
+    /// Sends new likes to the sync task, `None` when no backend is enabled
+    sync_tx: Option<UnboundedSender<Favorite>>,
+}
+
+impl Favorites {
+    /// Load the list and start pushing to the enabled backends
+    pub fn new(config: &FavoritesConfig, store: Option<SharedStore>) -> Self {
+        let liked = store
+            .as_ref()
+            .map(|store| store.lock().favorites())
+            .transpose()
+            .unwrap_or_else(|error| {
+                warn!("Failed to read favorites: {}", error);
+                None
+            })
+            .unwrap_or_default()
+            .iter()
+            .map(Favorite::key)
+            .collect();
+        let backends = Backends::from_config(config);
+        let sync_tx = match (path(config), backends.enabled().is_empty()) {
+            (Some(path), false) => {
+                let (sync_tx, sync_rx) = mpsc::unbounded_channel();
+                let queue = queue_path(&path);
+                tokio::spawn(backends.run(queue, sync_rx));
+                Some(sync_tx)
+            }
+            _ => None,
+        };
+        Self {
+            store,
+            liked,
+            sync_tx,
+        }
+    }
+
+    /// Like `title`, heard on `station`
+    ///
+    /// Returns `None` when the track was already liked.
+    pub fn like(&mut self, station: &str, title: &str) -> io::Result<Option<Favorite>> {
+        let favorite = Favorite::new(station, title, SystemTime::now());
+        if self.liked.contains(&favorite.key()) {
+            return Ok(None);
+        }
+        let store = self
+            .store
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no store"))?;
+        store.lock().add_favorite(&favorite)?;
+        self.liked.insert(favorite.key());
+        if let Some(sync_tx) = &self.sync_tx {
+            let _ = sync_tx.send(favorite.clone());
+        }
+        Ok(Some(favorite))
+    }
+}
+
+/// The JSON Lines favorites list of the file backend, from the configuration
+/// or `favorites.jsonl` in the user data directory
+pub fn path(config: &FavoritesConfig) -> Option<PathBuf> {
+    config
+        .path
+        .clone()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("somars").join("favorites.jsonl")))
+}
+
+/// Pushes that still have to be made, kept next to the file backend's list
+fn queue_path(path: &Path) -> PathBuf {
+    path.with_file_name("favorites-queue.jsonl")
+}
+
+/// Replace the queue file with `queue`, removing it when empty
+fn save_queue(path: &Path, queue: &[Pending]) -> io::Result<()> {
+    if queue.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        };
+    }
+    replace(path, queue)
+}
+
+/// How long to wait after the `attempts`th failure
+fn retry_delay(attempts: u32) -> Duration {
+    RETRY_DELAY
+        .saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX_RETRY_DELAY)
+}
+
+/// The enabled backends and their settings
+#[derive(Debug, Clone, Default)]
+struct Backends {
+    lastfm: Option<LastFmConfig>,
+    listenbrainz: Option<ListenBrainzConfig>,
+    webhook: Option<WebhookConfig>,
+}
+
+impl Backends {
+    /// Backends that are enabled and have the credentials they need
+    fn from_config(config: &FavoritesConfig) -> Self {
+        let usable = |enabled: bool, name: &str, missing: &[(&str, &str)]| {
+            let missing: Vec<&str> = missing
+                .iter()
+                .filter(|(_, value)| value.trim().is_empty())
+                .map(|(field, _)| *field)
+                .collect();
+            if enabled && !missing.is_empty() {
+                warn!(
+                    "Not syncing favorites to {}: missing {}",
+                    name,
+                    missing.join(", ")
+                );
+            }
+            enabled && missing.is_empty()
+        };
+        let lastfm = &config.lastfm;
+        let listenbrainz = &config.listenbrainz;
+        let webhook = &config.webhook;
+        Self {
+            lastfm: usable(
+                lastfm.enabled,
+                "Last.fm",
+                &[
+                    ("api_key", &lastfm.api_key),
+                    ("api_secret", &lastfm.api_secret),
+                    ("session_key", &lastfm.session_key),
+                ],
+            )
+            .then(|| lastfm.clone()),
+            listenbrainz: usable(
+                listenbrainz.enabled,
+                "ListenBrainz",
+                &[("token", &listenbrainz.token)],
+            )
+            .then(|| listenbrainz.clone()),
+            webhook: usable(webhook.enabled, "the webhook", &[("url", &webhook.url)])
+                .then(|| webhook.clone()),
+        }
+    }
+
+    fn enabled(&self) -> Vec<Backend> {
+        [
+            self.lastfm.as_ref().map(|_| Backend::LastFm),
+            self.listenbrainz.as_ref().map(|_| Backend::ListenBrainz),
+            self.webhook.as_ref().map(|_| Backend::Webhook),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Push new likes and retry queued ones until the app exits
+    async fn run(self, queue_path: PathBuf, mut sync_rx: UnboundedReceiver<Favorite>) {
+        let backends = self.enabled();
+        let mut queue: Vec<Pending> = read(&queue_path).unwrap_or_else(|error| {
+            warn!("Failed to read favorites queue: {}", error);
+            Vec::new()
+        });
+        // Backends disabled since the pushes were queued
+        queue.retain(|pending| backends.contains(&pending.backend));
+        if !queue.is_empty() {
+            info!("Retrying {} queued favorite pushes", queue.len());
+        }
+        loop {
+            let mut changed = false;
+            let mut index = 0;
+            while index < queue.len() {
+                if queue[index].next_attempt > Instant::now() {
+                    index += 1;
+                    continue;
+                }
+                changed = true;
+                let pending = &mut queue[index];
+                match self.push(pending.backend, &pending.favorite).await {
+                    Ok(()) => {
+                        debug!(backend = ?pending.backend, "Pushed favorite");
+                        queue.remove(index);
+                    }
+                    Err(PushError::Permanent(error)) => {
+                        warn!(backend = ?pending.backend, "Failed to push favorite: {}", error);
+                        queue.remove(index);
+                    }
+                    Err(PushError::Retry(error)) if pending.attempts + 1 >= MAX_ATTEMPTS => {
+                        warn!(backend = ?pending.backend, "Giving up pushing favorite: {}", error);
+                        queue.remove(index);
+                    }
+                    Err(PushError::Retry(error)) => {
+                        pending.attempts += 1;
+                        let delay = retry_delay(pending.attempts);
+                        pending.next_attempt = Instant::now() + delay;
+                        debug!(
+                            backend = ?pending.backend,
+                            "Failed to push favorite, retrying in {:?}: {}",
+                            delay,
+                            error
+                        );
+                        index += 1;
+                    }
+                }
+            }
+            if changed {
+                if let Err(error) = save_queue(&queue_path, &queue) {
+                    warn!("Failed to save favorites queue: {}", error);
+                }
+            }
+
+            let next_attempt = queue.iter().map(|pending| pending.next_attempt).min();
+            tokio::select! {
+                favorite = sync_rx.recv() => {
+                    let Some(favorite) = favorite else {
+                        return;
+                    };
+                    queue.extend(backends.iter().map(|backend| Pending {
+                        backend: *backend,
+                        favorite: favorite.clone(),
+                        attempts: 0,
+                        next_attempt: Instant::now(),
+                    }));
+                    // Queue before pushing, so the like survives quitting mid-push
+                    if let Err(error) = save_queue(&queue_path, &queue) {
+                        warn!("Failed to save favorites queue: {}", error);
+                    }
+                }
+                _ = tokio::time::sleep_until(next_attempt.unwrap_or_else(Instant::now)),
+                    if next_attempt.is_some() => {}
+            }
+        }
+    }
+
+    async fn push(&self, backend: Backend, favorite: &Favorite) -> Result<(), PushError> {
+        let disabled = || PushError::Permanent("backend is disabled".to_string());
+        match backend {
+            Backend::LastFm => {
+                love_on_lastfm(self.lastfm.as_ref().ok_or_else(disabled)?, favorite).await
+            }
+            Backend::ListenBrainz => {
+                love_on_listenbrainz(self.listenbrainz.as_ref().ok_or_else(disabled)?, favorite)
+                    .await
+            }
+            Backend::Webhook => {
+                post_webhook(self.webhook.as_ref().ok_or_else(disabled)?, favorite).await
+            }
+        }
+    }
+}
+
+/// Add the track to the user's Last.fm loved tracks
+async fn love_on_lastfm(config: &LastFmConfig, favorite: &Favorite) -> Result<(), PushError> {
+    if favorite.artist.is_empty() {
+        return Err(PushError::Permanent(format!(
+            "Last.fm needs an artist: {}",
+            favorite.title
+        )));
+    }
+    let mut params = vec![
+        ("api_key", config.api_key.as_str()),
+        ("artist", favorite.artist.as_str()),
+        ("method", "track.love"),
+        ("sk", config.session_key.as_str()),
+        ("track", favorite.title.as_str()),
+    ];
+    let signature = lastfm_signature(&params, &config.api_secret);
+    params.push(("api_sig", &signature));
+    params.push(("format", "json"));
+    let response = http::client().post(LASTFM_API).form(&params).send().await?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    match body.get("error").and_then(|code| code.as_u64()) {
+        // Service offline, temporarily unavailable and rate limited
+        Some(11 | 16 | 29) => Err(PushError::Retry(lastfm_message(&body))),
+        Some(_) => Err(PushError::Permanent(lastfm_message(&body))),
+        None => check_status(status),
+    }
+}
+
+/// Last.fm signs calls with the MD5 of the sorted parameters and the secret
+fn lastfm_signature(params: &[(&str, &str)], secret: &str) -> String {
+    let mut params = params.to_vec();
+    params.sort();
+    let mut payload = String::new();
+    for (key, value) in params {
+        payload.push_str(key);
+        payload.push_str(value);
+    }
+    payload.push_str(secret);
+    format!("{:x}", md5::compute(payload))
+}
+
+fn lastfm_message(body: &serde_json::Value) -> String {
+    body.get("message")
+        .and_then(|message| message.as_str())
+        .unwrap_or("unknown error")
+        .to_string()
+}
+
+/// Send "love" feedback for the track's MusicBrainz recording to ListenBrainz
+async fn love_on_listenbrainz(
+    config: &ListenBrainzConfig,
+    favorite: &Favorite,
+) -> Result<(), PushError> {
+    let api_url = config.api_url.trim_end_matches('/');
+    let authorization = format!("Token {}", config.token.trim());
+    let response = http::client()
+        .get(format!("{}/1/metadata/lookup/", api_url))
+        .header(reqwest::header::AUTHORIZATION, &authorization)
+        .query(&[
+            ("artist_name", favorite.artist.as_str()),
+            ("recording_name", favorite.title.as_str()),
+        ])
+        .send()
+        .await?;
+    check_status(response.status())?;
+    let lookup: serde_json::Value = response.json().await?;
+    let Some(recording_mbid) = lookup.get("recording_mbid").and_then(|mbid| mbid.as_str()) else {
+        return Err(PushError::Permanent(format!(
+            "no MusicBrainz recording for {}",
+            favorite.track()
+        )));
+    };
+    let response = http::client()
+        .post(format!("{}/1/feedback/recording-feedback", api_url))
+        .header(reqwest::header::AUTHORIZATION, &authorization)
+        .json(&serde_json::json!({ "recording_mbid": recording_mbid, "score": 1 }))
+        .send()
+        .await?;
+    check_status(response.status())
+}
+
+/// POST the like as JSON to the user's webhook
+async fn post_webhook(config: &WebhookConfig, favorite: &Favorite) -> Result<(), PushError> {
+    let response = http::client()
+        .post(&config.url)
+        .json(&serde_json::json!({
+            "event": "like",
+            "liked": favorite.liked,
+            "station": favorite.station,
+            "artist": favorite.artist,
+            "title": favorite.title,
+        }))
+        .send()
+        .await?;
+    check_status(response.status())
+}
+
+/// Server errors, timeouts and rate limits are retried, other failures are not
+fn check_status(status: reqwest::StatusCode) -> Result<(), PushError> {
+    use reqwest::StatusCode;
+
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error()
+        || matches!(
+            status,
+            StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS
+        )
+    {
+        Err(PushError::Retry(format!("HTTP {}", status)))
+    } else {
+        Err(PushError::Permanent(format!("HTTP {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_lastfm_calls() {
+        let params = [
+            ("track", "Awake"),
+            ("method", "track.love"),
+            ("artist", "Tycho"),
+            ("sk", "SESSION"),
+            ("api_key", "key"),
+        ];
+        assert_eq!(
+            lastfm_signature(&params, "secret"),
+            "4783ebecdc1ce75b9be2e6efd8598d08"
+        );
+    }
+
+    #[test]
+    fn backs_off_between_retries() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(3), Duration::from_secs(120));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), MAX_RETRY_DELAY);
+    }
+
+    #[tokio::test]
+    async fn likes_tracks_once_and_keeps_the_queue() {
+        let dir = std::env::temp_dir().join(format!("somars-favorites-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = FavoritesConfig {
+            path: Some(dir.join("favorites.jsonl")),
+            ..FavoritesConfig::default()
+        };
+        let open = || {
+            let store = crate::storage::FileStore::new(None, path(&config));
+            Some(SharedStore::new(Box::new(store)))
+        };
+
+        let mut favorites = Favorites::new(&config, open());
+        let liked = favorites
+            .like("Groove Salad", "Tycho - Awake")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (liked.artist.as_str(), liked.title.as_str()),
+            ("Tycho", "Awake")
+        );
+        assert_eq!(
+            favorites.like("Groove Salad", "tycho - awake").unwrap(),
+            None
+        );
+        // Likes are remembered across restarts
+        let mut favorites = Favorites::new(&config, open());
+        assert_eq!(favorites.like("Drone Zone", "Tycho - Awake").unwrap(), None);
+        assert!(favorites
+            .like("Drone Zone", "Station ID")
+            .unwrap()
+            .is_some());
+        assert_eq!(favorites.liked.len(), 2);
+        // Without a store nothing can be liked
+        assert!(Favorites::new(&config, None)
+            .like("Drone Zone", "Tycho - Awake")
+            .is_err());
+
+        let queue = queue_path(config.path.as_deref().unwrap());
+        let pending = Pending {
+            backend: Backend::Webhook,
+            favorite: liked,
+            attempts: 2,
+            next_attempt: Instant::now(),
+        };
+        save_queue(&queue, std::slice::from_ref(&pending)).unwrap();
+        let queued: Vec<Pending> = read(&queue).unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(
+            (queued[0].backend, queued[0].attempts, &queued[0].favorite),
+            (pending.backend, pending.attempts, &pending.favorite)
+        );
+        save_queue(&queue, &[]).unwrap();
+        assert!(!queue.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
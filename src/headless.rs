@@ -0,0 +1,297 @@
+//! Output for scripts, without the terminal interface
+//!
+//! `somars --list-stations` prints the catalog as a table, or with `--json`
+//! as an array, and exits.
+//!
+//! `somars --no-tui` plays a station and prints a line to standard output for
+//! every track, for piping into other programs:
+//!
+//! ```text
+//! 14:05:09  Groove Salad  Boards of Canada - Roygbiv
+//! ```
+//!
+//! With `--json` each line is a JSON object instead:
+//!
+//! ```json
+//! {"time":"2024-03-01T14:05:09+01:00","station":{"id":"groovesalad","title":"Groove Salad"},"track":"Boards of Canada - Roygbiv","artist":"Boards of Canada","title":"Roygbiv"}
+//! ```
+//!
+//! Errors go to standard error. A stream that ends is reconnected after a
+//! pause, and playback stops on Ctrl+C or once nothing reads the output.
+
+use crate::action::Action;
+use crate::audio::{
+    self,
+    output::Output,
+    player::{self, PlayRequest, PlayerCommand, PlayerEvent},
+    MetadataEvent,
+};
+use crate::config::Config;
+use crate::providers::ProviderRegistry;
+use crate::scrobble_log::split_title;
+use crate::station::Station;
+use crate::{voice, MessageType};
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Pause before reconnecting a stream that ended
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// A track as printed with `--json`
+#[derive(Debug, Serialize)]
+struct TrackLine<'a> {
+    time: String,
+    station: StationRef<'a>,
+    track: &'a str,
+    artist: &'a str,
+    title: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct StationRef<'a> {
+    id: &'a str,
+    title: &'a str,
+}
+
+/// The line printed for `track` starting on `station`
+fn track_line(station: &Station, track: &str, json: bool) -> String {
+    let now = chrono::Utc::now();
+    let clock = crate::clock::current();
+    if !json {
+        return format!("{}  {}  {}", clock.timestamp(now), station.title, track);
+    }
+    let (artist, title) = split_title(track).unwrap_or(("", track.trim()));
+    serde_json::to_string(&TrackLine {
+        time: clock.rfc3339(now),
+        station: StationRef {
+            id: &station.id,
+            title: &station.title,
+        },
+        track,
+        artist,
+        title,
+    })
+    .unwrap_or_default()
+}
+
+/// A station as listed with `--json`
+#[derive(Debug, Serialize)]
+struct StationEntry<'a> {
+    id: &'a str,
+    title: &'a str,
+    /// Genres separated by `|`
+    genre: &'a str,
+    /// 0 when unknown
+    listeners: u32,
+    source: &'a str,
+}
+
+/// `stations` as a table with a header, or as a JSON array
+pub fn station_list(stations: &[Station], json: bool) -> String {
+    if json {
+        let entries: Vec<StationEntry> = stations
+            .iter()
+            .map(|station| StationEntry {
+                id: &station.id,
+                title: &station.title,
+                genre: &station.genre,
+                listeners: station.listeners,
+                source: &station.source,
+            })
+            .collect();
+        return serde_json::to_string_pretty(&entries).unwrap_or_default() + "\n";
+    }
+    let rows: Vec<[String; 4]> =
+        std::iter::once(["ID", "TITLE", "GENRE", "LISTENERS"].map(str::to_string))
+            .chain(stations.iter().map(|station| {
+                [
+                    station.id.clone(),
+                    station.title.clone(),
+                    station.genre.replace('|', ", "),
+                    station.listeners.to_string(),
+                ]
+            }))
+            .collect();
+    let width = |column: usize| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    let widths = [width(0), width(1), width(2)];
+    rows.iter()
+        .map(|row| {
+            format!(
+                "{:w0$}  {:w1$}  {:w2$}  {}\n",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            )
+        })
+        .collect()
+}
+
+/// The station asked for with `--url` or `--station`
+pub async fn pick_station(
+    config: &Config,
+    station: Option<&str>,
+    url: Option<&str>,
+) -> Result<Station> {
+    if let Some(url) = url {
+        return Ok(Station::from_url(url));
+    }
+    let Some(wanted) = station else {
+        return Err(eyre!("Pick a station to play with --station or --url"));
+    };
+    let (stations, errors) = ProviderRegistry::from_config(config, false)
+        .fetch_all()
+        .await;
+    for (provider, error) in &errors {
+        eprintln!("Error loading stations from {}: {}", provider, error);
+    }
+    voice::find_station(&stations, wanted, &config.voice_aliases)
+        .map(|index| stations[index].clone())
+        .ok_or_else(|| eyre!("Unknown station: {}", wanted))
+}
+
+/// Play `station` and print its tracks until interrupted
+pub async fn run(config: &Config, output: Output, station: Station, json: bool) -> Result<()> {
+    let (metadata_tx, mut metadata_rx) = mpsc::channel(32);
+    let (log_tx, mut log_rx) = mpsc::channel(32);
+    let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+    let fader = audio::transition::Fader::new(Duration::from_millis(config.audio_fade_ms));
+    let player = player::spawn(output.mixer(), fader, metadata_tx, log_tx, action_tx);
+    let play = || {
+        player.send(PlayerCommand::Play(Box::new(PlayRequest {
+            station: station.clone(),
+            volume: config.volume_curve.gain(config.volume),
+            config: audio::stream::StreamConfig {
+                metadata_encoding: audio::encoding::for_station(
+                    &config.metadata_encoding,
+                    &station,
+                ),
+                title_rules: audio::title_rules::TitleRules::for_station(
+                    &config.title_cleanup,
+                    &station,
+                ),
+                ..audio::stream::StreamConfig::from_app_config(config)
+            },
+        })))
+    };
+    play();
+
+    let mut last_track: Option<String> = None;
+    let mut reconnect: Option<Instant> = None;
+    let mut stdout = io::stdout();
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            Some(event) = metadata_rx.recv() => {
+                let MetadataEvent::Track { title, .. } = event else {
+                    continue;
+                };
+                if last_track.as_ref() == Some(&title) {
+                    continue;
+                }
+                let line = track_line(&station, &title, json);
+                last_track = Some(title);
+                // Stop once whatever reads the output has gone away
+                if writeln!(stdout, "{}", line).and_then(|_| stdout.flush()).is_err() {
+                    break;
+                }
+            }
+            Some(message) = log_rx.recv() => {
+                if message.message_type == MessageType::Error {
+                    eprintln!("{}", message.message);
+                }
+            }
+            Some(action) = action_rx.recv() => {
+                if let Action::Player(PlayerEvent::Ended { error, .. }) = action {
+                    if let Some(error) = error {
+                        eprintln!("Playback error: {}", error);
+                    }
+                    reconnect = Some(Instant::now() + RECONNECT_DELAY);
+                }
+            }
+            _ = tokio::time::sleep_until(reconnect.unwrap_or_else(Instant::now)),
+                if reconnect.is_some() =>
+            {
+                reconnect = None;
+                play();
+            }
+        }
+    }
+
+    player.shutdown().await;
+    drop(output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_tracks_as_text_or_json() {
+        let station = Station {
+            id: "groovesalad".to_string(),
+            title: "Groove Salad".to_string(),
+            ..Station::from_url("https://ice.somafm.com/groovesalad")
+        };
+        let text = track_line(&station, "Boards of Canada - Roygbiv", false);
+        assert!(text.ends_with("  Groove Salad  Boards of Canada - Roygbiv"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&track_line(&station, "Boards of Canada - Roygbiv", true))
+                .unwrap();
+        assert_eq!(json["station"]["id"], "groovesalad");
+        assert_eq!(json["track"], "Boards of Canada - Roygbiv");
+        assert_eq!(json["artist"], "Boards of Canada");
+        assert_eq!(json["title"], "Roygbiv");
+    }
+
+    #[test]
+    fn lists_stations_in_columns() {
+        let stations = [
+            Station {
+                id: "groovesalad".to_string(),
+                title: "Groove Salad".to_string(),
+                genre: "ambient|electronica".to_string(),
+                listeners: 1234,
+                ..Station::from_url("https://ice.somafm.com/groovesalad")
+            },
+            Station {
+                id: "u80s".to_string(),
+                title: "Underground 80s".to_string(),
+                genre: "80s".to_string(),
+                ..Station::from_url("https://ice.somafm.com/u80s")
+            },
+        ];
+        let table = station_list(&stations, false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines[0],
+            "ID           TITLE            GENRE                 LISTENERS"
+        );
+        assert_eq!(
+            lines[1],
+            "groovesalad  Groove Salad     ambient, electronica  1234"
+        );
+        assert_eq!(
+            lines[2],
+            "u80s         Underground 80s  80s                   0"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&station_list(&stations, true)).unwrap();
+        assert_eq!(json[0]["genre"], "ambient|electronica");
+        assert_eq!(json[1]["listeners"], 0);
+    }
+}
@@ -0,0 +1,228 @@
+//! Listening history export
+//!
+//! Writes the track log out for listening diaries and other tools, one row
+//! per play with when it started on the configured clock, how long it was
+//! heard, the station, artist and title, and when the listening session it
+//! belongs to started, grouped as [`track_log::sessions`] groups them. Two
+//! formats are written:
+//!
+//! - CSV with a header row, quoted where a field needs it
+//! - JSON, an array of objects with the same fields
+
+use crate::error::AppError;
+use crate::track_log::{self, TrackRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    /// The format a file name's extension asks for
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()?.to_str()?.parse().ok()
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Unknown export format: {} (expected csv or json)",
+                value
+            )),
+        }
+    }
+}
+
+/// One play as exported
+#[derive(Debug, Serialize)]
+struct Row<'a> {
+    started: String,
+    seconds: u64,
+    station: &'a str,
+    artist: &'a str,
+    title: &'a str,
+    /// When the play's session started
+    session: String,
+}
+
+impl<'a> Row<'a> {
+    fn new(play: &'a TrackRecord, session_started: u64) -> Self {
+        Self {
+            started: rfc3339(play.started),
+            seconds: play.seconds,
+            station: &play.station,
+            artist: &play.artist,
+            title: &play.title,
+            session: rfc3339(session_started),
+        }
+    }
+}
+
+/// Unix time `time` on the configured clock
+fn rfc3339(time: u64) -> String {
+    i64::try_from(time)
+        .ok()
+        .and_then(|time| DateTime::<Utc>::from_timestamp(time, 0))
+        .map(|time| crate::clock::current().rfc3339(time))
+        .unwrap_or_default()
+}
+
+/// `plays`, oldest first, in `format`
+pub fn render(plays: &[TrackRecord], format: ExportFormat) -> String {
+    // Sessions hold runs of consecutive plays, oldest first too
+    let session_starts = track_log::sessions(plays)
+        .into_iter()
+        .flat_map(|session| std::iter::repeat_n(session.started, session.tracks));
+    let rows = plays
+        .iter()
+        .zip(session_starts)
+        .map(|(play, session)| Row::new(play, session));
+    match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("started,seconds,station,artist,title,session\n");
+            for row in rows {
+                let fields = [
+                    row.started,
+                    row.seconds.to_string(),
+                    csv_field(row.station),
+                    csv_field(row.artist),
+                    csv_field(row.title),
+                    row.session,
+                ];
+                csv.push_str(&fields.join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+        ExportFormat::Json => {
+            let rows: Vec<Row> = rows.collect();
+            serde_json::to_string_pretty(&rows).unwrap_or_default() + "\n"
+        }
+    }
+}
+
+/// `value` as a CSV field, quoted if it holds a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write `plays` to `path` in `format`
+pub fn export(path: &Path, plays: &[TrackRecord], format: ExportFormat) -> Result<(), AppError> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, render(plays, format))?;
+    Ok(())
+}
+
+/// `somars-history-2024-03-01.csv` in `dir`, for an export made on `date`
+pub fn file_path(dir: &Path, date: &str, format: ExportFormat) -> PathBuf {
+    dir.join(format!("somars-history-{}.{}", date, format.extension()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(started: u64, artist: &str, title: &str) -> TrackRecord {
+        TrackRecord {
+            started,
+            seconds: 240,
+            station: "Groove Salad".to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+            session: started,
+        }
+    }
+
+    #[test]
+    fn writes_csv_with_quoted_fields() {
+        let plays = [
+            play(0, "Boards of Canada", "Roygbiv"),
+            play(240, "Crosby, Stills & Nash", "Say \"Hi\""),
+        ];
+        let csv = render(&plays, ExportFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "started,seconds,station,artist,title,session");
+        assert!(lines[1].contains(",240,Groove Salad,Boards of Canada,Roygbiv,"));
+        assert!(lines[2].contains(",Groove Salad,\"Crosby, Stills & Nash\",\"Say \"\"Hi\"\"\","));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn writes_json_rows() {
+        let json = render(&[play(0, "", "Station ID")], ExportFormat::Json);
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows[0]["title"], "Station ID");
+        assert_eq!(rows[0]["seconds"], 240);
+        assert!(rows[0]["started"].as_str().unwrap().starts_with("19"));
+        assert_eq!(rows[0]["session"], rows[0]["started"]);
+    }
+
+    #[test]
+    fn marks_each_play_with_its_session() {
+        let session = |started: u64, session: u64| TrackRecord {
+            session,
+            ..play(started, "Artist", "Title")
+        };
+        let plays = [
+            session(1000, 1000),
+            session(1240, 1000),
+            session(5000, 5000),
+        ];
+        let json = render(&plays, ExportFormat::Json);
+        let rows: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(rows[1]["session"], rows[0]["started"]);
+        assert_eq!(rows[2]["session"], rows[2]["started"]);
+        assert_ne!(rows[1]["session"], rows[2]["session"]);
+    }
+
+    #[test]
+    fn picks_the_format_from_the_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("diary.JSON")),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("diary.csv")),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(ExportFormat::from_path(Path::new("diary")), None);
+        assert!("xml".parse::<ExportFormat>().is_err());
+    }
+}
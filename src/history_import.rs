@@ -0,0 +1,234 @@
+//! Listening history import
+//!
+//! Seeds the track log from other players, so statistics don't start from
+//! zero. Two formats are understood:
+//!
+//! - Audioscrobbler `.scrobbler.log` files, as written by Rockbox, many
+//!   portable players and somars' own export. Skipped plays are ignored.
+//! - MPD's log file, whose `player: played "..."` lines record every song MPD
+//!   started. Listening time is estimated from the gap to the next song.
+
+use crate::error::AppError;
+use crate::scrobble_log::split_title;
+use crate::storage::Store;
+use crate::track_log::TrackRecord;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone};
+use std::path::Path;
+
+/// Longest gap between MPD songs still counted as listening time
+const MAX_MPD_GAP_SECONDS: u64 = 30 * 60;
+
+/// Import plays from `source` into the track log in `store`
+///
+/// Returns the number of plays added; plays already in the log are skipped.
+pub fn import(source: &Path, store: &mut dyn Store) -> Result<usize, AppError> {
+    let contents = String::from_utf8_lossy(&std::fs::read(source)?).into_owned();
+    let records = parse(&contents, Local::now());
+    if records.is_empty() {
+        return Err(AppError::Parse(format!(
+            "no plays found in {}; expected a .scrobbler.log or MPD log file",
+            source.display()
+        )));
+    }
+    Ok(store.add_plays(records)?)
+}
+
+fn parse(contents: &str, now: DateTime<Local>) -> Vec<TrackRecord> {
+    if contents
+        .trim_start_matches('\u{feff}')
+        .starts_with("#AUDIOSCROBBLER")
+    {
+        parse_scrobbler_log(contents)
+    } else {
+        parse_mpd_log(contents, now)
+    }
+}
+
+/// Tab-separated artist, album, title, track, length, rating, timestamp, MBID
+fn parse_scrobbler_log(contents: &str) -> Vec<TrackRecord> {
+    let mut utc = false;
+    let mut client = "scrobbler.log".to_string();
+    let mut records = Vec::new();
+    for line in contents.lines() {
+        if let Some(header) = line.strip_prefix('#') {
+            if let Some(tz) = header.strip_prefix("TZ/") {
+                utc = tz.trim() == "UTC";
+            } else if let Some(name) = header.strip_prefix("CLIENT/") {
+                client = name
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("scrobbler.log")
+                    .to_string();
+            }
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [artist, _album, title, _track, length, rating, timestamp, ..] = fields[..] else {
+            continue;
+        };
+        let Ok(timestamp) = timestamp.trim().parse::<i64>() else {
+            continue;
+        };
+        if rating.trim() != "L" || title.trim().is_empty() {
+            continue;
+        }
+        // Without a time zone, timestamps are local wall-clock time
+        let started = if utc {
+            Some(timestamp)
+        } else {
+            DateTime::from_timestamp(timestamp, 0).and_then(|time| {
+                Local
+                    .from_local_datetime(&time.naive_utc())
+                    .earliest()
+                    .map(|local| local.timestamp())
+            })
+        };
+        let Some(started) = started.and_then(|started| u64::try_from(started).ok()) else {
+            continue;
+        };
+        records.push(TrackRecord {
+            started,
+            seconds: length.trim().parse().unwrap_or(0),
+            station: client.clone(),
+            artist: artist.trim().to_string(),
+            title: title.trim().to_string(),
+            session: 0,
+        });
+    }
+    records
+}
+
+/// `player: played "<path or URL>"` lines, with either the classic
+/// `Jan 21 13:40 : ` prefix or an ISO 8601 timestamp
+fn parse_mpd_log(contents: &str, now: DateTime<Local>) -> Vec<TrackRecord> {
+    let mut records: Vec<TrackRecord> = Vec::new();
+    for line in contents.lines() {
+        let Some((prefix, played)) = line.split_once("player: played \"") else {
+            continue;
+        };
+        let Some(started) = mpd_timestamp(prefix.trim().trim_end_matches(':').trim(), now) else {
+            continue;
+        };
+        let (artist, title) = mpd_song(played.trim_end().trim_end_matches('"'));
+        if let Some(previous) = records.last_mut() {
+            let gap = started.saturating_sub(previous.started);
+            if gap <= MAX_MPD_GAP_SECONDS {
+                previous.seconds = gap;
+            }
+        }
+        records.push(TrackRecord {
+            started,
+            seconds: 0,
+            station: "MPD".to_string(),
+            artist,
+            title,
+            session: 0,
+        });
+    }
+    records
+}
+
+fn mpd_timestamp(prefix: &str, now: DateTime<Local>) -> Option<u64> {
+    let time = match NaiveDateTime::parse_from_str(prefix, "%Y-%m-%dT%H:%M:%S") {
+        Ok(time) => Local.from_local_datetime(&time).earliest()?,
+        Err(_) => {
+            // The classic format has no year: assume the most recent one
+            let time = NaiveDateTime::parse_from_str(
+                &format!("{} {}", now.year(), prefix),
+                "%Y %b %d %H:%M",
+            )
+            .ok()?;
+            let time = Local.from_local_datetime(&time).earliest()?;
+            if time > now {
+                time.with_year(now.year() - 1)?
+            } else {
+                time
+            }
+        }
+    };
+    u64::try_from(time.timestamp()).ok()
+}
+
+/// Artist and title from an MPD song path such as `Artist/Album/01 Title.flac`
+fn mpd_song(song: &str) -> (String, String) {
+    if song.contains("://") {
+        return (String::new(), song.to_string());
+    }
+    let path = Path::new(song);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Drop a leading track number
+    let stem = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches([' ', '.', '-', '_'])
+        .to_string();
+    if let Some((artist, title)) = split_title(&stem) {
+        return (artist.to_string(), title.to_string());
+    }
+    let components: Vec<_> = path.components().collect();
+    let artist = if components.len() >= 3 {
+        components[0].as_os_str().to_string_lossy().into_owned()
+    } else {
+        String::new()
+    };
+    (artist, stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scrobbler_log() {
+        let log = "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/Rockbox sansa $Revision$\n\
+                   Boards of Canada\tGeogaddi\tMusic Is Math\t3\t321\tL\t1700000000\t\n\
+                   Boards of Canada\tGeogaddi\tDandelion\t4\t75\tS\t1700000400\t\n\
+                   broken line\n";
+        let records = parse(log, Local::now());
+        assert_eq!(
+            records,
+            vec![TrackRecord {
+                started: 1_700_000_000,
+                seconds: 321,
+                station: "Rockbox".to_string(),
+                artist: "Boards of Canada".to_string(),
+                title: "Music Is Math".to_string(),
+                session: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_mpd_log() {
+        let now = Local.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let log = "Feb 10 20:00 : player: played \"Aphex Twin/SAW 85-92/03 Pulsewidth.flac\"\n\
+                   Feb 10 20:03 : update: added x\n\
+                   Feb 10 20:04 : player: played \"Various/Artist - Song.mp3\"\n\
+                   Dec 31 23:59 : player: played \"http://ice1.somafm.com/groovesalad-128-mp3\"\n\
+                   2024-02-11T08:00:00 player: played \"loose.ogg\"\n";
+        let records = parse(log, now);
+        assert_eq!(records.len(), 4);
+        assert_eq!(
+            (records[0].artist.as_str(), records[0].title.as_str()),
+            ("Aphex Twin", "Pulsewidth")
+        );
+        assert_eq!(records[0].seconds, 240);
+        assert_eq!(
+            (records[1].artist.as_str(), records[1].title.as_str()),
+            ("Artist", "Song")
+        );
+        // A December entry before March belongs to the previous year
+        let expected = Local.with_ymd_and_hms(2023, 12, 31, 23, 59, 0).unwrap();
+        assert_eq!(records[2].started, expected.timestamp() as u64);
+        assert_eq!(
+            records[2].title,
+            "http://ice1.somafm.com/groovesalad-128-mp3"
+        );
+        assert_eq!(
+            (records[3].artist.as_str(), records[3].title.as_str()),
+            ("", "loose")
+        );
+    }
+}
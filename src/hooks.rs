@@ -0,0 +1,337 @@
+//! External commands run on playback events
+//!
+//! Commands set in the `[hooks]` table run when the track changes, when
+//! playback starts or stops and ahead of a [scheduled show](crate::schedule),
+//! so any script can follow what plays without built-in support for it:
+//!
+//! ```toml
+//! [hooks]
+//! on_track_change = "notify-send {station} '{artist} - {title}'"
+//! on_play = "lights dim"
+//! on_stop = "lights up"
+//! on_show_reminder = "notify-send 'Soon on {station}' '{title}'"
+//! ```
+//!
+//! A command is split into words like a shell would, with single and double
+//! quotes keeping spaces in a word, and `{artist}`, `{title}`, `{station}`
+//! and `{station_id}` replaced in each word. It runs directly rather than
+//! through a shell, so a title can't run anything or split into more
+//! arguments. Commands run in the background with no input and their output
+//! thrown away; failures go to the log file.
+
+use crate::scrobble_log::split_title;
+use crate::station::Station;
+use crate::utils::fill_placeholders;
+use crate::PlaybackState;
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Commands to run, read from the `[hooks]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run when a new track starts, with its artist and title
+    #[serde(default)]
+    pub on_track_change: Option<String>,
+    /// Run when a station starts playing or playback resumes
+    #[serde(default)]
+    pub on_play: Option<String>,
+    /// Run when playback stops or pauses
+    #[serde(default)]
+    pub on_stop: Option<String>,
+    /// Run ahead of a scheduled show, with the show's name as its title
+    #[serde(default)]
+    pub on_show_reminder: Option<String>,
+}
+
+/// What a hook's placeholders are filled with
+#[derive(Debug, Default, PartialEq)]
+pub struct HookContext<'a> {
+    pub station: &'a str,
+    pub station_id: &'a str,
+    pub artist: &'a str,
+    pub title: &'a str,
+}
+
+impl<'a> HookContext<'a> {
+    /// `station` playing `track`, an ICY `Artist - Title` string if any
+    pub fn new(station: Option<&'a Station>, track: Option<&'a str>) -> Self {
+        let (artist, title) = match track {
+            Some(track) => split_title(track).unwrap_or(("", track.trim())),
+            None => ("", ""),
+        };
+        Self {
+            station: station.map(|station| station.title.as_str()).unwrap_or(""),
+            station_id: station.map(|station| station.id.as_str()).unwrap_or(""),
+            artist,
+            title,
+        }
+    }
+
+    fn fill(&self, word: &str) -> String {
+        fill_placeholders(
+            word,
+            &[
+                ("artist", self.artist),
+                ("title", self.title),
+                ("station_id", self.station_id),
+                ("station", self.station),
+            ],
+        )
+    }
+}
+
+/// Runs the configured commands as playback changes
+#[derive(Debug)]
+pub struct Hooks {
+    config: HooksConfig,
+    /// Station playing, while one is
+    playing: Option<Station>,
+    /// Track last reported
+    track: Option<String>,
+}
+
+impl Hooks {
+    pub fn new(config: &HooksConfig) -> Self {
+        Self {
+            config: config.clone(),
+            playing: None,
+            track: None,
+        }
+    }
+
+    /// Playback became `state` on `station`
+    ///
+    /// `on_play` runs when a station starts playing, including a switch
+    /// from another one, and `on_stop` when playing ends.
+    pub fn playback_changed(&mut self, state: &PlaybackState, station: Option<&Station>) {
+        let now_playing = station.filter(|_| *state == PlaybackState::Playing);
+        if now_playing.map(|station| &station.id)
+            == self.playing.as_ref().map(|station| &station.id)
+        {
+            return;
+        }
+        match now_playing {
+            Some(station) => {
+                self.track = None;
+                self.playing = Some(station.clone());
+                run(
+                    "on_play",
+                    self.config.on_play.as_deref(),
+                    &HookContext::new(Some(station), None),
+                );
+            }
+            None => {
+                if let Some(station) = self.playing.take() {
+                    run(
+                        "on_stop",
+                        self.config.on_stop.as_deref(),
+                        &HookContext::new(Some(&station), self.track.as_deref()),
+                    );
+                }
+                self.track = None;
+            }
+        }
+    }
+
+    /// Playback ends with the app, running `on_stop` if something played
+    pub fn quit(&mut self) {
+        self.playback_changed(&PlaybackState::Stopped, None);
+    }
+
+    /// The show `title` on `station` starts soon
+    pub fn show_reminder(&self, station: Option<&Station>, title: &str) {
+        let mut context = HookContext::new(station, None);
+        context.title = title;
+        run(
+            "on_show_reminder",
+            self.config.on_show_reminder.as_deref(),
+            &context,
+        );
+    }
+
+    /// `station` started playing the track `title`
+    pub fn track_changed(&mut self, station: &Station, title: &str) {
+        if self.track.as_deref() == Some(title) {
+            return;
+        }
+        self.track = Some(title.to_string());
+        run(
+            "on_track_change",
+            self.config.on_track_change.as_deref(),
+            &HookContext::new(Some(station), Some(title)),
+        );
+    }
+}
+
+/// Start `command` in the background, filled in from `context`
+fn run(hook: &'static str, command: Option<&str>, context: &HookContext) {
+    let Some(command) = command else {
+        return;
+    };
+    let args = match expand(command, context) {
+        Ok(args) if !args.is_empty() => args,
+        Ok(_) => return,
+        Err(error) => {
+            warn!("Hook {} not run: {}", hook, error);
+            return;
+        }
+    };
+    debug!("Running hook {}: {:?}", hook, args);
+    // Started here rather than in the task, so one run on quitting isn't lost
+    let child = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(error) => {
+            warn!("Hook {} failed to start {}: {}", hook, args[0], error);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) if !status.success() => warn!("Hook {} exited with {}", hook, status),
+            Ok(_) => {}
+            Err(error) => warn!("Hook {} failed: {}", hook, error),
+        }
+    });
+}
+
+/// `command` split into arguments with the placeholders in each filled in
+pub fn expand(command: &str, context: &HookContext) -> Result<Vec<String>, String> {
+    Ok(split_words(command)?
+        .iter()
+        .map(|word| context.fill(word))
+        .collect())
+}
+
+/// Split `command` at whitespace outside quotes
+///
+/// Single quotes keep everything up to the next one, double quotes do too
+/// but let a backslash escape `"` and `\`, and outside quotes a backslash
+/// keeps the next character.
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(word.take());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unclosed single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unclosed double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unclosed double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders_without_splitting_them() {
+        let station = Station::for_test("groovesalad", "Groove Salad");
+        let context = HookContext::new(Some(&station), Some("Boards of Canada - Roygbiv; rm -rf"));
+        assert_eq!(
+            expand(
+                "notify-send {station} '{artist} - {title}' id={station_id}",
+                &context
+            ),
+            Ok(vec![
+                "notify-send".to_string(),
+                "Groove Salad".to_string(),
+                "Boards of Canada - Roygbiv; rm -rf".to_string(),
+                "id=groovesalad".to_string(),
+            ])
+        );
+
+        // A title that isn't `Artist - Title` is all title
+        let context = HookContext::new(Some(&station), Some("Station ID"));
+        assert_eq!(context.artist, "");
+        assert_eq!(context.title, "Station ID");
+
+        // Values aren't filled in again, nor are unknown braces
+        let context = HookContext::new(Some(&station), Some("{station} - {title}"));
+        assert_eq!(
+            expand("{artist}:{title}:{album}", &context),
+            Ok(vec!["{station}:{title}:{album}".to_string()])
+        );
+    }
+
+    #[test]
+    fn splits_words_like_a_shell() {
+        assert_eq!(
+            split_words(r#"  say "a \"quoted\" word" it\'s 'C:\dir' "" "#),
+            Ok(vec![
+                "say".to_string(),
+                "a \"quoted\" word".to_string(),
+                "it's".to_string(),
+                "C:\\dir".to_string(),
+                String::new(),
+            ])
+        );
+        assert!(split_words("say 'unclosed").is_err());
+        assert_eq!(split_words("   "), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn runs_play_and_stop_once_per_change() {
+        let mut hooks = Hooks::new(&HooksConfig::default());
+        let groove = Station::for_test("groovesalad", "Groove Salad");
+        let drone = Station::for_test("dronezone", "Drone Zone");
+
+        hooks.playback_changed(&PlaybackState::Playing, Some(&groove));
+        assert_eq!(hooks.playing.as_ref(), Some(&groove));
+        hooks.track_changed(&groove, "Artist - Title");
+        assert_eq!(hooks.track.as_deref(), Some("Artist - Title"));
+
+        // Switching stations starts a new play and forgets the track
+        hooks.playback_changed(&PlaybackState::Playing, Some(&drone));
+        assert_eq!(hooks.playing.as_ref(), Some(&drone));
+        assert_eq!(hooks.track, None);
+
+        hooks.playback_changed(&PlaybackState::Paused, Some(&drone));
+        assert_eq!(hooks.playing, None);
+        hooks.playback_changed(&PlaybackState::Stopped, Some(&drone));
+        assert_eq!(hooks.playing, None);
+    }
+}
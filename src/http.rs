@@ -0,0 +1,195 @@
+//! Shared HTTP client
+//!
+//! All API traffic (channels.json, song feeds, playlists, artwork) goes through
+//! one pooled client so connections — including negotiated HTTP/2 sessions —
+//! are reused across requests instead of being set up for every call. Audio
+//! streams use their own ICY-aware client in [`crate::audio::stream`].
+//!
+//! Both clients take their timeouts, proxy, User-Agent and extra headers from
+//! the `[network]` table. Without a proxy there, the `HTTP_PROXY`,
+//! `HTTPS_PROXY` and `NO_PROXY` environment variables are honored.
+
+use once_cell::sync::{Lazy, OnceCell};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::warn;
+
+/// Network settings, read from the `[network]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Seconds to wait for a connection; 0 waits as long as the system does
+    #[serde(default = "default_connect_timeout_seconds")]
+    pub connect_timeout_seconds: u64,
+    /// Seconds to wait for more data on a connection; 0 waits forever
+    #[serde(default = "default_read_timeout_seconds")]
+    pub read_timeout_seconds: u64,
+    /// Proxy for every request, such as `http://proxy.example.com:3128`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// User-Agent sent instead of `somars/<version>`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Headers added to every request, such as a token a private Icecast
+    /// server asks for
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn default_connect_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_read_timeout_seconds() -> u64 {
+    30
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_seconds: default_connect_timeout_seconds(),
+            read_timeout_seconds: default_read_timeout_seconds(),
+            proxy: None,
+            user_agent: None,
+            headers: BTreeMap::new(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Set the timeouts, proxy and headers on a client being built
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder = builder.user_agent(self.user_agent());
+        match self.headers() {
+            Ok(headers) => builder = builder.default_headers(headers),
+            Err(error) => warn!("{}", error),
+        }
+        if self.connect_timeout_seconds > 0 {
+            builder = builder.connect_timeout(Duration::from_secs(self.connect_timeout_seconds));
+        }
+        if self.read_timeout_seconds > 0 {
+            builder = builder.read_timeout(Duration::from_secs(self.read_timeout_seconds));
+        }
+        match self.proxy() {
+            Ok(Some(proxy)) => builder.proxy(proxy),
+            Ok(None) => builder,
+            Err(error) => {
+                warn!("{}", error);
+                builder
+            }
+        }
+    }
+
+    fn user_agent(&self) -> &str {
+        self.user_agent
+            .as_deref()
+            .map(str::trim)
+            .filter(|agent| !agent.is_empty())
+            .unwrap_or(concat!("somars/", env!("CARGO_PKG_VERSION")))
+    }
+
+    fn headers(&self) -> Result<HeaderMap, String> {
+        self.headers
+            .iter()
+            .map(|(name, value)| {
+                let header = HeaderName::from_bytes(name.trim().as_bytes())
+                    .map_err(|error| format!("Invalid header name {:?}: {}", name, error))?;
+                let mut value = HeaderValue::from_str(value.trim())
+                    .map_err(|error| format!("Invalid value for header {}: {}", name, error))?;
+                value.set_sensitive(true);
+                Ok((header, value))
+            })
+            .collect()
+    }
+
+    fn proxy(&self) -> Result<Option<reqwest::Proxy>, String> {
+        let Some(url) = self
+            .proxy
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+        else {
+            return Ok(None);
+        };
+        reqwest::Proxy::all(url)
+            .map(Some)
+            .map_err(|error| format!("Invalid proxy {:?}: {}", url, error))
+    }
+}
+
+static NETWORK: OnceCell<NetworkConfig> = OnceCell::new();
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    let builder = reqwest::Client::builder()
+        .gzip(true)
+        .pool_idle_timeout(Duration::from_secs(90));
+    network().apply(builder).build().unwrap_or_else(|error| {
+        warn!(%error, "Failed to build shared HTTP client; using defaults");
+        reqwest::Client::new()
+    })
+});
+
+/// Set up the clients from `config`, failing on a proxy or header that
+/// can't be used
+///
+/// Only the first call has an effect; later calls are ignored.
+pub fn init(config: &NetworkConfig) -> Result<(), String> {
+    config.proxy()?;
+    config.headers()?;
+    let _ = NETWORK.set(config.clone());
+    Ok(())
+}
+
+/// The network settings, defaults if [`init`] has not been called
+pub fn network() -> &'static NetworkConfig {
+    NETWORK.get_or_init(NetworkConfig::default)
+}
+
+/// Get the shared HTTP client
+///
+/// `reqwest::Client` is reference counted, so callers can clone it freely.
+pub fn client() -> &'static reqwest::Client {
+    &CLIENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_is_shared() {
+        assert!(std::ptr::eq(client(), client()));
+    }
+
+    #[test]
+    fn checks_the_proxy() {
+        let config: NetworkConfig = toml::from_str("proxy = \"http://proxy:3128\"").unwrap();
+        assert_eq!(config.connect_timeout_seconds, 10);
+        assert!(matches!(config.proxy(), Ok(Some(_))));
+        assert!(matches!(NetworkConfig::default().proxy(), Ok(None)));
+        let broken = NetworkConfig {
+            proxy: Some("not a url".to_string()),
+            ..NetworkConfig::default()
+        };
+        assert!(broken.proxy().is_err());
+    }
+
+    #[test]
+    fn checks_the_headers() {
+        let config: NetworkConfig =
+            toml::from_str("user_agent = \"Player/1.0\"\n[headers]\nX-Token = \"secret\"\n")
+                .unwrap();
+        assert_eq!(config.user_agent(), "Player/1.0");
+        let headers = config.headers().unwrap();
+        assert_eq!(headers["x-token"], "secret");
+        assert!(headers["x-token"].is_sensitive());
+        assert!(NetworkConfig::default().user_agent().starts_with("somars/"));
+
+        let broken = NetworkConfig {
+            headers: [("Bad Name".to_string(), "x".to_string())].into(),
+            ..NetworkConfig::default()
+        };
+        assert!(broken.headers().is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use fluent::{FluentBundle, FluentResource};
+use fluent_bundle::resolver::{errors::ReferenceKind, ResolverError};
 use once_cell::sync::Lazy;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -15,6 +16,12 @@ pub static LOCALES: Lazy<Vec<LanguageIdentifier>> =
 #[allow(dead_code)]
 pub static DEFAULT_LOCALE: Lazy<LanguageIdentifier> = Lazy::new(|| langid!("en"));
 
+// Messages of each supported locale; English is the reference
+const SOURCES: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en/main.ftl")),
+    ("ru", include_str!("../locales/ru/main.ftl")),
+];
+
 // Thread-local storage for bundles
 thread_local! {
     static BUNDLES: RefCell<HashMap<String, FluentBundle<FluentResource>>> = RefCell::new(HashMap::new());
@@ -31,7 +38,7 @@ pub fn init(preferred_locale: Option<String>) {
     // Set the current locale based on preference or environment
     let locale_to_use = if let Some(locale) = preferred_locale {
         // Use the preferred locale if provided and supported
-        if locale == "ru" || locale == "en" {
+        if is_supported(&locale) {
             locale
         } else {
             // Fallback to environment detection
@@ -54,29 +61,21 @@ pub fn init(preferred_locale: Option<String>) {
 
         // Only initialize if not already done
         if bundles.is_empty() {
-            // English (default)
-            let en_ftl = include_str!("../locales/en/main.ftl");
-            let en_resource = FluentResource::try_new(en_ftl.to_string())
-                .expect("Failed to parse English FTL resource");
-
-            let mut en_bundle = FluentBundle::new(vec![langid!("en")]);
-            en_bundle
-                .add_resource(en_resource)
-                .expect("Failed to add English resource to bundle");
-
-            // Russian
-            let ru_ftl = include_str!("../locales/ru/main.ftl");
-            let ru_resource = FluentResource::try_new(ru_ftl.to_string())
-                .expect("Failed to parse Russian FTL resource");
-
-            let mut ru_bundle = FluentBundle::new(vec![langid!("ru")]);
-            ru_bundle
-                .add_resource(ru_resource)
-                .expect("Failed to add Russian resource to bundle");
-
-            // Store bundles
-            bundles.insert("en".to_string(), en_bundle);
-            bundles.insert("ru".to_string(), ru_bundle);
+            for (locale, ftl) in SOURCES {
+                let resource = FluentResource::try_new(ftl.to_string())
+                    .unwrap_or_else(|_| panic!("Failed to parse {} FTL resource", locale));
+                let langid: LanguageIdentifier = locale
+                    .parse()
+                    .expect("Supported locales are valid identifiers");
+
+                let mut bundle = FluentBundle::new(vec![langid]);
+                // Callers fill in `{$variables}` themselves, so no isolation marks
+                bundle.set_use_isolating(false);
+                bundle
+                    .add_resource(resource)
+                    .unwrap_or_else(|_| panic!("Failed to add {} resource to bundle", locale));
+                bundles.insert(locale.to_string(), bundle);
+            }
         }
     });
 }
@@ -95,7 +94,7 @@ fn detect_locale_from_environment() -> String {
                 .to_lowercase();
 
             // Check if it's a supported locale
-            if lang_code == "ru" || lang_code == "en" {
+            if is_supported(&lang_code) {
                 return lang_code;
             }
         }
@@ -119,7 +118,8 @@ pub fn get_message(key: &str) -> String {
                 if let Some(pattern) = message.value() {
                     let mut errors = vec![];
                     let value = bundle.format_pattern(pattern, None, &mut errors);
-                    if errors.is_empty() {
+                    // Variables are left as `{$name}` for the caller to replace
+                    if errors.iter().all(is_variable_reference) {
                         return Some(value.to_string());
                     }
                 }
@@ -136,3 +136,126 @@ pub fn get_message(key: &str) -> String {
 pub fn t(key: &str) -> String {
     get_message(key)
 }
+
+fn is_variable_reference(error: &fluent_bundle::FluentError) -> bool {
+    matches!(
+        error,
+        fluent_bundle::FluentError::ResolverError(ResolverError::Reference(
+            ReferenceKind::Variable { .. }
+        ))
+    )
+}
+
+fn is_supported(locale: &str) -> bool {
+    SOURCES.iter().any(|(supported, _)| *supported == locale)
+}
+
+// The locale messages are shown in
+pub fn current_locale() -> String {
+    CURRENT_LOCALE
+        .read()
+        .expect("Locale lock poisoned: another thread panicked while holding the read lock. This is a bug.")
+        .clone()
+}
+
+// Switch to `locale` at runtime; false if it isn't supported
+pub fn set_locale(locale: &str) -> bool {
+    if !is_supported(locale) {
+        return false;
+    }
+    let mut current_locale = CURRENT_LOCALE.write()
+        .expect("Locale lock poisoned: another thread panicked while holding the write lock. This is a bug.");
+    *current_locale = locale.to_string();
+    true
+}
+
+/// How much of a locale is translated, compared with English
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completeness {
+    pub locale: String,
+    /// The language's name in that language
+    pub name: String,
+    pub translated: usize,
+    pub total: usize,
+    /// English message IDs the locale lacks
+    pub missing: Vec<String>,
+}
+
+impl Completeness {
+    pub fn percent(&self) -> usize {
+        (self.translated * 100)
+            .checked_div(self.total)
+            .unwrap_or(100)
+    }
+}
+
+// Translation completeness of every supported locale
+pub fn completeness() -> Vec<Completeness> {
+    let reference = message_ids(SOURCES[0].1);
+    SOURCES
+        .iter()
+        .map(|(locale, ftl)| {
+            let ids = message_ids(ftl);
+            let missing: Vec<String> = reference
+                .iter()
+                .filter(|id| !ids.contains(id))
+                .map(|id| id.to_string())
+                .collect();
+            Completeness {
+                locale: locale.to_string(),
+                name: message_value(ftl, "language-name")
+                    .unwrap_or(locale)
+                    .to_string(),
+                translated: reference.len() - missing.len(),
+                total: reference.len(),
+                missing,
+            }
+        })
+        .collect()
+}
+
+// IDs of the messages defined in an FTL source
+fn message_ids(ftl: &str) -> Vec<&str> {
+    ftl.lines()
+        .filter(|line| line.starts_with(|c: char| c.is_ascii_alphabetic()))
+        .filter_map(|line| line.split_once('='))
+        .map(|(id, _)| id.trim())
+        .filter(|id| {
+            id.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .collect()
+}
+
+// The single-line value of message `id` in an FTL source
+fn message_value<'a>(ftl: &'a str, id: &str) -> Option<&'a str> {
+    ftl.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == id)
+        .map(|(_, value)| value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_locales_with_english() {
+        let ftl = "# Comment\nlanguage-name = Test\nplay = Play\n  .attr = x\nmulti =\n    Line\n";
+        assert_eq!(message_ids(ftl), ["language-name", "play", "multi"]);
+        assert_eq!(message_value(ftl, "language-name"), Some("Test"));
+
+        let locales = completeness();
+        assert_eq!(locales[0].locale, "en");
+        assert_eq!(locales[0].name, "English");
+        assert_eq!(locales[0].percent(), 100);
+        assert!(locales[0].missing.is_empty());
+
+        // Variables are left for the caller to fill in
+        init(Some("en".to_string()));
+        assert_eq!(t("favorite-added"), "Liked {$track}");
+        for locale in &locales {
+            assert_eq!(locale.translated + locale.missing.len(), locale.total);
+        }
+    }
+}
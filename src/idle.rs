@@ -0,0 +1,96 @@
+//! Adaptive tick rate
+//!
+//! somars often runs all day in a background pane. While the terminal is
+//! unfocused or nobody has touched a key for a while, the animation tick slows
+//! right down; focus or any input brings it back.
+
+use std::time::{Duration, Instant};
+
+/// Tick period while someone is watching
+pub const ACTIVE_TICK: Duration = Duration::from_millis(250);
+/// Tick period while the terminal is unfocused or untouched
+pub const IDLE_TICK: Duration = Duration::from_secs(2);
+
+/// Tracks terminal focus and the last input to pick the tick period
+#[derive(Debug)]
+pub struct Activity {
+    focused: bool,
+    last_input: Instant,
+    /// Inactivity before going idle, `None` to only follow focus
+    idle_after: Option<Duration>,
+}
+
+impl Activity {
+    /// Go idle after `idle_after_seconds` without input; zero never does
+    pub fn new(idle_after_seconds: u64) -> Self {
+        Self {
+            focused: true,
+            last_input: Instant::now(),
+            idle_after: (idle_after_seconds > 0).then(|| Duration::from_secs(idle_after_seconds)),
+        }
+    }
+
+    /// A key, mouse or paste event arrived
+    pub fn input(&mut self) {
+        self.input_at(Instant::now());
+    }
+
+    /// The terminal gained or lost focus
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+        if focused {
+            self.input();
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.is_idle_at(Instant::now())
+    }
+
+    /// Tick period for the current state
+    pub fn tick_period(&self) -> Duration {
+        if self.is_idle() {
+            IDLE_TICK
+        } else {
+            ACTIVE_TICK
+        }
+    }
+
+    fn input_at(&mut self, now: Instant) {
+        self.last_input = now;
+    }
+
+    fn is_idle_at(&self, now: Instant) -> bool {
+        !self.focused
+            || self
+                .idle_after
+                .is_some_and(|idle_after| now.duration_since(self.last_input) >= idle_after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idles_when_unfocused_or_untouched() {
+        let start = Instant::now();
+        let mut activity = Activity::new(60);
+        activity.input_at(start);
+        assert!(!activity.is_idle_at(start + Duration::from_secs(59)));
+        assert!(activity.is_idle_at(start + Duration::from_secs(60)));
+
+        activity.input_at(start + Duration::from_secs(60));
+        assert!(!activity.is_idle_at(start + Duration::from_secs(61)));
+
+        activity.focused = false;
+        assert!(activity.is_idle_at(start + Duration::from_secs(61)));
+
+        // Zero disables the inactivity timeout, but focus still counts
+        let mut activity = Activity::new(0);
+        activity.input_at(start);
+        assert!(!activity.is_idle_at(start + Duration::from_secs(86_400)));
+        activity.focused = false;
+        assert!(activity.is_idle_at(start));
+    }
+}
@@ -0,0 +1,239 @@
+//! Single-instance detection
+//!
+//! A running somars holds an exclusive lock on `somars.lock` next to the
+//! config file and records its PID and UDP control port in it. A second
+//! instance that finds the lock taken can attach to the first one as a remote
+//! control over UDP instead of fighting it for the audio device and port, or
+//! hand it what it was asked to play with [`forward`] and exit.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, BufRead, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How often a forwarded command is sent before giving up on an answer
+const FORWARD_ATTEMPTS: u32 = 10;
+/// How long to wait for each answer
+const FORWARD_REPLY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Lock held for the lifetime of the running instance
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+/// Another somars process holding the lock
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningInstance {
+    pub pid: Option<u32>,
+    /// UDP control port, if the instance is listening
+    pub udp_port: Option<u16>,
+}
+
+/// Outcome of [`acquire`]
+#[derive(Debug)]
+pub enum Acquire {
+    Acquired(InstanceLock),
+    Running(RunningInstance),
+}
+
+/// Default lock file path, next to the config file
+pub fn lock_path() -> Option<PathBuf> {
+    let config_path = crate::config::Config::default_config_path().ok()?;
+    Some(config_path.parent()?.join("somars.lock"))
+}
+
+/// Try to become the running instance
+///
+/// `udp_port` is recorded so other instances can attach as a remote.
+pub fn acquire(path: &Path, udp_port: Option<u16>) -> io::Result<Acquire> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+
+    match file.try_lock() {
+        Ok(()) => {
+            file.set_len(0)?;
+            file.rewind()?;
+            file.write_all(format_lock(std::process::id(), udp_port).as_bytes())?;
+            file.flush()?;
+            Ok(Acquire::Acquired(InstanceLock { _file: file }))
+        }
+        Err(TryLockError::WouldBlock) => {
+            // Some platforms refuse reads of a locked file; report what we can
+            let mut contents = String::new();
+            let _ = file.read_to_string(&mut contents);
+            Ok(Acquire::Running(parse_lock(&contents)))
+        }
+        Err(TryLockError::Error(error)) => Err(error),
+    }
+}
+
+fn format_lock(pid: u32, udp_port: Option<u16>) -> String {
+    match udp_port {
+        Some(port) => format!("pid={}\nudp_port={}\n", pid, port),
+        None => format!("pid={}\n", pid),
+    }
+}
+
+fn parse_lock(contents: &str) -> RunningInstance {
+    let value = |key: &str| {
+        contents
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .find(|(name, _)| name.trim() == key)
+            .map(|(_, value)| value.trim().to_string())
+    };
+    RunningInstance {
+        pid: value("pid").and_then(|pid| pid.parse().ok()),
+        udp_port: value("udp_port").and_then(|port| port.parse().ok()),
+    }
+}
+
+/// Forward commands typed on stdin to the running instance until EOF or `exit`
+///
+/// Commands are sent like [`forward`] sends them, led by `token` when one is
+/// given, and whatever the instance rejects is printed with its reason.
+/// This runs instead of the interface, so it talks to the terminal itself.
+#[allow(clippy::print_stdout, clippy::print_stderr)]
+pub async fn attach(port: u16, token: Option<&str>) -> io::Result<()> {
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+    socket.connect(("127.0.0.1", port)).await?;
+
+    println!("Attached to somars on UDP port {}. Type commands (e.g. `play`, `tune next`), `exit` to quit.", port);
+    let stdin = io::stdin();
+    let mut seq = 0u32;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let command = line.trim();
+        match command {
+            "" => continue,
+            "exit" | "quit" => break,
+            _ => {}
+        }
+        if let Err(reason) = crate::udp::parse_udp_packet(command) {
+            eprintln!("{}", reason);
+            continue;
+        }
+        seq = seq.wrapping_add(1);
+        if let Err(error) = send_until_answered(&socket, &packet(token, seq, command), seq).await {
+            eprintln!("{}: {}", command, error);
+        }
+    }
+    Ok(())
+}
+
+/// Send `commands` to the running instance on `port` in order, each once it
+/// acknowledged the one before
+///
+/// Commands are sent with a sequence number and led by `token` when one is
+/// given. A command the instance rejects or never answers stops the rest.
+pub async fn forward(port: u16, token: Option<&str>, commands: &[String]) -> io::Result<()> {
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await?;
+    socket.connect(("127.0.0.1", port)).await?;
+    for (seq, command) in (1u32..).zip(commands) {
+        crate::udp::parse_udp_packet(command)
+            .map_err(|reason| io::Error::new(io::ErrorKind::InvalidInput, reason))?;
+        send_until_answered(&socket, &packet(token, seq, command), seq).await?;
+    }
+    Ok(())
+}
+
+/// `command` with its sequence number, led by `token` when one is given
+fn packet(token: Option<&str>, seq: u32, command: &str) -> String {
+    match token {
+        Some(token) => format!("token {} seq {} {}", token, seq, command),
+        None => format!("seq {} {}", seq, command),
+    }
+}
+
+/// Resend `packet` until the instance acknowledges `seq` or rejects it
+async fn send_until_answered(
+    socket: &tokio::net::UdpSocket,
+    packet: &str,
+    seq: u32,
+) -> io::Result<()> {
+    let ok = format!("ok {}", seq);
+    let err = format!("err {} ", seq);
+    let mut buffer = [0u8; 1024];
+    for _ in 0..FORWARD_ATTEMPTS {
+        socket.send(packet.as_bytes()).await?;
+        let deadline = tokio::time::Instant::now() + FORWARD_REPLY_TIMEOUT;
+        // Answers to earlier retransmissions may still arrive; skip them
+        while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv(&mut buffer)).await {
+            let reply = match received {
+                Ok(len) => String::from_utf8_lossy(&buffer[..len]).into_owned(),
+                // Nothing listens on the port (yet); try again after the wait
+                Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+                    tokio::time::sleep_until(deadline).await;
+                    break;
+                }
+                Err(error) => return Err(error),
+            };
+            if reply == ok {
+                return Ok(());
+            }
+            if let Some(reason) = reply.strip_prefix(&err) {
+                return Err(io::Error::other(format!("rejected: {}", reason)));
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "the running instance did not answer",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_round_trip() {
+        assert_eq!(
+            parse_lock(&format_lock(42, Some(8069))),
+            RunningInstance {
+                pid: Some(42),
+                udp_port: Some(8069),
+            }
+        );
+        assert_eq!(
+            parse_lock(&format_lock(42, None)),
+            RunningInstance {
+                pid: Some(42),
+                udp_port: None,
+            }
+        );
+        assert_eq!(parse_lock("").pid, None);
+    }
+
+    #[test]
+    fn second_acquire_sees_running_instance() {
+        let path = std::env::temp_dir().join(format!("somars-lock-test-{}", std::process::id()));
+        let first = acquire(&path, Some(9000)).unwrap();
+        assert!(matches!(first, Acquire::Acquired(_)));
+
+        match acquire(&path, None).unwrap() {
+            Acquire::Running(instance) => {
+                if !cfg!(windows) {
+                    assert_eq!(instance.pid, Some(std::process::id()));
+                    assert_eq!(instance.udp_port, Some(9000));
+                }
+            }
+            Acquire::Acquired(_) => panic!("lock acquired twice"),
+        }
+
+        drop(first);
+        assert!(matches!(
+            acquire(&path, None).unwrap(),
+            Acquire::Acquired(_)
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+}
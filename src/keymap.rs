@@ -0,0 +1,156 @@
+//! Key bindings by context
+//!
+//! Which keys do what in each part of the interface, as shown by the key hint
+//! overlay. The table lists the keys the app and its components handle, so a
+//! new shortcut belongs here as well as in its handler.
+
+use crossterm::event::KeyCode;
+
+/// Toggles the key hint overlay; a function key so it works while typing
+pub const HINTS_KEY: KeyCode = KeyCode::F(1);
+
+/// What has the keyboard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyContext {
+    /// The main screen: station list, now playing and history
+    #[default]
+    Stations,
+    TrackSearch,
+    /// Typing a search in the history panel
+    HistorySearch,
+    LanguagePicker,
+    Stats,
+}
+
+impl KeyContext {
+    /// Message ID of the context's name
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Stations => "hints-stations",
+            Self::TrackSearch => "track-search-title",
+            Self::HistorySearch => "history-search-title",
+            Self::LanguagePicker => "language-title",
+            Self::Stats => "stats-title",
+        }
+    }
+
+    pub fn bindings(self) -> &'static [Binding] {
+        match self {
+            Self::Stations => STATIONS,
+            Self::TrackSearch => TRACK_SEARCH,
+            Self::HistorySearch => HISTORY_SEARCH,
+            Self::LanguagePicker => LANGUAGE_PICKER,
+            Self::Stats => STATS,
+        }
+    }
+}
+
+/// Keys and what they do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    /// Keys as shown where Unicode symbols are available
+    pub keys: &'static str,
+    /// Keys as shown on plain terminals
+    pub plain: &'static str,
+    /// Message ID of the short description
+    pub description: &'static str,
+}
+
+const fn bind(keys: &'static str, plain: &'static str, description: &'static str) -> Binding {
+    Binding {
+        keys,
+        plain,
+        description,
+    }
+}
+
+const STATIONS: &[Binding] = &[
+    bind("↵", "Enter", "hint-play"),
+    bind("Space", "Space", "hint-pause"),
+    bind("↑/↓", "Up/Down", "hint-select"),
+    bind("+/-", "+/-", "hint-volume"),
+    bind("←/→", "Left/Right", "hint-rewind"),
+    bind("[/{", "[/{", "hint-rewind-more"),
+    bind("]", "]", "hint-live"),
+    bind("1-9", "1-9", "hint-preset"),
+    bind("x", "x", "hint-random"),
+    bind("⌫", "Backspace", "hint-back"),
+    bind("f", "f", "hint-search"),
+    bind("l", "l", "hint-like"),
+    bind("R", "R", "hint-record"),
+    bind("S", "S", "hint-save-replay"),
+    bind("E", "E", "hint-export-history"),
+    bind("b", "b", "hint-quality"),
+    bind("e", "e", "hint-equalizer"),
+    bind("s", "s", "hint-sort"),
+    bind("g/G", "g/G", "hint-genre"),
+    bind("j/k", "j/k", "hint-history"),
+    bind("c", "c", "hint-session"),
+    bind("m", "m", "hint-history-filter"),
+    bind("F", "F", "hint-history-search"),
+    bind("r", "r", "hint-refresh"),
+    bind("L", "L", "hint-language"),
+    bind("T", "T", "hint-stats"),
+    bind("?", "?", "hint-help"),
+    bind("F1", "F1", "hint-hints"),
+    bind("q", "q", "hint-quit"),
+];
+
+const TRACK_SEARCH: &[Binding] = &[
+    bind("a-z", "a-z", "hint-type"),
+    bind("⌫", "Backspace", "hint-erase"),
+    bind("↑/↓", "Up/Down", "hint-scroll"),
+    bind("Esc", "Esc", "hint-close"),
+    bind("F1", "F1", "hint-hints"),
+];
+
+const HISTORY_SEARCH: &[Binding] = &[
+    bind("a-z", "a-z", "hint-type"),
+    bind("⌫", "Backspace", "hint-erase"),
+    bind("↑/↓", "Up/Down", "hint-scroll"),
+    bind("↵", "Enter", "hint-keep"),
+    bind("Esc", "Esc", "hint-clear"),
+    bind("F1", "F1", "hint-hints"),
+];
+
+const LANGUAGE_PICKER: &[Binding] = &[
+    bind("↑/↓", "Up/Down", "hint-select"),
+    bind("↵", "Enter", "hint-choose"),
+    bind("Esc", "Esc", "hint-close"),
+    bind("F1", "F1", "hint-hints"),
+];
+
+const STATS: &[Binding] = &[
+    bind("↑/↓", "Up/Down", "hint-scroll"),
+    bind("Esc", "Esc", "hint-close"),
+    bind("F1", "F1", "hint-hints"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_binding_is_translated() {
+        let ftl = include_str!("../locales/en/main.ftl");
+        let translated = |id: &str| ftl.lines().any(|line| line.starts_with(&format!("{id} =")));
+        let contexts = [
+            KeyContext::Stations,
+            KeyContext::TrackSearch,
+            KeyContext::HistorySearch,
+            KeyContext::LanguagePicker,
+            KeyContext::Stats,
+        ];
+        for context in contexts {
+            assert!(translated(context.title()));
+            for binding in context.bindings() {
+                assert!(translated(binding.description), "{}", binding.description);
+            }
+            // The overlay can always be dismissed from where it shows
+            assert!(context
+                .bindings()
+                .iter()
+                .any(|binding| binding.keys == "F1"));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! somars engine
+//!
+//! Everything behind the terminal interface: the station catalog and its
+//! providers, playback, the control protocols, configuration and the
+//! integrations that follow what plays. The `somars` binary is a terminal
+//! frontend on top of it; other frontends, bots and tests can drive the same
+//! engine through the modules below.
+//!
+//! The main entry points:
+//!
+//! - [`config::Config`] loads the settings from `config.toml`
+//! - [`providers::ProviderRegistry`] fetches the station list
+//! - [`audio::player`] plays a [`station::Station`], taking
+//!   [`audio::player::PlayerCommand`]s and reporting back as [`action::Action`]s
+//! - [`control::ControlCommand`] is what the UDP, MQTT, WebSocket and status
+//!   server controls ask for, and [`udp`] speaks the UDP protocol
+//!
+//! [`mock_stream`] has the pieces of a local Icecast server for tests, and
+//! [`audio::player::spawn_detached`] plays without an audio device.
+//!
+//! Some settings are process-wide and set once at startup: call
+//! [`i18n::init`], [`clock::init`], [`http::init`] and [`experimental::init`]
+//! before starting playback, or the defaults apply.
+//!
+//! Nothing here prints: a frontend may own the terminal, so problems go to
+//! `tracing` and the [`HistoryMessage`] channel instead.
+
+#![warn(clippy::print_stdout, clippy::print_stderr)]
+
+use serde::{Deserialize, Serialize};
+
+pub mod action;
+pub mod alarm;
+pub mod artwork;
+pub mod audio;
+pub mod catalog_cache;
+pub mod clock;
+pub mod config;
+pub mod control;
+pub mod data_usage;
+pub mod error;
+pub mod experimental;
+pub mod favorites;
+pub mod history_export;
+pub mod history_import;
+pub mod hooks;
+pub mod http;
+pub mod i18n;
+pub mod idle;
+pub mod instance;
+pub mod listening_stats;
+pub mod logging;
+pub mod media_session;
+pub mod mock_stream;
+pub mod mqtt;
+pub mod prefetch;
+pub mod presets;
+pub mod providers;
+pub mod schedule;
+pub mod scrobble_log;
+pub mod station;
+pub mod station_changes;
+/// Typed client for other tools; somars itself only serves the API
+pub mod status_client;
+pub mod status_file;
+pub mod status_server;
+pub mod storage;
+pub mod terminal_caps;
+pub mod theme;
+pub mod track_log;
+pub mod udp;
+pub mod update;
+pub mod utils;
+pub mod voice;
+pub mod webhook;
+pub mod websocket;
+
+/// Kind of a [`HistoryMessage`], which decides how and when it is shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MessageType {
+    Error,
+    Info,
+    System,
+    Background,
+    Playback,
+    /// Worth noticing even at the quietest log level, highlighted
+    Notice,
+}
+
+/// A line in the message history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryMessage {
+    pub message: String,
+    pub message_type: MessageType,
+    pub timestamp: String,
+}
+
+/// Whether a station is playing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PlaybackState {
+    Playing,
+    Paused,
+    Stopped,
+}
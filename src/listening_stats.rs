@@ -0,0 +1,155 @@
+//! Listening time per station
+//!
+//! The time played is added up per station from the plays in the track log's
+//! [`Store`](crate::storage::Store), so the stats popup and `somars stats`
+//! can rank the stations by how long they were heard across restarts.
+
+use crate::track_log::TrackRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Time a station was listened to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationTime {
+    /// Station title, as the track log keeps it
+    pub title: String,
+    pub seconds: u64,
+}
+
+/// Stations heard in `plays`, longest first
+pub fn ranking(plays: &[TrackRecord]) -> Vec<StationTime> {
+    let mut stations: HashMap<&str, u64> = HashMap::new();
+    for play in plays {
+        *stations.entry(&play.station).or_default() += play.seconds;
+    }
+    let mut ranking: Vec<StationTime> = stations
+        .into_iter()
+        .filter(|(_, seconds)| *seconds > 0)
+        .map(|(title, seconds)| StationTime {
+            title: title.to_string(),
+            seconds,
+        })
+        .collect();
+    ranking.sort_by(|a, b| {
+        b.seconds
+            .cmp(&a.seconds)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    ranking
+}
+
+/// A bar `width` cells long at most, as long as `seconds` is of `max`
+///
+/// Unicode eighth blocks make the bars finer where they can be shown.
+pub fn bar(seconds: u64, max: u64, width: usize, unicode: bool) -> String {
+    if max == 0 || width == 0 {
+        return String::new();
+    }
+    let eighths = (seconds.min(max) as u128 * width as u128 * 8 / max as u128) as usize;
+    if !unicode {
+        return "#".repeat(eighths / 8);
+    }
+    const PARTIAL: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let mut bar = "█".repeat(eighths / 8);
+    bar.push_str(PARTIAL[eighths % 8]);
+    bar
+}
+
+/// `ranking` as lines of title, time and a bar, with the total at the end
+pub fn report(ranking: &[StationTime], unicode: bool) -> String {
+    const BAR_WIDTH: usize = 30;
+    let max = ranking.first().map_or(0, |station| station.seconds);
+    let title_width = ranking
+        .iter()
+        .map(|station| station.title.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut report = String::new();
+    for station in ranking {
+        let time = crate::utils::format_length(Duration::from_secs(station.seconds));
+        report.push_str(&format!(
+            "{:title_width$}  {:>7}  {}\n",
+            station.title,
+            time,
+            bar(station.seconds, max, BAR_WIDTH, unicode),
+        ));
+    }
+    let total: u64 = ranking.iter().map(|station| station.seconds).sum();
+    report.push_str(&format!(
+        "{:title_width$}  {:>7}\n",
+        "Total",
+        crate::utils::format_length(Duration::from_secs(total)),
+    ));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_up_each_station() {
+        let play = |started, seconds, station: &str| TrackRecord {
+            started,
+            seconds,
+            station: station.to_string(),
+            artist: String::new(),
+            title: "Track".to_string(),
+            session: 0,
+        };
+        let plays = [
+            play(100, 90, "Groove Salad"),
+            play(200, 120, "Drone Zone"),
+            play(400, 90, "Drone Zone"),
+            play(500, 60, "Groove Salad"),
+            // Skipped right away
+            play(600, 0, "Fluid"),
+        ];
+        assert_eq!(
+            ranking(&plays),
+            vec![
+                StationTime {
+                    title: "Drone Zone".to_string(),
+                    seconds: 210,
+                },
+                StationTime {
+                    title: "Groove Salad".to_string(),
+                    seconds: 150,
+                },
+            ]
+        );
+        assert!(ranking(&[]).is_empty());
+    }
+
+    #[test]
+    fn draws_bars_relative_to_the_longest() {
+        assert_eq!(bar(10, 10, 4, true), "████");
+        assert_eq!(bar(5, 10, 3, true), "█▌");
+        assert_eq!(bar(5, 10, 3, false), "#");
+        assert_eq!(bar(0, 10, 4, true), "");
+        assert_eq!(bar(0, 0, 4, true), "");
+
+        let ranking = [
+            StationTime {
+                title: "Drone Zone".to_string(),
+                seconds: 7200,
+            },
+            StationTime {
+                title: "Underground 80s".to_string(),
+                seconds: 1800,
+            },
+        ];
+        let report = report(&ranking, false);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("Drone Zone         2h00m  {}", "#".repeat(30))
+        );
+        assert_eq!(
+            lines[1],
+            format!("Underground 80s      30m  {}", "#".repeat(7))
+        );
+        assert_eq!(lines[2], "Total              2h30m");
+    }
+}
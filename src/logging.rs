@@ -2,20 +2,75 @@
 //!
 //! This module sets up structured logging using the `tracing` crate.
 //!
-//! Logs go to `~/.config/somars/somars.log` to avoid corrupting the TUI.
-//! To see more detailed logs, set RUST_LOG.
+//! Logs go to `~/.config/somars/somars.log`, or the file set in the
+//! `[logging]` table, to avoid corrupting the TUI. Once the file grows past
+//! `max_size_mb` it is moved aside to `somars.log.1` and a new one started.
+//! Messages shown in the history panel are written too, under the
+//! `somars::history` target, filtered by the file's own level rather than the
+//! on-screen log level. To see more detailed logs, set RUST_LOG.
 
-use std::{fs::OpenOptions, path::PathBuf, sync::Mutex};
+use crate::{HistoryMessage, MessageType};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::{path::PathBuf, sync::Mutex};
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::EnvFilter;
 
+/// Target of the history panel messages in the log file
+pub const HISTORY_TARGET: &str = "somars::history";
+
+/// Log file settings, read from the `[logging]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Log file; `~/.config/somars/somars.log` when unset
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+    /// Lowest level written, such as `info` to keep the history panel's
+    /// messages; `RUST_LOG` overrides it
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// Megabytes the file may grow to before it is rotated; 0 never rotates
+    #[serde(default = "default_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Rotated files kept as `somars.log.1`, `somars.log.2` and so on
+    #[serde(default = "default_keep")]
+    pub keep: u32,
+}
+
+fn default_level() -> String {
+    "error".to_string()
+}
+
+fn default_max_size_mb() -> u64 {
+    5
+}
+
+fn default_keep() -> u32 {
+    3
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            level: default_level(),
+            max_size_mb: default_max_size_mb(),
+            keep: default_keep(),
+        }
+    }
+}
+
 /// Initialize the tracing subscriber for logging
 ///
 /// This sets up structured logging with:
-/// - File output (to avoid interfering with TUI stdout/stderr)
-/// - Default level: ERROR only (quiet by default)
+/// - File output (to avoid interfering with TUI stdout/stderr), rotated by size
+/// - Default level: ERROR only (quiet by default), or `config.level`
 /// - Configurable log level via RUST_LOG environment variable
 /// - Clean formatting without targets (for better readability)
 ///
+/// An unknown `config.level` falls back to ERROR and is logged as a warning.
+///
 /// # Environment Variables
 ///
 /// - `RUST_LOG`: Set the log level (e.g., `info`, `debug`, `warn`, `error`)
@@ -23,55 +78,60 @@ use tracing_subscriber::EnvFilter;
 ///   - `warn` - Warnings and errors
 ///   - `info` - General informational messages
 ///   - `debug` - Detailed debugging information
-///   - Module-specific filtering: `somars::audio=debug,somars=info`
+///   - Module-specific filtering: `somars_core::audio=debug,somars=info`
 ///
 /// # Example
 ///
 /// ```no_run
-/// # use somars::logging::init_logging;
-/// init_logging();
+/// # use somars_core::logging::{init_logging, LoggingConfig};
+/// init_logging(&LoggingConfig::default());
 /// ```
 ///
 /// # Log File
 ///
-/// Logs are written to:
+/// Logs are written to `[logging] file`, by default:
 ///
 /// ```bash
 /// ~/.config/somars/somars.log
 /// ```
-pub fn init_logging() {
-    // Read log level from environment, defaulting to ERROR (quiet by default)
+pub fn init_logging(config: &LoggingConfig) {
+    let level = config.level.trim().parse::<LevelFilter>().ok();
+    let directive = level.unwrap_or(LevelFilter::ERROR);
     // Users can set RUST_LOG=debug to see more detailed logs
-    let env_filter =
-        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("somars=error"));
-
-    if let Some(log_file) = open_log_file() {
-        tracing_subscriber::fmt()
-            .with_env_filter(env_filter)
-            .with_target(false)
-            .with_thread_ids(false)
-            .with_file(false)
-            .with_line_number(false)
-            .with_writer(Mutex::new(log_file))
-            .init();
-    } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(env_filter)
-            .with_target(false)
-            .with_thread_ids(false)
-            .with_file(false)
-            .with_line_number(false)
-            .with_writer(std::io::sink)
-            .init();
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("somars={0},somars_core={0}", directive)));
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter)
+        .with_target(false)
+        .with_thread_ids(false)
+        .with_file(false)
+        .with_line_number(false);
+    match open_log_file(config) {
+        Some(log_file) => builder.with_writer(Mutex::new(log_file)).init(),
+        None => builder.with_writer(io::sink).init(),
+    }
+
+    if level.is_none() {
+        tracing::warn!("Unknown log level {:?}; logging errors only", config.level);
     }
 }
 
-fn open_log_file() -> Option<std::fs::File> {
-    let path = log_path()?;
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent).ok()?;
+/// Write a message shown in the history panel to the log file
+pub fn record_history(message: &HistoryMessage) {
+    match message.message_type {
+        MessageType::Error => tracing::error!(target: HISTORY_TARGET, "{}", message.message),
+        MessageType::Notice => tracing::warn!(target: HISTORY_TARGET, "{}", message.message),
+        MessageType::Info | MessageType::System | MessageType::Playback => {
+            tracing::info!(target: HISTORY_TARGET, "{}", message.message)
+        }
+        MessageType::Background => tracing::debug!(target: HISTORY_TARGET, "{}", message.message),
     }
-    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+fn open_log_file(config: &LoggingConfig) -> Option<RotatingFile> {
+    let path = config.file.clone().or_else(log_path)?;
+    RotatingFile::open(path, config.max_size_mb * 1024 * 1024, config.keep).ok()
 }
 
 fn log_path() -> Option<PathBuf> {
@@ -79,8 +139,84 @@ fn log_path() -> Option<PathBuf> {
     Some(home.join(".config").join("somars").join("somars.log"))
 }
 
+/// Log file that moves itself aside once it grows too large
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    /// Bytes in the current file
+    size: u64,
+    /// Size to rotate at; 0 never rotates
+    max_size: u64,
+    /// Rotated files to keep
+    keep: u32,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, keep: u32) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            keep,
+        })
+    }
+
+    /// Path of the `index`th newest rotated file
+    fn rotated(&self, index: u32) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        name.into()
+    }
+
+    /// Move every rotated file one place older, dropping the oldest, and
+    /// start the log again
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep > 0 {
+            for index in (1..self.keep).rev() {
+                let older = self.rotated(index);
+                if older.exists() {
+                    fs::rename(&older, self.rotated(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Each event arrives in one write, so lines are never split
+        if self.max_size > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_init_logging() {
         // Note: This test will fail if a subscriber is already initialized
@@ -88,4 +224,42 @@ mod tests {
         // For testing purposes, we just verify the function exists and compiles
         // Actual testing would require a more complex setup
     }
+
+    #[test]
+    fn rotates_and_keeps_the_newest_files() {
+        let dir = std::env::temp_dir().join(format!("somars-log-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("somars.log");
+        let mut log = RotatingFile::open(path.clone(), 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "fourth\n");
+        assert_eq!(read(log.rotated(1)), "third\n");
+        assert_eq!(read(log.rotated(2)), "second\n");
+        assert!(!log.rotated(3).exists());
+
+        // Reopening carries on from the size already written
+        drop(log);
+        let mut log = RotatingFile::open(path.clone(), 10, 0).unwrap();
+        log.write_all(b"fifth\n").unwrap();
+        assert_eq!(read(path), "fifth\n");
+        assert_eq!(read(log.rotated(1)), "third\n");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_the_logging_table() {
+        let config: LoggingConfig = toml::from_str("level = \"info\"").unwrap();
+        assert_eq!(
+            config.level.parse::<LevelFilter>().ok(),
+            Some(LevelFilter::INFO)
+        );
+        assert_eq!(config.max_size_mb, 5);
+        assert_eq!(config.keep, 3);
+        assert_eq!(config.file, None);
+    }
 }
@@ -1,5 +1,4 @@
 use clap::Parser;
-use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
 
 use crossterm::{
@@ -9,61 +8,43 @@ use crossterm::{
 
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use std::{
-    collections::HashMap,
-    io, mem,
-    net::SocketAddr,
-    sync::{Arc, Mutex},
-    time::{Duration, Instant},
-};
-
-use rodio::{OutputStreamBuilder, Sink};
+use std::{io, mem, path::PathBuf, time::Duration};
 
-mod station;
-use crate::station::Station;
-
-mod action;
 mod app;
-mod audio;
 mod components;
-mod config;
-mod control;
-mod error;
+mod demo;
 mod event;
-mod i18n;
-mod logging;
+mod headless;
+mod keymap;
 #[cfg(target_os = "macos")]
 mod macos_runtime;
-mod media_session;
+mod soak;
+mod state;
 mod tui;
-mod utils;
+
 use app::App;
-use control::ControlCommand;
 use i18n::t;
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum MessageType {
-    Error,
-    Info,
-    System,
-    Background,
-    Playback,
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct HistoryMessage {
-    pub message: String,
-    pub message_type: MessageType,
-    pub timestamp: String,
-}
+use somars_core::{
+    action, alarm, artwork, audio, clock, config, control, data_usage, error, experimental,
+    favorites, history_export, history_import, hooks, http, i18n, idle, instance, listening_stats,
+    logging, media_session, mock_stream, mqtt, prefetch, presets, providers, schedule,
+    scrobble_log, station, station_changes, status_file, status_server, storage, terminal_caps,
+    theme, track_log, udp, update, utils, voice, webhook, websocket, HistoryMessage, MessageType,
+    PlaybackState,
+};
 
 #[derive(Parser)]
 #[command(version, about)]
+#[command(group = clap::ArgGroup::new("json_output").args(["no_tui", "list_stations"]))]
 struct Cli {
     /// Log level (1=minimal, 2=verbose)
     #[arg(long)]
     log_level: Option<u8>,
 
+    /// Write the log to this file instead of the one in the config
+    #[arg(long, value_name = "FILE")]
+    log_file: Option<PathBuf>,
+
     /// Station ID to automatically play on startup
     #[arg(short, long)]
     station: Option<String>,
@@ -80,6 +61,10 @@ struct Cli {
     #[arg(short = 'b', long)]
     broadcast: Option<String>,
 
+    /// Play a stream URL directly instead of a SomaFM station
+    #[arg(short = 'u', long, conflicts_with = "station")]
+    url: Option<String>,
+
     /// Set the locale (en, ru)
     #[arg(short = 'L', long)]
     locale: Option<String>,
@@ -91,19 +76,101 @@ struct Cli {
     /// Path to config file
     #[arg(long)]
     config: Option<String>,
+
+    /// Start playing at HH:MM, the --station or the last station
+    #[arg(long, value_name = "HH:MM")]
+    alarm: Option<String>,
+
+    /// Fade the alarm in over this many seconds
+    #[arg(long, value_name = "SECONDS", requires = "alarm")]
+    fade_in: Option<u64>,
+
+    /// Sound systems to try in order, e.g. `pipewire,pulse,alsa`
+    #[arg(long, value_name = "HOST", value_delimiter = ',')]
+    audio_host: Vec<audio::output::AudioHost>,
+
+    /// Start even if another instance is already running, instead of
+    /// handing it --station or --url
+    #[arg(long, alias = "force")]
+    force_new: bool,
+
+    /// Turn on experimental features, e.g. `recording,dsp`
+    #[arg(long, value_name = "FEATURE", value_delimiter = ',')]
+    enable_feature: Vec<experimental::Feature>,
+
+    /// Import plays from a .scrobbler.log or MPD log file into the track log and exit
+    #[arg(long, value_name = "FILE")]
+    import_history: Option<PathBuf>,
+
+    /// Try somars with made-up stations played locally, without the network
+    /// and without saving settings
+    #[arg(long, conflicts_with_all = ["url", "soak"])]
+    demo: bool,
+
+    /// Play the --station, --url or last station without the interface,
+    /// printing each track to standard output
+    #[arg(long, conflicts_with_all = ["demo", "soak", "alarm"])]
+    no_tui: bool,
+
+    /// Print the stations with their IDs, genres and listeners and exit
+    #[arg(long)]
+    list_stations: bool,
+
+    /// Print JSON with --no-tui or --list-stations
+    #[arg(long, requires = "json_output")]
+    json: bool,
+
+    /// Cycle mock stations for HOURS while checking for leaks (default 8)
+    #[arg(
+        long,
+        hide = true,
+        value_name = "HOURS",
+        num_args = 0..=1,
+        default_missing_value = "8"
+    )]
+    soak: Option<f64>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub enum PlaybackState {
-    Playing,
-    Paused,
-    Stopped,
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Search the track log by artist, title or station and exit
+    SearchHistory {
+        /// Words that must all appear, e.g. `boards of canada`
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Export the track log to CSV or JSON and exit
+    ExportHistory {
+        /// File to write; the export goes to standard output when left out
+        file: Option<PathBuf>,
+        /// `csv` or `json`; taken from the file's extension when left out
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<history_export::ExportFormat>,
+    },
+    /// Replace this binary with the latest release and exit
+    SelfUpdate,
+    /// List the most recent listening sessions in the track log and exit
+    Sessions {
+        /// How many sessions to list
+        #[arg(default_value_t = 20)]
+        count: usize,
+    },
+    /// Rank the stations by time listened and exit
+    Stats,
 }
 
 #[cfg(not(target_os = "macos"))]
-#[tokio::main]
-async fn main() -> color_eyre::eyre::Result<()> {
-    async_main().await
+fn main() -> color_eyre::eyre::Result<()> {
+    // Changing the environment is only sound while no other thread runs
+    somars_core::audio::output::set_stream_properties();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|error| color_eyre::eyre::eyre!("Failed to initialize async runtime: {error}"))?
+        .block_on(async_main())
 }
 
 #[cfg(target_os = "macos")]
@@ -120,9 +187,6 @@ fn main() -> color_eyre::eyre::Result<()> {
 }
 
 async fn async_main() -> color_eyre::eyre::Result<()> {
-    // Initialize logging early (before other operations)
-    logging::init_logging();
-
     let cli = Cli::parse();
 
     // Handle print config path mode
@@ -149,31 +213,285 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
             config::Config::default()
         })
     } else {
-        config::Config::load_or_default()
+        // Logging starts with the config, so say it here
+        config::Config::load().unwrap_or_else(|e| {
+            eprintln!("Config load failed: {}. Using defaults.", e);
+            config::Config::default()
+        })
     };
 
+    // Initialize logging early (before other operations)
+    if let Some(log_file) = cli.log_file.clone() {
+        config.logging.file = Some(log_file);
+    }
+    logging::init_logging(&config.logging);
+
+    // The soak test and the demo run on defaults and leave the user's logs alone
+    if cli.soak.is_some() || cli.demo {
+        config = config::Config {
+            track_log: config::TrackLogConfig {
+                enabled: false,
+                ..config::TrackLogConfig::default()
+            },
+            storage: storage::StorageConfig {
+                backend: storage::Backend::Memory,
+                path: None,
+            },
+            last_playing_refresh_seconds: 0,
+            station_changes: false,
+            data_usage: data_usage::DataUsageConfig {
+                enabled: false,
+                ..data_usage::DataUsageConfig::default()
+            },
+            last_station: None,
+            ..config::Config::default()
+        };
+    }
+
     // Apply CLI overrides
     if let Some(log_level) = cli.log_level {
         config.log_level = log_level;
     }
     let config_file_path = cli.config.clone();
 
-    // Determine initial station: CLI argument takes priority over config
-    let initial_station = cli.station.or_else(|| config.last_station.clone());
+    // Determine initial station: CLI argument takes priority over config.
+    // A stream URL replaces both.
+    let initial_station = if cli.url.is_some() {
+        None
+    } else {
+        cli.station.clone().or_else(|| config.last_station.clone())
+    };
 
     // Initialize i18n
-    i18n::init(cli.locale.clone());
+    i18n::init(cli.locale.clone().or_else(|| config.locale.clone()));
+
+    // An alarm plays its station at the set time instead of right away.
+    // Command line settings aren't saved to the config.
+    let mut alarm_config = config.alarm.clone();
+    if let Some(time) = cli.alarm.clone() {
+        alarm_config.time = Some(time);
+        alarm_config.station = cli.station.clone().or(alarm_config.station);
+    }
+    if let Some(seconds) = cli.fade_in {
+        alarm_config.fade_in_seconds = seconds;
+    }
+    let alarm = alarm::Alarm::from_config(&alarm_config, initial_station.as_deref())
+        .map_err(|e| color_eyre::eyre::eyre!(e))?;
+    let initial_station = if alarm.is_some() {
+        None
+    } else {
+        initial_station
+    };
+
+    // Detect terminal capabilities before any UI is built
+    terminal_caps::init(&config.terminal);
+    clock::init(&config.time).map_err(|e| color_eyre::eyre::eyre!(e))?;
+    http::init(&config.network).map_err(|e| color_eyre::eyre::eyre!(e))?;
+    for name in experimental::init(&config.experimental, &cli.enable_feature) {
+        warn!("Ignoring unknown experimental feature {:?}", name);
+    }
+
+    // Handle history import mode
+    if let Some(source) = cli.import_history {
+        let mut store = storage::open(&config)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open the store: {}", e))?;
+        let added = history_import::import(&source, store.as_mut())
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to import history: {}", e))?;
+        println!("Imported {} plays into {}", added, store.location());
+        return Ok(());
+    }
+
+    // Handle track log search mode
+    if let Some(Command::SearchHistory { query }) = cli.command {
+        let store = storage::open(&config)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open the store: {}", e))?;
+        let hits = store
+            .search(&query.join(" "))
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to read track log: {}", e))?;
+        if hits.is_empty() {
+            println!(
+                "No plays of \"{}\" in {}",
+                query.join(" "),
+                store.location()
+            );
+        }
+        for hit in hits {
+            println!(
+                "{}  {:>4}x  {}  {}",
+                hit.last_heard_local(),
+                hit.plays,
+                hit.station,
+                hit.track()
+            );
+        }
+        return Ok(());
+    }
+
+    // Handle track log export mode
+    if let Some(Command::ExportHistory { file, format }) = cli.command {
+        let store = storage::open(&config)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open the store: {}", e))?;
+        let plays = store
+            .plays()
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to read track log: {}", e))?;
+        let format = format
+            .or_else(|| {
+                file.as_deref()
+                    .and_then(history_export::ExportFormat::from_path)
+            })
+            .unwrap_or_default();
+        match file {
+            Some(path) => {
+                history_export::export(&path, &plays, format)
+                    .map_err(|e| color_eyre::eyre::eyre!("Failed to export history: {}", e))?;
+                println!("Exported {} plays to {}", plays.len(), path.display());
+            }
+            None => print!("{}", history_export::render(&plays, format)),
+        }
+        return Ok(());
+    }
+
+    // Handle self-update mode
+    if let Some(Command::SelfUpdate) = cli.command {
+        match update::self_update().await {
+            Ok(version) => println!("Updated somars to {}", version),
+            Err(message) => println!("{}", message),
+        }
+        return Ok(());
+    }
+
+    // Handle listening session summary mode
+    if let Some(Command::Sessions { count }) = cli.command {
+        let store = storage::open(&config)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open the store: {}", e))?;
+        let plays = store
+            .plays()
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to read track log: {}", e))?;
+        let sessions = track_log::sessions(&plays);
+        if sessions.is_empty() {
+            println!("No sessions in {}", store.location());
+        }
+        for session in sessions.iter().skip(sessions.len().saturating_sub(count)) {
+            println!("{}  {}", session.started_local(), session.summary());
+        }
+        return Ok(());
+    }
+
+    // Handle listening stats mode
+    if let Some(Command::Stats) = cli.command {
+        let store = storage::open(&config)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open the store: {}", e))?;
+        let ranking = store
+            .station_times()
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to read track log: {}", e))?;
+        if ranking.is_empty() {
+            println!("Nothing listened to yet");
+        } else {
+            print!(
+                "{}",
+                listening_stats::report(&ranking, terminal_caps::current().unicode)
+            );
+        }
+        return Ok(());
+    }
+
+    // Handle station list mode
+    if cli.list_stations {
+        let (stations, errors) = providers::ProviderRegistry::from_config(&config, true)
+            .fetch_all()
+            .await;
+        for (provider, error) in &errors {
+            eprintln!("Error loading stations from {}: {}", provider, error);
+        }
+        if stations.is_empty() && !errors.is_empty() {
+            return Err(color_eyre::eyre::eyre!("No stations could be loaded"));
+        }
+        print!("{}", headless::station_list(&stations, cli.json));
+        return Ok(());
+    }
 
     // Handle broadcast mode
     if let Some(message) = cli.broadcast {
-        send_udp_broadcast(&message, cli.port.unwrap_or(config.udp_port))
+        udp::send_udp_broadcast(&message, cli.port.unwrap_or(config.udp_port))
             .await
             .map_err(|e| color_eyre::eyre::eyre!("Failed to send UDP broadcast: {}", e))?;
         return Ok(());
     }
 
+    let udp_enabled = cli.listen || config.udp_enabled;
+    let udp_port = cli.port.unwrap_or(config.udp_port);
+
+    // Don't fight another instance for the audio device and UDP port
+    let _instance_lock = match instance::lock_path()
+        .map(|path| instance::acquire(&path, udp_enabled.then_some(udp_port)))
+    {
+        Some(Ok(instance::Acquire::Acquired(lock))) => Some(lock),
+        Some(Ok(instance::Acquire::Running(_))) if cli.no_tui && !cli.force_new => {
+            return Err(color_eyre::eyre::eyre!(
+                "somars is already playing. Use --force-new to play here as well"
+            ));
+        }
+        Some(Ok(instance::Acquire::Running(running))) if !cli.force_new => {
+            let commands = forwarded_commands(&cli);
+            if commands.is_empty() {
+                return handle_running_instance(running, &config.remote).await;
+            }
+            return forward_to_running_instance(running, &config.remote, &commands).await;
+        }
+        Some(Ok(instance::Acquire::Running(_))) => {
+            warn!("Another instance is running; starting anyway because of --force-new");
+            None
+        }
+        Some(Err(e)) => {
+            warn!("Failed to acquire instance lock: {}", e);
+            None
+        }
+        None => None,
+    };
+
+    let audio_hosts = if cli.audio_host.is_empty() {
+        &config.audio_hosts
+    } else {
+        &cli.audio_host
+    };
+
+    // Handle playing without the interface
+    if cli.no_tui {
+        let station =
+            headless::pick_station(&config, initial_station.as_deref(), cli.url.as_deref()).await?;
+        let output = audio::output::Output::open(
+            config.audio_backend,
+            audio_hosts,
+            config.audio_output_buffer_frames,
+        )?;
+        return headless::run(&config, output, station, cli.json).await;
+    }
+
+    let soak = match cli.soak {
+        Some(hours) if hours.is_finite() && hours > 0.0 => Some(
+            soak::Soak::start(Duration::from_secs_f64(hours * 3600.0))
+                .await
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to start soak test: {}", e))?,
+        ),
+        Some(hours) => return Err(color_eyre::eyre::eyre!("Invalid soak duration {}", hours)),
+        None => None,
+    };
+    let demo = if cli.demo {
+        Some(
+            demo::Demo::start()
+                .await
+                .map_err(|e| color_eyre::eyre::eyre!("Failed to start the demo: {}", e))?,
+        )
+    } else {
+        None
+    };
+
     // Setup terminal
+    tui::install_panic_hook();
     enable_raw_mode().map_err(|e| color_eyre::eyre::eyre!("Failed to enable raw mode: {}", e))?;
+
+    // Pick a theme for the terminal background while nothing else reads input
+    theme::init(&config.theme);
     let mut stdout = io::stdout();
     execute!(
         stdout,
@@ -185,33 +503,20 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
     terminal.clear()?;
 
     // Create app state
-    let stream = OutputStreamBuilder::from_default_device()
-         .and_then(|builder| {
-             builder
-                 .with_buffer_size(rodio::cpal::BufferSize::Fixed(config.audio_output_buffer_frames))
-                 .with_error_callback(|err| error!("Audio output stream error: {}", err))
-                 .open_stream()
-         })
-         .map_err(|e| {
-         error::AppError::Audio(format!("Failed to initialize audio output stream: {}. This could be due to:\n\
-                                         - No audio output device available\n\
-                                         - Audio device is busy or locked by another application\n\
-                                         - Missing audio system dependencies (e.g., ALSA on Linux)\n\
-                                         Try checking your system's audio settings or restarting your audio service.", e))
-     })?;
-
-    let mixer = stream.mixer();
-    let sink = Sink::connect_new(mixer);
+    let output = audio::output::Output::open(
+        config.audio_backend,
+        audio_hosts,
+        config.audio_output_buffer_frames,
+    )?;
 
     // Create channels for logging and control
     let (log_tx, mut log_rx) = tokio::sync::mpsc::channel(32);
     let (command_tx, mut command_rx) = tokio::sync::mpsc::channel(32);
 
     // Start UDP listener if enabled
-    let udp_enabled = cli.listen || config.udp_enabled;
-    let udp_port = cli.port.unwrap_or(config.udp_port);
     if udp_enabled {
         let port = udp_port;
+        let permissions = control::Permissions::from_config(&config.remote);
         let command_tx = command_tx.clone();
         let log_tx = log_tx.clone();
 
@@ -220,19 +525,19 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
             .send(HistoryMessage {
                 message: t("udp-starting").replace("{$port}", &port.to_string()),
                 message_type: MessageType::Info,
-                timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+                timestamp: crate::clock::timestamp(),
             })
             .await;
 
         tokio::spawn(async move {
-            if let Err(e) = handle_udp_commands(port, command_tx).await {
+            if let Err(e) = udp::handle_udp_commands(port, permissions, command_tx).await {
                 error!("UDP listener error: {}", e);
                 // Add error logging here too
                 let _ = log_tx
                     .send(HistoryMessage {
                         message: t("udp-error").replace("{$error}", &e.to_string()),
                         message_type: MessageType::Error,
-                        timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+                        timestamp: crate::clock::timestamp(),
                     })
                     .await;
             }
@@ -244,9 +549,8 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
     let (metadata_tx, mut metadata_rx) = tokio::sync::mpsc::channel(32);
 
     // Create the new App
-    let sink = Arc::new(Mutex::new(sink));
     let mut app = App::new(
-        sink,
+        output,
         metadata_tx,
         log_tx.clone(),
         config.clone(),
@@ -257,83 +561,69 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
     let metadata_action_tx = app.action_tx.clone();
     tokio::spawn(async move {
         while let Some(event) = metadata_rx.recv().await {
-            if let audio::MetadataEvent::Track { station, title } = event {
-                let _ = metadata_action_tx.send(action::Action::MetadataUpdate { station, title });
-            }
+            let action = match event {
+                audio::MetadataEvent::Track { station, title } => {
+                    action::Action::MetadataUpdate { station, title }
+                }
+                audio::MetadataEvent::StreamInfo(info) => action::Action::SetStreamInfo(Some(info)),
+                _ => continue,
+            };
+            let _ = metadata_action_tx.send(action);
         }
     });
 
+    let features = experimental::enabled_features();
+    if !features.is_empty() {
+        let names: Vec<&str> = features.iter().map(|feature| feature.as_str()).collect();
+        let _ = app
+            .action_tx
+            .send(action::Action::AddHistoryMessage(HistoryMessage {
+                message: t("experimental-on").replace("{$features}", &names.join(", ")),
+                message_type: MessageType::Notice,
+                timestamp: crate::clock::timestamp(),
+            }));
+    }
+    if let Some(url) = cli.url.clone() {
+        let _ = app.action_tx.send(action::Action::TuneUrl(url));
+    }
+    if let Some(alarm) = alarm {
+        alarm.spawn(app.action_tx.clone());
+    }
+
     // Spawn station fetching task
-    let action_tx_clone = app.action_tx.clone();
-    tokio::spawn(async move {
-        match Station::fetch_all().await {
-            Ok(stations) => {
-                let _ = action_tx_clone.send(action::Action::UpdateStations(stations));
-            }
-            Err(e) => {
-                let _ = action_tx_clone.send(action::Action::Error(format!(
-                    "Error loading stations: {}",
-                    e
-                )));
-            }
+    let mut registry = match (&soak, &demo) {
+        (Some(soak), _) => {
+            let mut registry = providers::ProviderRegistry::default();
+            registry.register(soak.provider());
+            soak.spawn_driver(app.action_tx.clone());
+            registry
         }
-    });
+        (None, Some(demo)) => {
+            let mut registry = providers::ProviderRegistry::default();
+            registry.register(demo.provider());
+            registry
+        }
+        (None, None) => providers::ProviderRegistry::from_config(&config, false),
+    };
+    if let Some(url) = cli.url.clone() {
+        registry.register(providers::UrlProvider::new(url));
+    }
+    app.fetch_stations(registry);
+    if config.last_playing_refresh_seconds > 0 {
+        providers::spawn_live_status_refresh(
+            config.api.clone(),
+            Duration::from_secs(config.last_playing_refresh_seconds),
+            app.action_tx.clone(),
+        );
+    }
+    schedule::spawn_refresh(config.schedule.clone(), app.action_tx.clone());
 
     // Handle UDP commands by converting them to Actions
     let udp_action_tx = app.action_tx.clone();
     let _udp_log_tx = log_tx.clone();
     tokio::spawn(async move {
         while let Some(cmd) = command_rx.recv().await {
-            match cmd {
-                ControlCommand::Play => {
-                    let _ = udp_action_tx.send(action::Action::Play);
-                }
-                ControlCommand::Stop => {
-                    let _ = udp_action_tx.send(action::Action::Stop);
-                }
-                ControlCommand::TogglePause => {
-                    let _ = udp_action_tx.send(action::Action::TogglePause);
-                }
-                ControlCommand::VolumeUp => {
-                    let _ = udp_action_tx.send(action::Action::VolumeUp);
-                }
-                ControlCommand::VolumeDown => {
-                    let _ = udp_action_tx.send(action::Action::VolumeDown);
-                }
-                ControlCommand::SetVolume(level) => {
-                    let _ = udp_action_tx.send(action::Action::SetVolume(level));
-                }
-                ControlCommand::Tune(station_id) => {
-                    let _ = udp_action_tx.send(action::Action::TuneStation(station_id));
-                }
-                ControlCommand::TuneNext => {
-                    let _ = udp_action_tx.send(action::Action::TuneNext);
-                }
-                ControlCommand::TunePrev => {
-                    let _ = udp_action_tx.send(action::Action::TunePrev);
-                }
-                ControlCommand::SelectUp => {
-                    let _ = udp_action_tx.send(action::Action::StationUp);
-                }
-                ControlCommand::SelectDown => {
-                    let _ = udp_action_tx.send(action::Action::StationDown);
-                }
-                ControlCommand::Toggle => {
-                    let _ = udp_action_tx.send(action::Action::TogglePlayStop);
-                }
-                ControlCommand::ToggleHelp => {
-                    let _ = udp_action_tx.send(action::Action::ToggleHelp);
-                }
-                ControlCommand::ScrollHistoryUp => {
-                    let _ = udp_action_tx.send(action::Action::ScrollHistoryUp);
-                }
-                ControlCommand::ScrollHistoryDown => {
-                    let _ = udp_action_tx.send(action::Action::ScrollHistoryDown);
-                }
-                ControlCommand::Quit => {
-                    let _ = udp_action_tx.send(action::Action::Quit);
-                }
-            }
+            let _ = udp_action_tx.send(action::Action::from(cmd));
         }
     });
 
@@ -350,39 +640,42 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
         }
     });
 
+    spawn_signal_handler(app.action_tx.clone());
+
     // Run the application
     app.run().await?;
 
-    if let Some(handle) = app.audio_manager.take_handle() {
-        handle.abort();
-    }
-
-    // Stop audio playback to prevent the OutputStream warning
-    if let Some(sink) = &app.sink {
-        if let Ok(sink) = sink.lock() {
-            sink.stop();
-        }
-    }
-    // Drop the sink explicitly to stop audio before OutputStream is dropped
-    drop(app.sink.take());
+    // Stop audio before the OutputStream is dropped, preventing its warning
+    app.player.shutdown().await;
 
     // Give the audio system time to finish
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
     // Save configuration before quitting
-    config.volume = app.volume;
+    config.volume = app.state.volume();
     config.log_level = app.log_level;
     config.udp_port = udp_port;
     config.udp_enabled = udp_enabled;
+    config.stream_quality = app.config.stream_quality;
+    config.station_sort = app.config.station_sort;
+    config.equalizer.preset = app.config.equalizer.preset;
+    config.presets = app.config.presets.clone();
+    config.locale = app.config.locale.clone();
 
     // Save the last played station
     if let Some(index) = app.active_station {
-        if let Some(station) = app.stations.get(index) {
+        if let Some(station) = app
+            .stations
+            .get(index)
+            .filter(|station| station.id != station::URL_STATION_ID)
+        {
             config.last_station = Some(station.id.clone());
         }
     }
 
-    let save_result = if let Some(path) = &config_file_path {
+    let save_result = if soak.is_some() || demo.is_some() {
+        Ok(())
+    } else if let Some(path) = &config_file_path {
         config.save_to_path(path)
     } else {
         config.save()
@@ -401,239 +694,110 @@ async fn async_main() -> color_eyre::eyre::Result<()> {
 
     // Forget the OutputStream to prevent the warning message
     // This is safe since we're about to exit anyway
-    mem::forget(stream);
-
-    Ok(())
-}
+    mem::forget(app.output);
 
-async fn send_udp_broadcast(message: &str, port: u16) -> Result<(), error::AppError> {
-    use tokio::net::UdpSocket;
-
-    // Validate message length to prevent potential abuse
-    const MAX_BROADCAST_MESSAGE_LEN: usize = 256;
-    if message.len() > MAX_BROADCAST_MESSAGE_LEN {
-        return Err(error::AppError::Udp(format!(
-            "Broadcast message too long: {} bytes (max: {})",
-            message.len(),
-            MAX_BROADCAST_MESSAGE_LEN
-        )));
+    match soak {
+        Some(soak) => soak.finish(),
+        None => Ok(()),
     }
-
-    let socket = UdpSocket::bind("0.0.0.0:0")
-        .await
-        .map_err(|e| error::AppError::Udp(format!("Failed to bind UDP socket: {}", e)))?;
-    socket
-        .set_broadcast(true)
-        .map_err(|e| error::AppError::Udp(format!("Failed to enable broadcast: {}", e)))?;
-    let target_addr = format!("255.255.255.255:{}", port);
-    socket
-        .send_to(message.as_bytes(), &target_addr)
-        .await
-        .map_err(|e| {
-            error::AppError::Udp(format!(
-                "Failed to send UDP packet to {}: {}",
-                target_addr, e
-            ))
-        })?;
-    Ok(())
 }
 
-async fn handle_udp_commands(
-    port: u16,
-    tx: tokio::sync::mpsc::Sender<ControlCommand>,
-) -> Result<(), error::AppError> {
-    use tokio::net::UdpSocket;
-
-    let socket = UdpSocket::bind(("0.0.0.0", port))
-        .await
-        .map_err(|e| error::AppError::Udp(format!("Failed to bind to port {}: {}", port, e)))?;
-    let mut buf = [0; 1024];
-
-    // Rate limiting: max 10 requests per second per IP
-    const MAX_REQUESTS_PER_SECOND: u32 = 10;
-    const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
-    let mut rate_tracker: HashMap<SocketAddr, Vec<Instant>> = HashMap::new();
-    let mut seq_tracker: HashMap<SocketAddr, Vec<(u32, Instant)>> = HashMap::new();
-
-    loop {
-        let (len, addr) = socket
-            .recv_from(&mut buf)
-            .await
-            .map_err(|e| error::AppError::Udp(format!("Failed to receive UDP packet: {}", e)))?;
-
-        // Validate message length before processing
-        const MAX_UDP_MESSAGE_LEN: usize = 256;
-        if len > MAX_UDP_MESSAGE_LEN {
-            warn!(
-                "UDP packet from {} too large: {} bytes (max: {})",
-                addr, len, MAX_UDP_MESSAGE_LEN
-            );
-            continue;
-        }
-
-        // Rate limiting check
-        let now = Instant::now();
-        let timestamps = rate_tracker.entry(addr).or_insert_with(Vec::new);
-
-        // Remove timestamps older than the rate limit window
-        timestamps.retain(|&ts| now.duration_since(ts) < RATE_LIMIT_WINDOW);
-
-        // Check if rate limit exceeded
-        if timestamps.len() >= MAX_REQUESTS_PER_SECOND as usize {
-            warn!(
-                "UDP rate limit exceeded for {}: {} requests in last second",
-                addr,
-                timestamps.len()
-            );
-            continue;
-        }
-
-        // Record this request
-        timestamps.push(now);
-
-        // Clean up old entries from rate tracker periodically
-        if rate_tracker.len() > 100 {
-            rate_tracker.retain(|_, times| !times.is_empty());
+/// Quit the usual way on SIGINT or SIGTERM, which stops playback and saves
+/// the config; a second signal while quitting restores the terminal and
+/// exits at once
+fn spawn_signal_handler(action_tx: tokio::sync::mpsc::UnboundedSender<action::Action>) {
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_signal().await {
+            warn!("Failed to listen for signals: {}", e);
+            return;
         }
-
-        let msg = String::from_utf8_lossy(&buf[..len]).trim().to_lowercase();
-
-        let (seq, cmd) = match parse_udp_packet(&msg) {
-            Ok(packet) => packet,
-            Err(reason) => {
-                warn!("Ignoring UDP command from {}: {}", addr, reason);
-                continue;
-            }
-        };
-
-        if let Some(seq) = seq {
-            if seen_sequence(&mut seq_tracker, addr, seq, Instant::now()) {
-                send_udp_ack(&socket, addr, seq).await;
-                continue;
-            }
+        info!("Signal received, shutting down");
+        let _ = action_tx.send(action::Action::Quit);
+        if wait_for_signal().await.is_ok() {
+            tui::restore();
+            std::process::exit(130);
         }
+    });
+}
 
-        info!("Received UDP command from {}: {:?}", addr, cmd);
-
-        tx.send(cmd)
-            .await
-            .map_err(|e| error::AppError::Udp(format!("Failed to send command to app: {}", e)))?;
+#[cfg(unix)]
+async fn wait_for_signal() -> io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
 
-        if let Some(seq) = seq {
-            send_udp_ack(&socket, addr, seq).await;
-        }
+    let mut terminate = signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => result,
+        _ = terminate.recv() => Ok(()),
     }
 }
 
-fn parse_udp_packet(input: &str) -> Result<(Option<u32>, ControlCommand), String> {
-    let words = input.split_whitespace().collect::<Vec<_>>();
-    if words.is_empty() {
-        return Err("empty command".to_string());
-    }
-
-    let (seq, command_words) = if words.first() == Some(&"seq") {
-        if words.len() < 3 {
-            return Err("sequenced command missing sequence or body".to_string());
-        }
-        let seq = words[1]
-            .parse::<u32>()
-            .map_err(|_| format!("invalid sequence: {}", words[1]))?;
-        (Some(seq), &words[2..])
-    } else {
-        (None, &words[..])
-    };
-
-    let cmd = match command_words {
-        ["play"] => ControlCommand::Play,
-        ["stop"] => ControlCommand::Stop,
-        ["toggle"] => ControlCommand::Toggle,
-        ["toggle", "pause"] => ControlCommand::TogglePause,
-        ["volume", "up"] => ControlCommand::VolumeUp,
-        ["volume", "down"] => ControlCommand::VolumeDown,
-        ["volume", num] => {
-            let value = num
-                .parse::<f32>()
-                .map_err(|_| format!("invalid volume value: {}", num))?;
-            if !(0.0..=2.0).contains(&value) {
-                return Err(format!("volume value out of range (0.0-2.0): {}", value));
-            }
-            ControlCommand::SetVolume(value)
-        }
-        ["tune", "next"] => ControlCommand::TuneNext,
-        ["tune", "prev"] => ControlCommand::TunePrev,
-        ["tune", id] => {
-            if id.len() > 32
-                || !id
-                    .chars()
-                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
-            {
-                return Err(format!("invalid station ID format: {}", id));
-            }
-            ControlCommand::Tune(id.to_string())
-        }
-        ["select", "up"] => ControlCommand::SelectUp,
-        ["select", "down"] => ControlCommand::SelectDown,
-        _ => return Err(format!("unknown command: {}", input)),
-    };
-
-    Ok((seq, cmd))
+#[cfg(not(unix))]
+async fn wait_for_signal() -> io::Result<()> {
+    tokio::signal::ctrl_c().await
 }
 
-fn seen_sequence(
-    seq_tracker: &mut HashMap<SocketAddr, Vec<(u32, Instant)>>,
-    addr: SocketAddr,
-    seq: u32,
-    now: Instant,
-) -> bool {
-    const SEQ_RETENTION: Duration = Duration::from_secs(60);
-    let entries = seq_tracker.entry(addr).or_default();
-    entries.retain(|(_, seen_at)| now.duration_since(*seen_at) < SEQ_RETENTION);
-    if entries.iter().any(|(seen_seq, _)| *seen_seq == seq) {
-        return true;
+/// What the command line asks to play, as commands for a running instance
+fn forwarded_commands(cli: &Cli) -> Vec<String> {
+    let mut commands = Vec::new();
+    if let Some(station) = &cli.station {
+        commands.push(format!("tune {}", station));
     }
-    entries.push((seq, now));
-    false
-}
-
-async fn send_udp_ack(socket: &tokio::net::UdpSocket, addr: SocketAddr, seq: u32) {
-    let ack = format!("ok {}", seq);
-    if let Err(e) = socket.send_to(ack.as_bytes(), addr).await {
-        warn!("Failed to send UDP ack to {}: {}", addr, e);
+    if let Some(url) = &cli.url {
+        commands.push(format!("tune url {}", url));
     }
+    commands
 }
 
-#[cfg(test)]
-mod udp_tests {
-    use super::*;
-
-    #[test]
-    fn parses_plain_toggle_play_stop() {
-        assert_eq!(
-            parse_udp_packet("toggle").unwrap(),
-            (None, ControlCommand::Toggle)
-        );
-    }
-
-    #[test]
-    fn parses_sequenced_toggle_pause() {
-        assert_eq!(
-            parse_udp_packet("seq 42 toggle pause").unwrap(),
-            (Some(42), ControlCommand::TogglePause)
-        );
-    }
+/// Hand `commands` to the running instance instead of starting a second one
+async fn forward_to_running_instance(
+    running: instance::RunningInstance,
+    remote: &config::RemoteConfig,
+    commands: &[String],
+) -> color_eyre::eyre::Result<()> {
+    let Some(port) = running.udp_port else {
+        return Err(color_eyre::eyre::eyre!(
+            "Another instance is running without UDP control. Start it with --listen to hand it stations, or use --force-new to start anyway"
+        ));
+    };
+    // Senders without a token may not be allowed to change the station
+    instance::forward(port, remote.strongest_token(), commands)
+        .await
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to reach the running instance: {}", e))?;
+    eprintln!("Sent to the running instance on UDP port {}.", port);
+    Ok(())
+}
 
-    #[test]
-    fn rejects_bad_volume() {
-        assert!(parse_udp_packet("volume 9.0").is_err());
-        assert!(parse_udp_packet("volume loud").is_err());
+/// Explain that somars is already running and offer to attach as a remote
+async fn handle_running_instance(
+    running: instance::RunningInstance,
+    remote: &config::RemoteConfig,
+) -> color_eyre::eyre::Result<()> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    let pid = running
+        .pid
+        .map(|pid| format!(" (pid {})", pid))
+        .unwrap_or_default();
+    eprintln!("somars is already running{}.", pid);
+
+    let Some(port) = running.udp_port else {
+        return Err(color_eyre::eyre::eyre!(
+            "Another instance is running without UDP control. Start it with --listen to control it remotely, or use --force-new to start anyway"
+        ));
+    };
+    if !io::stdin().is_terminal() {
+        return Err(color_eyre::eyre::eyre!(
+            "Another instance is running. Send it commands with `somars --port {} --broadcast <CMD>`, or use --force-new to start anyway",
+            port
+        ));
     }
 
-    #[test]
-    fn detects_duplicate_sequence() {
-        let addr = "127.0.0.1:8069".parse().unwrap();
-        let mut tracker = HashMap::new();
-        let now = Instant::now();
-        assert!(!seen_sequence(&mut tracker, addr, 7, now));
-        assert!(seen_sequence(&mut tracker, addr, 7, now));
+    eprint!("Attach to it as a remote control? [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        instance::attach(port, remote.strongest_token()).await?;
     }
+    Ok(())
 }
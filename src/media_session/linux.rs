@@ -324,7 +324,7 @@ fn build_metadata(station: &Station, track_title: Option<&str>) -> Metadata {
         .trackid(track_id)
         .title(track_title.unwrap_or(&station.title))
         .album(&station.title)
-        .url(&station.url);
+        .url(station.link());
 
     if !station.dj.is_empty() {
         builder = builder.artist([station.dj.clone()]);
@@ -353,6 +353,11 @@ mod tests {
             url: "https://ice1.somafm.com/groovesalad-128-mp3".to_string(),
             image: "https://somafm.com/img/groovesalad120.png".to_string(),
             last_playing: String::new(),
+            listeners: 0,
+            playlist: String::new(),
+            low_bitrate_playlist: String::new(),
+            playlists: Vec::new(),
+            source: String::new(),
         }
     }
 
@@ -1,5 +1,4 @@
 use crate::{action::Action, station::Station, PlaybackState};
-use anyhow::{bail, Context};
 use dispatch::Queue;
 use mediaplayer::prelude::{
     Artwork, CommandEvent, CommandToken, HandlerStatus, NowPlayingInfo, NowPlayingInfoCenter,
@@ -7,15 +6,12 @@ use mediaplayer::prelude::{
 };
 use std::{
     cell::RefCell,
-    collections::{hash_map::DefaultHasher, HashSet},
-    hash::{Hash, Hasher},
+    collections::HashSet,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 use tracing::warn;
 
-const MAX_ARTWORK_BYTES: u64 = 10 * 1024 * 1024;
-
 #[derive(Clone, Debug)]
 struct SessionState {
     playback_state: PlaybackState,
@@ -82,7 +78,7 @@ impl MediaSessionHandle {
 
     fn set_station_metadata(&self, station: Station, track_title: Option<String>) {
         let artwork_url = station.image.trim().to_string();
-        let cached_path = cached_artwork_path(&artwork_url);
+        let cached_path = crate::artwork::cached(&artwork_url);
         let snapshot = {
             let Ok(mut state) = self.state.lock() else {
                 return;
@@ -125,7 +121,7 @@ impl MediaSessionHandle {
         let state = Arc::clone(&self.state);
         let requests = Arc::clone(&self.artwork_requests);
         runtime.spawn(async move {
-            let result = download_artwork(&artwork_url).await;
+            let result = crate::artwork::download(&artwork_url).await;
             if let Ok(mut requests) = requests.lock() {
                 requests.remove(&artwork_url);
             }
@@ -251,7 +247,7 @@ fn publish_metadata(
         .album_title(&station.title)
         .live_stream(true)
         .media_type(NowPlayingMediaType::Audio)
-        .asset_url(&station.url)
+        .asset_url(station.link())
         .service_identifier("somars")
         .default_playback_rate(1.0)
         .playback_rate(if matches!(playback_state, PlaybackState::Playing) {
@@ -283,68 +279,6 @@ fn publish_metadata(
         .set_now_playing_info_with_artwork(&info, session.artwork.as_ref());
 }
 
-fn cached_artwork_path(url: &str) -> Option<PathBuf> {
-    if url.is_empty() {
-        return None;
-    }
-    let path = artwork_cache_path(url);
-    std::fs::metadata(&path)
-        .is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0)
-        .then_some(path)
-}
-
-fn artwork_cache_path(url: &str) -> PathBuf {
-    let mut hasher = DefaultHasher::new();
-    url.hash(&mut hasher);
-    dirs::cache_dir()
-        .unwrap_or_else(std::env::temp_dir)
-        .join("somars")
-        .join("artwork")
-        .join(format!("{:016x}", hasher.finish()))
-}
-
-async fn download_artwork(url: &str) -> anyhow::Result<PathBuf> {
-    let path = artwork_cache_path(url);
-    if std::fs::metadata(&path).is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0) {
-        return Ok(path);
-    }
-
-    let response = reqwest::get(url)
-        .await
-        .with_context(|| format!("request failed: {url}"))?
-        .error_for_status()
-        .with_context(|| format!("server rejected artwork: {url}"))?;
-    if response
-        .content_length()
-        .is_some_and(|length| length > MAX_ARTWORK_BYTES)
-    {
-        bail!("artwork exceeds {MAX_ARTWORK_BYTES} bytes");
-    }
-    let bytes = response
-        .bytes()
-        .await
-        .with_context(|| format!("failed to read artwork: {url}"))?;
-    if bytes.is_empty() {
-        bail!("artwork response is empty");
-    }
-    if bytes.len() as u64 > MAX_ARTWORK_BYTES {
-        bail!("artwork exceeds {MAX_ARTWORK_BYTES} bytes");
-    }
-
-    let parent = path.parent().context("artwork cache has no parent")?;
-    tokio::fs::create_dir_all(parent)
-        .await
-        .context("failed to create artwork cache")?;
-    let temporary_path = path.with_extension(format!("tmp-{}", std::process::id()));
-    tokio::fs::write(&temporary_path, &bytes)
-        .await
-        .context("failed to write artwork cache")?;
-    tokio::fs::rename(&temporary_path, &path)
-        .await
-        .context("failed to commit artwork cache")?;
-    Ok(path)
-}
-
 fn to_native_playback_state(state: &PlaybackState) -> NativePlaybackState {
     match state {
         PlaybackState::Playing => NativePlaybackState::Playing,
@@ -0,0 +1,126 @@
+//! Mock Icecast streams
+//!
+//! The soak test, the demo mode and the integration tests serve endless WAV
+//! streams with ICY titles from a local server, so they play without the
+//! network. This module has the parts they share: reading the request, the
+//! response headers, the WAV header and the metadata framing.
+
+use std::io;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+/// 8 kHz 16-bit mono PCM, 128 kbit/s
+pub const SAMPLE_RATE: u32 = 8_000;
+pub const BYTES_PER_SECOND: usize = SAMPLE_RATE as usize * 2;
+/// One metadata block per second of audio
+pub const METAINT: usize = BYTES_PER_SECOND;
+
+/// Read the HTTP request and return its path without the leading slash,
+/// `None` if the client went away first
+pub async fn read_request_path(socket: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = socket.read(&mut buffer).await?;
+        if read == 0 || request.len() > 16 * 1024 {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&request)
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or("/")
+            .trim_start_matches('/')
+            .to_string(),
+    ))
+}
+
+/// Response headers of a stream called `name`
+pub fn response_header(name: &str) -> String {
+    format!(
+        "HTTP/1.0 200 OK\r\nContent-Type: audio/wav\r\nicy-name: {}\r\nicy-br: 128\r\nicy-metaint: {}\r\n\r\n",
+        name, METAINT
+    )
+}
+
+/// Canonical WAV header with the largest data size, for a stream that doesn't end
+pub fn wav_header() -> Vec<u8> {
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&u32::MAX.to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    header.extend_from_slice(&1u16.to_le_bytes()); // mono
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(BYTES_PER_SECOND as u32).to_le_bytes());
+    header.extend_from_slice(&2u16.to_le_bytes()); // block align
+    header.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&(u32::MAX - 36).to_le_bytes());
+    header
+}
+
+/// Inserts an ICY metadata block every `metaint` bytes of audio
+pub struct IcyFramer {
+    metaint: usize,
+    /// Audio bytes since the last metadata block
+    since_metadata: usize,
+    /// Title sent in the last block, so unchanged titles go out as empty blocks
+    last_title: String,
+}
+
+impl IcyFramer {
+    pub fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            since_metadata: 0,
+            last_title: String::new(),
+        }
+    }
+
+    pub fn frame(&mut self, mut audio: &[u8], title: &str) -> Vec<u8> {
+        let mut framed = Vec::with_capacity(audio.len() + 64);
+        while !audio.is_empty() {
+            let take = audio.len().min(self.metaint - self.since_metadata);
+            framed.extend_from_slice(&audio[..take]);
+            audio = &audio[take..];
+            self.since_metadata += take;
+            if self.since_metadata == self.metaint {
+                self.since_metadata = 0;
+                if title == self.last_title {
+                    framed.push(0);
+                } else {
+                    self.last_title = title.to_string();
+                    let mut block = format!("StreamTitle='{}';", title).into_bytes();
+                    block.resize(block.len().div_ceil(16) * 16, 0);
+                    framed.push((block.len() / 16) as u8);
+                    framed.extend_from_slice(&block);
+                }
+            }
+        }
+        framed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_icy_metadata() {
+        let mut framer = IcyFramer::new(4);
+        let framed = framer.frame(&[1, 2, 3, 4, 5, 6], "A - B");
+        assert_eq!(&framed[..4], &[1, 2, 3, 4]);
+        // "StreamTitle='A - B';" is 20 bytes, padded to two 16-byte blocks
+        assert_eq!(framed[4], 2);
+        assert!(framed[5..].starts_with(b"StreamTitle='A - B';\0"));
+        assert_eq!(&framed[37..], &[5, 6]);
+
+        // The same title again is an empty block
+        let framed = framer.frame(&[7, 8, 9], "A - B");
+        assert_eq!(framed, vec![7, 8, 0, 9]);
+    }
+}
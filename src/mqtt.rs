@@ -0,0 +1,436 @@
+//! MQTT integration for home automation
+//!
+//! Subscribes to `<prefix>/command` for remote control and publishes retained
+//! playback state to `<prefix>/state`, `<prefix>/station`, `<prefix>/title`,
+//! `<prefix>/volume` and `<prefix>/availability`. The spoken station names
+//! accepted by `tune` are published to `<prefix>/stations` as JSON.
+//!
+//! Command payloads use the same syntax as the UDP interface (`play`, `stop`,
+//! `volume 0.5`, `tune groovesalad`, ...), including the `token` prefix. A
+//! bare volume level can also be sent to `<prefix>/volume/set`, with the
+//! permission of senders without a token.
+//!
+//! When discovery is enabled, a Home Assistant discovery message is published
+//! so somars shows up as a `media_player` entity, and all state is mirrored as
+//! JSON to `<prefix>/attributes`.
+
+use crate::{
+    action::Action, config::MqttConfig, control::Permissions, station::Station, PlaybackState,
+};
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+enum Update {
+    PlaybackState(PlaybackState),
+    Station(Box<Station>),
+    TrackTitle(String),
+    Volume(f32),
+    VoiceNames(BTreeMap<String, String>),
+}
+
+/// Handle for publishing application state to the MQTT broker
+#[derive(Clone, Debug)]
+pub struct MqttHandle {
+    update_tx: Option<mpsc::UnboundedSender<Update>>,
+}
+
+impl MqttHandle {
+    /// Connect to the broker if MQTT is enabled in the configuration.
+    pub fn start(
+        config: &MqttConfig,
+        permissions: Permissions,
+        action_tx: mpsc::UnboundedSender<Action>,
+    ) -> Self {
+        if !config.enabled {
+            return Self { update_tx: None };
+        }
+
+        let (update_tx, update_rx) = mpsc::unbounded_channel();
+        let config = config.clone();
+        tokio::spawn(async move {
+            run(config, permissions, action_tx, update_rx).await;
+        });
+        Self {
+            update_tx: Some(update_tx),
+        }
+    }
+
+    pub fn set_playback_state(&self, state: PlaybackState) {
+        self.send(Update::PlaybackState(state));
+    }
+
+    pub fn set_station(&self, station: Station) {
+        self.send(Update::Station(Box::new(station)));
+    }
+
+    pub fn set_track_title(&self, title: String) {
+        self.send(Update::TrackTitle(title));
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.send(Update::Volume(volume));
+    }
+
+    /// Publish the spoken names `tune` accepts, for voice assistant routines
+    pub fn set_voice_names(&self, names: BTreeMap<String, String>) {
+        self.send(Update::VoiceNames(names));
+    }
+
+    fn send(&self, update: Update) {
+        if let Some(update_tx) = &self.update_tx {
+            let _ = update_tx.send(update);
+        }
+    }
+}
+
+/// Topic names derived from the configured prefix
+#[derive(Debug, Clone, PartialEq)]
+struct Topics {
+    command: String,
+    state: String,
+    station: String,
+    title: String,
+    volume: String,
+    volume_set: String,
+    stations: String,
+    availability: String,
+    attributes: String,
+    /// Home Assistant discovery config topic, if discovery is enabled
+    discovery: Option<String>,
+}
+
+impl Topics {
+    fn new(config: &MqttConfig) -> Self {
+        let prefix = config.topic_prefix.trim_end_matches('/');
+        let discovery = config.discovery.then(|| {
+            format!(
+                "{}/media_player/{}/config",
+                config.discovery_prefix.trim_end_matches('/'),
+                object_id(&config.client_id)
+            )
+        });
+        Self {
+            command: format!("{}/command", prefix),
+            state: format!("{}/state", prefix),
+            station: format!("{}/station", prefix),
+            title: format!("{}/title", prefix),
+            volume: format!("{}/volume", prefix),
+            volume_set: format!("{}/volume/set", prefix),
+            stations: format!("{}/stations", prefix),
+            availability: format!("{}/availability", prefix),
+            attributes: format!("{}/attributes", prefix),
+            discovery,
+        }
+    }
+
+    /// Map a state update to its status topic and payload
+    fn status_message(&self, update: &Update) -> (&str, String) {
+        match update {
+            Update::PlaybackState(state) => {
+                (&self.state, playback_state_payload(state).to_string())
+            }
+            Update::Station(station) => (&self.station, station.id.clone()),
+            Update::TrackTitle(title) => (&self.title, title.clone()),
+            Update::Volume(volume) => (&self.volume, format!("{:.2}", volume)),
+            Update::VoiceNames(names) => (
+                &self.stations,
+                serde_json::to_string(names).unwrap_or_default(),
+            ),
+        }
+    }
+}
+
+/// Now-playing attributes mirrored to `<prefix>/attributes`
+#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+struct Attributes {
+    state: Option<&'static str>,
+    station_id: Option<String>,
+    station_title: Option<String>,
+    genre: Option<String>,
+    title: Option<String>,
+    volume: Option<f32>,
+}
+
+impl Attributes {
+    fn apply(&mut self, update: &Update) {
+        match update {
+            Update::PlaybackState(state) => self.state = Some(playback_state_payload(state)),
+            Update::Station(station) => {
+                self.station_id = Some(station.id.clone());
+                self.station_title = Some(station.title.clone());
+                self.genre = Some(station.genre.clone());
+                self.title = None;
+            }
+            Update::TrackTitle(title) => self.title = Some(title.clone()),
+            Update::Volume(volume) => self.volume = Some(*volume),
+            Update::VoiceNames(_) => {}
+        }
+    }
+}
+
+/// Home Assistant discovery payload for the "MQTT Media Player" integration
+fn discovery_payload(config: &MqttConfig, topics: &Topics) -> serde_json::Value {
+    let id = object_id(&config.client_id);
+    serde_json::json!({
+        "name": "somars",
+        "unique_id": id,
+        "availability_topic": topics.availability,
+        "json_attributes_topic": topics.attributes,
+        "state_state_topic": topics.state,
+        "state_title_topic": topics.title,
+        "state_album_topic": topics.station,
+        "state_volume_topic": topics.volume,
+        "command_volume_topic": topics.volume_set,
+        "command_play_topic": topics.command,
+        "command_play_payload": "play",
+        "command_pause_topic": topics.command,
+        "command_pause_payload": "toggle pause",
+        "command_playpause_topic": topics.command,
+        "command_playpause_payload": "toggle pause",
+        "command_stop_topic": topics.command,
+        "command_stop_payload": "stop",
+        "command_next_topic": topics.command,
+        "command_next_payload": "tune next",
+        "command_previous_topic": topics.command,
+        "command_previous_payload": "tune prev",
+        "device": {
+            "identifiers": [id],
+            "name": "somars",
+            "manufacturer": "somars",
+            "model": env!("CARGO_PKG_DESCRIPTION"),
+            "sw_version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// Home Assistant object IDs may only contain `[a-zA-Z0-9_-]`
+fn object_id(client_id: &str) -> String {
+    client_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn playback_state_payload(state: &PlaybackState) -> &'static str {
+    match state {
+        PlaybackState::Playing => "playing",
+        PlaybackState::Paused => "paused",
+        PlaybackState::Stopped => "stopped",
+    }
+}
+
+fn mqtt_options(config: &MqttConfig, topics: &Topics) -> MqttOptions {
+    let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        &topics.availability,
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &config.username {
+        options.set_credentials(username, config.password.clone().unwrap_or_default());
+    }
+    options
+}
+
+async fn run(
+    config: MqttConfig,
+    permissions: Permissions,
+    action_tx: mpsc::UnboundedSender<Action>,
+    mut update_rx: mpsc::UnboundedReceiver<Update>,
+) {
+    let topics = Topics::new(&config);
+    let (client, mut eventloop) = AsyncClient::new(mqtt_options(&config, &topics), 32);
+
+    let publisher = client.clone();
+    let publish_topics = topics.clone();
+    tokio::spawn(async move {
+        let mut attributes = Attributes::default();
+        while let Some(update) = update_rx.recv().await {
+            attributes.apply(&update);
+            let (topic, payload) = publish_topics.status_message(&update);
+            let attributes_json = serde_json::to_string(&attributes).unwrap_or_default();
+            let result = match publisher
+                .publish(topic, QoS::AtLeastOnce, true, payload)
+                .await
+            {
+                Ok(()) => {
+                    publisher
+                        .publish(
+                            &publish_topics.attributes,
+                            QoS::AtLeastOnce,
+                            true,
+                            attributes_json,
+                        )
+                        .await
+                }
+                Err(error) => Err(error),
+            };
+            if let Err(error) = result {
+                warn!(%error, "Failed to publish MQTT status");
+                break;
+            }
+        }
+    });
+
+    loop {
+        match eventloop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!("Connected to MQTT broker {}:{}", config.host, config.port);
+                // Subscriptions do not survive a clean-session reconnect.
+                let _ = client.try_subscribe(&topics.command, QoS::AtLeastOnce);
+                let _ = client.try_subscribe(&topics.volume_set, QoS::AtLeastOnce);
+                if let Some(discovery) = &topics.discovery {
+                    let payload = discovery_payload(&config, &topics).to_string();
+                    let _ = client.try_publish(discovery, QoS::AtLeastOnce, true, payload);
+                }
+                let _ = client.try_publish(&topics.availability, QoS::AtLeastOnce, true, "online");
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == topics.volume_set => {
+                let level = String::from_utf8_lossy(&publish.payload).trim().to_string();
+                match permissions.authorize(&format!("volume {}", level)) {
+                    Ok((_, command)) => {
+                        if action_tx.send(Action::from(command)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(reason) => warn!("Ignoring MQTT volume: {}", reason),
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == topics.command => {
+                let payload = String::from_utf8_lossy(&publish.payload).trim().to_string();
+                match permissions.authorize(&payload) {
+                    Ok((_, command)) => {
+                        info!("Received MQTT command: {:?}", command);
+                        if action_tx.send(Action::from(command)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(reason) => warn!("Ignoring MQTT command: {}", reason),
+                }
+            }
+            Ok(_) => {}
+            Err(error) => {
+                warn!(%error, "MQTT connection error, retrying");
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> MqttConfig {
+        MqttConfig {
+            enabled: true,
+            ..MqttConfig::default()
+        }
+    }
+
+    #[test]
+    fn topics_use_prefix() {
+        let topics = Topics::new(&MqttConfig {
+            topic_prefix: "home/somars/".to_string(),
+            ..config()
+        });
+        assert_eq!(topics.command, "home/somars/command");
+        assert_eq!(topics.availability, "home/somars/availability");
+        assert_eq!(
+            topics.discovery.as_deref(),
+            Some("homeassistant/media_player/somars/config")
+        );
+    }
+
+    #[test]
+    fn discovery_can_be_disabled() {
+        let topics = Topics::new(&MqttConfig {
+            discovery: false,
+            ..config()
+        });
+        assert!(topics.discovery.is_none());
+    }
+
+    #[test]
+    fn discovery_payload_references_topics() {
+        let config = MqttConfig {
+            client_id: "somars living room".to_string(),
+            ..config()
+        };
+        let topics = Topics::new(&config);
+        let payload = discovery_payload(&config, &topics);
+        assert_eq!(payload["unique_id"], "somars_living_room");
+        assert_eq!(payload["state_state_topic"], "somars/state");
+        assert_eq!(payload["command_volume_topic"], "somars/volume/set");
+        assert_eq!(payload["command_stop_payload"], "stop");
+    }
+
+    #[test]
+    fn attributes_track_latest_state() {
+        let mut attributes = Attributes::default();
+        attributes.apply(&Update::TrackTitle("Old".to_string()));
+        attributes.apply(&Update::Station(Box::new(Station {
+            id: "groovesalad".to_string(),
+            title: "Groove Salad".to_string(),
+            description: String::new(),
+            dj: String::new(),
+            genre: "ambient".to_string(),
+            url: String::new(),
+            image: String::new(),
+            last_playing: String::new(),
+            listeners: 0,
+            playlist: String::new(),
+            low_bitrate_playlist: String::new(),
+            playlists: Vec::new(),
+            source: String::new(),
+        })));
+        attributes.apply(&Update::Volume(0.5));
+        assert_eq!(attributes.station_id.as_deref(), Some("groovesalad"));
+        assert_eq!(attributes.title, None);
+        assert_eq!(attributes.volume, Some(0.5));
+    }
+
+    #[test]
+    fn status_message_formats_payloads() {
+        let topics = Topics::new(&config());
+        assert_eq!(
+            topics.status_message(&Update::PlaybackState(PlaybackState::Paused)),
+            ("somars/state", "paused".to_string())
+        );
+        assert_eq!(
+            topics.status_message(&Update::Volume(0.5)),
+            ("somars/volume", "0.50".to_string())
+        );
+        let names = BTreeMap::from([("groove salad".to_string(), "groovesalad".to_string())]);
+        assert_eq!(
+            topics.status_message(&Update::VoiceNames(names)),
+            (
+                "somars/stations",
+                r#"{"groove salad":"groovesalad"}"#.to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn disabled_handle_ignores_updates() {
+        let (action_tx, _action_rx) = mpsc::unbounded_channel();
+        let handle = MqttHandle::start(&MqttConfig::default(), Permissions::default(), action_tx);
+        handle.set_volume(1.0);
+        assert!(handle.update_tx.is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//! Prefetching the station the selection rests on
+//!
+//! Once the selection has stayed on a station for a moment, the app is sent
+//! [`Action::HoverStation`] and fetches the station's artwork, playlist and
+//! recent songs in the background, so they are there when it is tuned.
+//! Scrolling past stations fetches nothing.
+
+use crate::action::Action;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+/// How long the selection rests on a station before it is prefetched
+pub const HOVER_DELAY: Duration = Duration::from_secs(1);
+
+/// Waits for the selection to rest, and remembers what was prefetched
+#[derive(Debug, Default)]
+pub struct HoverPrefetch {
+    /// Sends `HoverStation` once the delay is over
+    task: Option<JoinHandle<()>>,
+    /// Stations prefetched this session
+    prefetched: HashSet<String>,
+}
+
+impl HoverPrefetch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The selection moved to `station_id`, or to nothing
+    ///
+    /// Any earlier wait is dropped. A station prefetched already is only
+    /// waited for again when `wants_songs` says its recent songs are due.
+    pub fn selected(
+        &mut self,
+        station_id: Option<&str>,
+        wants_songs: bool,
+        action_tx: &UnboundedSender<Action>,
+    ) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        let Some(station_id) = station_id else {
+            return;
+        };
+        if self.prefetched.contains(station_id) && !wants_songs {
+            return;
+        }
+        let station_id = station_id.to_string();
+        let action_tx = action_tx.clone();
+        self.task = Some(tokio::spawn(async move {
+            tokio::time::sleep(HOVER_DELAY).await;
+            let _ = action_tx.send(Action::HoverStation(station_id));
+        }));
+    }
+
+    /// Whether `station_id`'s artwork and playlist still need fetching; only
+    /// true the first time
+    pub fn start(&mut self, station_id: &str) -> bool {
+        self.prefetched.insert(station_id.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_for_the_selection_to_rest() {
+        let (action_tx, mut actions) = mpsc::unbounded_channel();
+        let mut prefetch = HoverPrefetch::new();
+        let rest = |time| tokio::time::sleep(time);
+
+        // Scrolling on before the delay is over drops the earlier station
+        prefetch.selected(Some("groovesalad"), false, &action_tx);
+        rest(HOVER_DELAY - Duration::from_millis(100)).await;
+        prefetch.selected(Some("dronezone"), false, &action_tx);
+        rest(HOVER_DELAY - Duration::from_millis(100)).await;
+        assert!(actions.try_recv().is_err());
+        rest(Duration::from_millis(200)).await;
+        assert_eq!(
+            actions.try_recv().ok(),
+            Some(Action::HoverStation("dronezone".to_string()))
+        );
+        assert!(prefetch.start("dronezone"));
+        assert!(!prefetch.start("dronezone"));
+
+        // A prefetched station is skipped until its songs are due again
+        prefetch.selected(Some("dronezone"), false, &action_tx);
+        rest(HOVER_DELAY * 2).await;
+        assert!(actions.try_recv().is_err());
+        prefetch.selected(Some("dronezone"), true, &action_tx);
+        rest(HOVER_DELAY * 2).await;
+        assert_eq!(
+            actions.try_recv().ok(),
+            Some(Action::HoverStation("dronezone".to_string()))
+        );
+
+        // Selecting nothing stops the wait
+        prefetch.selected(Some("fluid"), false, &action_tx);
+        prefetch.selected(None, false, &action_tx);
+        rest(HOVER_DELAY * 2).await;
+        assert!(actions.try_recv().is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Car-radio style station presets
+//!
+//! Nine slots hold station IDs in the `[presets]` config table. `1`–`9` tune a
+//! slot and `Shift` with the same key stores the selected station in it.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Number of preset slots
+pub const SLOTS: u8 = 9;
+
+/// Symbols on the US layout's shifted digit keys, for terminals that report
+/// `Shift+1` as `!`
+const SHIFTED_DIGITS: [char; 9] = ['!', '@', '#', '$', '%', '^', '&', '*', '('];
+
+/// What a key press does to a preset slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetKey {
+    Tune(u8),
+    Assign(u8),
+}
+
+impl PresetKey {
+    /// The preset action for `key`, if it is a digit or shifted digit key
+    pub fn from_key(key: &KeyEvent) -> Option<Self> {
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        if let Some(slot) = c.to_digit(10).and_then(|digit| slot(digit as u8)) {
+            return Some(if key.modifiers.contains(KeyModifiers::SHIFT) {
+                Self::Assign(slot)
+            } else {
+                Self::Tune(slot)
+            });
+        }
+        SHIFTED_DIGITS
+            .iter()
+            .position(|shifted| *shifted == c)
+            .map(|index| Self::Assign(index as u8 + 1))
+    }
+}
+
+/// `number` if it names a preset slot
+pub fn slot(number: u8) -> Option<u8> {
+    (1..=SLOTS).contains(&number).then_some(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), modifiers)
+    }
+
+    #[test]
+    fn maps_digit_keys_to_presets() {
+        assert_eq!(
+            PresetKey::from_key(&key('1', KeyModifiers::NONE)),
+            Some(PresetKey::Tune(1))
+        );
+        assert_eq!(
+            PresetKey::from_key(&key('9', KeyModifiers::SHIFT)),
+            Some(PresetKey::Assign(9))
+        );
+        assert_eq!(
+            PresetKey::from_key(&key('#', KeyModifiers::SHIFT)),
+            Some(PresetKey::Assign(3))
+        );
+        assert_eq!(PresetKey::from_key(&key('0', KeyModifiers::NONE)), None);
+        assert_eq!(PresetKey::from_key(&key('q', KeyModifiers::NONE)), None);
+    }
+}
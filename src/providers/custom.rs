@@ -0,0 +1,72 @@
+//! Stations listed in the config file
+
+use super::StationProvider;
+use crate::config::CustomStation;
+use crate::error::AppError;
+use crate::station::Station;
+use futures::future::BoxFuture;
+
+const NAME: &str = "Custom";
+
+/// Stations from `[[custom_stations]]` entries in the config file
+pub struct CustomProvider {
+    stations: Vec<CustomStation>,
+}
+
+impl CustomProvider {
+    pub fn new(stations: Vec<CustomStation>) -> Self {
+        Self { stations }
+    }
+}
+
+impl StationProvider for CustomProvider {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        let stations = self.stations.iter().map(to_station).collect();
+        Box::pin(async move { Ok(stations) })
+    }
+}
+
+fn to_station(custom: &CustomStation) -> Station {
+    Station {
+        id: custom.id.clone(),
+        title: custom.title.clone(),
+        description: custom.description.clone(),
+        dj: String::new(),
+        genre: custom.genre.clone(),
+        url: custom.url.clone(),
+        image: custom.image.clone(),
+        last_playing: String::new(),
+        listeners: 0,
+        playlist: String::new(),
+        low_bitrate_playlist: String::new(),
+        playlists: Vec::new(),
+        source: NAME.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_config_entries() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+            [[custom_stations]]
+            id = "local"
+            title = "Local Radio"
+            url = "http://radio.local:8000/live"
+            "#,
+        )
+        .unwrap();
+        let station = to_station(&config.custom_stations[0]);
+        assert_eq!(station.id, "local");
+        assert_eq!(station.url, "http://radio.local:8000/live");
+        assert_eq!(station.source, "Custom");
+        assert!(station.genre.is_empty());
+    }
+}
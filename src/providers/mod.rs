@@ -0,0 +1,222 @@
+//! Station providers
+//!
+//! Each [`StationProvider`] contributes stations from one source: the SomaFM
+//! catalog, stations listed in the config file, a Radio Browser search or a
+//! stream URL given on the command line. The [`ProviderRegistry`] queries all
+//! of them and merges the results in registration order.
+
+mod custom;
+mod radio_browser;
+mod somafm;
+mod url;
+
+pub use custom::CustomProvider;
+pub use radio_browser::RadioBrowserProvider;
+pub use somafm::{
+    fetch_recent_songs, spawn_live_status_refresh, RecentSong, SomaFmProvider, NAME as SOMAFM,
+};
+pub use url::UrlProvider;
+
+use crate::action::Action;
+use crate::catalog_cache::CatalogCache;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::station::Station;
+use futures::future::BoxFuture;
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How often an unreachable catalog is retried
+const OFFLINE_RETRY: Duration = Duration::from_secs(60);
+
+/// A source of stations
+pub trait StationProvider: Send + Sync {
+    /// Human-readable source name, shown next to its stations
+    fn name(&self) -> &str;
+
+    /// Fetch the provider's stations
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>>;
+
+    /// Whether the last fetch could not reach the source
+    fn offline(&self) -> bool {
+        false
+    }
+}
+
+/// Ordered collection of station providers
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: Vec<Box<dyn StationProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Build the registry from the configuration
+    ///
+    /// With `refresh`, the SomaFM catalog is downloaded even if the cached copy
+    /// is still fresh.
+    pub fn from_config(config: &Config, refresh: bool) -> Self {
+        let mut registry = Self::default();
+        registry.register(SomaFmProvider::new(
+            config.api.clone(),
+            config.stream_quality,
+            CatalogCache::new(config.catalog_cache_ttl_minutes, refresh),
+        ));
+        if !config.custom_stations.is_empty() {
+            registry.register(CustomProvider::new(config.custom_stations.clone()));
+        }
+        if config.radio_browser.enabled {
+            registry.register(RadioBrowserProvider::new(config.radio_browser.clone()));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, provider: impl StationProvider + 'static) {
+        self.providers.push(Box::new(provider));
+    }
+
+    /// Fetch stations from all providers concurrently
+    ///
+    /// Stations are grouped by provider in registration order; when IDs clash
+    /// the first provider wins. Failing providers are reported by name and
+    /// don't prevent the others from loading.
+    pub async fn fetch_all(&self) -> (Vec<Station>, Vec<(String, AppError)>) {
+        let results =
+            futures::future::join_all(self.providers.iter().map(|provider| provider.fetch())).await;
+
+        let mut seen = HashSet::new();
+        let mut stations = Vec::new();
+        let mut errors = Vec::new();
+        for (provider, result) in self.providers.iter().zip(results) {
+            match result {
+                Ok(fetched) => stations.extend(
+                    fetched
+                        .into_iter()
+                        .filter(|station| seen.insert(station.id.clone())),
+                ),
+                Err(error) => errors.push((provider.name().to_string(), error)),
+            }
+        }
+        (stations, errors)
+    }
+
+    /// Whether any provider could not reach its source on the last fetch
+    pub fn offline(&self) -> bool {
+        self.providers.iter().any(|provider| provider.offline())
+    }
+
+    /// Fetch all stations in the background and send them to the app
+    ///
+    /// While offline, the fetch is retried periodically until the sources can
+    /// be reached again, and the app gets the fresh list once they can. The
+    /// retries stop once the app is gone, or when the returned handle aborts
+    /// them for a newer fetch.
+    pub fn spawn_fetch(self, action_tx: mpsc::UnboundedSender<Action>) -> tokio::task::AbortHandle {
+        tokio::spawn(async move {
+            let (stations, errors) = self.fetch_all().await;
+            for (provider, e) in &errors {
+                let _ = action_tx.send(Action::Error(format!(
+                    "Error loading stations from {}: {}",
+                    provider, e
+                )));
+            }
+            if !stations.is_empty() || errors.is_empty() {
+                let _ = action_tx.send(Action::UpdateStations(stations));
+            }
+
+            let mut offline = self.offline();
+            let _ = action_tx.send(Action::SetOffline(offline));
+            while offline && !action_tx.is_closed() {
+                tokio::time::sleep(OFFLINE_RETRY).await;
+                let (stations, errors) = self.fetch_all().await;
+                offline = self.offline();
+                if !offline {
+                    if !stations.is_empty() || errors.is_empty() {
+                        let _ = action_tx.send(Action::UpdateStations(stations));
+                    }
+                    let _ = action_tx.send(Action::SetOffline(false));
+                }
+            }
+        })
+        .abort_handle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str, Vec<&'static str>);
+
+    impl StationProvider for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+            let stations = self
+                .1
+                .iter()
+                .map(|id| Station {
+                    id: id.to_string(),
+                    source: self.0.to_string(),
+                    ..Station::from_url("http://example.com/stream")
+                })
+                .collect();
+            Box::pin(async move { Ok(stations) })
+        }
+    }
+
+    struct Failing;
+
+    impl StationProvider for Failing {
+        fn name(&self) -> &str {
+            "Broken"
+        }
+
+        fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+            Box::pin(async { Err(AppError::Station("offline".to_string())) })
+        }
+
+        fn offline(&self) -> bool {
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn merges_providers_in_order() {
+        let mut registry = ProviderRegistry::default();
+        registry.register(Fixed("First", vec!["a", "b"]));
+        registry.register(Failing);
+        registry.register(Fixed("Second", vec!["b", "c"]));
+
+        let (stations, errors) = registry.fetch_all().await;
+        let ids: Vec<(&str, &str)> = stations
+            .iter()
+            .map(|station| (station.id.as_str(), station.source.as_str()))
+            .collect();
+        assert_eq!(ids, vec![("a", "First"), ("b", "First"), ("c", "Second")]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "Broken");
+        assert!(registry.offline());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn offline_retries_stop_once_the_app_is_gone() {
+        let mut registry = ProviderRegistry::default();
+        registry.register(Failing);
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let fetch = registry.spawn_fetch(action_tx);
+
+        tokio::time::sleep(OFFLINE_RETRY * 3).await;
+        assert!(!fetch.is_finished());
+        assert!(matches!(
+            std::iter::from_fn(|| action_rx.try_recv().ok()).last(),
+            Some(Action::SetOffline(true))
+        ));
+
+        drop(action_rx);
+        tokio::time::sleep(OFFLINE_RETRY * 2).await;
+        assert!(fetch.is_finished());
+    }
+}
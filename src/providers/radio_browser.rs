@@ -0,0 +1,133 @@
+//! Stations from the Radio Browser community directory
+
+use super::StationProvider;
+use crate::config::RadioBrowserConfig;
+use crate::error::AppError;
+use crate::station::Station;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+const NAME: &str = "Radio Browser";
+
+#[derive(Debug, Deserialize)]
+struct RadioBrowserStation {
+    stationuuid: String,
+    name: String,
+    url_resolved: String,
+    #[serde(default)]
+    favicon: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    country: String,
+}
+
+/// Stations matching the `[radio_browser]` search from radio-browser.info
+pub struct RadioBrowserProvider {
+    config: RadioBrowserConfig,
+}
+
+impl RadioBrowserProvider {
+    pub fn new(config: RadioBrowserConfig) -> Self {
+        Self { config }
+    }
+
+    async fn search(&self) -> Result<Vec<Station>, AppError> {
+        let mut query = vec![
+            ("hidebroken", "true".to_string()),
+            ("order", "clickcount".to_string()),
+            ("reverse", "true".to_string()),
+            ("limit", self.config.limit.to_string()),
+        ];
+        if let Some(tag) = &self.config.tag {
+            query.push(("tag", tag.clone()));
+        }
+        if let Some(country) = &self.config.country_code {
+            query.push(("countrycode", country.clone()));
+        }
+
+        let response = crate::http::client()
+            .get(format!(
+                "{}/json/stations/search",
+                self.config.server.trim_end_matches('/')
+            ))
+            .query(&query)
+            .send()
+            .await
+            .map_err(AppError::Network)?;
+        if !response.status().is_success() {
+            return Err(AppError::Station(format!(
+                "Failed to search Radio Browser: HTTP {}",
+                response.status()
+            )));
+        }
+
+        let stations: Vec<RadioBrowserStation> =
+            response.json().await.map_err(AppError::Network)?;
+        Ok(stations.into_iter().map(to_station).collect())
+    }
+}
+
+impl StationProvider for RadioBrowserProvider {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        Box::pin(self.search())
+    }
+}
+
+fn to_station(station: RadioBrowserStation) -> Station {
+    // Short, stable ID that still fits the UDP `tune <ID>` limits
+    let id = format!(
+        "rb-{}",
+        station
+            .stationuuid
+            .chars()
+            .filter(|c| *c != '-')
+            .take(12)
+            .collect::<String>()
+    );
+    Station {
+        id,
+        title: station.name.trim().to_string(),
+        description: station.country,
+        dj: String::new(),
+        genre: station.tags.replace(',', "|"),
+        url: station.url_resolved,
+        image: station.favicon,
+        last_playing: String::new(),
+        listeners: 0,
+        playlist: String::new(),
+        low_bitrate_playlist: String::new(),
+        playlists: Vec::new(),
+        source: NAME.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_search_results() {
+        let stations: Vec<RadioBrowserStation> = serde_json::from_str(
+            r#"[{
+                "stationuuid": "9617a958-0601-11e8-ae97-52543be04c81",
+                "name": " Jazz FM ",
+                "url_resolved": "http://jazz.example/stream",
+                "favicon": "",
+                "tags": "jazz,smooth jazz",
+                "country": "United Kingdom",
+                "clickcount": 10
+            }]"#,
+        )
+        .unwrap();
+        let station = to_station(stations.into_iter().next().unwrap());
+        assert_eq!(station.id, "rb-9617a9580601");
+        assert_eq!(station.title, "Jazz FM");
+        assert_eq!(station.genre, "jazz|smooth jazz");
+        assert_eq!(station.source, "Radio Browser");
+    }
+}
@@ -0,0 +1,321 @@
+//! SomaFM channel catalog
+
+use super::StationProvider;
+use crate::action::Action;
+use crate::catalog_cache::CatalogCache;
+use crate::config::ApiConfig;
+use crate::error::AppError;
+use crate::station::{LiveStatus, Playlist, Station, StreamQuality};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+pub const NAME: &str = "SomaFM";
+
+#[derive(Debug, Deserialize)]
+struct Channel {
+    id: String,
+    title: String,
+    description: String,
+    dj: String,
+    genre: String,
+    image: String,
+    #[serde(rename = "lastPlaying")]
+    last_playing: String,
+    /// Listener count, sent as a string
+    #[serde(default)]
+    listeners: String,
+    playlists: Vec<Playlist>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelResponse {
+    channels: Vec<Channel>,
+}
+
+/// A song in a channel's `songs/<id>.json`
+#[derive(Debug, Deserialize)]
+struct Song {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    artist: String,
+    #[serde(default)]
+    album: String,
+    /// Unix time it started, sent as a string
+    #[serde(default)]
+    date: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongsResponse {
+    songs: Vec<Song>,
+}
+
+/// A song a SomaFM channel played recently
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentSong {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    /// Unix time it started, 0 when unknown
+    pub played: i64,
+}
+
+/// Stations from SomaFM's `channels.json`
+pub struct SomaFmProvider {
+    api: ApiConfig,
+    quality: StreamQuality,
+    cache: CatalogCache,
+    /// Set when the last download failed
+    offline: AtomicBool,
+}
+
+impl SomaFmProvider {
+    pub fn new(api: ApiConfig, quality: StreamQuality, cache: CatalogCache) -> Self {
+        Self {
+            api,
+            quality,
+            cache,
+            offline: AtomicBool::new(false),
+        }
+    }
+}
+
+impl StationProvider for SomaFmProvider {
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        Box::pin(load_channels(
+            &self.api,
+            self.quality,
+            &self.cache,
+            &self.offline,
+        ))
+    }
+
+    fn offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+}
+
+/// Channels from the disk cache while it is fresh, otherwise from the API,
+/// falling back to an expired copy when the download fails
+async fn load_channels(
+    api: &ApiConfig,
+    quality: StreamQuality,
+    cache: &CatalogCache,
+    offline: &AtomicBool,
+) -> Result<Vec<Station>, AppError> {
+    offline.store(false, Ordering::Relaxed);
+    if let Some(stations) = cache
+        .fresh()
+        .and_then(|body| parse_channels(&body, api, quality).ok())
+    {
+        Station::remember_streams(cache.streams());
+        return Ok(stations);
+    }
+
+    let downloaded = async {
+        let body = fetch_channels(api).await?;
+        let stations = parse_channels(&body, api, quality)?;
+        cache.store(&body);
+        Ok(stations)
+    };
+    let error = match downloaded.await {
+        Ok(stations) => return Ok(stations),
+        Err(error) => error,
+    };
+    offline.store(true, Ordering::Relaxed);
+    let Some(body) = cache.stale() else {
+        return Err(error);
+    };
+    warn!("Using the cached SomaFM catalog: {}", error);
+    Station::remember_streams(cache.streams());
+    parse_channels(&body, api, quality)
+}
+
+/// Refresh each channel's `lastPlaying` and listener count every `interval`
+/// in the background
+///
+/// Results are sent as [`Action::UpdateLiveStatus`]; failed downloads are
+/// skipped until the next round.
+pub fn spawn_live_status_refresh(
+    api: ApiConfig,
+    interval: Duration,
+    action_tx: mpsc::UnboundedSender<Action>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick fires immediately, when the catalog was just loaded
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let status = match fetch_live_status(&api).await {
+                Ok(status) => status,
+                Err(error) => {
+                    debug!("Failed to refresh lastPlaying: {}", error);
+                    continue;
+                }
+            };
+            if action_tx.send(Action::UpdateLiveStatus(status)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn fetch_live_status(api: &ApiConfig) -> Result<HashMap<String, LiveStatus>, AppError> {
+    let body = fetch_channels(api).await?;
+    let response: ChannelResponse = serde_json::from_str(&body)?;
+    Ok(response
+        .channels
+        .into_iter()
+        .map(|channel| {
+            let status = LiveStatus {
+                title: channel.title,
+                listeners: parse_listeners(&channel.listeners),
+                last_playing: channel.last_playing,
+            };
+            (channel.id, status)
+        })
+        .collect())
+}
+
+/// The songs `channel` played last, newest first
+pub async fn fetch_recent_songs(
+    api: &ApiConfig,
+    channel: &str,
+) -> Result<Vec<RecentSong>, AppError> {
+    let response = crate::http::client()
+        .get(api.songs_url(channel))
+        .send()
+        .await
+        .map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(AppError::Station(format!(
+            "Failed to fetch recent songs: HTTP {}",
+            response.status()
+        )));
+    }
+    let body = response.text().await.map_err(AppError::Network)?;
+    parse_recent_songs(&body)
+}
+
+fn parse_recent_songs(body: &str) -> Result<Vec<RecentSong>, AppError> {
+    let response: SongsResponse = serde_json::from_str(body)?;
+    Ok(response
+        .songs
+        .into_iter()
+        .map(|song| RecentSong {
+            artist: song.artist.trim().to_string(),
+            title: song.title.trim().to_string(),
+            album: song.album.trim().to_string(),
+            played: song.date.trim().parse().unwrap_or(0),
+        })
+        .collect())
+}
+
+/// SomaFM sends listener counts as strings
+fn parse_listeners(listeners: &str) -> u32 {
+    listeners.trim().parse().unwrap_or(0)
+}
+
+/// Download the raw `channels.json` body
+async fn fetch_channels(api: &ApiConfig) -> Result<String, AppError> {
+    let response = crate::http::client()
+        .get(api.channels_url())
+        .send()
+        .await
+        .map_err(AppError::Network)?;
+
+    // Check if the response is successful
+    if !response.status().is_success() {
+        return Err(AppError::Station(format!(
+            "Failed to fetch channels: HTTP {}",
+            response.status()
+        )));
+    }
+
+    response.text().await.map_err(AppError::Network)
+}
+
+fn parse_channels(
+    body: &str,
+    api: &ApiConfig,
+    quality: StreamQuality,
+) -> Result<Vec<Station>, AppError> {
+    let mut response: ChannelResponse = serde_json::from_str(body)?;
+
+    // Point playlist and artwork links at the configured mirror
+    for channel in &mut response.channels {
+        channel.image = api.resolve(&channel.image);
+        for playlist in &mut channel.playlists {
+            playlist.url = api.resolve(&playlist.url);
+        }
+    }
+
+    // Stream URLs are resolved from the playlists on first play
+    let stations = response
+        .channels
+        .into_iter()
+        .map(|channel| {
+            let mut station = Station {
+                id: channel.id,
+                title: channel.title,
+                description: channel.description,
+                dj: channel.dj,
+                genre: channel.genre,
+                url: String::new(),
+                image: channel.image,
+                last_playing: channel.last_playing,
+                listeners: parse_listeners(&channel.listeners),
+                playlist: String::new(),
+                low_bitrate_playlist: String::new(),
+                playlists: channel.playlists,
+                source: NAME.to_string(),
+            };
+            station.select_quality(quality);
+            if station.playlist.is_empty() {
+                warn!("No playlist URL found for station {}", station.id);
+            }
+            station
+        })
+        .collect();
+
+    Ok(stations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recent_songs() {
+        let songs = parse_recent_songs(
+            r#"{"id":"groovesalad","songs":[
+                {"title":"Roygbiv","artist":"Boards of Canada","album":"Music Has the Right to Children","albumart":"","date":"1709301909"},
+                {"title":" Station ID ","artist":"","album":"","date":""}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            songs[0],
+            RecentSong {
+                artist: "Boards of Canada".to_string(),
+                title: "Roygbiv".to_string(),
+                album: "Music Has the Right to Children".to_string(),
+                played: 1_709_301_909,
+            }
+        );
+        assert_eq!(songs[1].title, "Station ID");
+        assert_eq!(songs[1].played, 0);
+        assert!(parse_recent_songs("{}").is_err());
+    }
+}
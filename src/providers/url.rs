@@ -0,0 +1,28 @@
+//! A single stream given by URL
+
+use super::StationProvider;
+use crate::error::AppError;
+use crate::station::Station;
+use futures::future::BoxFuture;
+
+/// Provides the synthetic station for a stream URL passed with `--url`
+pub struct UrlProvider {
+    url: String,
+}
+
+impl UrlProvider {
+    pub fn new(url: String) -> Self {
+        Self { url }
+    }
+}
+
+impl StationProvider for UrlProvider {
+    fn name(&self) -> &str {
+        "URL"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        let station = Station::from_url(&self.url);
+        Box::pin(async move { Ok(vec![station]) })
+    }
+}
@@ -0,0 +1,428 @@
+//! Scheduled shows
+//!
+//! SomaFM announces live DJ sets and special events on its website rather
+//! than in its API, and publishes no feed of them, so there is no schedule to
+//! pull by default: nothing is shown until an iCalendar feed is set in the
+//! `[schedule]` table, such as one exported from a calendar that follows the
+//! announcements:
+//!
+//! ```toml
+//! [schedule]
+//! enabled = true
+//! url = "https://example.com/somafm-shows.ics"
+//! remind_minutes = 10   # 0 for no reminder
+//! ```
+//!
+//! An event belongs to a station when its summary, location, categories or
+//! URL name the station's title or ID as whole words, so `Groove Salad: Live
+//! DJ set` and an event with `LOCATION:groovesalad` are both shown for Groove
+//! Salad. When several stations are named, the ID wins, then the longest
+//! title, so `Groove Salad Classic: DJ set` is only on Groove Salad Classic.
+//! Single events are read; repeating rules are not expanded.
+
+use crate::action::Action;
+use crate::error::AppError;
+use crate::station::Station;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Schedule settings, read from the `[schedule]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// iCalendar feed of the shows
+    #[serde(default)]
+    pub url: String,
+    /// Minutes between downloads of the feed
+    #[serde(default = "default_refresh_minutes")]
+    pub refresh_minutes: u64,
+    /// Minutes before a show starts to remind of it; 0 for no reminder
+    #[serde(default)]
+    pub remind_minutes: u64,
+}
+
+fn default_refresh_minutes() -> u64 {
+    60
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            refresh_minutes: default_refresh_minutes(),
+            remind_minutes: 0,
+        }
+    }
+}
+
+/// A show in the schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Show {
+    pub title: String,
+    pub description: String,
+    /// Unix time it starts
+    pub start: i64,
+    /// Unix time it ends, `None` when the feed doesn't say
+    pub end: Option<i64>,
+    /// Location, categories and URL of the event, lowercased, for matching
+    /// it to a station
+    pub tags: String,
+}
+
+impl Show {
+    /// How well the show names `station`: its ID beats any title, and a
+    /// longer title beats a shorter one; `None` when it isn't named
+    fn score(&self, station: &Station) -> Option<usize> {
+        let text = words(&format!("{} {}", self.title, self.tags));
+        let id = station.id.to_lowercase();
+        if !id.is_empty() && text.contains(&id) {
+            return Some(usize::MAX);
+        }
+        let title = words(&station.title);
+        (!title.is_empty() && text.windows(title.len()).any(|window| window == title))
+            .then(|| station.title.trim().len())
+    }
+
+    /// Whether the show is on `station`, named by it and by no station in
+    /// `catalog` more closely
+    pub fn is_on(&self, station: &Station, catalog: &[Station]) -> bool {
+        let Some(score) = self.score(station) else {
+            return false;
+        };
+        catalog
+            .iter()
+            .filter(|other| other.id != station.id)
+            .all(|other| self.score(other).is_none_or(|other| other < score))
+    }
+
+    /// Whether the show has started by `now` and not ended
+    pub fn on_air(&self, now: i64) -> bool {
+        self.start <= now && self.end.is_some_and(|end| now < end)
+    }
+}
+
+/// The station in `catalog` the show is on, the one it names most closely
+pub fn station_of<'a>(show: &Show, catalog: &'a [Station]) -> Option<&'a Station> {
+    catalog
+        .iter()
+        .filter_map(|station| Some((show.score(station)?, station)))
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, station)| station)
+}
+
+/// Shows on `station` not over by `now`, soonest first, at most `limit`
+pub fn upcoming<'a>(
+    shows: &'a [Show],
+    station: &Station,
+    catalog: &[Station],
+    now: i64,
+    limit: usize,
+) -> Vec<&'a Show> {
+    let mut upcoming: Vec<&Show> = shows
+        .iter()
+        .filter(|show| show.end.unwrap_or(show.start) > now)
+        .filter(|show| show.is_on(station, catalog))
+        .collect();
+    upcoming.sort_by_key(|show| show.start);
+    upcoming.truncate(limit);
+    upcoming
+}
+
+/// Shows already reminded of, so each is reminded of once
+#[derive(Debug, Default)]
+pub struct Reminders {
+    reminded: HashSet<(String, i64)>,
+}
+
+impl Reminders {
+    /// Shows starting within `lead` of `now` not reminded of yet
+    pub fn due<'a>(&mut self, shows: &'a [Show], now: i64, lead: Duration) -> Vec<&'a Show> {
+        let until = now.saturating_add(i64::try_from(lead.as_secs()).unwrap_or(i64::MAX));
+        shows
+            .iter()
+            .filter(|show| show.start > now && show.start <= until)
+            .filter(|show| self.reminded.insert((show.title.clone(), show.start)))
+            .collect()
+    }
+}
+
+/// Download the schedule every `refresh_minutes` in the background
+///
+/// Results are sent as [`Action::UpdateSchedule`]; failed downloads are
+/// retried on the next round.
+pub fn spawn_refresh(config: ScheduleConfig, action_tx: mpsc::UnboundedSender<Action>) {
+    if !config.enabled {
+        return;
+    }
+    if config.url.trim().is_empty() {
+        warn!("Schedule enabled without a url");
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval(Duration::from_secs(config.refresh_minutes.max(1) * 60));
+        loop {
+            ticker.tick().await;
+            let shows = match fetch(&config.url).await {
+                Ok(shows) => shows,
+                Err(error) => {
+                    warn!("Failed to fetch the schedule: {}", error);
+                    continue;
+                }
+            };
+            debug!("Schedule has {} shows", shows.len());
+            if action_tx.send(Action::UpdateSchedule(shows)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+async fn fetch(url: &str) -> Result<Vec<Show>, AppError> {
+    let response = crate::http::client()
+        .get(url)
+        .send()
+        .await
+        .map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(AppError::Station(format!(
+            "Failed to fetch the schedule: HTTP {}",
+            response.status()
+        )));
+    }
+    let body = response.text().await.map_err(AppError::Network)?;
+    Ok(parse_ics(&body))
+}
+
+/// The events in an iCalendar feed, skipping any without a start time
+pub fn parse_ics(body: &str) -> Vec<Show> {
+    // Lines starting with a space or tab continue the one before
+    let unfolded = body
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    let mut shows = Vec::new();
+    let mut event: Option<Vec<(&str, &str, &str)>> = None;
+    for line in unfolded.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name.to_ascii_uppercase().as_str(), value.trim()) {
+            ("BEGIN", "VEVENT") => event = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(show) = event.take().and_then(|properties| to_show(&properties)) {
+                    shows.push(show);
+                }
+            }
+            _ => {
+                if let Some(properties) = &mut event {
+                    properties.push((name, params, value));
+                }
+            }
+        }
+    }
+    shows
+}
+
+fn to_show(properties: &[(&str, &str, &str)]) -> Option<Show> {
+    let property = |wanted: &str| {
+        properties
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(wanted))
+    };
+    let text = |wanted: &str| {
+        property(wanted)
+            .map(|(_, _, value)| unescape(value))
+            .unwrap_or_default()
+    };
+    let time =
+        |wanted: &str| property(wanted).and_then(|(_, params, value)| parse_time(params, value));
+    let tags = ["LOCATION", "CATEGORIES", "URL"]
+        .map(text)
+        .join(" ")
+        .to_lowercase();
+    Some(Show {
+        title: text("SUMMARY"),
+        description: text("DESCRIPTION"),
+        start: time("DTSTART")?,
+        end: time("DTEND"),
+        tags,
+    })
+}
+
+/// Lowercased words of `text`, split on anything but letters and digits
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Undo iCalendar text escaping
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n' | 'N') => text.push('\n'),
+                Some(c) => text.push(c),
+                None => {}
+            },
+            c => text.push(c),
+        }
+    }
+    text.trim().to_string()
+}
+
+/// Unix time of a `DTSTART` or `DTEND` value: UTC with a trailing `Z`, in the
+/// `TZID` parameter's zone, in the local zone without either, or a whole
+/// day's start
+fn parse_time(params: &str, value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&time).timestamp());
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y%m%d").map(|date| date.and_time(Default::default()))
+        })
+        .ok()?;
+    let zone = params.split(';').find_map(|param| {
+        let (name, zone) = param.split_once('=')?;
+        name.eq_ignore_ascii_case("TZID")
+            .then(|| zone.trim_matches('"').parse::<Tz>().ok())?
+    });
+    match zone {
+        Some(zone) => zone
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|time| time.timestamp()),
+        None => Local
+            .from_local_datetime(&time)
+            .earliest()
+            .map(|time: DateTime<Local>| time.timestamp()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Groove Salad: Live DJ set\\, all night\r\n\
+        DESCRIPTION:Three hours\\nof downtempo with a very long descr\r\n \
+        iption\r\n\
+        DTSTART:20240301T200000Z\r\n\
+        DTEND:20240301T230000Z\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Deep Space One special\r\n\
+        LOCATION:https://somafm.com/deepspaceone/\r\n\
+        DTSTART;TZID=America/Los_Angeles:20240302T120000\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:No start\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn reads_events_from_a_feed() {
+        let shows = parse_ics(FEED);
+        assert_eq!(shows.len(), 2);
+        assert_eq!(shows[0].title, "Groove Salad: Live DJ set, all night");
+        assert_eq!(
+            shows[0].description,
+            "Three hours\nof downtempo with a very long description"
+        );
+        assert_eq!(shows[0].start, 1_709_323_200);
+        assert_eq!(shows[0].end, Some(1_709_334_000));
+        // Noon in Los Angeles is 20:00 UTC in winter
+        assert_eq!(shows[1].start, 1_709_409_600);
+        assert_eq!(shows[1].end, None);
+    }
+
+    #[test]
+    fn picks_each_stations_upcoming_shows() {
+        let shows = parse_ics(FEED);
+        let groove = Station::for_test("groovesalad", "Groove Salad");
+        let space = Station::for_test("deepspaceone", "Deep Space One");
+        let drone = Station::for_test("dronezone", "Drone Zone");
+        let catalog = [groove.clone(), space.clone(), drone.clone()];
+        let before = 1_709_300_000;
+
+        assert_eq!(
+            upcoming(&shows, &groove, &catalog, before, 3),
+            vec![&shows[0]]
+        );
+        assert_eq!(
+            upcoming(&shows, &space, &catalog, before, 3),
+            vec![&shows[1]]
+        );
+        assert!(upcoming(&shows, &drone, &catalog, before, 3).is_empty());
+
+        // On air until it ends, then gone
+        let during = shows[0].start + 60;
+        assert!(shows[0].on_air(during));
+        assert_eq!(upcoming(&shows, &groove, &catalog, during, 3).len(), 1);
+        assert!(upcoming(&shows, &groove, &catalog, 1_709_334_000, 3).is_empty());
+    }
+
+    #[test]
+    fn matches_the_station_named_most_closely() {
+        let show = |title: &str, tags: &str| Show {
+            title: title.to_string(),
+            description: String::new(),
+            start: 0,
+            end: None,
+            tags: tags.to_string(),
+        };
+        let groove = Station::for_test("groovesalad", "Groove Salad");
+        let classic = Station::for_test("gsclassic", "Groove Salad Classic");
+        let catalog = [groove.clone(), classic.clone()];
+
+        let set = show("Groove Salad Classic: DJ set", "");
+        assert!(!set.is_on(&groove, &catalog));
+        assert!(set.is_on(&classic, &catalog));
+        assert_eq!(station_of(&set, &catalog), Some(&classic));
+
+        let live = show("Groove Salad: live", "");
+        assert!(live.is_on(&groove, &catalog));
+        assert!(!live.is_on(&classic, &catalog));
+        assert_eq!(station_of(&live, &catalog), Some(&groove));
+
+        // The ID beats a longer title
+        let tagged = show(
+            "Groove Salad Classic night",
+            "https://somafm.com/groovesalad/",
+        );
+        assert_eq!(station_of(&tagged, &catalog), Some(&groove));
+
+        // Titles match whole words only
+        let other = show("Groove Saladbar opening", "");
+        assert_eq!(station_of(&other, &catalog), None);
+    }
+
+    #[test]
+    fn reminds_of_each_show_once() {
+        let shows = parse_ics(FEED);
+        let mut reminders = Reminders::default();
+        let lead = Duration::from_secs(600);
+        assert!(reminders.due(&shows, shows[0].start - 700, lead).is_empty());
+        assert_eq!(
+            reminders.due(&shows, shows[0].start - 500, lead),
+            vec![&shows[0]]
+        );
+        assert!(reminders.due(&shows, shows[0].start - 100, lead).is_empty());
+    }
+}
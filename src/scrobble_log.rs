@@ -0,0 +1,183 @@
+//! Audioscrobbler `.scrobbler.log` export
+//!
+//! Appends finished tracks to a log in the Audioscrobbler portable player
+//! format (the one Rockbox writes), so plays can be submitted later with
+//! offline scrobbling tools.
+
+use crate::config::{Config, ScrobblerLogConfig};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+const HEADER: &str = concat!(
+    "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/somars ",
+    env!("CARGO_PKG_VERSION"),
+    "\n"
+);
+
+/// A track that is currently playing
+#[derive(Debug, Clone, PartialEq)]
+struct Play {
+    artist: String,
+    title: String,
+    started: SystemTime,
+}
+
+/// Writes finished tracks to the scrobbler log
+#[derive(Debug)]
+pub struct ScrobbleLog {
+    /// Log file, `None` when the export is disabled
+    path: Option<PathBuf>,
+    min_played: Duration,
+    current: Option<Play>,
+}
+
+impl ScrobbleLog {
+    pub fn new(config: &ScrobblerLogConfig) -> Self {
+        let path = config.enabled.then(|| {
+            config.path.clone().or_else(|| {
+                Config::default_config_path()
+                    .ok()
+                    .and_then(|path| Some(path.parent()?.join(".scrobbler.log")))
+            })
+        });
+        Self {
+            path: path.flatten(),
+            min_played: Duration::from_secs(config.min_seconds),
+            current: None,
+        }
+    }
+
+    /// A new track title arrived; finishes the previous track
+    ///
+    /// Titles without an `Artist - Title` split cannot be scrobbled and only end
+    /// the previous track.
+    pub fn track_started(&mut self, title: &str) {
+        self.track_started_at(title, SystemTime::now());
+    }
+
+    /// Playback stopped, paused or moved to another station
+    pub fn finish(&mut self) {
+        self.finish_at(SystemTime::now());
+    }
+
+    fn track_started_at(&mut self, title: &str, now: SystemTime) {
+        if self.path.is_none() {
+            return;
+        }
+        let next = split_title(title);
+        let current = self
+            .current
+            .as_ref()
+            .map(|play| (play.artist.as_str(), play.title.as_str()));
+        if next.is_some() && next == current {
+            return;
+        }
+        self.finish_at(now);
+        self.current = next.map(|(artist, title)| Play {
+            artist: artist.to_string(),
+            title: title.to_string(),
+            started: now,
+        });
+    }
+
+    fn finish_at(&mut self, now: SystemTime) {
+        let (Some(path), Some(play)) = (&self.path, self.current.take()) else {
+            return;
+        };
+        let entry = format_entry(&play, now, self.min_played);
+        if let Err(error) = append(path, &entry) {
+            warn!(
+                "Failed to write scrobbler log {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Split an ICY `Artist - Title` string
+pub fn split_title(title: &str) -> Option<(&str, &str)> {
+    let (artist, title) = title.split_once(" - ")?;
+    let (artist, title) = (artist.trim(), title.trim());
+    (!artist.is_empty() && !title.is_empty()).then_some((artist, title))
+}
+
+/// One log line: artist, album, title, track number, length, rating, timestamp, MusicBrainz ID
+fn format_entry(play: &Play, now: SystemTime, min_played: Duration) -> String {
+    let played = now.duration_since(play.started).unwrap_or_default();
+    let rating = if played >= min_played { "L" } else { "S" };
+    let timestamp = play
+        .started
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format!(
+        "{}\t\t{}\t\t{}\t{}\t{}\t\n",
+        field(&play.artist),
+        field(&play.title),
+        played.as_secs(),
+        rating,
+        timestamp
+    )
+}
+
+/// Tabs and line breaks would break the log's columns
+fn field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+fn append(path: &Path, entry: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        file.write_all(HEADER.as_bytes())?;
+    }
+    file.write_all(entry.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_listened_and_skipped_entries() {
+        let started = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let play = Play {
+            artist: "Boards of\tCanada".to_string(),
+            title: "Roygbiv".to_string(),
+            started,
+        };
+        let min = Duration::from_secs(30);
+        assert_eq!(
+            format_entry(&play, started + Duration::from_secs(151), min),
+            "Boards of Canada\t\tRoygbiv\t\t151\tL\t1700000000\t\n"
+        );
+        assert!(format_entry(&play, started + Duration::from_secs(10), min).contains("\tS\t"));
+    }
+
+    #[test]
+    fn logs_previous_track_on_change() {
+        let path =
+            std::env::temp_dir().join(format!("somars-scrobbler-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut log = ScrobbleLog::new(&ScrobblerLogConfig {
+            enabled: true,
+            path: Some(path.clone()),
+            min_seconds: 30,
+        });
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        log.track_started_at("Artist - One", start);
+        // Repeated metadata for the same track is ignored
+        log.track_started_at("Artist - One", start + Duration::from_secs(20));
+        log.track_started_at("Station ID", start + Duration::from_secs(60));
+        log.finish_at(start + Duration::from_secs(70));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(contents.starts_with("#AUDIOSCROBBLER/1.1\n"));
+        let entries: Vec<_> = contents.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(entries, ["Artist\t\tOne\t\t60\tL\t1000\t"]);
+    }
+}
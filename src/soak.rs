@@ -0,0 +1,310 @@
+//! Long-running soak test
+//!
+//! `somars --soak [HOURS]` plays stations from a local mock Icecast server for
+//! hours while a driver tunes between them, changes the volume and mutes.
+//! The server drops and stalls connections now and then, like a flaky
+//! network. Every few minutes the live task count and resident memory are
+//! compared with a baseline taken after warm-up; if either keeps growing the
+//! run stops and reports it. Whether the terminal was restored is checked
+//! after the TUI exits.
+
+use crate::action::{Action, RandomScope};
+use crate::error::AppError;
+use crate::mock_stream::{self, IcyFramer, METAINT, SAMPLE_RATE};
+use crate::providers::StationProvider;
+use crate::station::Station;
+use crate::utils;
+use futures::future::BoxFuture;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{info, warn};
+
+/// Mock stations served
+const STATIONS: usize = 4;
+/// How often the stream title changes
+const TRACK_SECONDS: u64 = 30;
+
+/// Time between driver steps
+const STEP: Duration = Duration::from_secs(20);
+/// Time before the baseline is taken, so caches and buffers have filled
+const WARM_UP: Duration = Duration::from_secs(180);
+/// Steps between invariant checks
+const CHECK_EVERY: u64 = 9;
+/// Tasks allowed above the baseline
+const TASK_SLACK: usize = 32;
+/// Resident memory allowed above the baseline
+const MEMORY_SLACK: u64 = 96 * 1024 * 1024;
+
+/// A running soak test
+pub struct Soak {
+    port: u16,
+    duration: Duration,
+    started: Instant,
+    failures: Arc<Mutex<Vec<String>>>,
+}
+
+impl Soak {
+    /// Start the mock server for a run of `duration`
+    pub async fn start(duration: Duration) -> io::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+        let port = listener.local_addr()?.port();
+        tokio::spawn(serve(listener));
+        info!("Soak test mock server listening on port {}", port);
+        Ok(Self {
+            port,
+            duration,
+            started: Instant::now(),
+            failures: Arc::default(),
+        })
+    }
+
+    /// Provider for the mock stations
+    pub fn provider(&self) -> SoakProvider {
+        SoakProvider { port: self.port }
+    }
+
+    /// Drive the app until the run is over or an invariant breaks
+    pub fn spawn_driver(&self, action_tx: UnboundedSender<Action>) {
+        let duration = self.duration;
+        let failures = self.failures.clone();
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let mut interval = tokio::time::interval(STEP);
+            let mut baseline: Option<Sample> = None;
+            let mut step = 0u64;
+            while started.elapsed() < duration {
+                interval.tick().await;
+                step += 1;
+                let action = match step % 6 {
+                    0 => Action::Mute,
+                    1 => Action::Unmute,
+                    2 | 4 => Action::TuneRandom(RandomScope::All),
+                    3 => Action::SetVolume(0.2 + utils::random_index(80) as f32 / 100.0),
+                    _ => Action::TogglePlayStop,
+                };
+                if action_tx.send(action).is_err() {
+                    return;
+                }
+
+                if started.elapsed() < WARM_UP || !step.is_multiple_of(CHECK_EVERY) {
+                    continue;
+                }
+                let sample = Sample::take();
+                info!("Soak test after {:?}: {:?}", started.elapsed(), sample);
+                let Some(baseline) = &baseline else {
+                    baseline = Some(sample);
+                    continue;
+                };
+                let broken = baseline.broken_by(&sample);
+                if !broken.is_empty() {
+                    for failure in &broken {
+                        let _ = action_tx.send(Action::Error(format!("Soak test: {}", failure)));
+                    }
+                    failures.lock().unwrap().extend(broken);
+                    break;
+                }
+            }
+            let _ = action_tx.send(Action::Quit);
+        });
+    }
+
+    /// Report the run once the terminal has been cleaned up
+    pub fn finish(self) -> color_eyre::eyre::Result<()> {
+        let mut failures = std::mem::take(&mut *self.failures.lock().unwrap());
+        if crossterm::terminal::is_raw_mode_enabled().unwrap_or(false) {
+            failures.push("terminal left in raw mode".to_string());
+        }
+        println!(
+            "Soak test ran for {}",
+            utils::format_duration(self.started.elapsed())
+        );
+        if failures.is_empty() {
+            println!("All invariants held");
+            return Ok(());
+        }
+        for failure in &failures {
+            println!("FAILED: {}", failure);
+        }
+        Err(color_eyre::eyre::eyre!(
+            "Soak test failed: {}",
+            failures.join("; ")
+        ))
+    }
+}
+
+/// Stations on the mock server
+pub struct SoakProvider {
+    port: u16,
+}
+
+impl StationProvider for SoakProvider {
+    fn name(&self) -> &str {
+        "Soak"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Vec<Station>, AppError>> {
+        let stations = (1..=STATIONS)
+            .map(|n| Station {
+                id: format!("soak-{}", n),
+                title: format!("Soak Test {}", n),
+                genre: "test".to_string(),
+                source: self.name().to_string(),
+                ..Station::from_url(&format!("http://127.0.0.1:{}/soak-{}", self.port, n))
+            })
+            .collect();
+        Box::pin(async move { Ok(stations) })
+    }
+}
+
+/// Process health at one point in time
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    tasks: usize,
+    /// Resident memory in bytes, `None` where it can't be read
+    memory: Option<u64>,
+}
+
+impl Sample {
+    fn take() -> Self {
+        Self {
+            tasks: tokio::runtime::Handle::current()
+                .metrics()
+                .num_alive_tasks(),
+            memory: resident_memory(),
+        }
+    }
+
+    /// What grew too far since `self`
+    fn broken_by(&self, now: &Sample) -> Vec<String> {
+        let mut broken = Vec::new();
+        if now.tasks > self.tasks + TASK_SLACK {
+            broken.push(format!(
+                "task leak: {} tasks alive, {} at baseline",
+                now.tasks, self.tasks
+            ));
+        }
+        if let (Some(before), Some(now)) = (self.memory, now.memory) {
+            if now > before + MEMORY_SLACK {
+                broken.push(format!(
+                    "memory grew from {} MiB to {} MiB",
+                    before / (1024 * 1024),
+                    now / (1024 * 1024)
+                ));
+            }
+        }
+        broken
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+    None
+}
+
+async fn serve(listener: TcpListener) {
+    let mut connection = 0u64;
+    loop {
+        match listener.accept().await {
+            Ok((socket, _)) => {
+                connection += 1;
+                tokio::spawn(async move {
+                    if let Err(error) = stream(socket, connection).await {
+                        info!("Soak test connection {} ended: {}", connection, error);
+                    }
+                });
+            }
+            Err(error) => {
+                warn!("Soak test mock server failed: {}", error);
+                return;
+            }
+        }
+    }
+}
+
+/// Serve an endless WAV stream with ICY titles, then drop or stall the
+/// connection after a while
+async fn stream(mut socket: TcpStream, connection: u64) -> io::Result<()> {
+    let Some(path) = mock_stream::read_request_path(&mut socket).await? else {
+        return Ok(());
+    };
+    socket
+        .write_all(mock_stream::response_header(&path).as_bytes())
+        .await?;
+
+    let lifetime = Duration::from_secs(30 + utils::random_index(120) as u64);
+    let stall = utils::random_index(4) == 0;
+    let started = Instant::now();
+    let mut framer = IcyFramer::new(METAINT);
+    let mut audio = mock_stream::wav_header();
+    let mut phase = 0u32;
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    while started.elapsed() < lifetime {
+        interval.tick().await;
+        let elapsed = started.elapsed().as_secs();
+        let title = format!(
+            "Soak Artist {} - Track {}",
+            connection,
+            elapsed / TRACK_SECONDS + 1
+        );
+        tone(&mut audio, &mut phase, SAMPLE_RATE as usize / 10);
+        socket
+            .write_all(&framer.frame(&std::mem::take(&mut audio), &title))
+            .await?;
+    }
+    if stall {
+        // Keep the connection open without sending anything
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+    Ok(())
+}
+
+/// Append `samples` of a quiet 440 Hz tone
+fn tone(audio: &mut Vec<u8>, phase: &mut u32, samples: usize) {
+    for _ in 0..samples {
+        let t = *phase as f32 / SAMPLE_RATE as f32;
+        let sample = ((t * 440.0 * std::f32::consts::TAU).sin() * 1_000.0) as i16;
+        audio.extend_from_slice(&sample.to_le_bytes());
+        *phase = (*phase + 1) % SAMPLE_RATE;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_growth_past_the_slack() {
+        let baseline = Sample {
+            tasks: 20,
+            memory: Some(100 * 1024 * 1024),
+        };
+        let steady = Sample {
+            tasks: 20 + TASK_SLACK,
+            memory: Some(100 * 1024 * 1024 + MEMORY_SLACK),
+        };
+        assert!(baseline.broken_by(&steady).is_empty());
+
+        let leaking = Sample {
+            tasks: 21 + TASK_SLACK,
+            memory: Some(101 * 1024 * 1024 + MEMORY_SLACK),
+        };
+        assert_eq!(baseline.broken_by(&leaking).len(), 2);
+        let unknown = Sample {
+            memory: None,
+            ..steady
+        };
+        assert!(baseline.broken_by(&unknown).is_empty());
+    }
+}
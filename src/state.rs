@@ -0,0 +1,478 @@
+//! App state and the events that change it
+//!
+//! What the app keeps about playback, the listening time, the volume and
+//! quitting only changes through [`AppState::reduce`], which marks the state
+//! dirty when the screen needs drawing again. The main loop draws once it has
+//! handled what arrived, and only when something is dirty. Being plain data,
+//! the transitions are tested without a terminal or an audio device, as is
+//! the [`UnderrunWatch`] that restarts a station gone quiet.
+
+use crate::PlaybackState;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// How often the output is checked for an underrun
+const UNDERRUN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Shortest time between restarts for an underrun
+const UNDERRUN_RESTART_DEBOUNCE: Duration = Duration::from_secs(10);
+/// Restarts for one underrun before playback is stopped
+const MAX_UNDERRUN_RESTARTS: u32 = 3;
+
+/// Something that happened to the app
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppEvent {
+    /// A station started playing at the given time, replacing whatever played
+    Tuned(Instant),
+    /// Playback became paused, resumed or stopped at the given time
+    PlaybackChanged(PlaybackState, Instant),
+    /// The paused stream was disconnected; resuming connects again
+    Disconnected,
+    /// Set the volume, 0 to 2; turning it up ends a mute
+    SetVolume(f32),
+    /// Move the volume by a step, from where it was before any mute
+    AdjustVolume(f32),
+    Mute,
+    Unmute,
+    /// Something kept outside this state changed on screen
+    Redraw,
+    Quit,
+}
+
+/// How playback is resumed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    /// Tune the station again, joining the stream live
+    Reconnect,
+    /// Carry on from where the stream was paused
+    Unpause,
+    /// Play the selected station
+    Start,
+}
+
+/// Playback, volume and quitting, as the main loop sees them
+#[derive(Debug)]
+pub struct AppState {
+    playback_state: PlaybackState,
+    volume: f32,
+    /// Volume to restore on unmute, `None` when not muted
+    muted_volume: Option<f32>,
+    /// Time played before the current stretch
+    total_played: Duration,
+    /// When the current stretch of playing began
+    playback_start_time: Option<Instant>,
+    /// When playback was paused, while it is
+    last_pause_time: Option<Instant>,
+    /// The paused stream was disconnected after the pause timeout
+    pause_disconnected: bool,
+    should_quit: bool,
+    /// Something changed since the screen was last drawn
+    dirty: bool,
+}
+
+impl AppState {
+    pub fn new(volume: f32) -> Self {
+        Self {
+            playback_state: PlaybackState::Stopped,
+            volume: volume.clamp(0.0, 2.0),
+            muted_volume: None,
+            total_played: Duration::ZERO,
+            playback_start_time: None,
+            last_pause_time: None,
+            pause_disconnected: false,
+            should_quit: false,
+            // Nothing has been drawn yet
+            dirty: true,
+        }
+    }
+
+    /// Apply `event`, returning whether it changed anything
+    pub fn reduce(&mut self, event: AppEvent) -> bool {
+        let changed = match event {
+            AppEvent::Tuned(now) => {
+                self.end_stretch(now);
+                self.playback_start_time = Some(now);
+                self.last_pause_time = None;
+                self.pause_disconnected = false;
+                self.playback_state = PlaybackState::Playing;
+                true
+            }
+            AppEvent::PlaybackChanged(state, now) => {
+                if state == self.playback_state {
+                    return false;
+                }
+                self.end_stretch(now);
+                match state {
+                    PlaybackState::Playing => self.playback_start_time = Some(now),
+                    PlaybackState::Paused => self.last_pause_time = Some(now),
+                    PlaybackState::Stopped => {}
+                }
+                if state != PlaybackState::Paused {
+                    self.last_pause_time = None;
+                    self.pause_disconnected = false;
+                }
+                self.playback_state = state;
+                true
+            }
+            AppEvent::Disconnected => {
+                if self.playback_state != PlaybackState::Paused || self.pause_disconnected {
+                    return false;
+                }
+                self.pause_disconnected = true;
+                true
+            }
+            AppEvent::SetVolume(volume) => {
+                let volume = volume.clamp(0.0, 2.0);
+                if volume > 0.0 {
+                    self.muted_volume = None;
+                }
+                std::mem::replace(&mut self.volume, volume) != volume
+            }
+            AppEvent::AdjustVolume(delta) => {
+                let from = self.muted_volume.take().unwrap_or(self.volume);
+                self.volume = (from + delta).clamp(0.0, 2.0);
+                true
+            }
+            AppEvent::Mute => {
+                if self.muted_volume.is_some() || self.volume == 0.0 {
+                    return false;
+                }
+                self.muted_volume = Some(std::mem::take(&mut self.volume));
+                true
+            }
+            AppEvent::Unmute => match self.muted_volume.take() {
+                Some(volume) => {
+                    self.volume = volume;
+                    true
+                }
+                None => false,
+            },
+            AppEvent::Redraw => true,
+            AppEvent::Quit => {
+                self.should_quit = true;
+                return true;
+            }
+        };
+        self.dirty |= changed;
+        changed
+    }
+
+    /// Count the stretch played so far into the total
+    fn end_stretch(&mut self, now: Instant) {
+        if let Some(start) = self.playback_start_time.take() {
+            self.total_played += now.saturating_duration_since(start);
+        }
+    }
+
+    /// Ask for the screen to be drawn again
+    pub fn mark_dirty(&mut self) {
+        self.reduce(AppEvent::Redraw);
+    }
+
+    /// Whether the screen needs drawing, clearing the flag
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    pub fn playback_state(&self) -> PlaybackState {
+        self.playback_state.clone()
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Time played before the current stretch
+    pub fn total_played(&self) -> Duration {
+        self.total_played
+    }
+
+    /// Time played this session up to `now`, including the current stretch
+    pub fn listened(&self, now: Instant) -> Duration {
+        match self.playback_start_time {
+            Some(start) if self.playback_state == PlaybackState::Playing => {
+                self.total_played + now.saturating_duration_since(start)
+            }
+            _ => self.total_played,
+        }
+    }
+
+    /// When playback was paused, while it is
+    pub fn paused_at(&self) -> Option<Instant> {
+        self.last_pause_time
+    }
+
+    pub fn pause_disconnected(&self) -> bool {
+        self.pause_disconnected
+    }
+
+    /// Whether the stream has been paused for `timeout` by `now` and is still
+    /// connected; a zero timeout never disconnects
+    pub fn pause_expired(&self, now: Instant, timeout: Duration) -> bool {
+        !timeout.is_zero()
+            && !self.pause_disconnected
+            && self.playback_state == PlaybackState::Paused
+            && self
+                .paused_at()
+                .is_some_and(|paused| now.saturating_duration_since(paused) >= timeout)
+    }
+
+    /// What resuming takes from here, `None` while playing
+    pub fn resume(&self) -> Option<Resume> {
+        match self.playback_state {
+            PlaybackState::Paused if self.pause_disconnected => Some(Resume::Reconnect),
+            PlaybackState::Paused => Some(Resume::Unpause),
+            PlaybackState::Stopped => Some(Resume::Start),
+            PlaybackState::Playing => None,
+        }
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+}
+
+/// What the underrun watchdog wants done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Underrun {
+    /// Tune the station again; the output has had nothing for this long
+    Restart(Duration),
+    /// Stop playing, as restarting didn't help
+    GiveUp,
+}
+
+/// Watches for the output staying empty while playing
+///
+/// The playback task rebuffers and reconnects on its own; this catches what
+/// it can't, such as the task ending while the app still plays.
+#[derive(Debug, Default)]
+pub struct UnderrunWatch {
+    /// When the output last had audio queued, or playback started
+    last_audio: Option<Instant>,
+    last_check: Option<Instant>,
+    /// When playback was last restarted for an underrun
+    last_restart: Option<Instant>,
+    /// Restarts since the output last had audio queued
+    restarts: u32,
+    /// The current underrun has been logged
+    detected: bool,
+}
+
+impl UnderrunWatch {
+    /// A station started at `now`; time without audio counts from then
+    pub fn tuned(&mut self, now: Instant) {
+        self.last_audio = Some(now);
+    }
+
+    /// Playback paused or stopped; the next underrun gets its restarts again
+    pub fn reset(&mut self) {
+        self.restarts = 0;
+        self.last_restart = None;
+    }
+
+    /// Nothing is watched, as nothing should be playing; time without audio
+    /// counts again from when it is
+    pub fn idle(&mut self) {
+        self.last_audio = None;
+        self.detected = false;
+    }
+
+    /// Look at the output at `now`, with `queued` sources in the sink, and
+    /// say what to do once it has been empty for `timeout`
+    pub fn check(&mut self, now: Instant, queued: usize, timeout: Duration) -> Option<Underrun> {
+        if self
+            .last_check
+            .is_some_and(|checked| now.saturating_duration_since(checked) < UNDERRUN_CHECK_INTERVAL)
+        {
+            return None;
+        }
+        self.last_check = Some(now);
+
+        if queued > 0 {
+            self.last_audio = Some(now);
+            self.detected = false;
+            self.restarts = 0;
+            return None;
+        }
+        let empty_for = now.saturating_duration_since(*self.last_audio.get_or_insert(now));
+        if empty_for < timeout {
+            return None;
+        }
+        if !self.detected {
+            warn!(
+                "Output underrun: nothing to play for {}s",
+                empty_for.as_secs()
+            );
+            self.detected = true;
+        }
+        if self.last_restart.is_some_and(|restarted| {
+            now.saturating_duration_since(restarted) < UNDERRUN_RESTART_DEBOUNCE
+        }) {
+            return None;
+        }
+        if self.restarts >= MAX_UNDERRUN_RESTARTS {
+            return Some(Underrun::GiveUp);
+        }
+        self.restarts += 1;
+        self.last_restart = Some(now);
+        Some(Underrun::Restart(empty_for))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_listening_time_across_pauses_and_stations() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let mut state = AppState::new(1.0);
+        assert!(state.take_dirty());
+
+        state.reduce(AppEvent::Tuned(at(0)));
+        assert_eq!(state.playback_state(), PlaybackState::Playing);
+        assert_eq!(state.listened(at(10)), Duration::from_secs(10));
+
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Paused, at(10)));
+        assert_eq!(state.paused_at(), Some(at(10)));
+        assert_eq!(state.listened(at(60)), Duration::from_secs(10));
+        // The same state again changes nothing, not even the pause time
+        assert!(!state.reduce(AppEvent::PlaybackChanged(PlaybackState::Paused, at(20))));
+        assert_eq!(state.paused_at(), Some(at(10)));
+
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Playing, at(60)));
+        assert_eq!(state.paused_at(), None);
+        // Switching stations keeps what the last one played
+        state.reduce(AppEvent::Tuned(at(65)));
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Stopped, at(70)));
+        assert_eq!(state.listened(at(100)), Duration::from_secs(20));
+        assert_eq!(state.total_played(), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn disconnects_long_pauses_and_reconnects_on_resume() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let timeout = Duration::from_secs(300);
+        let mut state = AppState::new(1.0);
+        assert_eq!(state.resume(), Some(Resume::Start));
+        // Only a paused stream is disconnected
+        assert!(!state.reduce(AppEvent::Disconnected));
+
+        state.reduce(AppEvent::Tuned(at(0)));
+        assert_eq!(state.resume(), None);
+        assert!(!state.pause_expired(at(1000), timeout));
+
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Paused, at(10)));
+        assert_eq!(state.resume(), Some(Resume::Unpause));
+        assert!(!state.pause_expired(at(309), timeout));
+        assert!(state.pause_expired(at(310), timeout));
+        assert!(!state.pause_expired(at(310), Duration::ZERO));
+
+        assert!(state.reduce(AppEvent::Disconnected));
+        assert!(state.pause_disconnected());
+        assert!(!state.reduce(AppEvent::Disconnected));
+        // Disconnected once is enough
+        assert!(!state.pause_expired(at(1000), timeout));
+        assert_eq!(state.resume(), Some(Resume::Reconnect));
+
+        // Resuming tunes the station again, which connects it
+        state.reduce(AppEvent::Tuned(at(400)));
+        assert!(!state.pause_disconnected());
+        assert_eq!(state.resume(), None);
+
+        // Stopping forgets the disconnect too
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Paused, at(500)));
+        state.reduce(AppEvent::Disconnected);
+        state.reduce(AppEvent::PlaybackChanged(PlaybackState::Stopped, at(900)));
+        assert!(!state.pause_disconnected());
+        assert_eq!(state.resume(), Some(Resume::Start));
+    }
+
+    #[test]
+    fn mutes_and_restores_the_volume() {
+        let mut state = AppState::new(0.8);
+        assert!(state.reduce(AppEvent::Mute));
+        assert_eq!(state.volume(), 0.0);
+        assert!(!state.reduce(AppEvent::Mute));
+
+        // A step from a mute starts where the volume was
+        state.reduce(AppEvent::AdjustVolume(0.05));
+        assert!((state.volume() - 0.85).abs() < 1e-6);
+        assert!(!state.reduce(AppEvent::Unmute));
+
+        state.reduce(AppEvent::Mute);
+        assert!(state.reduce(AppEvent::Unmute));
+        assert!((state.volume() - 0.85).abs() < 1e-6);
+
+        state.reduce(AppEvent::Mute);
+        state.reduce(AppEvent::SetVolume(0.3));
+        assert!(!state.reduce(AppEvent::Unmute));
+        state.reduce(AppEvent::SetVolume(5.0));
+        assert_eq!(state.volume(), 2.0);
+    }
+
+    #[test]
+    fn only_changes_make_the_screen_dirty() {
+        let mut state = AppState::new(1.0);
+        state.take_dirty();
+        assert!(!state.reduce(AppEvent::SetVolume(1.0)));
+        assert!(!state.reduce(AppEvent::Unmute));
+        assert!(!state.take_dirty());
+
+        state.reduce(AppEvent::SetVolume(0.5));
+        state.mark_dirty();
+        assert!(state.take_dirty());
+        assert!(!state.take_dirty());
+
+        assert!(!state.should_quit());
+        state.reduce(AppEvent::Quit);
+        assert!(state.should_quit());
+    }
+
+    #[test]
+    fn restarts_an_empty_output_a_few_times_then_gives_up() {
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+        let timeout = Duration::from_secs(5);
+        let mut watch = UnderrunWatch::default();
+        watch.tuned(at(0));
+
+        assert_eq!(watch.check(at(1), 0, timeout), None);
+        assert_eq!(
+            watch.check(at(5), 0, timeout),
+            Some(Underrun::Restart(Duration::from_secs(5)))
+        );
+        // Another check within the second does nothing
+        assert_eq!(watch.check(at(5), 0, timeout), None);
+        // Restarts are ten seconds apart
+        assert_eq!(watch.check(at(14), 0, timeout), None);
+        assert_eq!(
+            watch.check(at(15), 0, timeout),
+            Some(Underrun::Restart(Duration::from_secs(15)))
+        );
+
+        // Audio arriving ends the underrun and its restarts
+        assert_eq!(watch.check(at(16), 2, timeout), None);
+        assert_eq!(watch.check(at(20), 0, timeout), None);
+        // Empty long enough again, but the last restart was just now
+        assert_eq!(watch.check(at(21), 0, timeout), None);
+        for restart in 0..3 {
+            assert!(matches!(
+                watch.check(at(25 + restart * 10), 0, timeout),
+                Some(Underrun::Restart(_))
+            ));
+        }
+        assert_eq!(watch.check(at(55), 0, timeout), Some(Underrun::GiveUp));
+
+        // Stopping gives the next underrun its restarts back
+        watch.reset();
+        watch.idle();
+        watch.tuned(at(100));
+        assert_eq!(watch.check(at(104), 0, timeout), None);
+        assert!(matches!(
+            watch.check(at(105), 0, timeout),
+            Some(Underrun::Restart(_))
+        ));
+    }
+}
@@ -1,5 +1,175 @@
 use crate::error::AppError;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::warn;
+
+/// ID of the synthetic station used for streams played by URL
+pub const URL_STATION_ID: &str = "url";
+
+/// Stream URLs already resolved from playlists, keyed by playlist URL
+static RESOLVED: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(Default::default);
+
+/// Stream quality tier of a SomaFM playlist
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamQuality {
+    #[default]
+    Highest,
+    High,
+    Low,
+}
+
+impl StreamQuality {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Highest => "highest",
+            Self::High => "high",
+            Self::Low => "low",
+        }
+    }
+
+    /// The next tier, wrapping from low back to highest
+    pub fn next(self) -> Self {
+        match self {
+            Self::Highest => Self::High,
+            Self::High => Self::Low,
+            Self::Low => Self::Highest,
+        }
+    }
+
+    /// Tiers to try when this one is not offered, closest first
+    fn preference(self) -> [Self; 3] {
+        match self {
+            Self::Highest => [Self::Highest, Self::High, Self::Low],
+            Self::High => [Self::High, Self::Highest, Self::Low],
+            Self::Low => [Self::Low, Self::High, Self::Highest],
+        }
+    }
+}
+
+impl fmt::Display for StreamQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for StreamQuality {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "highest" => Ok(Self::Highest),
+            "high" => Ok(Self::High),
+            "low" => Ok(Self::Low),
+            _ => Err(format!("Unknown stream quality: {}", value)),
+        }
+    }
+}
+
+/// What a station is playing right now and to how many listeners
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiveStatus {
+    /// Station title, to notice renamed channels
+    pub title: String,
+    pub last_playing: String,
+    pub listeners: u32,
+}
+
+/// Order of the station list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StationSort {
+    /// Order the providers list them in
+    #[default]
+    Catalog,
+    /// Most listeners first
+    Listeners,
+    Title,
+    Genre,
+}
+
+impl StationSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Catalog => "catalog",
+            Self::Listeners => "listeners",
+            Self::Title => "title",
+            Self::Genre => "genre",
+        }
+    }
+
+    /// The next sort mode, wrapping back to catalog order
+    pub fn next(self) -> Self {
+        match self {
+            Self::Catalog => Self::Listeners,
+            Self::Listeners => Self::Title,
+            Self::Title => Self::Genre,
+            Self::Genre => Self::Catalog,
+        }
+    }
+
+    /// Sort `stations`, given in catalog order; ties keep that order
+    pub fn sort(self, stations: &mut [Station]) {
+        match self {
+            Self::Catalog => {}
+            Self::Listeners => stations.sort_by_key(|station| std::cmp::Reverse(station.listeners)),
+            Self::Title => stations.sort_by_cached_key(|station| station.title.to_lowercase()),
+            Self::Genre => stations.sort_by_cached_key(|station| station.genre.to_lowercase()),
+        }
+    }
+}
+
+impl fmt::Display for StationSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Every genre in `stations`, in lower case and alphabetical order
+pub fn genres(stations: &[Station]) -> Vec<String> {
+    let genres: std::collections::BTreeSet<String> = stations
+        .iter()
+        .flat_map(Station::genres)
+        .map(str::to_lowercase)
+        .collect();
+    genres.into_iter().collect()
+}
+
+/// A genre in the filter row above the station list, toggled with Alt and its key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenreChip {
+    pub genre: String,
+    /// The first letter or digit of the genre not taken by an earlier chip
+    pub key: char,
+}
+
+/// Chips for every genre in `stations` that still has a free key
+pub fn genre_chips(stations: &[Station]) -> Vec<GenreChip> {
+    let mut taken = Vec::new();
+    genres(stations)
+        .into_iter()
+        .filter_map(|genre| {
+            let key = genre
+                .chars()
+                .filter(char::is_ascii_alphanumeric)
+                .find(|key| !taken.contains(key))?;
+            taken.push(key);
+            Some(GenreChip { genre, key })
+        })
+        .collect()
+}
+
+/// A playlist offered for a station
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Playlist {
+    pub url: String,
+    pub format: String,
+    pub quality: String,
+}
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Station {
@@ -11,75 +181,186 @@ pub struct Station {
     pub url: String,
     pub image: String,
     pub last_playing: String,
+    /// Current listener count, 0 when unknown
+    #[serde(default)]
+    pub listeners: u32,
+    /// Playlist URL the stream URL is resolved from on first play, empty when
+    /// `url` is set up front
+    #[serde(default)]
+    pub playlist: String,
+    /// Playlist URL of the lowest-bitrate stream, empty if there is none
+    #[serde(default)]
+    pub low_bitrate_playlist: String,
+    /// All playlists offered for the station, empty for single-stream stations
+    #[serde(default)]
+    pub playlists: Vec<Playlist>,
+    /// Name of the provider the station came from
+    #[serde(default)]
+    pub source: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct Playlist {
-    url: String,
-    format: String,
-    quality: String,
-}
+impl Station {
+    /// Build a synthetic station for a direct Icecast/Shoutcast stream URL
+    pub fn from_url(url: &str) -> Self {
+        let title = url::Url::parse(url)
+            .ok()
+            .and_then(|parsed| {
+                parsed
+                    .host_str()
+                    .map(|host| format!("{}{}", host, parsed.path().trim_end_matches('/')))
+            })
+            .unwrap_or_else(|| url.to_string());
+        Station {
+            id: URL_STATION_ID.to_string(),
+            title,
+            description: url.to_string(),
+            dj: String::new(),
+            genre: String::new(),
+            url: url.to_string(),
+            image: String::new(),
+            last_playing: String::new(),
+            listeners: 0,
+            playlist: String::new(),
+            low_bitrate_playlist: String::new(),
+            playlists: Vec::new(),
+            source: "URL".to_string(),
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-struct Channel {
-    id: String,
-    title: String,
-    description: String,
-    dj: String,
-    genre: String,
-    image: String,
-    #[serde(rename = "lastPlaying")]
-    last_playing: String,
-    playlists: Vec<Playlist>,
-}
+    /// A SomaFM station with just an ID and a title, for tests
+    #[cfg(test)]
+    pub fn for_test(id: &str, title: &str) -> Self {
+        Station {
+            id: id.to_string(),
+            title: title.to_string(),
+            source: crate::providers::SOMAFM.to_string(),
+            ..Station::from_url(&format!("https://ice.somafm.com/{id}"))
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-struct ChannelResponse {
-    channels: Vec<Channel>,
-}
+    /// The station's genres; SomaFM separates them with `|`
+    pub fn genres(&self) -> impl Iterator<Item = &str> {
+        self.genre
+            .split('|')
+            .map(str::trim)
+            .filter(|genre| !genre.is_empty())
+    }
 
-impl Station {
-    pub async fn parse_pls(url: &str) -> Result<String, AppError> {
-        // Handle empty URLs
-        if url.is_empty() {
-            return Err(AppError::Station("Empty PLS URL provided".to_string()));
+    /// Whether `genre` is one of the station's genres, ignoring case
+    pub fn has_genre(&self, genre: &str) -> bool {
+        self.genres().any(|g| g.eq_ignore_ascii_case(genre))
+    }
+
+    /// Point the station at the playlist for `quality` and the low-bitrate fallback below it
+    ///
+    /// The stream URL is cleared and resolved again on the next play.
+    pub fn select_quality(&mut self, quality: StreamQuality) {
+        let Some(playlist_url) = self.playlist_for(quality).map(|p| p.url.clone()) else {
+            return;
+        };
+
+        // Lowest quality variant, resolved only if playback needs to downgrade
+        let others = || self.playlists.iter().filter(|p| p.url != playlist_url);
+        self.low_bitrate_playlist = others()
+            .find(|p| p.quality == "low")
+            .or_else(|| {
+                // Nothing lower than the low tier to fall back to
+                (quality != StreamQuality::Low)
+                    .then(|| others().next_back())
+                    .flatten()
+            })
+            .map(|p| p.url.clone())
+            .unwrap_or_default();
+
+        if playlist_url != self.playlist {
+            self.url.clear();
+            self.playlist = playlist_url;
         }
+    }
 
-        let response = reqwest::get(url).await.map_err(|e| AppError::Network(e))?;
+    /// Stream URL to play, resolving the station's playlist on first use
+    ///
+    /// Falls back to the playlist URL itself if it cannot be resolved.
+    pub async fn stream_url(&self) -> String {
+        if !self.url.is_empty() || self.playlist.is_empty() {
+            return self.url.clone();
+        }
+        match Self::resolve_playlist(&self.playlist).await {
+            Ok(url) => url,
+            Err(e) => {
+                warn!("Failed to resolve playlist for station {}: {}", self.id, e);
+                self.playlist.clone()
+            }
+        }
+    }
 
-        // Check if the response is successful
-        if !response.status().is_success() {
-            return Err(AppError::Station(format!(
-                "Failed to fetch PLS file: HTTP {}",
-                response.status()
-            )));
+    /// URL identifying the station's stream, before it has been resolved
+    #[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+    pub fn link(&self) -> &str {
+        if self.url.is_empty() {
+            &self.playlist
+        } else {
+            &self.url
         }
+    }
 
-        let pls_content = response.text().await.map_err(|e| AppError::Network(e))?;
+    /// Playlist for `quality`, or the closest tier offered
+    ///
+    /// MP3 is preferred within a tier.
+    pub fn playlist_for(&self, quality: StreamQuality) -> Option<&Playlist> {
+        quality
+            .preference()
+            .into_iter()
+            .find_map(|tier| {
+                let mut tier_playlists = self
+                    .playlists
+                    .iter()
+                    .filter(|playlist| playlist.quality == tier.as_str());
+                let first = tier_playlists.clone().next();
+                tier_playlists
+                    .find(|playlist| playlist.format == "mp3")
+                    .or(first)
+            })
+            .or_else(|| self.playlists.first())
+    }
 
-        let mut stream_url = String::new();
-        for line in pls_content.lines() {
-            if line.starts_with("File") {
-                if let Some(url) = line.split('=').nth(1) {
-                    stream_url = url.trim().to_string();
-                    break;
-                }
+    /// Seed the playlist cache with stream URLs resolved in an earlier session
+    pub fn remember_streams(streams: HashMap<String, String>) {
+        if let Ok(mut cache) = RESOLVED.lock() {
+            for (playlist, stream) in streams {
+                cache.entry(playlist).or_insert(stream);
             }
         }
+    }
 
-        if stream_url.is_empty() {
-            Err(AppError::Station(
-                "No stream URL found in PLS file".to_string(),
-            ))
-        } else {
-            Ok(stream_url)
+    /// Forget the stream URL resolved from `playlist`, so the next play
+    /// fetches the playlist again
+    pub fn forget_stream(playlist: &str) {
+        if let Ok(mut cache) = RESOLVED.lock() {
+            cache.remove(playlist);
         }
     }
 
-    pub async fn fetch_all() -> Result<Vec<Self>, AppError> {
-        let client = reqwest::Client::new();
-        let response = client
-            .get("https://somafm.com/channels.json")
+    /// Fetch a PLS or M3U playlist and return its first stream URL
+    ///
+    /// Successful lookups are cached for the rest of the session and saved
+    /// with the catalog cache.
+    pub async fn resolve_playlist(url: &str) -> Result<String, AppError> {
+        // Handle empty URLs
+        if url.is_empty() {
+            return Err(AppError::Station("Empty playlist URL provided".to_string()));
+        }
+
+        if let Some(resolved) = RESOLVED
+            .lock()
+            .ok()
+            .and_then(|cache| cache.get(url).cloned())
+        {
+            return Ok(resolved);
+        }
+
+        let response = crate::http::client()
+            .get(url)
             .send()
             .await
             .map_err(|e| AppError::Network(e))?;
@@ -87,71 +368,296 @@ impl Station {
         // Check if the response is successful
         if !response.status().is_success() {
             return Err(AppError::Station(format!(
-                "Failed to fetch channels: HTTP {}",
+                "Failed to fetch playlist: HTTP {}",
                 response.status()
             )));
         }
 
-        let response: ChannelResponse = response.json().await.map_err(|e| AppError::Network(e))?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let content = response.text().await.map_err(|e| AppError::Network(e))?;
 
-        let stations = futures::future::try_join_all(response.channels.into_iter().map(
-            |channel| async move {
-                // Find the highest quality mp3 URL as primary choice
-                let mp3_highest = channel
-                    .playlists
-                    .iter()
-                    .find(|p| p.format == "mp3" && p.quality == "highest")
-                    .map(|p| p.url.clone());
+        let format = PlaylistFormat::detect(url, content_type.as_deref(), &content);
+        let entry = match format {
+            PlaylistFormat::Pls => parse_pls(&content),
+            PlaylistFormat::M3u => parse_m3u(&content),
+        };
 
-                // Fallback to any mp3 URL
-                let mp3_any = channel
-                    .playlists
-                    .iter()
-                    .find(|p| p.format == "mp3")
-                    .map(|p| p.url.clone());
-
-                // Fallback to any playlist URL
-                let any_url = if channel.playlists.is_empty() {
-                    None
-                } else {
-                    Some(channel.playlists[0].url.clone())
-                };
-
-                // Try URLs in order of preference
-                let playlist_url = mp3_highest.or(mp3_any).or(any_url).unwrap_or_default();
-
-                // Only try to parse PLS if we have a URL
-                let stream_url = if !playlist_url.is_empty() {
-                    match Self::parse_pls(&playlist_url).await {
-                        Ok(url) => url,
-                        Err(e) => {
-                            eprintln!(
-                                "Warning: Failed to parse playlist for station {}: {}",
-                                channel.id, e
-                            );
-                            // Return the playlist URL directly as fallback
-                            playlist_url.clone()
-                        }
-                    }
-                } else {
-                    eprintln!("Warning: No playlist URL found for station {}", channel.id);
-                    String::new()
-                };
-
-                Ok::<Station, AppError>(Station {
-                    id: channel.id,
-                    title: channel.title,
-                    description: channel.description,
-                    dj: channel.dj,
-                    genre: channel.genre,
-                    url: stream_url,
-                    image: channel.image,
-                    last_playing: channel.last_playing,
-                })
-            },
-        ))
-        .await?;
-
-        Ok(stations)
+        match entry {
+            Some(entry) => {
+                let resolved = resolve_entry(url, entry);
+                if let Ok(mut cache) = RESOLVED.lock() {
+                    cache.insert(url.to_string(), resolved.clone());
+                }
+                crate::catalog_cache::remember_stream(url, &resolved);
+                Ok(resolved)
+            }
+            None => Err(AppError::Station(format!(
+                "No stream URL found in {} file",
+                format.name()
+            ))),
+        }
+    }
+}
+
+/// Playlist file formats understood by [`Station::resolve_playlist`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    Pls,
+    M3u,
+}
+
+impl PlaylistFormat {
+    /// Pick the format from the URL extension, then the Content-Type, then the content itself
+    pub fn detect(url: &str, content_type: Option<&str>, content: &str) -> Self {
+        let path = url
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if path.ends_with(".pls") {
+            return Self::Pls;
+        }
+        if path.ends_with(".m3u") || path.ends_with(".m3u8") {
+            return Self::M3u;
+        }
+
+        let mime = content_type
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_ascii_lowercase());
+        match mime.as_deref() {
+            Some("audio/x-scpls" | "audio/scpls") => return Self::Pls,
+            Some(
+                "audio/x-mpegurl"
+                | "audio/mpegurl"
+                | "application/x-mpegurl"
+                | "application/vnd.apple.mpegurl",
+            ) => return Self::M3u,
+            _ => {}
+        }
+
+        let first_line = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+        if first_line.eq_ignore_ascii_case("[playlist]") {
+            Self::Pls
+        } else if first_line.starts_with('#') || first_line.contains("://") {
+            Self::M3u
+        } else {
+            Self::Pls
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Pls => "PLS",
+            Self::M3u => "M3U",
+        }
+    }
+}
+
+/// First `FileN=` entry of a PLS playlist
+fn parse_pls(content: &str) -> Option<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("File"))
+        .filter_map(|line| line.split_once('='))
+        .map(|(_, url)| url.trim())
+        .find(|url| !url.is_empty())
+}
+
+/// First entry of an M3U/M3U8 playlist, skipping `#EXTM3U`/`#EXTINF` directives
+fn parse_m3u(content: &str) -> Option<&str> {
+    content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Resolve a playlist entry relative to the playlist's own URL
+fn resolve_entry(playlist_url: &str, entry: &str) -> String {
+    url::Url::parse(playlist_url)
+        .and_then(|base| base.join(entry))
+        .map(String::from)
+        .unwrap_or_else(|_| entry.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_url_builds_synthetic_station() {
+        let station = Station::from_url("http://example.com:8000/live/stream.mp3");
+        assert_eq!(station.id, URL_STATION_ID);
+        assert_eq!(station.title, "example.com/live/stream.mp3");
+        assert_eq!(station.url, "http://example.com:8000/live/stream.mp3");
+    }
+
+    #[test]
+    fn parses_pls_and_m3u_entries() {
+        let pls = "[playlist]\nnumberofentries=1\nFile1=https://ice1.somafm.com/groovesalad-256-mp3?a=b\nTitle1=Groove Salad\n";
+        assert_eq!(
+            parse_pls(pls),
+            Some("https://ice1.somafm.com/groovesalad-256-mp3?a=b")
+        );
+
+        let m3u = "\u{feff}#EXTM3U\n\n#EXTINF:-1,Groove Salad\nhttps://ice2.somafm.com/groovesalad-128-mp3\nhttps://ice4.somafm.com/groovesalad-128-mp3\n";
+        assert_eq!(
+            parse_m3u(m3u),
+            Some("https://ice2.somafm.com/groovesalad-128-mp3")
+        );
+        assert_eq!(parse_m3u("#EXTM3U\n"), None);
+        assert_eq!(
+            resolve_entry("http://radio.local/lists/live.m3u", "../stream.mp3"),
+            "http://radio.local/stream.mp3"
+        );
+    }
+
+    #[test]
+    fn detects_playlist_format() {
+        use PlaylistFormat::*;
+        assert_eq!(
+            PlaylistFormat::detect("https://somafm.com/groovesalad.pls", None, ""),
+            Pls
+        );
+        assert_eq!(
+            PlaylistFormat::detect("https://example.com/live.M3U8?token=1", None, ""),
+            M3u
+        );
+        assert_eq!(
+            PlaylistFormat::detect(
+                "https://example.com/listen",
+                Some("audio/x-mpegurl; charset=utf-8"),
+                ""
+            ),
+            M3u
+        );
+        assert_eq!(
+            PlaylistFormat::detect(
+                "https://example.com/listen",
+                None,
+                "[playlist]\nFile1=http://a/b"
+            ),
+            Pls
+        );
+        assert_eq!(
+            PlaylistFormat::detect("https://example.com/listen", None, "http://a/b\n"),
+            M3u
+        );
+    }
+
+    #[test]
+    fn sorts_stations() {
+        let station = |id: &str, title: &str, genre: &str, listeners: u32| Station {
+            id: id.to_string(),
+            title: title.to_string(),
+            genre: genre.to_string(),
+            listeners,
+            ..Station::from_url("http://example.com/stream")
+        };
+        let catalog = vec![
+            station("groovesalad", "Groove Salad", "ambient", 900),
+            station("dronezone", "Drone Zone", "ambient", 400),
+            station("bootliquor", "Boot Liquor", "americana", 900),
+        ];
+        let ids = |sort: StationSort| {
+            let mut stations = catalog.clone();
+            sort.sort(&mut stations);
+            stations.into_iter().map(|s| s.id).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids(StationSort::Catalog),
+            ["groovesalad", "dronezone", "bootliquor"]
+        );
+        assert_eq!(
+            ids(StationSort::Listeners),
+            ["groovesalad", "bootliquor", "dronezone"]
+        );
+        assert_eq!(
+            ids(StationSort::Title),
+            ["bootliquor", "dronezone", "groovesalad"]
+        );
+        assert_eq!(
+            ids(StationSort::Genre),
+            ["groovesalad", "dronezone", "bootliquor"]
+        );
+    }
+
+    #[test]
+    fn lists_genres() {
+        let station = |genre: &str| Station {
+            genre: genre.to_string(),
+            ..Station::from_url("http://example.com/stream")
+        };
+        let stations = vec![
+            station("ambient|electronica"),
+            station("Ambient"),
+            station(""),
+            station("folk| americana"),
+        ];
+        assert_eq!(
+            genres(&stations),
+            ["ambient", "americana", "electronica", "folk"]
+        );
+        assert!(stations[3].has_genre("Americana"));
+        assert!(!stations[0].has_genre("ambi"));
+
+        let keys: Vec<_> = genre_chips(&stations)
+            .into_iter()
+            .map(|chip| (chip.genre, chip.key))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("ambient".to_string(), 'a'),
+                ("americana".to_string(), 'm'),
+                ("electronica".to_string(), 'e'),
+                ("folk".to_string(), 'f'),
+            ]
+        );
+    }
+
+    #[test]
+    fn picks_playlist_for_quality() {
+        let playlist = |format: &str, quality: &str| Playlist {
+            url: format!("https://somafm.com/{}-{}.pls", format, quality),
+            format: format.to_string(),
+            quality: quality.to_string(),
+        };
+        let mut station = Station::from_url("https://somafm.com/groovesalad");
+        station.playlists = vec![
+            playlist("aac", "highest"),
+            playlist("mp3", "highest"),
+            playlist("aacp", "low"),
+        ];
+        let url = |quality| station.playlist_for(quality).map(|p| p.url.as_str());
+        assert_eq!(
+            url(StreamQuality::Highest),
+            Some("https://somafm.com/mp3-highest.pls")
+        );
+        assert_eq!(
+            url(StreamQuality::High),
+            Some("https://somafm.com/mp3-highest.pls")
+        );
+        assert_eq!(
+            url(StreamQuality::Low),
+            Some("https://somafm.com/aacp-low.pls")
+        );
+        station.select_quality(StreamQuality::Low);
+        assert_eq!(station.playlist, "https://somafm.com/aacp-low.pls");
+        assert!(station.url.is_empty());
+        assert_eq!(station.link(), "https://somafm.com/aacp-low.pls");
+        assert_eq!("low".parse(), Ok(StreamQuality::Low));
+        assert_eq!(StreamQuality::Low.next(), StreamQuality::Highest);
     }
 }
@@ -0,0 +1,186 @@
+//! Station list changes
+//!
+//! SomaFM adds, renames and retires channels every so often, with nothing in
+//! the station list to say so. The channels seen are remembered in the data
+//! folder, so one that appeared while somars wasn't running is still pointed
+//! out the next time the list loads.
+
+use crate::station::{LiveStatus, Station, URL_STATION_ID};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Channels added, renamed and removed since the list was last seen
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StationChanges {
+    /// ID and title of each new channel
+    pub added: Vec<(String, String)>,
+    /// ID, old title and new title of each renamed channel
+    pub renamed: Vec<(String, String, String)>,
+    /// ID and last known title of each channel gone from the list
+    pub removed: Vec<(String, String)>,
+}
+
+/// Titles of the channels seen before, by ID
+#[derive(Debug)]
+pub struct KnownStations {
+    path: Option<PathBuf>,
+    /// `None` until a list has been seen, so the first one isn't all new
+    titles: Option<BTreeMap<String, String>>,
+}
+
+impl KnownStations {
+    /// The channels seen on earlier runs, read from `path`
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let titles = path.as_deref().and_then(read);
+        Self { path, titles }
+    }
+
+    /// Compare `stations` with the channels seen before and remember them
+    ///
+    /// Stations that aren't from SomaFM are left out. Nothing counts as
+    /// changed the first time a list is seen.
+    pub fn update(&mut self, stations: &[Station]) -> StationChanges {
+        let titles = titles(stations);
+        if titles.is_empty() {
+            return StationChanges::default();
+        }
+        let changes = self
+            .titles
+            .as_ref()
+            .map(|known| diff(known, &titles))
+            .unwrap_or_default();
+        if self.titles.as_ref() != Some(&titles) {
+            if let Some(path) = &self.path {
+                write(path, &titles);
+            }
+            self.titles = Some(titles);
+        }
+        changes
+    }
+}
+
+/// Where the channels seen are remembered
+pub fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("somars").join("known_stations.json"))
+}
+
+/// Whether `live` lists different channels than the SomaFM stations in
+/// `catalog`, or gives one another title
+pub fn live_differs(catalog: &[Station], live: &HashMap<String, LiveStatus>) -> bool {
+    let titles = titles(catalog);
+    titles.len() != live.len()
+        || live
+            .iter()
+            .any(|(id, status)| titles.get(id) != Some(&status.title))
+}
+
+fn titles(stations: &[Station]) -> BTreeMap<String, String> {
+    stations
+        .iter()
+        .filter(|station| {
+            station.id != URL_STATION_ID && station.source == crate::providers::SOMAFM
+        })
+        .map(|station| (station.id.clone(), station.title.clone()))
+        .collect()
+}
+
+fn diff(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> StationChanges {
+    let mut changes = StationChanges::default();
+    for (id, title) in new {
+        match old.get(id) {
+            None => changes.added.push((id.clone(), title.clone())),
+            Some(old_title) if old_title != title => {
+                changes
+                    .renamed
+                    .push((id.clone(), old_title.clone(), title.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+    for (id, title) in old {
+        if !new.contains_key(id) {
+            changes.removed.push((id.clone(), title.clone()));
+        }
+    }
+    changes
+}
+
+fn read(path: &Path) -> Option<BTreeMap<String, String>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write(path: &Path, titles: &BTreeMap<String, String>) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let contents = serde_json::to_string(titles).map_err(std::io::Error::other)?;
+            std::fs::write(path, contents)
+        });
+    if let Err(error) = result {
+        warn!(
+            "Failed to save known stations {}: {}",
+            path.display(),
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_out_changes_since_the_last_list() {
+        let path =
+            std::env::temp_dir().join(format!("somars-known-stations-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut known = KnownStations::load(Some(path.clone()));
+        let first = [
+            Station::for_test("groovesalad", "Groove Salad"),
+            Station::for_test("bootliquor", "Boot Liquor"),
+        ];
+        assert_eq!(known.update(&first), StationChanges::default());
+
+        // The next run compares with the saved list
+        let mut known = KnownStations::load(Some(path.clone()));
+        let custom = Station {
+            source: "Custom".to_string(),
+            ..Station::for_test("mine", "Mine")
+        };
+        let second = [
+            Station::for_test("groovesalad", "Groove Salad Classic"),
+            Station::for_test("vaporwaves", "Vaporwaves"),
+            custom,
+        ];
+        assert_eq!(
+            known.update(&second),
+            StationChanges {
+                added: vec![("vaporwaves".to_string(), "Vaporwaves".to_string())],
+                renamed: vec![(
+                    "groovesalad".to_string(),
+                    "Groove Salad".to_string(),
+                    "Groove Salad Classic".to_string()
+                )],
+                removed: vec![("bootliquor".to_string(), "Boot Liquor".to_string())],
+            }
+        );
+        assert_eq!(known.update(&second), StationChanges::default());
+
+        let live: HashMap<String, LiveStatus> = [("vaporwaves", "Vaporwaves")]
+            .into_iter()
+            .map(|(id, title)| {
+                let status = LiveStatus {
+                    title: title.to_string(),
+                    ..LiveStatus::default()
+                };
+                (id.to_string(), status)
+            })
+            .collect();
+        assert!(live_differs(&second, &live));
+        assert!(!live_differs(&second[1..], &live));
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,58 @@
+//! Typed client for the status HTTP API
+//!
+//! For tools that want to read a running somars instance without handling
+//! the JSON themselves. The endpoints are described by the OpenAPI document
+//! the server publishes at `/openapi.json`.
+
+use crate::status_server::NowPlaying;
+use url::Url;
+
+/// Client for one somars status server
+#[derive(Debug, Clone)]
+pub struct StatusClient {
+    base_url: Url,
+    client: reqwest::Client,
+    token: Option<String>,
+}
+
+impl StatusClient {
+    /// Client for the server at `base_url`, e.g. `http://127.0.0.1:8071`
+    pub fn new(base_url: &str) -> Result<Self, url::ParseError> {
+        let mut base_url = Url::parse(base_url)?;
+        // Keep a path prefix such as a reverse proxy's when joining endpoints
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        Ok(Self {
+            base_url,
+            client: crate::http::client().clone(),
+            token: None,
+        })
+    }
+
+    /// Send `token` with every request, for servers that need one
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// `GET /now.json`
+    pub async fn now_playing(&self) -> reqwest::Result<NowPlaying> {
+        self.get("now.json").await?.json().await
+    }
+
+    /// `GET /openapi.json`
+    pub async fn openapi(&self) -> reqwest::Result<serde_json::Value> {
+        self.get("openapi.json").await?.json().await
+    }
+
+    async fn get(&self, endpoint: &str) -> reqwest::Result<reqwest::Response> {
+        // Endpoint names are fixed, so joining them can't fail
+        let url = self.base_url.join(endpoint).expect("valid endpoint");
+        let mut request = self.client.get(url);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await?.error_for_status()
+    }
+}
@@ -0,0 +1,305 @@
+//! Now playing line for status bars
+//!
+//! Keeps a one-line description of what plays in a file set in the
+//! `[status_file]` table, for tmux, polybar, conky and other bars that read a
+//! file or a named pipe. The line is built from a template:
+//!
+//! ```toml
+//! [status_file]
+//! enabled = true
+//! path = "/tmp/somars-now"
+//! format = "♪ {artist} - {title} [{station}]"
+//! ```
+//!
+//! `{state}`, `{station}`, `{station_id}`, `{track}` (the whole stream
+//! title), `{artist}`, `{title}` and `{volume}` (a percentage) are filled in,
+//! and the result trimmed. While stopped, and once somars quits, the file
+//! holds `stopped_text` instead.
+//!
+//! A regular file is replaced through a temporary one, so a bar never reads
+//! half a line. A named pipe gets each new line written to it, and lines no
+//! one is reading are dropped rather than waited on.
+
+use crate::scrobble_log::split_title;
+use crate::station::Station;
+use crate::utils::fill_placeholders;
+use crate::PlaybackState;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// Status file settings, read from the `[status_file]` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusFileConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// File or named pipe to write to; `somars-now` in the temporary
+    /// directory when unset
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Template for the line while playing or paused
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Line written while stopped
+    #[serde(default)]
+    pub stopped_text: String,
+}
+
+fn default_format() -> String {
+    "{track} [{station}]".to_string()
+}
+
+impl Default for StatusFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            format: default_format(),
+            stopped_text: String::new(),
+        }
+    }
+}
+
+impl StatusFileConfig {
+    /// Where the line is written
+    pub fn path(&self) -> PathBuf {
+        self.path
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("somars-now"))
+    }
+}
+
+/// What the line describes
+#[derive(Debug, Clone, PartialEq)]
+struct NowPlaying {
+    state: PlaybackState,
+    station: Option<(String, String)>,
+    track: Option<String>,
+    volume: f32,
+}
+
+impl NowPlaying {
+    /// The line for this, built from `config`
+    fn render(&self, config: &StatusFileConfig) -> String {
+        if self.state == PlaybackState::Stopped {
+            return config.stopped_text.clone();
+        }
+        let state = crate::i18n::t(match self.state {
+            PlaybackState::Playing => "playing",
+            PlaybackState::Paused => "paused",
+            PlaybackState::Stopped => "stopped",
+        });
+        let (station_id, station) = self
+            .station
+            .as_ref()
+            .map(|(id, title)| (id.as_str(), title.as_str()))
+            .unwrap_or_default();
+        let track = self.track.as_deref().unwrap_or_default();
+        let (artist, title) = split_title(track).unwrap_or(("", track.trim()));
+        let volume = format!("{:.0}%", self.volume * 100.0);
+        fill_placeholders(
+            &config.format,
+            &[
+                ("state", &state),
+                ("station", station),
+                ("station_id", station_id),
+                ("track", track),
+                ("artist", artist),
+                ("title", title),
+                ("volume", &volume),
+            ],
+        )
+        .trim()
+        .to_string()
+    }
+}
+
+/// Keeps the status file up to date with what plays
+#[derive(Debug)]
+pub struct StatusFile {
+    config: StatusFileConfig,
+    now: NowPlaying,
+    /// Lines for the writing task, `None` when disabled
+    line_tx: Option<watch::Sender<String>>,
+}
+
+impl StatusFile {
+    /// Start writing the status file if it is enabled in the configuration
+    pub fn start(config: &StatusFileConfig, volume: f32) -> Self {
+        let now = NowPlaying {
+            state: PlaybackState::Stopped,
+            station: None,
+            track: None,
+            volume,
+        };
+        let line_tx = config.enabled.then(|| {
+            let (line_tx, line_rx) = watch::channel(now.render(config));
+            tokio::spawn(run(config.path(), line_rx));
+            line_tx
+        });
+        Self {
+            config: config.clone(),
+            now,
+            line_tx,
+        }
+    }
+
+    pub fn set_playback_state(&mut self, state: PlaybackState) {
+        if state == PlaybackState::Stopped {
+            self.now.track = None;
+        }
+        self.now.state = state;
+        self.update();
+    }
+
+    pub fn set_station(&mut self, station: &Station) {
+        self.now.station = Some((station.id.clone(), station.title.clone()));
+        self.now.track = None;
+        self.update();
+    }
+
+    pub fn set_track_title(&mut self, title: String) {
+        self.now.track = Some(title);
+        self.update();
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.now.volume = volume;
+        self.update();
+    }
+
+    /// Leave the stopped text behind, as nothing plays once somars quits
+    pub fn quit(&mut self) {
+        if self.line_tx.take().is_some() {
+            write(&self.config.path(), &self.config.stopped_text);
+        }
+    }
+
+    fn update(&self) {
+        if let Some(line_tx) = &self.line_tx {
+            let line = self.now.render(&self.config);
+            line_tx.send_if_modified(|current| {
+                let changed = *current != line;
+                if changed {
+                    *current = line;
+                }
+                changed
+            });
+        }
+    }
+}
+
+/// Write each new line until the app exits
+async fn run(path: PathBuf, mut line_rx: watch::Receiver<String>) {
+    loop {
+        let line = line_rx.borrow_and_update().clone();
+        let writing = path.clone();
+        let _ = tokio::task::spawn_blocking(move || write(&writing, &line)).await;
+        if line_rx.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+fn write(path: &Path, line: &str) {
+    let result = if is_fifo(path) {
+        write_fifo(path, line)
+    } else {
+        replace(path, line)
+    };
+    if let Err(error) = result {
+        warn!("Failed to write status file {}: {}", path.display(), error);
+    }
+}
+
+/// Replace the file's contents through a temporary file
+fn replace(path: &Path, line: &str) -> io::Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    std::fs::write(&partial, format!("{}\n", line))?;
+    std::fs::rename(&partial, path)
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
+
+/// Write the line to a named pipe if something is reading it
+#[cfg(unix)]
+fn write_fifo(path: &Path, line: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let pipe = std::fs::OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path);
+    let mut pipe = match pipe {
+        Ok(pipe) => pipe,
+        // No reader
+        Err(error) if error.raw_os_error() == Some(libc::ENXIO) => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    match pipe.write_all(format!("{}\n", line).as_bytes()) {
+        // The reader isn't keeping up
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(not(unix))]
+fn write_fifo(path: &Path, line: &str) -> io::Result<()> {
+    replace(path, line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_the_line_from_the_template() {
+        let config = StatusFileConfig {
+            format: "{state}: {artist} - {title} on {station} ({station_id}) {volume}".to_string(),
+            stopped_text: "off".to_string(),
+            ..StatusFileConfig::default()
+        };
+        let mut now = NowPlaying {
+            state: PlaybackState::Playing,
+            station: Some(("groovesalad".to_string(), "Groove Salad".to_string())),
+            track: Some("Boards of Canada - Roygbiv".to_string()),
+            volume: 0.8,
+        };
+        assert_eq!(
+            now.render(&config),
+            format!(
+                "{}: Boards of Canada - Roygbiv on Groove Salad (groovesalad) 80%",
+                crate::i18n::t("playing")
+            )
+        );
+
+        // The default drops what isn't known yet
+        now.track = None;
+        assert_eq!(now.render(&StatusFileConfig::default()), "[Groove Salad]");
+
+        now.state = PlaybackState::Stopped;
+        assert_eq!(now.render(&config), "off");
+    }
+
+    #[test]
+    fn replaces_the_whole_file() {
+        let path = std::env::temp_dir().join(format!("somars-status-file-{}", std::process::id()));
+        write(&path, "first line that is long");
+        write(&path, "second");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "second\n");
+        let _ = std::fs::remove_file(&path);
+    }
+}
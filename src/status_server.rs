@@ -0,0 +1,476 @@
+//! Now Playing HTTP endpoint
+//!
+//! Serves the current station, track, playback state and volume as JSON at
+//! `GET /now.json`, for status bars and dashboards that just want to poll:
+//!
+//! ```json
+//! {"state":"playing","station":{"id":"groovesalad","title":"Groove Salad","genre":"ambient|electronica"},"track":"Artist - Title","volume":0.8,"recording":null}
+//! ```
+//!
+//! The API is described by an OpenAPI document at `GET /openapi.json`, and
+//! [`crate::status_client`] is a typed client for it. When senders without a
+//! token may not read the status, a token with the `status` permission goes in
+//! an `Authorization: Bearer` header or a `token` query parameter.
+
+use crate::audio::recorder::RecordingStatus;
+use crate::control::{request_token, Permission, Permissions};
+use crate::{config::StatusServerConfig, station::Station, PlaybackState};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+/// Largest request head read before answering
+const MAX_REQUEST_BYTES: usize = 8192;
+/// Connections idle this long are dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// OpenAPI document served at `/openapi.json`
+static OPENAPI: Lazy<String> = Lazy::new(|| openapi().to_string());
+
+/// Playback state as reported by `/now.json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NowState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+/// The station in `/now.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NowStation {
+    pub id: String,
+    pub title: String,
+    /// Genres separated by `|`
+    pub genre: String,
+}
+
+/// Body of `/now.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub state: NowState,
+    /// `None` until a station is tuned
+    pub station: Option<NowStation>,
+    /// ICY stream title, `None` until one arrives
+    pub track: Option<String>,
+    pub volume: f32,
+    /// The recording in progress, `None` when not recording
+    #[serde(default)]
+    pub recording: Option<RecordingStatus>,
+}
+
+impl Default for NowPlaying {
+    fn default() -> Self {
+        Self {
+            state: NowState::Stopped,
+            station: None,
+            track: None,
+            volume: 1.0,
+            recording: None,
+        }
+    }
+}
+
+/// Handle for updating the state served at `/now.json`
+#[derive(Clone, Debug)]
+pub struct StatusServerHandle {
+    state_tx: Option<watch::Sender<NowPlaying>>,
+}
+
+impl StatusServerHandle {
+    /// Start the HTTP server if it is enabled in the configuration.
+    pub fn start(config: &StatusServerConfig, permissions: Permissions, volume: f32) -> Self {
+        if !config.enabled {
+            return Self { state_tx: None };
+        }
+
+        let (state_tx, state_rx) = watch::channel(NowPlaying {
+            volume,
+            ..NowPlaying::default()
+        });
+        let address = format!("{}:{}", config.bind, config.port);
+        tokio::spawn(async move {
+            run(address, permissions, state_rx).await;
+        });
+        Self {
+            state_tx: Some(state_tx),
+        }
+    }
+
+    pub fn set_playback_state(&self, state: PlaybackState) {
+        self.update(|now| {
+            now.state = match state {
+                PlaybackState::Playing => NowState::Playing,
+                PlaybackState::Paused => NowState::Paused,
+                PlaybackState::Stopped => NowState::Stopped,
+            }
+        });
+    }
+
+    pub fn set_station(&self, station: Station) {
+        self.update(|now| {
+            now.station = Some(NowStation {
+                id: station.id,
+                title: station.title,
+                genre: station.genre,
+            });
+            now.track = None;
+        });
+    }
+
+    pub fn set_track_title(&self, title: String) {
+        self.update(|now| now.track = Some(title));
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.update(|now| now.volume = volume);
+    }
+
+    pub fn set_recording(&self, recording: Option<RecordingStatus>) {
+        self.update(|now| now.recording = recording);
+    }
+
+    fn update(&self, modify: impl FnOnce(&mut NowPlaying)) {
+        if let Some(state_tx) = &self.state_tx {
+            state_tx.send_modify(modify);
+        }
+    }
+}
+
+async fn run(address: String, permissions: Permissions, state_rx: watch::Receiver<NowPlaying>) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            warn!(%error, %address, "Failed to start status HTTP server");
+            return;
+        }
+    };
+    info!(%address, "Status HTTP server listening");
+    accept(listener, permissions, state_rx).await;
+}
+
+async fn accept(
+    listener: TcpListener,
+    permissions: Permissions,
+    state_rx: watch::Receiver<NowPlaying>,
+) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                warn!(%error, "Failed to accept HTTP connection");
+                continue;
+            }
+        };
+        let body = serde_json::to_string(&*state_rx.borrow()).unwrap_or_default();
+        let permissions = permissions.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(REQUEST_TIMEOUT, serve(stream, body, &permissions)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => debug!(%error, %peer, "HTTP client disconnected"),
+                Err(_) => debug!(%peer, "HTTP client timed out"),
+            }
+        });
+    }
+}
+
+/// Answer one request and close the connection
+async fn serve(
+    mut stream: TcpStream,
+    body: String,
+    permissions: &Permissions,
+) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    stream
+        .write_all(response(&request, &body, permissions).as_bytes())
+        .await?;
+    stream.shutdown().await
+}
+
+/// Full HTTP response for the request head `request`
+fn response(request: &str, body: &str, permissions: &Permissions) -> String {
+    let request_line = request.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next());
+    // Query strings are commonly added to defeat caches
+    let (path, query) = match target.map(|target| target.split_once('?').unwrap_or((target, ""))) {
+        Some((path, query)) => (Some(path), query),
+        None => (None, ""),
+    };
+    let authorization = request.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("authorization")
+            .then_some(value)
+    });
+    let token = request_token(authorization, query);
+
+    let (status, body) = match (method, path) {
+        ("GET" | "HEAD", Some("/now.json"))
+            if !permissions.allows(token.as_deref(), Permission::Status) =>
+        {
+            ("401 Unauthorized", r#"{"error":"unauthorized"}"#)
+        }
+        ("GET" | "HEAD", Some("/now.json")) => ("200 OK", body),
+        ("GET" | "HEAD", Some("/openapi.json")) => ("200 OK", OPENAPI.as_str()),
+        ("GET" | "HEAD", _) => ("404 Not Found", r#"{"error":"not found"}"#),
+        _ => (
+            "405 Method Not Allowed",
+            r#"{"error":"method not allowed"}"#,
+        ),
+    };
+    let mut response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Cache-Control: no-store\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    if method != "HEAD" {
+        response.push_str(body);
+    }
+    response
+}
+
+/// OpenAPI 3.0 description of the endpoints above
+fn openapi() -> serde_json::Value {
+    let json = |description: &str, schema: serde_json::Value| {
+        serde_json::json!({
+            "description": description,
+            "content": { "application/json": { "schema": schema } }
+        })
+    };
+    let error = json(
+        "Unknown path or method",
+        serde_json::json!({ "$ref": "#/components/schemas/Error" }),
+    );
+    serde_json::json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "somars status API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Read-only playback status of a running somars instance. \
+                Enable it with the [status_server] config table."
+        },
+        "servers": [{ "url": "http://127.0.0.1:8071" }],
+        "paths": {
+            "/now.json": {
+                "get": {
+                    "operationId": "nowPlaying",
+                    "summary": "Current playback state, station, track and volume",
+                    "security": [{}, { "bearer": [] }, { "query": [] }],
+                    "responses": {
+                        "200": json(
+                            "Now playing",
+                            serde_json::json!({ "$ref": "#/components/schemas/NowPlaying" })
+                        ),
+                        "401": json(
+                            "A token with the status permission is required",
+                            serde_json::json!({ "$ref": "#/components/schemas/Error" })
+                        ),
+                        "default": error
+                    }
+                }
+            },
+            "/openapi.json": {
+                "get": {
+                    "operationId": "openapi",
+                    "summary": "This document",
+                    "responses": {
+                        "200": json("OpenAPI document", serde_json::json!({ "type": "object" })),
+                        "default": error
+                    }
+                }
+            }
+        },
+        "components": {
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "A token from the [remote.tokens] config table"
+                },
+                "query": { "type": "apiKey", "in": "query", "name": "token" }
+            },
+            "schemas": {
+                "NowPlaying": {
+                    "type": "object",
+                    "required": ["state", "station", "track", "volume"],
+                    "properties": {
+                        "state": {
+                            "type": "string",
+                            "enum": ["playing", "paused", "stopped"]
+                        },
+                        "station": {
+                            "allOf": [{ "$ref": "#/components/schemas/Station" }],
+                            "nullable": true,
+                            "description": "null until a station is tuned"
+                        },
+                        "track": {
+                            "type": "string",
+                            "nullable": true,
+                            "description": "Stream title, usually `Artist - Title`; null until one arrives"
+                        },
+                        "volume": { "type": "number", "format": "float", "minimum": 0, "maximum": 2 },
+                        "recording": {
+                            "allOf": [{ "$ref": "#/components/schemas/Recording" }],
+                            "nullable": true,
+                            "description": "The recording in progress; null when not recording"
+                        }
+                    }
+                },
+                "Recording": {
+                    "type": "object",
+                    "required": ["file", "seconds", "bytes"],
+                    "properties": {
+                        "file": {
+                            "type": "string",
+                            "example": "groovesalad-20240302-211400.mp3"
+                        },
+                        "seconds": { "type": "integer", "description": "Time recorded so far" },
+                        "bytes": { "type": "integer", "description": "Size of the file so far" }
+                    }
+                },
+                "Station": {
+                    "type": "object",
+                    "required": ["id", "title", "genre"],
+                    "properties": {
+                        "id": { "type": "string", "example": "groovesalad" },
+                        "title": { "type": "string", "example": "Groove Salad" },
+                        "genre": {
+                            "type": "string",
+                            "description": "Genres separated by `|`",
+                            "example": "ambient|electronica"
+                        }
+                    }
+                },
+                "Error": {
+                    "type": "object",
+                    "required": ["error"],
+                    "properties": { "error": { "type": "string" } }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_now_json_only() {
+        let open = Permissions::default();
+        let ok = response("GET /now.json?t=1 HTTP/1.1", "{}", &open);
+        assert!(ok.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(ok.ends_with("\r\n\r\n{}"));
+        assert!(response("HEAD /now.json HTTP/1.1", "{}", &open).ends_with("\r\n\r\n"));
+        assert!(response("GET / HTTP/1.1", "{}", &open).starts_with("HTTP/1.1 404"));
+        assert!(response("POST /now.json HTTP/1.1", "{}", &open).starts_with("HTTP/1.1 405"));
+    }
+
+    #[test]
+    fn requires_a_status_token_when_configured() {
+        let permissions = Permissions::from_config(&crate::config::RemoteConfig {
+            anonymous: Permission::None,
+            tokens: [("dashboard".to_string(), Permission::Status)].into(),
+        });
+        let status = |request: &str| response(request, "{}", &permissions)[9..12].to_string();
+        assert_eq!(status("GET /now.json HTTP/1.1\r\n\r\n"), "401");
+        assert_eq!(
+            status("GET /now.json?token=dashboard HTTP/1.1\r\n\r\n"),
+            "200"
+        );
+        assert_eq!(
+            status("GET /now.json HTTP/1.1\r\nauthorization: Bearer dashboard\r\n\r\n"),
+            "200"
+        );
+        assert_eq!(status("GET /now.json?token=other HTTP/1.1\r\n\r\n"), "401");
+        // The API description stays public
+        assert_eq!(status("GET /openapi.json HTTP/1.1\r\n\r\n"), "200");
+    }
+
+    #[test]
+    fn openapi_describes_every_route() {
+        let spec = openapi();
+        let paths = spec["paths"].as_object().unwrap();
+        assert_eq!(paths.len(), 2);
+        for path in paths.keys() {
+            let request = format!("GET {} HTTP/1.1", path);
+            assert!(
+                response(&request, "{}", &Permissions::default()).starts_with("HTTP/1.1 200 OK")
+            );
+        }
+        // The schema lists every field the server sends
+        let now = serde_json::to_value(NowPlaying::default()).unwrap();
+        let properties = spec["components"]["schemas"]["NowPlaying"]["properties"]
+            .as_object()
+            .unwrap();
+        assert!(now
+            .as_object()
+            .unwrap()
+            .keys()
+            .all(|field| properties.contains_key(field)));
+    }
+
+    #[test]
+    fn station_change_clears_track() {
+        let (state_tx, state_rx) = watch::channel(NowPlaying::default());
+        let handle = StatusServerHandle {
+            state_tx: Some(state_tx),
+        };
+        handle.set_track_title("Old - Track".to_string());
+        handle.set_station(Station {
+            id: "groovesalad".to_string(),
+            title: "Groove Salad".to_string(),
+            genre: "ambient".to_string(),
+            ..Station::from_url("http://example.com/stream")
+        });
+        handle.set_playback_state(PlaybackState::Playing);
+        handle.set_volume(0.5);
+        assert_eq!(
+            serde_json::to_string(&*state_rx.borrow()).unwrap(),
+            r#"{"state":"playing","station":{"id":"groovesalad","title":"Groove Salad","genre":"ambient"},"track":null,"volume":0.5,"recording":null}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn client_reads_the_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let now = NowPlaying {
+            state: NowState::Paused,
+            track: Some("Artist - Title".to_string()),
+            ..NowPlaying::default()
+        };
+        let (_state_tx, state_rx) = watch::channel(now.clone());
+        let permissions = Permissions::from_config(&crate::config::RemoteConfig {
+            anonymous: Permission::None,
+            tokens: [("dashboard".to_string(), Permission::Status)].into(),
+        });
+        tokio::spawn(accept(listener, permissions, state_rx));
+
+        let client =
+            crate::status_client::StatusClient::new(&format!("http://{}", address)).unwrap();
+        assert!(client.now_playing().await.is_err());
+        assert_eq!(client.openapi().await.unwrap()["openapi"], "3.0.3");
+        let client = client.with_token("dashboard");
+        assert_eq!(client.now_playing().await.unwrap(), now);
+    }
+}
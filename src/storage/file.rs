@@ -0,0 +1,176 @@
+//! Store keeping JSON Lines files
+//!
+//! Plays go to `tracks.jsonl` and likes to `favorites.jsonl`, one JSON object
+//! per line. Lines that don't parse are skipped when reading, so a file cut
+//! short by a crash loses one entry at most.
+
+use super::Store;
+use crate::favorites::Favorite;
+use crate::track_log::{self, TrackRecord};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Keeps plays and likes in JSON Lines files
+#[derive(Debug)]
+pub struct FileStore {
+    /// `None` when the user data directory is unknown
+    tracks: Option<PathBuf>,
+    favorites: Option<PathBuf>,
+}
+
+impl FileStore {
+    pub fn new(tracks: Option<PathBuf>, favorites: Option<PathBuf>) -> Self {
+        Self { tracks, favorites }
+    }
+}
+
+fn required(path: &Option<PathBuf>) -> io::Result<&Path> {
+    path.as_deref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user data directory"))
+}
+
+impl Store for FileStore {
+    fn location(&self) -> String {
+        self.tracks
+            .as_deref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default()
+    }
+
+    fn plays(&self) -> io::Result<Vec<TrackRecord>> {
+        read(required(&self.tracks)?)
+    }
+
+    fn add_plays(&mut self, records: Vec<TrackRecord>) -> io::Result<usize> {
+        let path = required(&self.tracks)?;
+        let mut plays = read(path)?;
+        let records = track_log::new_plays(&plays, records);
+        let added = records.len();
+        let last = plays.last().map_or(0, |play| play.started);
+        if records.iter().all(|record| record.started >= last) {
+            // The usual case, a play just heard: no need to rewrite the log
+            append(path, &records)?;
+            return Ok(added);
+        }
+        plays.extend(records);
+        plays.sort_by_key(|play| play.started);
+        replace(path, &plays)?;
+        Ok(added)
+    }
+
+    fn favorites(&self) -> io::Result<Vec<Favorite>> {
+        read(required(&self.favorites)?)
+    }
+
+    fn add_favorite(&mut self, favorite: &Favorite) -> io::Result<bool> {
+        let path = required(&self.favorites)?;
+        if read::<Favorite>(path)?
+            .iter()
+            .any(|liked| liked.key() == favorite.key())
+        {
+            return Ok(false);
+        }
+        append(path, std::slice::from_ref(favorite))?;
+        Ok(true)
+    }
+}
+
+/// Read a JSON Lines file, skipping lines that don't parse; a missing file
+/// reads as empty
+pub(crate) fn read<T: for<'de> Deserialize<'de>>(path: &Path) -> io::Result<Vec<T>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Add `items` to the end of a JSON Lines file, creating it if needed
+pub(crate) fn append<T: Serialize>(path: &Path, items: &[T]) -> io::Result<()> {
+    if items.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for item in items {
+        lines.push_str(&serde_json::to_string(item).map_err(io::Error::other)?);
+        lines.push('\n');
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+/// Replace a JSON Lines file with `items`, through a temporary file so a crash
+/// leaves the old contents
+pub(crate) fn replace<T: Serialize>(path: &Path, items: &[T]) -> io::Result<()> {
+    let partial = path.with_extension("jsonl.partial");
+    let _ = std::fs::remove_file(&partial);
+    append(&partial, items)?;
+    std::fs::rename(&partial, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_plays_in_order_and_likes_once() {
+        let dir = std::env::temp_dir().join(format!("somars-file-store-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut store = FileStore::new(
+            Some(dir.join("tracks.jsonl")),
+            Some(dir.join("favorites.jsonl")),
+        );
+        let play = |started, title: &str| TrackRecord {
+            started,
+            seconds: 10,
+            station: "Fluid".to_string(),
+            artist: "A".to_string(),
+            title: title.to_string(),
+            session: 0,
+        };
+        assert!(store.plays().unwrap().is_empty());
+        assert_eq!(store.add_plays(vec![play(200, "Two")]).unwrap(), 1);
+        // An older play is sorted in, a known one skipped
+        assert_eq!(
+            store
+                .add_plays(vec![play(100, "One"), play(200, "Two")])
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.add_plays(vec![play(300, "Three")]).unwrap(), 1);
+        let titles: Vec<_> = store
+            .plays()
+            .unwrap()
+            .into_iter()
+            .map(|play| play.title)
+            .collect();
+        assert_eq!(titles, ["One", "Two", "Three"]);
+        assert_eq!(store.search("two").unwrap()[0].last_heard, 200);
+
+        let favorite = Favorite {
+            liked: 150,
+            station: "Fluid".to_string(),
+            artist: "A".to_string(),
+            title: "One".to_string(),
+        };
+        assert!(store.add_favorite(&favorite).unwrap());
+        let shouted = Favorite {
+            title: "ONE".to_string(),
+            ..favorite.clone()
+        };
+        assert!(!store.add_favorite(&shouted).unwrap());
+        assert_eq!(store.favorites().unwrap(), [favorite]);
+
+        assert!(FileStore::new(None, None).plays().is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
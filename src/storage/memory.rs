@@ -0,0 +1,47 @@
+//! Store keeping everything in memory
+
+use super::Store;
+use crate::favorites::Favorite;
+use crate::track_log::{self, TrackRecord};
+use std::io;
+
+/// Keeps plays and likes until the process exits
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    plays: Vec<TrackRecord>,
+    favorites: Vec<Favorite>,
+}
+
+impl Store for MemoryStore {
+    fn location(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn plays(&self) -> io::Result<Vec<TrackRecord>> {
+        Ok(self.plays.clone())
+    }
+
+    fn add_plays(&mut self, records: Vec<TrackRecord>) -> io::Result<usize> {
+        let records = track_log::new_plays(&self.plays, records);
+        let added = records.len();
+        self.plays.extend(records);
+        self.plays.sort_by_key(|play| play.started);
+        Ok(added)
+    }
+
+    fn favorites(&self) -> io::Result<Vec<Favorite>> {
+        Ok(self.favorites.clone())
+    }
+
+    fn add_favorite(&mut self, favorite: &Favorite) -> io::Result<bool> {
+        if self
+            .favorites
+            .iter()
+            .any(|liked| liked.key() == favorite.key())
+        {
+            return Ok(false);
+        }
+        self.favorites.push(favorite.clone());
+        Ok(true)
+    }
+}
@@ -0,0 +1,138 @@
+//! State storage
+//!
+//! The track log and liked tracks are kept by a [`Store`], picked with
+//! `backend` in the `[storage]` table:
+//!
+//! - `sqlite`, the default: one SQLite database, which the track search can
+//!   query without reading every play. Needs the `sqlite` feature.
+//! - `file`: the JSON Lines files `tracks.jsonl` and `favorites.jsonl`, for
+//!   builds without SQLite.
+//! - `memory`: nothing outlives the process, for tests and the demo.
+//!
+//! The player, the track search popup and the command line each open the
+//! store once; the player shares its store through a [`SharedStore`].
+
+pub(crate) mod file;
+mod memory;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use crate::config::Config;
+use crate::favorites::Favorite;
+use crate::listening_stats::{self, StationTime};
+use crate::track_log::{self, SearchHit, TrackRecord};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+/// Where plays and likes are kept
+pub trait Store: Send + std::fmt::Debug {
+    /// Where the store keeps its data, for messages
+    fn location(&self) -> String;
+
+    /// All plays, oldest first
+    fn plays(&self) -> io::Result<Vec<TrackRecord>>;
+
+    /// Add `records`, skipping plays already kept: those starting at the same
+    /// time with the same title
+    ///
+    /// Returns the number of plays added.
+    fn add_plays(&mut self, records: Vec<TrackRecord>) -> io::Result<usize>;
+
+    /// Plays matching `query`, as [`track_log::search`] finds them
+    fn search(&self, query: &str) -> io::Result<Vec<SearchHit>> {
+        Ok(track_log::search(&self.plays()?, query))
+    }
+
+    /// Time heard per station, longest first, as
+    /// [`listening_stats::ranking`] adds it up
+    fn station_times(&self) -> io::Result<Vec<StationTime>> {
+        Ok(listening_stats::ranking(&self.plays()?))
+    }
+
+    /// All liked tracks
+    fn favorites(&self) -> io::Result<Vec<Favorite>>;
+
+    /// Like a track, unless it is liked already; returns whether it was added
+    fn add_favorite(&mut self, favorite: &Favorite) -> io::Result<bool>;
+}
+
+/// Storage backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Sqlite,
+    File,
+    Memory,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        if cfg!(feature = "sqlite") {
+            Backend::Sqlite
+        } else {
+            Backend::File
+        }
+    }
+}
+
+/// Storage settings, read from the `[storage]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: Backend,
+    /// SQLite database file, `somars.db` in the user data directory when
+    /// unset; the file backend uses the `path` of `[track_log]` and
+    /// `[favorites]`
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+impl StorageConfig {
+    /// The SQLite database file
+    #[cfg(feature = "sqlite")]
+    pub fn database_path(&self) -> Option<PathBuf> {
+        self.path
+            .clone()
+            .or_else(|| dirs::data_dir().map(|dir| dir.join("somars").join("somars.db")))
+    }
+}
+
+/// Open the configured store
+pub fn open(config: &Config) -> io::Result<Box<dyn Store>> {
+    match config.storage.backend {
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Ok(Box::new(sqlite::SqliteStore::open(config)?)),
+        #[cfg(not(feature = "sqlite"))]
+        Backend::Sqlite => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "somars was built without the sqlite feature; set backend = \"file\" in [storage]",
+        )),
+        Backend::File => Ok(Box::new(FileStore::new(
+            track_log::path(&config.track_log),
+            crate::favorites::path(&config.favorites),
+        ))),
+        Backend::Memory => Ok(Box::new(MemoryStore::default())),
+    }
+}
+
+/// A store shared by the track log, the favorites and the track search
+#[derive(Debug, Clone)]
+pub struct SharedStore(Arc<Mutex<Box<dyn Store>>>);
+
+impl SharedStore {
+    pub fn new(store: Box<dyn Store>) -> Self {
+        Self(Arc::new(Mutex::new(store)))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Box<dyn Store>> {
+        // A failed write leaves nothing half-done in memory
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
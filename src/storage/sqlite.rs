@@ -0,0 +1,389 @@
+//! Store keeping an SQLite database
+//!
+//! Plays and likes are kept in one database, `somars.db` in the user data
+//! directory unless `path` in the `[storage]` table says otherwise. It runs in
+//! write-ahead log mode, so the player can keep logging while
+//! `somars search-history` and other commands read it.
+//!
+//! Track logs and favorites lists kept by the file backend are imported the
+//! first time the database is opened and renamed with an `.imported` suffix.
+
+use super::Store;
+use crate::config::Config;
+use crate::favorites::{self, Favorite};
+use crate::listening_stats::StationTime;
+use crate::track_log::{self, SearchHit, TrackRecord};
+use rusqlite::{params, params_from_iter, Connection};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// How long to wait for another process writing to the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tables of each schema version, applied in turn to bring an older database
+/// up to date
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE plays (
+        started INTEGER NOT NULL,
+        seconds INTEGER NOT NULL,
+        station TEXT NOT NULL,
+        artist TEXT NOT NULL,
+        title TEXT NOT NULL,
+        session INTEGER NOT NULL DEFAULT 0,
+        -- Artist, title and station in lower case, for search
+        search TEXT NOT NULL,
+        UNIQUE (started, artist, title)
+    );
+    CREATE INDEX plays_by_track ON plays (artist, title, station);
+    CREATE TABLE favorites (
+        liked INTEGER NOT NULL,
+        station TEXT NOT NULL,
+        artist TEXT NOT NULL,
+        title TEXT NOT NULL,
+        -- Artist and title in lower case; a track is liked once
+        key TEXT NOT NULL UNIQUE
+    );
+"];
+
+/// Keeps plays and likes in an SQLite database
+#[derive(Debug)]
+pub struct SqliteStore {
+    db: Connection,
+    path: PathBuf,
+}
+
+impl SqliteStore {
+    /// Open the configured database, importing the files of the file backend
+    pub fn open(config: &Config) -> io::Result<Self> {
+        let path = config
+            .storage
+            .database_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no user data directory"))?;
+        let mut store = Self::open_path(&path)?;
+        if let Some(legacy) = track_log::path(&config.track_log) {
+            import_legacy(&legacy, |path| store.add_plays(super::file::read(path)?))?;
+        }
+        if let Some(legacy) = favorites::path(&config.favorites) {
+            import_legacy(&legacy, |path| {
+                let mut added = 0;
+                for favorite in super::file::read::<Favorite>(path)? {
+                    added += usize::from(store.add_favorite(&favorite)?);
+                }
+                Ok(added)
+            })?;
+        }
+        Ok(store)
+    }
+
+    /// Open the database at `path`, creating it or bringing its tables up to
+    /// date
+    pub fn open_path(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut db = Connection::open(path).map_err(io::Error::other)?;
+        migrate(&mut db).map_err(io::Error::other)?;
+        Ok(Self {
+            db,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Store for SqliteStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn plays(&self) -> io::Result<Vec<TrackRecord>> {
+        let mut statement = self
+            .db
+            .prepare(
+                "SELECT started, seconds, station, artist, title, session FROM plays
+                 ORDER BY started, rowid",
+            )
+            .map_err(io::Error::other)?;
+        let plays = statement
+            .query_map([], |row| {
+                Ok(TrackRecord {
+                    started: row.get(0)?,
+                    seconds: row.get(1)?,
+                    station: row.get(2)?,
+                    artist: row.get(3)?,
+                    title: row.get(4)?,
+                    session: row.get(5)?,
+                })
+            })
+            .and_then(Iterator::collect)
+            .map_err(io::Error::other)?;
+        Ok(plays)
+    }
+
+    fn add_plays(&mut self, records: Vec<TrackRecord>) -> io::Result<usize> {
+        let mut insert = || -> rusqlite::Result<usize> {
+            let transaction = self.db.transaction()?;
+            let mut added = 0;
+            {
+                let mut statement = transaction.prepare(
+                    "INSERT OR IGNORE INTO plays
+                     (started, seconds, station, artist, title, session, search)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                )?;
+                for record in &records {
+                    let search = format!("{} {} {}", record.artist, record.title, record.station)
+                        .to_lowercase();
+                    added += statement.execute(params![
+                        record.started,
+                        record.seconds,
+                        record.station,
+                        record.artist,
+                        record.title,
+                        record.session,
+                        search,
+                    ])?;
+                }
+            }
+            transaction.commit()?;
+            Ok(added)
+        };
+        insert().map_err(io::Error::other)
+    }
+
+    fn search(&self, query: &str) -> io::Result<Vec<SearchHit>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        let matches = vec!["instr(search, ?) > 0"; words.len()].join(" AND ");
+        let sql = format!(
+            "SELECT artist, title, station, COUNT(*), MAX(started) FROM plays WHERE {matches}
+             GROUP BY artist, title, station ORDER BY MAX(started) DESC"
+        );
+        let mut statement = self.db.prepare(&sql).map_err(io::Error::other)?;
+        let hits = statement
+            .query_map(params_from_iter(&words), |row| {
+                Ok(SearchHit {
+                    artist: row.get(0)?,
+                    title: row.get(1)?,
+                    station: row.get(2)?,
+                    plays: row.get(3)?,
+                    last_heard: row.get(4)?,
+                })
+            })
+            .and_then(Iterator::collect)
+            .map_err(io::Error::other)?;
+        Ok(hits)
+    }
+
+    fn station_times(&self) -> io::Result<Vec<StationTime>> {
+        let mut statement = self
+            .db
+            .prepare(
+                "SELECT station, SUM(seconds) FROM plays GROUP BY station
+                 HAVING SUM(seconds) > 0 ORDER BY SUM(seconds) DESC, station",
+            )
+            .map_err(io::Error::other)?;
+        let ranking = statement
+            .query_map([], |row| {
+                Ok(StationTime {
+                    title: row.get(0)?,
+                    seconds: row.get(1)?,
+                })
+            })
+            .and_then(Iterator::collect)
+            .map_err(io::Error::other)?;
+        Ok(ranking)
+    }
+
+    fn favorites(&self) -> io::Result<Vec<Favorite>> {
+        let mut statement = self
+            .db
+            .prepare("SELECT liked, station, artist, title FROM favorites ORDER BY liked, rowid")
+            .map_err(io::Error::other)?;
+        let favorites = statement
+            .query_map([], |row| {
+                Ok(Favorite {
+                    liked: row.get(0)?,
+                    station: row.get(1)?,
+                    artist: row.get(2)?,
+                    title: row.get(3)?,
+                })
+            })
+            .and_then(Iterator::collect)
+            .map_err(io::Error::other)?;
+        Ok(favorites)
+    }
+
+    fn add_favorite(&mut self, favorite: &Favorite) -> io::Result<bool> {
+        let added = self
+            .db
+            .execute(
+                "INSERT OR IGNORE INTO favorites (liked, station, artist, title, key)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    favorite.liked,
+                    favorite.station,
+                    favorite.artist,
+                    favorite.title,
+                    favorite.key(),
+                ],
+            )
+            .map_err(io::Error::other)?;
+        Ok(added > 0)
+    }
+}
+
+fn migrate(db: &mut Connection) -> rusqlite::Result<()> {
+    db.busy_timeout(BUSY_TIMEOUT)?;
+    // In-memory databases stay in `memory` mode
+    db.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    let version: usize = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+    let transaction = db.transaction()?;
+    for migration in &MIGRATIONS[version..] {
+        transaction.execute_batch(migration)?;
+    }
+    transaction.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    transaction.commit()
+}
+
+/// Import the JSON Lines file at `path` with `import`, if there is one, and
+/// rename it so it is only imported once
+fn import_legacy(path: &Path, import: impl FnOnce(&Path) -> io::Result<usize>) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let imported = import(path)?;
+    let mut renamed = path.as_os_str().to_owned();
+    renamed.push(".imported");
+    std::fs::rename(path, &renamed)?;
+    info!("Imported {} entries from {}", imported, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{FavoritesConfig, TrackLogConfig};
+    use crate::storage::StorageConfig;
+
+    #[test]
+    fn imports_legacy_files_once() {
+        let dir = std::env::temp_dir().join(format!("somars-database-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let tracks = dir.join("tracks.jsonl");
+        std::fs::write(
+            &tracks,
+            "{\"started\":100,\"seconds\":60,\"station\":\"Fluid\",\"artist\":\"A\",\"title\":\"One\"}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("favorites.jsonl"),
+            "{\"liked\":160,\"station\":\"Fluid\",\"artist\":\"A\",\"title\":\"One\"}\n",
+        )
+        .unwrap();
+        let config = Config {
+            storage: StorageConfig {
+                path: Some(dir.join("somars.db")),
+                ..StorageConfig::default()
+            },
+            track_log: TrackLogConfig {
+                enabled: true,
+                path: Some(tracks.clone()),
+                ..TrackLogConfig::default()
+            },
+            favorites: FavoritesConfig {
+                path: Some(dir.join("favorites.jsonl")),
+                ..FavoritesConfig::default()
+            },
+            ..Config::default()
+        };
+
+        let store = SqliteStore::open(&config).unwrap();
+        assert_eq!(store.plays().unwrap().len(), 1);
+        assert!(!tracks.exists());
+        assert!(dir.join("tracks.jsonl.imported").exists());
+        assert!(dir.join("favorites.jsonl.imported").exists());
+        drop(store);
+
+        // Opening again keeps what was imported and imports nothing new
+        let store = SqliteStore::open(&config).unwrap();
+        assert_eq!(store.plays().unwrap()[0].title, "One");
+        assert_eq!(store.favorites().unwrap().len(), 1);
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn searches_plays() {
+        let play = |started, station: &str, artist: &str, title: &str| TrackRecord {
+            started,
+            seconds: 0,
+            station: station.to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+            session: 0,
+        };
+        let mut store = SqliteStore::open_path(Path::new(":memory:")).unwrap();
+        store
+            .add_plays(vec![
+                play(100, "Groove Salad", "Boards of Canada", "Roygbiv"),
+                play(300, "Groove Salad", "Boards of Canada", "Roygbiv"),
+                play(400, "Fluid", "Boards of Canada", "Dayvan Cowboy"),
+                play(500, "Sonic Universe", "Кино", "Группа крови"),
+            ])
+            .unwrap();
+        assert_eq!(
+            store
+                .add_plays(vec![play(100, "MPD", "Boards of Canada", "Roygbiv")])
+                .unwrap(),
+            0
+        );
+        let search = |query| store.search(query).unwrap();
+
+        let hits = search("boards CANADA");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].station, "Fluid");
+        assert_eq!((hits[1].plays, hits[1].last_heard), (2, 300));
+        // Case is ignored beyond ASCII
+        assert_eq!(search("КРОВИ")[0].artist, "Кино");
+        // Words are matched literally
+        assert!(search("%").is_empty());
+        assert!(search("  ").is_empty());
+    }
+
+    #[test]
+    fn adds_up_time_per_station() {
+        let play = |started, seconds, station: &str| TrackRecord {
+            started,
+            seconds,
+            station: station.to_string(),
+            artist: String::new(),
+            title: started.to_string(),
+            session: 0,
+        };
+        let plays = vec![
+            play(100, 90, "Groove Salad"),
+            play(200, 120, "Drone Zone"),
+            play(400, 90, "Drone Zone"),
+            play(500, 60, "Groove Salad"),
+            play(600, 0, "Fluid"),
+        ];
+        let mut store = SqliteStore::open_path(Path::new(":memory:")).unwrap();
+        store.add_plays(plays.clone()).unwrap();
+        // The same ranking as adding up every play
+        assert_eq!(
+            store.station_times().unwrap(),
+            crate::listening_stats::ranking(&plays)
+        );
+        assert_eq!(store.station_times().unwrap()[0].seconds, 210);
+    }
+}
@@ -0,0 +1,286 @@
+//! Terminal capability detection
+//!
+//! Detects what the attached terminal can render (colour depth, Unicode glyphs,
+//! inline graphics, OSC 52 clipboard) so UI features can degrade gracefully
+//! instead of misrendering. Every probe can be overridden from the
+//! `[terminal]` table in the config file, which also turns off setting the
+//! window title.
+
+use once_cell::sync::OnceCell;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+/// Colour depth supported by the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSupport {
+    /// 16 ANSI colours
+    Basic,
+    /// 256-colour palette
+    #[serde(rename = "256")]
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+/// Inline image protocol supported by the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsProtocol {
+    None,
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+/// Detected terminal capabilities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    pub color: ColorSupport,
+    pub unicode: bool,
+    pub graphics: GraphicsProtocol,
+    pub osc52_clipboard: bool,
+}
+
+/// Terminal capability overrides, read from the `[terminal]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TerminalConfig {
+    #[serde(default)]
+    pub color: Option<ColorSupport>,
+    #[serde(default)]
+    pub unicode: Option<bool>,
+    #[serde(default)]
+    pub graphics: Option<GraphicsProtocol>,
+    #[serde(default)]
+    pub osc52_clipboard: Option<bool>,
+    /// Show the station and track in the window title; on when unset
+    #[serde(default)]
+    pub window_title: Option<bool>,
+}
+
+impl TerminalConfig {
+    /// Whether to set the window title
+    pub fn window_title(&self) -> bool {
+        self.window_title.unwrap_or(true)
+    }
+}
+
+static CAPABILITIES: OnceCell<TerminalCapabilities> = OnceCell::new();
+
+/// Detect capabilities from the environment and apply config overrides.
+///
+/// Only the first call has an effect; later calls are ignored.
+pub fn init(config: &TerminalConfig) {
+    let _ = CAPABILITIES.set(TerminalCapabilities::detect(&Env::from_process()).apply(config));
+}
+
+/// Get the active terminal capabilities
+///
+/// Falls back to environment detection if [`init`] has not been called.
+pub fn current() -> TerminalCapabilities {
+    *CAPABILITIES.get_or_init(|| TerminalCapabilities::detect(&Env::from_process()))
+}
+
+/// Environment variables consulted during detection
+#[derive(Debug, Default, Clone)]
+struct Env {
+    term: String,
+    term_program: String,
+    colorterm: String,
+    locale: String,
+    kitty: bool,
+    tmux: bool,
+    windows_terminal: bool,
+}
+
+impl Env {
+    fn from_process() -> Self {
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .map(|name| var(name))
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self {
+            term: var("TERM"),
+            term_program: var("TERM_PROGRAM"),
+            colorterm: var("COLORTERM"),
+            locale,
+            kitty: std::env::var_os("KITTY_WINDOW_ID").is_some(),
+            tmux: std::env::var_os("TMUX").is_some(),
+            windows_terminal: std::env::var_os("WT_SESSION").is_some(),
+        }
+    }
+}
+
+impl TerminalCapabilities {
+    fn detect(env: &Env) -> Self {
+        let term = env.term.to_lowercase();
+        let term_program = env.term_program.to_lowercase();
+        let colorterm = env.colorterm.to_lowercase();
+
+        let color = if term == "dumb" {
+            ColorSupport::Basic
+        } else if colorterm == "truecolor"
+            || colorterm == "24bit"
+            || env.windows_terminal
+            || env.kitty
+            || matches!(term_program.as_str(), "iterm.app" | "wezterm" | "ghostty")
+        {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Basic
+        };
+
+        let locale = env.locale.to_lowercase();
+        let unicode = cfg!(windows)
+            || env.windows_terminal
+            || locale.contains("utf-8")
+            || locale.contains("utf8");
+
+        let graphics = if env.kitty || term == "xterm-kitty" || term_program == "ghostty" {
+            GraphicsProtocol::Kitty
+        } else if matches!(term_program.as_str(), "iterm.app" | "wezterm") {
+            GraphicsProtocol::Iterm2
+        } else if term.contains("sixel") || term.starts_with("foot") || term == "mlterm" {
+            GraphicsProtocol::Sixel
+        } else {
+            GraphicsProtocol::None
+        };
+
+        let osc52_clipboard = env.tmux
+            || env.kitty
+            || env.windows_terminal
+            || matches!(
+                term_program.as_str(),
+                "iterm.app" | "wezterm" | "ghostty" | "vscode"
+            )
+            || term.starts_with("alacritty")
+            || term.starts_with("foot")
+            || term.starts_with("xterm-kitty");
+
+        Self {
+            color,
+            unicode,
+            graphics,
+            osc52_clipboard,
+        }
+    }
+
+    fn apply(mut self, config: &TerminalConfig) -> Self {
+        if let Some(color) = config.color {
+            self.color = color;
+        }
+        if let Some(unicode) = config.unicode {
+            self.unicode = unicode;
+        }
+        if let Some(graphics) = config.graphics {
+            self.graphics = graphics;
+        }
+        if let Some(osc52_clipboard) = config.osc52_clipboard {
+            self.osc52_clipboard = osc52_clipboard;
+        }
+        self
+    }
+
+    /// Pick the best colour the terminal can display
+    ///
+    /// Returns the RGB colour on truecolor terminals, the nearest 256-colour
+    /// palette entry on 256-colour terminals and `fallback` otherwise.
+    #[allow(dead_code)]
+    pub fn color(&self, rgb: (u8, u8, u8), fallback: Color) -> Color {
+        match self.color {
+            ColorSupport::TrueColor => Color::Rgb(rgb.0, rgb.1, rgb.2),
+            ColorSupport::Ansi256 => Color::Indexed(rgb_to_ansi256(rgb)),
+            ColorSupport::Basic => fallback,
+        }
+    }
+
+    /// Pick a Unicode glyph or its ASCII fallback
+    pub fn symbol(&self, unicode: &'static str, ascii: &'static str) -> &'static str {
+        if self.unicode {
+            unicode
+        } else {
+            ascii
+        }
+    }
+}
+
+/// Map an RGB colour onto the 6x6x6 cube of the xterm 256-colour palette
+fn rgb_to_ansi256((r, g, b): (u8, u8, u8)) -> u8 {
+    let level = |value: u8| ((value as u16 * 5 + 127) / 255) as u8;
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_truecolor_utf8_kitty() {
+        let env = Env {
+            term: "xterm-kitty".to_string(),
+            colorterm: "truecolor".to_string(),
+            locale: "en_US.UTF-8".to_string(),
+            kitty: true,
+            ..Env::default()
+        };
+        let caps = TerminalCapabilities::detect(&env);
+        assert_eq!(caps.color, ColorSupport::TrueColor);
+        assert!(caps.unicode);
+        assert_eq!(caps.graphics, GraphicsProtocol::Kitty);
+        assert!(caps.osc52_clipboard);
+    }
+
+    #[test]
+    fn dumb_terminal_degrades() {
+        let env = Env {
+            term: "dumb".to_string(),
+            locale: "C".to_string(),
+            ..Env::default()
+        };
+        let caps = TerminalCapabilities::detect(&env);
+        assert_eq!(caps.color, ColorSupport::Basic);
+        assert_eq!(caps.graphics, GraphicsProtocol::None);
+        assert!(!caps.osc52_clipboard);
+        if !cfg!(windows) {
+            assert!(!caps.unicode);
+            assert_eq!(caps.symbol("♪", "*"), "*");
+        }
+    }
+
+    #[test]
+    fn config_overrides_detection() {
+        let env = Env {
+            term: "xterm-256color".to_string(),
+            ..Env::default()
+        };
+        let config: TerminalConfig = toml::from_str(
+            r#"
+            color = "basic"
+            unicode = true
+            graphics = "sixel"
+            "#,
+        )
+        .unwrap();
+        let caps = TerminalCapabilities::detect(&env).apply(&config);
+        assert_eq!(caps.color, ColorSupport::Basic);
+        assert!(caps.unicode);
+        assert_eq!(caps.graphics, GraphicsProtocol::Sixel);
+        assert!(config.window_title());
+
+        let config: TerminalConfig = toml::from_str("window_title = false").unwrap();
+        assert!(!config.window_title());
+    }
+
+    #[test]
+    fn color_falls_back_by_depth() {
+        let mut caps = TerminalCapabilities::detect(&Env::default());
+        caps.color = ColorSupport::Ansi256;
+        assert_eq!(caps.color((255, 0, 0), Color::Red), Color::Indexed(196));
+        caps.color = ColorSupport::Basic;
+        assert_eq!(caps.color((255, 0, 0), Color::Red), Color::Red);
+    }
+}
@@ -0,0 +1,299 @@
+//! Colour themes
+//!
+//! Picks a dark or light palette to match the terminal background. The
+//! background is taken from the `[theme]` config table when set, otherwise
+//! from `COLORFGBG` or an OSC 11 query to the terminal. Each palette can be
+//! overridden from `[theme.dark]` and `[theme.light]`.
+
+use once_cell::sync::OnceCell;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use tracing::warn;
+
+/// Terminal background brightness
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+/// Colours used by the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Regular text
+    pub text: Color,
+    /// De-emphasised text (background log messages)
+    pub muted: Color,
+    /// Field labels
+    pub label: Color,
+    /// Playing state, system messages and the level meter
+    pub success: Color,
+    /// Warnings and the quit key
+    pub warning: Color,
+    /// Errors and the stopped state
+    pub error: Color,
+    /// Paused state and links
+    pub info: Color,
+    /// Secondary key hints and debug info
+    pub secondary: Color,
+    /// Accent key hints
+    pub accent: Color,
+    /// Background of the selected list row
+    pub selection: Color,
+}
+
+impl Theme {
+    pub const DARK: Self = Self {
+        text: Color::White,
+        muted: Color::DarkGray,
+        label: Color::Yellow,
+        success: Color::Green,
+        warning: Color::Yellow,
+        error: Color::Red,
+        info: Color::Blue,
+        secondary: Color::Cyan,
+        accent: Color::Magenta,
+        selection: Color::Blue,
+    };
+
+    pub const LIGHT: Self = Self {
+        text: Color::Black,
+        muted: Color::DarkGray,
+        label: Color::Blue,
+        success: Color::Green,
+        warning: Color::Red,
+        error: Color::Red,
+        info: Color::Blue,
+        secondary: Color::Magenta,
+        accent: Color::Magenta,
+        selection: Color::LightBlue,
+    };
+
+    fn for_background(background: Background) -> Self {
+        match background {
+            Background::Dark => Self::DARK,
+            Background::Light => Self::LIGHT,
+        }
+    }
+
+    fn apply(mut self, overrides: &ThemeOverrides) -> Self {
+        let slots = [
+            (&mut self.text, &overrides.text),
+            (&mut self.muted, &overrides.muted),
+            (&mut self.label, &overrides.label),
+            (&mut self.success, &overrides.success),
+            (&mut self.warning, &overrides.warning),
+            (&mut self.error, &overrides.error),
+            (&mut self.info, &overrides.info),
+            (&mut self.secondary, &overrides.secondary),
+            (&mut self.accent, &overrides.accent),
+            (&mut self.selection, &overrides.selection),
+        ];
+        for (slot, value) in slots {
+            if let Some(value) = value {
+                match Color::from_str(value) {
+                    Ok(color) => *slot = color,
+                    Err(_) => warn!("Ignoring invalid theme colour: {}", value),
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Per-palette colour overrides (names like `"yellow"`, `"#ffaa00"` or palette indices)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeOverrides {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub muted: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub success: Option<String>,
+    #[serde(default)]
+    pub warning: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub info: Option<String>,
+    #[serde(default)]
+    pub secondary: Option<String>,
+    #[serde(default)]
+    pub accent: Option<String>,
+    #[serde(default)]
+    pub selection: Option<String>,
+}
+
+/// Theme settings, read from the `[theme]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// Force a dark or light palette instead of detecting the background
+    #[serde(default)]
+    pub background: Option<Background>,
+    #[serde(default)]
+    pub dark: ThemeOverrides,
+    #[serde(default)]
+    pub light: ThemeOverrides,
+}
+
+static THEME: OnceCell<Theme> = OnceCell::new();
+
+/// Detect the terminal background and select the theme.
+///
+/// Call after raw mode is enabled and before the event reader starts, so the
+/// OSC 11 reply can be read from the terminal. Only the first call has an
+/// effect.
+pub fn init(config: &ThemeConfig) {
+    let background = config
+        .background
+        .or_else(|| {
+            std::env::var("COLORFGBG")
+                .ok()
+                .and_then(|value| parse_colorfgbg(&value))
+        })
+        .or_else(query_background)
+        .unwrap_or(Background::Dark);
+    let overrides = match background {
+        Background::Dark => &config.dark,
+        Background::Light => &config.light,
+    };
+    let _ = THEME.set(Theme::for_background(background).apply(overrides));
+}
+
+/// Get the active theme
+///
+/// Falls back to the dark theme if [`init`] has not been called.
+pub fn current() -> Theme {
+    *THEME.get_or_init(|| Theme::DARK)
+}
+
+/// Parse `COLORFGBG` (`"fg;bg"` or `"fg;default;bg"`) set by rxvt, Konsole and others
+fn parse_colorfgbg(value: &str) -> Option<Background> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(if matches!(background, 7 | 9..=15) {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// Parse an OSC 11 reply such as `ESC ] 11 ; rgb:ffff/ffff/ffff ESC \`
+fn parse_osc11_response(response: &str) -> Option<Background> {
+    let rgb = response.split("rgb:").nth(1)?;
+    let rgb = rgb.trim_end_matches(['\x07', '\x1b', '\\']);
+    let channels: Vec<f32> = rgb
+        .split('/')
+        .map(|channel| {
+            let digits = channel.get(..channel.len().min(4))?;
+            let max = 16f32.powi(digits.len() as i32) - 1.0;
+            u32::from_str_radix(digits, 16)
+                .ok()
+                .map(|value| value as f32 / max)
+        })
+        .collect::<Option<_>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance > 0.5 {
+        Background::Light
+    } else {
+        Background::Dark
+    })
+}
+
+/// Ask the terminal for its background colour with OSC 11
+#[cfg(unix)]
+fn query_background() -> Option<Background> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    const TIMEOUT_MS: i32 = 100;
+
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]11;?\x1b\\").ok()?;
+    tty.flush().ok()?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` points to a single valid pollfd for the duration of the call.
+        let ready = unsafe { libc::poll(&mut fd, 1, TIMEOUT_MS) };
+        if ready <= 0 {
+            break;
+        }
+        let len = tty.read(&mut buf).ok()?;
+        if len == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..len]);
+        if response.ends_with(b"\x07") || response.ends_with(b"\x1b\\") {
+            break;
+        }
+    }
+    parse_osc11_response(&String::from_utf8_lossy(&response))
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<Background> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_osc11_reply() {
+        assert_eq!(
+            parse_osc11_response("\x1b]11;rgb:ffff/ffff/ffff\x1b\\"),
+            Some(Background::Light)
+        );
+        assert_eq!(
+            parse_osc11_response("\x1b]11;rgb:1c1c/1c1c/1c1c\x07"),
+            Some(Background::Dark)
+        );
+        assert_eq!(
+            parse_osc11_response("\x1b]11;rgb:fd/f6/e3\x07"),
+            Some(Background::Light)
+        );
+        assert_eq!(parse_osc11_response(""), None);
+    }
+
+    #[test]
+    fn parses_colorfgbg() {
+        assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(parse_colorfgbg("0;default;15"), Some(Background::Light));
+        assert_eq!(parse_colorfgbg("garbage"), None);
+    }
+
+    #[test]
+    fn overrides_apply_to_palette() {
+        let config: ThemeConfig = toml::from_str(
+            r##"
+            background = "light"
+            [light]
+            label = "#aa5500"
+            text = "not a colour"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.background, Some(Background::Light));
+        let theme = Theme::LIGHT.apply(&config.light);
+        assert_eq!(theme.label, Color::Rgb(0xaa, 0x55, 0x00));
+        assert_eq!(theme.text, Theme::LIGHT.text);
+    }
+}
@@ -0,0 +1,458 @@
+//! Persistent track log
+//!
+//! Every track heard is added to the [store](crate::storage), one record per
+//! play. It is somars' long-term
+//! listening record and can be seeded from other players with
+//! `--import-history`, searched with `somars search-history` or from the track
+//! search popup, summarised by listening session with `somars sessions`, and
+//! exported with `somars export-history` or `E`.
+
+use crate::config::TrackLogConfig;
+use crate::scrobble_log::split_title;
+use crate::storage::SharedStore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// One play in the track log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackRecord {
+    /// Unix time the track started
+    pub started: u64,
+    /// Seconds listened, 0 when unknown
+    pub seconds: u64,
+    /// Station title, or the player it was imported from
+    pub station: String,
+    /// Empty when the title has no `Artist - Title` split
+    pub artist: String,
+    pub title: String,
+    /// Unix time the listening session started, 0 for imported plays
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub session: u64,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+impl TrackRecord {
+    /// Whether both records are the same play
+    fn same_play(&self, other: &Self) -> bool {
+        self.started == other.started && self.artist == other.artist && self.title == other.title
+    }
+}
+
+/// A track found by [`search`], with how often and when it was last heard
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub artist: String,
+    pub title: String,
+    pub station: String,
+    pub plays: usize,
+    /// Unix time of the most recent play
+    pub last_heard: u64,
+}
+
+impl SearchHit {
+    /// `Artist - Title`, or just the title when the artist is unknown
+    pub fn track(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artist, self.title)
+        }
+    }
+
+    /// Date and time of the most recent play, on the configured clock
+    pub fn last_heard_local(&self) -> String {
+        local_time(self.last_heard)
+    }
+}
+
+/// Date and time of a Unix time, on the configured clock
+fn local_time(time: u64) -> String {
+    i64::try_from(time)
+        .ok()
+        .and_then(|time| chrono::DateTime::from_timestamp(time, 0))
+        .map(|time| crate::clock::current().date_time(time))
+        .unwrap_or_default()
+}
+
+/// Plays whose artist, title or station contain every word of `query`
+///
+/// Matching ignores case. Plays of the same track on the same station are
+/// counted together; the most recently heard come first.
+pub fn search(plays: &[TrackRecord], query: &str) -> Vec<SearchHit> {
+    let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+    let mut grouped: HashMap<(&str, &str, &str), SearchHit> = HashMap::new();
+    for play in plays {
+        grouped
+            .entry((&play.artist, &play.title, &play.station))
+            .and_modify(|hit| {
+                hit.plays += 1;
+                hit.last_heard = hit.last_heard.max(play.started);
+            })
+            .or_insert_with(|| SearchHit {
+                artist: play.artist.clone(),
+                title: play.title.clone(),
+                station: play.station.clone(),
+                plays: 1,
+                last_heard: play.started,
+            });
+    }
+    // Each track is lowercased once, however often it was played
+    let mut hits: Vec<SearchHit> = grouped
+        .into_values()
+        .filter(|hit| {
+            let fields = [&hit.artist, &hit.title, &hit.station].map(|field| field.to_lowercase());
+            words
+                .iter()
+                .all(|word| fields.iter().any(|field| field.contains(word.as_str())))
+        })
+        .collect();
+    hits.sort_by(|a, b| {
+        b.last_heard
+            .cmp(&a.last_heard)
+            .then_with(|| (&a.artist, &a.title, &a.station).cmp(&(&b.artist, &b.title, &b.station)))
+    });
+    hits
+}
+
+/// Plays without a session further apart than this start a new one
+const SESSION_GAP_SECONDS: u64 = 10 * 60;
+
+/// Plays heard in one go on one station, from play to stop
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub station: String,
+    /// Unix time of the first play
+    pub started: u64,
+    /// From the start of the first play to the end of the last
+    pub seconds: u64,
+    pub tracks: usize,
+}
+
+impl Session {
+    /// `Groove Salad — 2h13m, 34 tracks`
+    pub fn summary(&self) -> String {
+        session_summary(
+            &self.station,
+            std::time::Duration::from_secs(self.seconds),
+            self.tracks,
+        )
+    }
+
+    /// Date and time the session started, on the configured clock
+    pub fn started_local(&self) -> String {
+        local_time(self.started)
+    }
+}
+
+/// `Groove Salad — 2h13m, 34 tracks`, for a session's header
+pub fn session_summary(station: &str, length: std::time::Duration, tracks: usize) -> String {
+    crate::i18n::t("session-summary")
+        .replace("{$station}", station)
+        .replace("{$length}", &crate::utils::format_length(length))
+        .replace("{$tracks}", &tracks.to_string())
+}
+
+/// Group `plays`, oldest first, into listening sessions
+///
+/// Plays logged by somars carry their session; imported plays are grouped by
+/// station, splitting where nothing was heard for a while.
+pub fn sessions(plays: &[TrackRecord]) -> Vec<Session> {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut previous: Option<&TrackRecord> = None;
+    for play in plays {
+        let continues = previous.is_some_and(|previous| {
+            previous.station == play.station
+                && previous.session == play.session
+                && (play.session != 0
+                    || play.started <= previous.started + previous.seconds + SESSION_GAP_SECONDS)
+        });
+        match sessions.last_mut() {
+            Some(session) if continues => {
+                session.seconds = (play.started + play.seconds).saturating_sub(session.started);
+                session.tracks += 1;
+            }
+            _ => sessions.push(Session {
+                station: play.station.clone(),
+                started: play.started,
+                seconds: play.seconds,
+                tracks: 1,
+            }),
+        }
+        previous = Some(play);
+    }
+    sessions
+}
+
+/// Adds finished tracks to the track log
+#[derive(Debug)]
+pub struct TrackLog {
+    /// `None` when the log is disabled or the store couldn't be opened
+    store: Option<SharedStore>,
+    current: Option<(TrackRecord, SystemTime)>,
+    /// Start of the listening session, `None` until a track plays
+    session: Option<u64>,
+}
+
+impl TrackLog {
+    pub fn new(config: &TrackLogConfig, store: Option<SharedStore>) -> Self {
+        Self {
+            store: store.filter(|_| config.enabled),
+            current: None,
+            session: None,
+        }
+    }
+
+    /// A new track title arrived on `station`; finishes the previous track
+    pub fn track_started(&mut self, station: &str, title: &str) {
+        self.track_started_at(station, title, SystemTime::now());
+    }
+
+    /// Playback stopped, paused or moved to another station
+    pub fn finish(&mut self) {
+        self.finish_at(SystemTime::now());
+    }
+
+    /// Playback stopped or moved to another station; the next track starts a
+    /// new listening session
+    pub fn end_session(&mut self) {
+        self.finish();
+        self.session = None;
+    }
+
+    fn track_started_at(&mut self, station: &str, title: &str, now: SystemTime) {
+        let title = title.trim();
+        if self.store.is_none() {
+            return;
+        }
+        let (artist, title) = split_title(title).unwrap_or(("", title));
+        if let Some((current, _)) = &self.current {
+            if current.station == station && current.artist == artist && current.title == title {
+                return;
+            }
+        }
+        self.finish_at(now);
+        if title.is_empty() {
+            return;
+        }
+        let started = unix_seconds(now);
+        let record = TrackRecord {
+            started,
+            seconds: 0,
+            station: station.to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+            session: *self.session.get_or_insert(started),
+        };
+        self.current = Some((record, now));
+    }
+
+    fn finish_at(&mut self, now: SystemTime) {
+        let (Some(store), Some((mut record, started))) = (&self.store, self.current.take()) else {
+            return;
+        };
+        record.seconds = now.duration_since(started).unwrap_or_default().as_secs();
+        if let Err(error) = store.lock().add_plays(vec![record]) {
+            warn!("Failed to write track log: {}", error);
+        }
+    }
+}
+
+/// The JSON Lines track log of the file backend, from the configuration or
+/// `tracks.jsonl` in the user data directory
+pub fn path(config: &TrackLogConfig) -> Option<PathBuf> {
+    config
+        .path
+        .clone()
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("somars").join("tracks.jsonl")))
+}
+
+/// The plays of `records` not in `plays` yet, each once, oldest first
+pub fn new_plays(plays: &[TrackRecord], mut records: Vec<TrackRecord>) -> Vec<TrackRecord> {
+    records.retain(|record| !plays.iter().any(|play| play.same_play(record)));
+    records.sort_by_key(|record| record.started);
+    records.dedup_by(|a, b| a.same_play(b));
+    records
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStore;
+    use std::time::Duration;
+
+    fn memory() -> SharedStore {
+        SharedStore::new(Box::new(MemoryStore::default()))
+    }
+
+    fn enabled() -> TrackLogConfig {
+        TrackLogConfig {
+            enabled: true,
+            ..TrackLogConfig::default()
+        }
+    }
+
+    #[test]
+    fn logs_plays_and_merges_without_duplicates() {
+        let mut log = TrackLog::new(&enabled(), Some(memory()));
+        let start = UNIX_EPOCH + Duration::from_secs(1_000);
+        log.track_started_at("Groove Salad", "Artist - One", start);
+        log.track_started_at(
+            "Groove Salad",
+            "Artist - One",
+            start + Duration::from_secs(5),
+        );
+        log.track_started_at(
+            "Groove Salad",
+            "Station ID",
+            start + Duration::from_secs(60),
+        );
+        log.finish_at(start + Duration::from_secs(70));
+
+        let mut store = log.store.as_ref().unwrap().lock();
+        let plays = store.plays().unwrap();
+        assert_eq!(plays.len(), 2);
+        assert_eq!(
+            plays[0],
+            TrackRecord {
+                started: 1_000,
+                seconds: 60,
+                station: "Groove Salad".to_string(),
+                artist: "Artist".to_string(),
+                title: "One".to_string(),
+                session: 1_000,
+            }
+        );
+        assert_eq!((plays[1].artist.as_str(), plays[1].seconds), ("", 10));
+
+        // Importing the same play twice only adds it once
+        let imported = TrackRecord {
+            started: 500,
+            seconds: 0,
+            station: "MPD".to_string(),
+            artist: "Other".to_string(),
+            title: "Two".to_string(),
+            session: 0,
+        };
+        assert_eq!(
+            store
+                .add_plays(vec![imported.clone(), plays[0].clone(), imported.clone()])
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.add_plays(vec![imported.clone()]).unwrap(), 0);
+        assert_eq!(store.plays().unwrap()[0], imported);
+        drop(store);
+
+        // Nothing is logged while the log is disabled
+        let disabled = TrackLogConfig {
+            enabled: false,
+            ..TrackLogConfig::default()
+        };
+        let mut log = TrackLog::new(&disabled, Some(memory()));
+        log.track_started_at("Groove Salad", "Artist - One", start);
+        log.finish_at(start + Duration::from_secs(70));
+        assert!(log.store.is_none());
+    }
+
+    #[test]
+    fn searches_plays() {
+        let play = |started, station: &str, artist: &str, title: &str| TrackRecord {
+            started,
+            seconds: 0,
+            station: station.to_string(),
+            artist: artist.to_string(),
+            title: title.to_string(),
+            session: 0,
+        };
+        let plays = vec![
+            play(100, "Groove Salad", "Boards of Canada", "Roygbiv"),
+            play(200, "Drone Zone", "Stars of the Lid", "Requiem"),
+            play(300, "Groove Salad", "Boards of Canada", "Roygbiv"),
+            play(400, "Fluid", "Boards of Canada", "Dayvan Cowboy"),
+            play(500, "Sonic Universe", "Кино", "Группа крови"),
+        ];
+        let search = |query| search(&plays, query);
+
+        let hits = search("boards CANADA");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(
+            (hits[0].track(), hits[0].station.as_str(), hits[0].plays),
+            ("Boards of Canada - Dayvan Cowboy".to_string(), "Fluid", 1)
+        );
+        assert_eq!((hits[1].plays, hits[1].last_heard), (2, 300));
+        // Station names match too
+        assert_eq!(search("drone").len(), 1);
+        assert!(search("boards requiem").is_empty());
+        assert!(search("  ").is_empty());
+        // Case is ignored beyond ASCII
+        assert_eq!(search("КРОВИ")[0].artist, "Кино");
+    }
+
+    #[test]
+    fn groups_plays_into_sessions() {
+        let mut log = TrackLog::new(&enabled(), Some(memory()));
+        let at = |seconds| UNIX_EPOCH + Duration::from_secs(seconds);
+        log.track_started_at("Groove Salad", "A - One", at(1_000));
+        log.track_started_at("Groove Salad", "A - Two", at(1_300));
+        // Stopping and playing the same station again starts a new session
+        log.finish_at(at(1_500));
+        log.session = None;
+        log.track_started_at("Groove Salad", "A - Three", at(1_560));
+        log.finish_at(at(1_800));
+        let mut plays = log.store.as_ref().unwrap().lock().plays().unwrap();
+
+        // Imported plays are split by station and by gaps
+        let imported = |started, station: &str| TrackRecord {
+            started,
+            seconds: 200,
+            station: station.to_string(),
+            artist: String::new(),
+            title: "Track".to_string(),
+            session: 0,
+        };
+        plays.extend([
+            imported(5_000, "MPD"),
+            imported(5_300, "MPD"),
+            imported(9_000, "MPD"),
+            imported(9_200, "Rockbox"),
+        ]);
+
+        let sessions = sessions(&plays);
+        let summary: Vec<_> = sessions
+            .iter()
+            .map(|session| {
+                (
+                    session.station.as_str(),
+                    session.started,
+                    session.seconds,
+                    session.tracks,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Groove Salad", 1_000, 500, 2),
+                ("Groove Salad", 1_560, 240, 1),
+                ("MPD", 5_000, 500, 2),
+                ("MPD", 9_000, 200, 1),
+                ("Rockbox", 9_200, 200, 1),
+            ]
+        );
+    }
+}
@@ -1,63 +1,91 @@
 //! Terminal User Interface (TUI) management
 //!
 //! Provides RAII-style terminal lifecycle management with async event handling.
+//! Terminal input is read from crossterm's [`EventStream`] right where the main
+//! loop awaits it, so input, channel messages and timers share one `select!`.
 
-use std::{
-    io::{stdout, Stdout},
-    ops::{Deref, DerefMut},
-    time::Duration,
-};
+use std::fmt;
+use std::io::{stdout, Stdout};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use color_eyre::eyre::Result;
 use crossterm::{
     cursor,
     event::{
-        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event as CrosstermEvent, EventStream, KeyEventKind,
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, Event as CrosstermEvent, EventStream, KeyEventKind,
     },
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
+    Command,
 };
-use futures::{FutureExt, StreamExt};
+use futures::StreamExt;
 use ratatui::backend::CrosstermBackend as Backend;
-use tokio::{
-    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
-    task::JoinHandle,
-};
-use tokio_util::sync::CancellationToken;
-use tracing::error;
 
 use crate::event::Event;
 
+/// Whether a window title was pushed and not popped yet, for [`restore`]
+static TITLE_PUSHED: AtomicBool = AtomicBool::new(false);
+
 /// TUI manages the terminal lifecycle and event stream
 pub struct Tui {
     /// The ratatui terminal
     pub terminal: ratatui::Terminal<Backend<Stdout>>,
-    /// The event loop task handle
-    pub task: JoinHandle<()>,
-    /// Cancellation token for stopping the event loop
-    pub cancellation_token: CancellationToken,
-    /// Event receiver channel
-    pub event_rx: UnboundedReceiver<Event>,
-    /// Event sender channel
-    pub event_tx: UnboundedSender<Event>,
+    /// Terminal input, `None` while stopped
+    events: Option<EventStream>,
+    /// Whether [`Event::Init`] is still to be delivered
+    init_pending: bool,
     /// Enable mouse capture
     pub mouse: bool,
     /// Enable bracketed paste
     pub paste: bool,
+    /// Set the window title
+    pub title: bool,
+    /// Window title last set, put back after a suspend
+    current_title: Option<String>,
+}
+
+/// Save the window title on the terminal's title stack (XTWINOPS 22)
+struct PushTitle;
+
+impl Command for PushTitle {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1b[22;0t")
+    }
+
+    /// The console keeps no title stack
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Restore the window title saved by [`PushTitle`] (XTWINOPS 23)
+struct PopTitle;
+
+impl Command for PopTitle {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str("\x1b[23;0t")
+    }
+
+    /// The console keeps no title stack
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 impl Tui {
     /// Create a new Tui instance
     pub fn new() -> Result<Self> {
-        let (event_tx, event_rx) = mpsc::unbounded_channel();
         Ok(Self {
             terminal: ratatui::Terminal::new(Backend::new(stdout()))?,
-            task: tokio::spawn(async {}),
-            cancellation_token: CancellationToken::new(),
-            event_rx,
-            event_tx,
+            events: None,
+            init_pending: false,
             mouse: false,
             paste: false,
+            title: false,
+            current_title: None,
         })
     }
 
@@ -75,89 +103,60 @@ impl Tui {
         self
     }
 
-    /// Start the event loop
-    pub fn start(&mut self) {
-        self.cancel(); // Cancel any existing task
-        self.cancellation_token = CancellationToken::new();
-        let event_loop = Self::event_loop(self.event_tx.clone(), self.cancellation_token.clone());
-        self.task = tokio::spawn(async {
-            event_loop.await;
-        });
+    /// Enable or disable setting the window title
+    pub fn title(mut self, title: bool) -> Self {
+        self.title = title;
+        self
     }
 
-    /// The main event loop that runs in a separate task
-    async fn event_loop(event_tx: UnboundedSender<Event>, cancellation_token: CancellationToken) {
-        let mut event_stream = EventStream::new();
-
-        // Send init event
-        if event_tx.send(Event::Init).is_err() {
-            return;
-        }
-
-        loop {
-            let event = tokio::select! {
-                _ = cancellation_token.cancelled() => {
-                    break;
-                }
-                crossterm_event = event_stream.next().fuse() => match crossterm_event {
-                    Some(Ok(event)) => match event {
-                        CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => Event::Key(key),
-                        CrosstermEvent::Mouse(mouse) => Event::Mouse(mouse),
-                        CrosstermEvent::Resize(x, y) => Event::Resize(x, y),
-                        CrosstermEvent::FocusLost => Event::FocusLost,
-                        CrosstermEvent::FocusGained => Event::FocusGained,
-                        CrosstermEvent::Paste(s) => Event::Paste(s),
-                        _ => continue, // ignore other events
-                    }
-                    Some(Err(_)) => Event::Error,
-                    None => break, // the event stream has stopped
-                },
-            };
-
-            if event_tx.send(event).is_err() {
-                // the receiver has been dropped, so there's no point in continuing the loop
-                break;
-            }
+    /// Set the window title, if enabled, when it differs from the last one
+    pub fn set_title(&mut self, title: &str) -> Result<()> {
+        if !self.title || self.current_title.as_deref() == Some(title) {
+            return Ok(());
         }
+        crossterm::execute!(stdout(), SetTitle(safe_title(title)))?;
+        self.current_title = Some(title.to_string());
+        Ok(())
+    }
 
-        cancellation_token.cancel();
+    /// Start reading terminal input
+    pub fn start(&mut self) {
+        self.events = Some(EventStream::new());
+        self.init_pending = true;
     }
 
-    /// Stop the event loop
-    pub fn stop(&self) -> Result<()> {
-        self.cancel();
-        let mut counter = 0;
-        while !self.task.is_finished() {
-            std::thread::sleep(Duration::from_millis(1));
-            counter += 1;
-            if counter > 50 {
-                self.task.abort();
-            }
-            if counter > 100 {
-                error!("Failed to abort task in 100 milliseconds for unknown reason");
-                break;
-            }
-        }
-        Ok(())
+    /// Stop reading terminal input
+    pub fn stop(&mut self) {
+        self.events = None;
+        self.init_pending = false;
     }
 
-    /// Enter alternate screen and start event loop
+    /// Enter alternate screen and start reading input
     pub fn enter(&mut self) -> Result<()> {
         crossterm::terminal::enable_raw_mode()?;
         crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+        // Focus reports let the app slow down in a background pane
+        crossterm::execute!(stdout(), EnableFocusChange)?;
         if self.mouse {
             crossterm::execute!(stdout(), EnableMouseCapture)?;
         }
         if self.paste {
             crossterm::execute!(stdout(), EnableBracketedPaste)?;
         }
+        if self.title {
+            crossterm::execute!(stdout(), PushTitle)?;
+            TITLE_PUSHED.store(true, Ordering::SeqCst);
+            if let Some(title) = &self.current_title {
+                crossterm::execute!(stdout(), SetTitle(safe_title(title)))?;
+            }
+        }
         self.start();
         Ok(())
     }
 
-    /// Exit alternate screen and stop event loop
+    /// Exit alternate screen and stop reading input
     pub fn exit(&mut self) -> Result<()> {
-        self.stop()?;
+        self.stop();
         if crossterm::terminal::is_raw_mode_enabled()? {
             self.flush()?;
             if self.paste {
@@ -166,17 +165,19 @@ impl Tui {
             if self.mouse {
                 crossterm::execute!(stdout(), DisableMouseCapture)?;
             }
+            crossterm::execute!(stdout(), DisableFocusChange)?;
+            if self.title {
+                // Terminals without a title stack are left with an empty
+                // title rather than the last station
+                crossterm::execute!(stdout(), SetTitle(""), PopTitle)?;
+                TITLE_PUSHED.store(false, Ordering::SeqCst);
+            }
             crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
             crossterm::terminal::disable_raw_mode()?;
         }
         Ok(())
     }
 
-    /// Cancel the event loop
-    pub fn cancel(&self) {
-        self.cancellation_token.cancel();
-    }
-
     /// Suspend the application (SIGTSTP)
     #[cfg(not(windows))]
     #[allow(dead_code)]
@@ -194,12 +195,85 @@ impl Tui {
         Ok(())
     }
 
-    /// Get the next event from the event channel
+    /// Wait for the next terminal event
+    ///
+    /// Cancel safe, so it can sit in a `select!`. Never resolves while input
+    /// is stopped; after the input stream closes, [`Event::Closed`] is
+    /// delivered once.
     pub async fn next_event(&mut self) -> Option<Event> {
-        self.event_rx.recv().await
+        if std::mem::take(&mut self.init_pending) {
+            return Some(Event::Init);
+        }
+        let Some(events) = self.events.as_mut() else {
+            return std::future::pending().await;
+        };
+        loop {
+            let event = match events.next().await {
+                Some(Ok(event)) => event,
+                Some(Err(_)) => return Some(Event::Error),
+                None => {
+                    self.events = None;
+                    return Some(Event::Closed);
+                }
+            };
+            match event {
+                CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => {
+                    return Some(Event::Key(key))
+                }
+                CrosstermEvent::Mouse(mouse) => return Some(Event::Mouse(mouse)),
+                CrosstermEvent::Resize(x, y) => return Some(Event::Resize(x, y)),
+                CrosstermEvent::FocusLost => return Some(Event::FocusLost),
+                CrosstermEvent::FocusGained => return Some(Event::FocusGained),
+                CrosstermEvent::Paste(s) => return Some(Event::Paste(s)),
+                _ => {} // ignore other events
+            }
+        }
     }
 }
 
+/// Leave the alternate screen and raw mode without a [`Tui`] at hand
+///
+/// For when the app can't shut down the usual way: a panic, or a second
+/// signal while it is still quitting. Does nothing once the terminal is back
+/// to normal.
+pub fn restore() {
+    if !crossterm::terminal::is_raw_mode_enabled().unwrap_or(false) {
+        return;
+    }
+    if TITLE_PUSHED.swap(false, Ordering::SeqCst) {
+        let _ = crossterm::execute!(stdout(), SetTitle(""), PopTitle);
+    }
+    let _ = crossterm::execute!(
+        stdout(),
+        DisableBracketedPaste,
+        DisableMouseCapture,
+        DisableFocusChange,
+        LeaveAlternateScreen,
+        cursor::Show
+    );
+    let _ = crossterm::terminal::disable_raw_mode();
+}
+
+/// `title` without control characters, so a stream title can't end the
+/// title sequence early and send escape sequences of its own
+fn safe_title(title: &str) -> String {
+    title.chars().filter(|c| !c.is_control()).collect()
+}
+
+/// Restore the terminal before a panic is reported, then exit
+///
+/// Release builds abort on a panic in any task; debug builds exit as well
+/// rather than carry on drawing into the restored terminal.
+pub fn install_panic_hook() {
+    let report = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        tracing::error!("{}", info);
+        report(info);
+        std::process::exit(101);
+    }));
+}
+
 impl Deref for Tui {
     type Target = ratatui::Terminal<Backend<Stdout>>;
 
@@ -219,3 +293,17 @@ impl Drop for Tui {
         let _ = self.exit();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_control_characters_from_titles() {
+        assert_eq!(
+            safe_title("somars – Groove Salad – Evil\x07\x1b[2J\u{9b}0m Track"),
+            "somars – Groove Salad – Evil[2J0m Track"
+        );
+        assert_eq!(safe_title("Café – 東京"), "Café – 東京");
+    }
+}
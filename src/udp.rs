@@ -0,0 +1,426 @@
+//! UDP control protocol
+//!
+//! Commands such as `toggle` or `tune groovesalad` arrive as plain text
+//! packets. One sent as `seq 7 toggle` is acknowledged, and a retransmission
+//! of it is dropped.
+
+use crate::action::RandomScope;
+use crate::control::{self, ControlCommand};
+use crate::error::AppError;
+use crate::presets;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Broadcast a command to every somars listening on `port`
+pub async fn send_udp_broadcast(message: &str, port: u16) -> Result<(), AppError> {
+    use tokio::net::UdpSocket;
+
+    // Validate message length to prevent potential abuse
+    const MAX_BROADCAST_MESSAGE_LEN: usize = 256;
+    if message.len() > MAX_BROADCAST_MESSAGE_LEN {
+        return Err(AppError::Udp(format!(
+            "Broadcast message too long: {} bytes (max: {})",
+            message.len(),
+            MAX_BROADCAST_MESSAGE_LEN
+        )));
+    }
+
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| AppError::Udp(format!("Failed to bind UDP socket: {}", e)))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| AppError::Udp(format!("Failed to enable broadcast: {}", e)))?;
+    let target_addr = format!("255.255.255.255:{}", port);
+    socket
+        .send_to(message.as_bytes(), &target_addr)
+        .await
+        .map_err(|e| {
+            AppError::Udp(format!(
+                "Failed to send UDP packet to {}: {}",
+                target_addr, e
+            ))
+        })?;
+    Ok(())
+}
+
+/// Listen for commands on `port`, passing the ones `permissions` allow to `tx`
+pub async fn handle_udp_commands(
+    port: u16,
+    permissions: control::Permissions,
+    tx: tokio::sync::mpsc::Sender<ControlCommand>,
+) -> Result<(), AppError> {
+    use tokio::net::UdpSocket;
+
+    let socket = UdpSocket::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| AppError::Udp(format!("Failed to bind to port {}: {}", port, e)))?;
+    let mut buf = [0; 1024];
+
+    // Rate limiting: max 10 requests per second per IP
+    const MAX_REQUESTS_PER_SECOND: u32 = 10;
+    const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+    let mut rate_tracker: HashMap<SocketAddr, Vec<Instant>> = HashMap::new();
+    let mut seq_tracker: HashMap<SocketAddr, Vec<(u32, Instant)>> = HashMap::new();
+
+    loop {
+        let (len, addr) = socket
+            .recv_from(&mut buf)
+            .await
+            .map_err(|e| AppError::Udp(format!("Failed to receive UDP packet: {}", e)))?;
+
+        // Validate message length before processing
+        const MAX_UDP_MESSAGE_LEN: usize = 256;
+        if len > MAX_UDP_MESSAGE_LEN {
+            warn!(
+                "UDP packet from {} too large: {} bytes (max: {})",
+                addr, len, MAX_UDP_MESSAGE_LEN
+            );
+            continue;
+        }
+
+        // Rate limiting check
+        let now = Instant::now();
+        let timestamps = rate_tracker.entry(addr).or_default();
+
+        // Remove timestamps older than the rate limit window
+        timestamps.retain(|&ts| now.duration_since(ts) < RATE_LIMIT_WINDOW);
+
+        // Check if rate limit exceeded
+        if timestamps.len() >= MAX_REQUESTS_PER_SECOND as usize {
+            warn!(
+                "UDP rate limit exceeded for {}: {} requests in last second",
+                addr,
+                timestamps.len()
+            );
+            continue;
+        }
+
+        // Record this request
+        timestamps.push(now);
+
+        // Clean up old entries from rate tracker periodically
+        if rate_tracker.len() > 100 {
+            rate_tracker.retain(|_, times| !times.is_empty());
+        }
+
+        let msg = String::from_utf8_lossy(&buf[..len]).trim().to_string();
+
+        let (seq, cmd) = match permissions.authorize(&msg) {
+            Ok(packet) => packet,
+            Err(reason) => {
+                warn!("Ignoring UDP command from {}: {}", addr, reason);
+                if let Some(seq) = control::sequence(&msg) {
+                    send_udp_reply(&socket, addr, &format!("err {} {}", seq, reason)).await;
+                }
+                continue;
+            }
+        };
+
+        if cmd == ControlCommand::Hello {
+            send_udp_reply(&socket, addr, &control::Hello::current().to_json()).await;
+            continue;
+        }
+
+        if let Some(seq) = seq {
+            if seen_sequence(&mut seq_tracker, addr, seq, Instant::now()) {
+                send_udp_ack(&socket, addr, seq).await;
+                continue;
+            }
+        }
+
+        info!("Received UDP command from {}: {:?}", addr, cmd);
+
+        tx.send(cmd)
+            .await
+            .map_err(|e| AppError::Udp(format!("Failed to send command to app: {}", e)))?;
+
+        if let Some(seq) = seq {
+            send_udp_ack(&socket, addr, seq).await;
+        }
+    }
+}
+
+/// Parse a packet into its sequence number, if any, and command
+pub fn parse_udp_packet(input: &str) -> Result<(Option<u32>, ControlCommand), String> {
+    // Keywords are case-insensitive; URLs keep their case
+    let original = input.split_whitespace().collect::<Vec<_>>();
+    let lowered = input.to_lowercase();
+    let words = lowered.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return Err("empty command".to_string());
+    }
+
+    let (seq, command_words) = if words.first() == Some(&"seq") {
+        if words.len() < 3 {
+            return Err("sequenced command missing sequence or body".to_string());
+        }
+        let seq = words[1]
+            .parse::<u32>()
+            .map_err(|_| format!("invalid sequence: {}", words[1]))?;
+        (Some(seq), &words[2..])
+    } else {
+        (None, &words[..])
+    };
+
+    let cmd = match command_words {
+        ["hello"] => ControlCommand::Hello,
+        ["play"] => ControlCommand::Play,
+        ["stop"] => ControlCommand::Stop,
+        ["toggle"] => ControlCommand::Toggle,
+        ["toggle", "pause"] => ControlCommand::TogglePause,
+        ["volume", "up"] => ControlCommand::VolumeUp,
+        ["volume", "down"] => ControlCommand::VolumeDown,
+        ["volume", "mute"] => ControlCommand::Mute,
+        ["volume", "unmute"] => ControlCommand::Unmute,
+        ["volume", num] => parse_volume(num)?,
+        ["tune", "url", _] => {
+            let url = original[original.len() - 1];
+            match url::Url::parse(url) {
+                Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {
+                    ControlCommand::TuneUrl(url.to_string())
+                }
+                _ => return Err(format!("invalid stream URL: {}", url)),
+            }
+        }
+        ["tune", "next"] => ControlCommand::TuneNext,
+        ["tune", "prev"] => ControlCommand::TunePrev,
+        ["tune", "back"] => ControlCommand::TuneBack,
+        ["tune", "random"] => ControlCommand::TuneRandom(RandomScope::List),
+        ["tune", "random", "all"] => ControlCommand::TuneRandom(RandomScope::All),
+        ["tune", "random", "presets"] => ControlCommand::TuneRandom(RandomScope::Presets),
+        // Station ID or a spoken name such as "groove salad"
+        ["tune", name @ ..] if !name.is_empty() => {
+            let name = name.join(" ");
+            if name.len() > 64
+                || !name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '\'' | '.'))
+            {
+                return Err(format!("invalid station name: {}", name));
+            }
+            ControlCommand::Tune(name)
+        }
+        ["quality", tier] => ControlCommand::Quality(tier.parse()?),
+        ["preset", number] => number
+            .parse()
+            .ok()
+            .and_then(presets::slot)
+            .map(ControlCommand::Preset)
+            .ok_or_else(|| format!("invalid preset (1-{}): {}", presets::SLOTS, number))?,
+        ["refresh"] => ControlCommand::Refresh,
+        ["like"] => ControlCommand::Like,
+        ["select", "up"] => ControlCommand::SelectUp,
+        ["select", "down"] => ControlCommand::SelectDown,
+        _ => return Err(format!("unknown command: {}", input)),
+    };
+
+    Ok((seq, cmd))
+}
+
+/// `0.5` or `50%` sets the volume; a leading sign (`+0.2`, `-10%`) changes it
+fn parse_volume(value: &str) -> Result<ControlCommand, String> {
+    let (number, divisor) = match value.strip_suffix('%') {
+        Some(number) => (number, 100.0),
+        None => (value, 1.0),
+    };
+    let level = number
+        .parse::<f32>()
+        .ok()
+        .filter(|level| level.is_finite())
+        .ok_or_else(|| format!("invalid volume value: {}", value))?
+        / divisor;
+    if value.starts_with(['+', '-']) {
+        if level.abs() > 2.0 {
+            return Err(format!("volume change out of range (-2.0-+2.0): {}", value));
+        }
+        return Ok(ControlCommand::AdjustVolume(level));
+    }
+    if !(0.0..=2.0).contains(&level) {
+        return Err(format!("volume value out of range (0.0-2.0): {}", level));
+    }
+    Ok(ControlCommand::SetVolume(level))
+}
+
+fn seen_sequence(
+    seq_tracker: &mut HashMap<SocketAddr, Vec<(u32, Instant)>>,
+    addr: SocketAddr,
+    seq: u32,
+    now: Instant,
+) -> bool {
+    const SEQ_RETENTION: Duration = Duration::from_secs(60);
+    let entries = seq_tracker.entry(addr).or_default();
+    entries.retain(|(_, seen_at)| now.duration_since(*seen_at) < SEQ_RETENTION);
+    if entries.iter().any(|(seen_seq, _)| *seen_seq == seq) {
+        return true;
+    }
+    entries.push((seq, now));
+    false
+}
+
+async fn send_udp_ack(socket: &tokio::net::UdpSocket, addr: SocketAddr, seq: u32) {
+    send_udp_reply(socket, addr, &format!("ok {}", seq)).await;
+}
+
+async fn send_udp_reply(socket: &tokio::net::UdpSocket, addr: SocketAddr, reply: &str) {
+    if let Err(e) = socket.send_to(reply.as_bytes(), addr).await {
+        warn!("Failed to send UDP reply to {}: {}", addr, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_toggle_play_stop() {
+        assert_eq!(
+            parse_udp_packet("toggle").unwrap(),
+            (None, ControlCommand::Toggle)
+        );
+    }
+
+    #[test]
+    fn parses_relative_volume() {
+        assert_eq!(
+            parse_udp_packet("volume +0.2").unwrap(),
+            (None, ControlCommand::AdjustVolume(0.2))
+        );
+        assert_eq!(
+            parse_udp_packet("volume -10%").unwrap(),
+            (None, ControlCommand::AdjustVolume(-0.1))
+        );
+        assert_eq!(
+            parse_udp_packet("volume 50%").unwrap(),
+            (None, ControlCommand::SetVolume(0.5))
+        );
+        assert_eq!(
+            parse_udp_packet("volume MUTE").unwrap(),
+            (None, ControlCommand::Mute)
+        );
+        assert_eq!(
+            parse_udp_packet("volume unmute").unwrap(),
+            (None, ControlCommand::Unmute)
+        );
+        assert!(parse_udp_packet("volume +300%").is_err());
+        assert!(parse_udp_packet("volume +nan").is_err());
+        assert!(parse_udp_packet("volume 3").is_err());
+    }
+
+    #[test]
+    fn parses_tune_back() {
+        assert_eq!(
+            parse_udp_packet("tune back").unwrap(),
+            (None, ControlCommand::TuneBack)
+        );
+    }
+
+    #[test]
+    fn parses_tune_random() {
+        assert_eq!(
+            parse_udp_packet("tune random").unwrap(),
+            (None, ControlCommand::TuneRandom(RandomScope::List))
+        );
+        assert_eq!(
+            parse_udp_packet("tune random presets").unwrap(),
+            (None, ControlCommand::TuneRandom(RandomScope::Presets))
+        );
+    }
+
+    #[test]
+    fn parses_preset() {
+        assert_eq!(
+            parse_udp_packet("preset 3").unwrap(),
+            (None, ControlCommand::Preset(3))
+        );
+        assert!(parse_udp_packet("preset 0").is_err());
+        assert!(parse_udp_packet("preset ten").is_err());
+    }
+
+    #[test]
+    fn parses_hello() {
+        assert_eq!(
+            parse_udp_packet("HELLO").unwrap(),
+            (None, ControlCommand::Hello)
+        );
+    }
+
+    #[test]
+    fn parses_refresh() {
+        assert_eq!(
+            parse_udp_packet("refresh").unwrap(),
+            (None, ControlCommand::Refresh)
+        );
+    }
+
+    #[test]
+    fn parses_like() {
+        assert_eq!(
+            parse_udp_packet("like").unwrap(),
+            (None, ControlCommand::Like)
+        );
+    }
+
+    #[test]
+    fn parses_sequenced_toggle_pause() {
+        assert_eq!(
+            parse_udp_packet("seq 42 toggle pause").unwrap(),
+            (Some(42), ControlCommand::TogglePause)
+        );
+    }
+
+    #[test]
+    fn parses_tune_url_preserving_case() {
+        assert_eq!(
+            parse_udp_packet("TUNE url http://Example.com/Live.mp3").unwrap(),
+            (
+                None,
+                ControlCommand::TuneUrl("http://Example.com/Live.mp3".to_string())
+            )
+        );
+        assert!(parse_udp_packet("tune url ftp://example.com/live.mp3").is_err());
+    }
+
+    #[test]
+    fn parses_spoken_station_name() {
+        assert_eq!(
+            parse_udp_packet("tune Groove Salad").unwrap(),
+            (None, ControlCommand::Tune("groove salad".to_string()))
+        );
+        assert_eq!(
+            parse_udp_packet("seq 3 tune next").unwrap(),
+            (Some(3), ControlCommand::TuneNext)
+        );
+        assert!(parse_udp_packet("tune").is_err());
+        assert!(parse_udp_packet("tune groove; rm").is_err());
+    }
+
+    #[test]
+    fn parses_quality() {
+        assert_eq!(
+            parse_udp_packet("quality LOW").unwrap(),
+            (
+                None,
+                ControlCommand::Quality(crate::station::StreamQuality::Low)
+            )
+        );
+        assert!(parse_udp_packet("quality ultra").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_volume() {
+        assert!(parse_udp_packet("volume 9.0").is_err());
+        assert!(parse_udp_packet("volume loud").is_err());
+    }
+
+    #[test]
+    fn detects_duplicate_sequence() {
+        let addr = "127.0.0.1:8069".parse().unwrap();
+        let mut tracker = HashMap::new();
+        let now = Instant::now();
+        assert!(!seen_sequence(&mut tracker, addr, 7, now));
+        assert!(seen_sequence(&mut tracker, addr, 7, now));
+    }
+}
@@ -0,0 +1,314 @@
+//! Update check and self-update
+//!
+//! With `[update] check = true`, somars asks GitHub for the latest release
+//! once at startup and shows a notice when it is newer than the running
+//! version. The request carries nothing but the usual user agent, and nothing
+//! is sent when the check is off, which is the default.
+//!
+//! `somars self-update` replaces the binary with the release's build for this
+//! platform, where the release has a plain executable for it and the binary
+//! can be replaced in place (not on Windows, where it is locked while
+//! running). The download must match the SHA-256 in the release's
+//! `<asset>.sha256` file and be an executable for this platform, or the
+//! binary is left alone.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Latest release of the repository on GitHub
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/skammer/somars/releases/latest";
+
+/// Update check settings, read from the `[update]` table
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct UpdateConfig {
+    /// Look for a newer release at startup
+    #[serde(default)]
+    pub check: bool,
+}
+
+/// A GitHub release
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    /// e.g. `v0.3.0`
+    pub tag_name: String,
+    /// Release notes page
+    pub html_url: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Version without the `v` prefix
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    /// Whether the release is newer than the running build
+    pub fn is_newer(&self) -> bool {
+        is_newer(self.version(), env!("CARGO_PKG_VERSION"))
+    }
+
+    /// The executable built for this platform, if the release has one
+    ///
+    /// Archives are skipped, as unpacking them isn't supported, and so are
+    /// checksums and signatures.
+    pub fn binary_for_platform(&self) -> Option<&Asset> {
+        let os: &[&str] = match std::env::consts::OS {
+            "macos" => &["macos", "darwin", "apple"],
+            "windows" => &["windows", "win64"],
+            os => &[os][..],
+        };
+        let arch: &[&str] = match std::env::consts::ARCH {
+            "x86_64" => &["x86_64", "amd64", "x64"],
+            "aarch64" => &["aarch64", "arm64"],
+            arch => &[arch][..],
+        };
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_lowercase();
+            let archive = [
+                ".tar", ".tgz", ".gz", ".zip", ".xz", ".sha256", ".sig", ".asc", ".deb", ".rpm",
+            ]
+            .iter()
+            .any(|extension| name.contains(extension));
+            !archive
+                && os.iter().any(|os| name.contains(os))
+                && arch.iter().any(|arch| name.contains(arch))
+        })
+    }
+
+    /// The `.sha256` file published next to `asset`
+    pub fn checksum_for(&self, asset: &Asset) -> Option<&Asset> {
+        let name = format!("{}.sha256", asset.name);
+        self.assets
+            .iter()
+            .find(|checksum| checksum.name.eq_ignore_ascii_case(&name))
+    }
+}
+
+/// Fetch the latest release
+pub async fn latest_release() -> reqwest::Result<Release> {
+    crate::http::client()
+        .get(LATEST_RELEASE_URL)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Compare dotted versions numerically; pre-release suffixes are ignored
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parse(candidate) > parse(current)
+}
+
+/// Download the release's binary for this platform over the running one
+///
+/// Returns the installed version, or an error explaining why the update
+/// can't be done here.
+pub async fn self_update() -> Result<String, String> {
+    let release = latest_release()
+        .await
+        .map_err(|error| format!("Failed to check for updates: {}", error))?;
+    if !release.is_newer() {
+        return Err(format!(
+            "somars {} is the latest version",
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+    if cfg!(windows) {
+        return Err(format!(
+            "Updating in place isn't supported on Windows; download {} from {}",
+            release.tag_name, release.html_url
+        ));
+    }
+    let asset = release.binary_for_platform().ok_or_else(|| {
+        format!(
+            "{} has no build for {}-{}; download it from {}",
+            release.tag_name,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            release.html_url
+        )
+    })?;
+
+    let checksum = release.checksum_for(asset).ok_or_else(|| {
+        format!(
+            "{} has no checksum for {}; download it from {}",
+            release.tag_name, asset.name, release.html_url
+        )
+    })?;
+    let expected = download(checksum).await?;
+    let expected = parse_checksum(&String::from_utf8_lossy(&expected))
+        .ok_or_else(|| format!("{} doesn't hold a SHA-256 checksum", checksum.name))?;
+    let bytes = download(asset).await?;
+    if sha256_hex(&bytes) != expected {
+        return Err(format!(
+            "{} doesn't match its checksum; the binary was left alone",
+            asset.name
+        ));
+    }
+    if !is_native_executable(&bytes) {
+        return Err(format!(
+            "{} isn't an executable for {}; the binary was left alone",
+            asset.name,
+            std::env::consts::OS
+        ));
+    }
+
+    let exe = std::env::current_exe()
+        .map_err(|error| format!("Can't find the running binary: {}", error))?;
+    replace_binary(&exe, &bytes)
+        .map_err(|error| format!("Failed to replace {}: {}", exe.display(), error))?;
+    Ok(release.version().to_string())
+}
+
+async fn download(asset: &Asset) -> Result<Vec<u8>, String> {
+    let bytes = crate::http::client()
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|error| format!("Failed to download {}: {}", asset.name, error))?
+        .bytes()
+        .await
+        .map_err(|error| format!("Failed to download {}: {}", asset.name, error))?;
+    Ok(bytes.to_vec())
+}
+
+/// The hex digest at the start of a `.sha256` file, as written by `sha256sum`
+fn parse_checksum(contents: &str) -> Option<String> {
+    let digest = contents.split_whitespace().next()?.to_ascii_lowercase();
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit())).then_some(digest)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Whether `bytes` start like an executable for this platform: ELF, or
+/// Mach-O on macOS
+fn is_native_executable(bytes: &[u8]) -> bool {
+    const ELF: &[u8] = b"\x7fELF";
+    const MACH_O: [&[u8]; 3] = [
+        b"\xcf\xfa\xed\xfe",
+        b"\xce\xfa\xed\xfe",
+        b"\xca\xfe\xba\xbe",
+    ];
+    if cfg!(target_os = "macos") {
+        MACH_O.iter().any(|magic| bytes.starts_with(magic))
+    } else {
+        bytes.starts_with(ELF)
+    }
+}
+
+/// Write `bytes` next to `exe` and rename it over the original
+fn replace_binary(exe: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let staged = exe.with_extension("update");
+    std::fs::write(&staged, bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(&staged, exe).inspect_err(|_| {
+        let _ = std::fs::remove_file(&staged);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_and_picks_the_platform_binary() {
+        assert!(is_newer("0.10.0", "0.9.3"));
+        assert!(is_newer("1.0", "0.99.99"));
+        assert!(!is_newer("0.2.1", "0.2.1"));
+        assert!(!is_newer("0.2.1-rc.1", "0.2.1"));
+
+        let asset = |name: &str| Asset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.com/{}", name),
+        };
+        let release = Release {
+            tag_name: "v99.0.0".to_string(),
+            html_url: "https://example.com".to_string(),
+            assets: vec![
+                asset(&format!(
+                    "somars-{}-{}.tar.gz",
+                    std::env::consts::ARCH,
+                    std::env::consts::OS
+                )),
+                asset(&format!(
+                    "somars-{}-{}",
+                    std::env::consts::ARCH,
+                    std::env::consts::OS
+                )),
+                asset(&format!(
+                    "somars-{}-{}.sha256",
+                    std::env::consts::ARCH,
+                    std::env::consts::OS
+                )),
+                asset("somars-sparc-plan9"),
+            ],
+        };
+        assert!(release.is_newer());
+        assert_eq!(release.version(), "99.0.0");
+        assert_eq!(release.binary_for_platform(), Some(&release.assets[1]));
+        assert_eq!(
+            release.checksum_for(&release.assets[1]),
+            Some(&release.assets[2])
+        );
+        assert_eq!(release.checksum_for(&release.assets[3]), None);
+    }
+
+    #[test]
+    fn skips_signatures_and_checks_downloads() {
+        let name = format!("somars-{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+        let release = Release {
+            tag_name: "v99.0.0".to_string(),
+            html_url: "https://example.com".to_string(),
+            assets: [".sig", ".asc"]
+                .iter()
+                .map(|extension| Asset {
+                    name: format!("{}{}", name, extension),
+                    browser_download_url: "https://example.com".to_string(),
+                })
+                .collect(),
+        };
+        assert_eq!(release.binary_for_platform(), None);
+
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(sha256_hex(b"abc"), digest);
+        assert_eq!(
+            parse_checksum(&format!("{}  {}\n", digest.to_uppercase(), name)),
+            Some(digest.to_string())
+        );
+        assert_eq!(parse_checksum("<!DOCTYPE html>"), None);
+        assert_eq!(parse_checksum(""), None);
+
+        assert!(!is_native_executable(b"<!DOCTYPE html>"));
+        assert!(!is_native_executable(b""));
+    }
+}
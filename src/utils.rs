@@ -6,6 +6,71 @@ pub fn format_duration(d: std::time::Duration) -> String {
     format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
 }
 
+/// Compact length such as `2h13m` or `45m`
+pub fn format_length(d: std::time::Duration) -> String {
+    let minutes = d.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h{:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// File size such as `3.2 MB` or `512 KB`
+pub fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = 1024 * KB;
+    if bytes >= MB {
+        format!("{:.1} MB", bytes as f64 / MB as f64)
+    } else {
+        format!("{} KB", bytes / KB)
+    }
+}
+
+/// A random index below `len`, which must not be zero
+///
+/// Seeded per call from the standard library's hasher keys; good enough for
+/// picking a station, not for anything that needs real randomness.
+pub fn random_index(len: usize) -> usize {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() % len as u64) as usize
+}
+
+/// `template` with each `{name}` in `values` replaced by its value
+///
+/// Done in one pass, so a value holding something like `{title}` is left as
+/// it is. Braces around other names are kept.
+pub fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = values.iter().find(|(name, _)| {
+            rest[1..].starts_with(name) && rest[1 + name.len()..].starts_with('}')
+        });
+        match value {
+            Some((name, value)) => {
+                filled.push_str(value);
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -28,4 +93,19 @@ mod tests {
         let duration = std::time::Duration::from_secs(3661); // 1 hour 1 minute 1 second
         assert_eq!(format_duration(duration), "01:01:01");
     }
+
+    #[test]
+    fn fills_placeholders_once() {
+        let values = [("station", "Groove Salad"), ("title", "{station}")];
+        assert_eq!(
+            fill_placeholders("{title} on {station} {album} {", &values),
+            "{station} on Groove Salad {album} {"
+        );
+    }
+
+    #[test]
+    fn random_index_stays_in_range() {
+        assert!((0..100).all(|_| random_index(3) < 3));
+        assert_eq!(random_index(1), 0);
+    }
 }
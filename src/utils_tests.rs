@@ -1,23 +1,29 @@
 #[cfg(test)]
 mod tests {
-    use crate::utils::format_duration;
-    
+    use crate::utils::{format_duration, format_size};
+
     #[test]
     fn test_format_duration() {
         // Test zero duration
         let duration = std::time::Duration::from_secs(0);
         assert_eq!(format_duration(duration), "00:00:00");
-        
+
         // Test seconds only
         let duration = std::time::Duration::from_secs(30);
         assert_eq!(format_duration(duration), "00:00:30");
-        
+
         // Test minutes and seconds
         let duration = std::time::Duration::from_secs(90); // 1 minute 30 seconds
         assert_eq!(format_duration(duration), "00:01:30");
-        
+
         // Test hours, minutes and seconds
         let duration = std::time::Duration::from_secs(3661); // 1 hour 1 minute 1 second
         assert_eq!(format_duration(duration), "01:01:01");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512 * 1024), "512 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 + 200 * 1024), "3.2 MB");
+    }
+}
@@ -0,0 +1,182 @@
+//! Voice-friendly station names
+//!
+//! Voice assistants send what they heard, e.g. `tune groove salad` rather than
+//! `tune groovesalad`, and sometimes mishear a letter or two. Station queries
+//! from the remote interfaces are matched against IDs, titles and configured
+//! aliases, ignoring case, spacing and punctuation, with a few typos allowed.
+
+use crate::station::Station;
+use std::collections::BTreeMap;
+
+/// Spoken name of a station: its title in lower case without punctuation
+pub fn voice_name(station: &Station) -> String {
+    station
+        .title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Voice names and aliases mapped to the station IDs they tune
+pub fn voice_names(
+    stations: &[Station],
+    aliases: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut names: BTreeMap<String, String> = stations
+        .iter()
+        .map(|station| (voice_name(station), station.id.clone()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    for (alias, id) in aliases {
+        if stations.iter().any(|station| station.id == *id) {
+            names.insert(alias.to_lowercase(), id.clone());
+        }
+    }
+    names
+}
+
+/// Index of the station `query` refers to
+///
+/// Tries, in order: the exact ID, a configured alias, an ID or title equal
+/// to the query once case and punctuation are dropped, the shortest name
+/// starting with the query, and finally the closest name within a small edit
+/// distance. Ties match nothing.
+pub fn find_station(
+    stations: &[Station],
+    query: &str,
+    aliases: &BTreeMap<String, String>,
+) -> Option<usize> {
+    let position = |id: &str| stations.iter().position(|station| station.id == id);
+    if let Some(index) = position(query) {
+        return Some(index);
+    }
+
+    let query = normalize(query);
+    if query.is_empty() {
+        return None;
+    }
+    if let Some(index) = aliases
+        .iter()
+        .find(|(alias, _)| normalize(alias) == query)
+        .and_then(|(_, id)| position(id))
+    {
+        return Some(index);
+    }
+
+    let keys: Vec<[String; 2]> = stations
+        .iter()
+        .map(|station| [normalize(&station.id), normalize(&station.title)])
+        .collect();
+    if let Some(index) = keys.iter().position(|key| key.contains(&query)) {
+        return Some(index);
+    }
+
+    // Prefix of a name, preferring the shortest: "groove" is Groove Salad
+    let prefixed = keys.iter().map(|key| {
+        key.iter()
+            .filter(|name| name.starts_with(&query))
+            .map(|name| name.len())
+            .min()
+    });
+    if let Some(index) = best(prefixed) {
+        return Some(index);
+    }
+
+    // One typo per four characters; short queries must match exactly
+    let max_distance = query.chars().count() / 4;
+    best(keys.iter().map(|key| {
+        key.iter()
+            .map(|name| edit_distance(name, &query))
+            .min()
+            .filter(|distance| *distance <= max_distance)
+    }))
+}
+
+/// Index of the unique lowest score, `None` if there is none or a tie
+fn best(scores: impl Iterator<Item = Option<usize>>) -> Option<usize> {
+    let scored: Vec<(usize, usize)> = scores
+        .enumerate()
+        .filter_map(|(index, score)| Some((index, score?)))
+        .collect();
+    let lowest = scored.iter().map(|(_, score)| *score).min()?;
+    let mut matches = scored.iter().filter(|(_, score)| *score == lowest);
+    let (index, _) = matches.next()?;
+    matches.next().is_none().then_some(*index)
+}
+
+/// Lower case alphanumerics only, so "Groove Salad" matches "groovesalad"
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Levenshtein distance between `a` and `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stations() -> Vec<Station> {
+        vec![
+            Station::for_test("groovesalad", "Groove Salad"),
+            Station::for_test("gsclassic", "Groove Salad Classic"),
+            Station::for_test("dronezone", "Drone Zone"),
+            Station::for_test("defcon", "DEF CON Radio"),
+            Station::for_test("7soul", "Seven Inch Soul"),
+        ]
+    }
+
+    #[test]
+    fn maps_titles_to_voice_names() {
+        let aliases = BTreeMap::from([
+            ("Office".to_string(), "dronezone".to_string()),
+            ("gone".to_string(), "missing".to_string()),
+        ]);
+        let names = voice_names(&stations(), &aliases);
+        assert_eq!(names["groove salad"], "groovesalad");
+        assert_eq!(names["def con radio"], "defcon");
+        assert_eq!(names["office"], "dronezone");
+        assert!(!names.contains_key("gone"));
+    }
+
+    #[test]
+    fn finds_stations_by_spoken_name() {
+        let stations = stations();
+        let aliases = BTreeMap::from([("the office".to_string(), "dronezone".to_string())]);
+        let find = |query| find_station(&stations, query, &aliases);
+
+        assert_eq!(find("groovesalad"), Some(0));
+        assert_eq!(find("groove salad"), Some(0));
+        assert_eq!(find("Groove Salad Classic"), Some(1));
+        assert_eq!(find("seven inch soul"), Some(4));
+        assert_eq!(find("the office"), Some(2));
+        // Prefix
+        assert_eq!(find("drone"), Some(2));
+        assert_eq!(find("groove"), Some(0));
+        // Misheard
+        assert_eq!(find("grove salad"), Some(0));
+        assert_eq!(find("drone zoan"), Some(2));
+        assert_eq!(find("def con"), Some(3));
+        assert_eq!(find("jazz"), None);
+        assert_eq!(find(""), None);
+    }
+}
@@ -0,0 +1,340 @@
+//! Webhook notifications
+//!
+//! POSTs what plays to a URL set in the `[webhook]` table whenever the track
+//! changes and whenever playback starts, pauses, stops or moves to another
+//! station. The body is one of:
+//!
+//! - `json`, the state as `/now.json` has it, tagged with what changed:
+//!   `{"event":"track","state":"playing","station":{...},"track":"Artist - Title"}`
+//! - `slack`, a one-line message as Slack's incoming webhooks take it:
+//!   `{"text":"Groove Salad: Artist - Title"}`
+//! - `discord`, the same line as Discord's webhooks take it:
+//!   `{"content":"Groove Salad: Artist - Title"}`
+//!
+//! Notifications go out one at a time in the order they happened, through
+//! the shared client with the `[network]` settings. Failures go to the log
+//! file.
+
+use crate::status_server::{NowState, NowStation};
+use crate::{station::Station, PlaybackState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Longest a notification may take before it is given up on
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Webhook settings, read from the `[webhook]` table
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackWebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// URL to POST to
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Headers added to every notification, such as an `Authorization` a
+    /// dashboard asks for
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Only notify of playback starting, pausing and stopping, not of every
+    /// track
+    #[serde(default)]
+    pub state_only: bool,
+}
+
+/// Body of a notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Slack,
+    Discord,
+}
+
+/// What a notification is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// Playback started, paused, stopped or moved to another station
+    State,
+    /// A new track started
+    Track,
+}
+
+/// Body of a `json` notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookPayload {
+    pub event: WebhookEvent,
+    pub state: NowState,
+    /// `None` until a station is tuned
+    pub station: Option<NowStation>,
+    /// ICY stream title, `None` until one arrives
+    pub track: Option<String>,
+}
+
+impl WebhookPayload {
+    /// The notification's body in `format`
+    pub fn body(&self, format: WebhookFormat) -> serde_json::Value {
+        match format {
+            WebhookFormat::Json => serde_json::to_value(self).unwrap_or_default(),
+            WebhookFormat::Slack => serde_json::json!({ "text": self.message() }),
+            WebhookFormat::Discord => serde_json::json!({ "content": self.message() }),
+        }
+    }
+
+    /// One line for a chat, like `Groove Salad: Artist - Title`
+    pub fn message(&self) -> String {
+        let station = self.station.as_ref().map(|station| station.title.as_str());
+        match (self.event, station, &self.track) {
+            (WebhookEvent::Track, Some(station), Some(track)) => format!("{}: {}", station, track),
+            (_, Some(station), _) if self.state != NowState::Stopped => {
+                format!("{}: {}", state_name(self.state), station)
+            }
+            _ => state_name(self.state),
+        }
+    }
+}
+
+fn state_name(state: NowState) -> String {
+    crate::i18n::t(match state {
+        NowState::Playing => "playing",
+        NowState::Paused => "paused",
+        NowState::Stopped => "stopped",
+    })
+}
+
+/// A change reported by the app
+#[derive(Debug)]
+enum Update {
+    State(PlaybackState),
+    Station(NowStation),
+    Track(String),
+}
+
+/// What was last notified, to only notify of changes
+#[derive(Debug)]
+struct Notifier {
+    state: NowState,
+    station: Option<NowStation>,
+    track: Option<String>,
+    /// Station notified with the state, to notice a switch while playing
+    notified_station: Option<String>,
+    state_only: bool,
+}
+
+impl Notifier {
+    fn new(state_only: bool) -> Self {
+        Self {
+            state: NowState::Stopped,
+            station: None,
+            track: None,
+            notified_station: None,
+            state_only,
+        }
+    }
+
+    /// Apply `update`, returning the notification it calls for
+    fn apply(&mut self, update: Update) -> Option<WebhookPayload> {
+        let event = match update {
+            Update::State(state) => {
+                let state = match state {
+                    PlaybackState::Playing => NowState::Playing,
+                    PlaybackState::Paused => NowState::Paused,
+                    PlaybackState::Stopped => NowState::Stopped,
+                };
+                let station = self.station.as_ref().map(|station| &station.id);
+                if state == self.state && station == self.notified_station.as_ref() {
+                    return None;
+                }
+                if state == NowState::Stopped {
+                    self.track = None;
+                }
+                self.state = state;
+                self.notified_station = station.cloned();
+                WebhookEvent::State
+            }
+            Update::Station(station) => {
+                self.station = Some(station);
+                self.track = None;
+                // The state that follows tells of the switch
+                return None;
+            }
+            Update::Track(title) => {
+                if self.track.as_ref() == Some(&title) {
+                    return None;
+                }
+                self.track = Some(title);
+                if self.state_only {
+                    return None;
+                }
+                WebhookEvent::Track
+            }
+        };
+        Some(WebhookPayload {
+            event,
+            state: self.state,
+            station: self.station.clone(),
+            track: self.track.clone(),
+        })
+    }
+}
+
+/// Handle for notifying the webhook
+#[derive(Clone, Debug)]
+pub struct WebhookHandle {
+    update_tx: Option<mpsc::UnboundedSender<Update>>,
+}
+
+impl WebhookHandle {
+    /// Start sending notifications if a webhook is enabled in the
+    /// configuration
+    pub fn start(config: &PlaybackWebhookConfig) -> Self {
+        if !config.enabled {
+            return Self { update_tx: None };
+        }
+        if config.url.trim().is_empty() {
+            warn!("Webhook enabled without a url");
+            return Self { update_tx: None };
+        }
+
+        let (update_tx, update_rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(config.clone(), update_rx));
+        Self {
+            update_tx: Some(update_tx),
+        }
+    }
+
+    pub fn set_playback_state(&self, state: PlaybackState) {
+        self.send(Update::State(state));
+    }
+
+    pub fn set_station(&self, station: Station) {
+        self.send(Update::Station(NowStation {
+            id: station.id,
+            title: station.title,
+            genre: station.genre,
+        }));
+    }
+
+    pub fn set_track_title(&self, title: String) {
+        self.send(Update::Track(title));
+    }
+
+    fn send(&self, update: Update) {
+        if let Some(update_tx) = &self.update_tx {
+            let _ = update_tx.send(update);
+        }
+    }
+}
+
+async fn run(config: PlaybackWebhookConfig, mut update_rx: mpsc::UnboundedReceiver<Update>) {
+    let mut notifier = Notifier::new(config.state_only);
+    while let Some(update) = update_rx.recv().await {
+        if let Some(payload) = notifier.apply(update) {
+            notify(&config, &payload).await;
+        }
+    }
+}
+
+async fn notify(config: &PlaybackWebhookConfig, payload: &WebhookPayload) {
+    let mut request = crate::http::client()
+        .post(&config.url)
+        .timeout(REQUEST_TIMEOUT)
+        .json(&payload.body(config.format));
+    for (name, value) in &config.headers {
+        request = request.header(name, value);
+    }
+    match request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(_) => debug!("Webhook notified of {:?}", payload.event),
+        Err(error) => warn!("Webhook notification failed: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station() -> NowStation {
+        NowStation {
+            id: "groovesalad".to_string(),
+            title: "Groove Salad".to_string(),
+            genre: "ambient|electronica".to_string(),
+        }
+    }
+
+    #[test]
+    fn notifies_of_changes_only() {
+        let mut notifier = Notifier::new(false);
+        assert_eq!(notifier.apply(Update::Station(station())), None);
+        let playing = notifier
+            .apply(Update::State(PlaybackState::Playing))
+            .unwrap();
+        assert_eq!(playing.event, WebhookEvent::State);
+        assert_eq!(playing.station.unwrap().id, "groovesalad");
+        assert_eq!(notifier.apply(Update::State(PlaybackState::Playing)), None);
+
+        let track = notifier
+            .apply(Update::Track("Artist - Title".to_string()))
+            .unwrap();
+        assert_eq!(track.event, WebhookEvent::Track);
+        assert_eq!(track.track.as_deref(), Some("Artist - Title"));
+        assert_eq!(
+            notifier.apply(Update::Track("Artist - Title".to_string())),
+            None
+        );
+
+        // Switching stations while playing is a state change too
+        notifier.apply(Update::Station(NowStation {
+            id: "dronezone".to_string(),
+            ..station()
+        }));
+        let switched = notifier
+            .apply(Update::State(PlaybackState::Playing))
+            .unwrap();
+        assert_eq!(switched.station.unwrap().id, "dronezone");
+        assert_eq!(switched.track, None);
+
+        let mut notifier = Notifier::new(true);
+        notifier.apply(Update::State(PlaybackState::Playing));
+        assert_eq!(notifier.apply(Update::Track("A - B".to_string())), None);
+    }
+
+    #[test]
+    fn formats_the_body() {
+        let payload = WebhookPayload {
+            event: WebhookEvent::Track,
+            state: NowState::Playing,
+            station: Some(station()),
+            track: Some("Artist - Title".to_string()),
+        };
+        let json = payload.body(WebhookFormat::Json);
+        assert_eq!(json["event"], "track");
+        assert_eq!(json["state"], "playing");
+        assert_eq!(json["station"]["id"], "groovesalad");
+        assert_eq!(json["track"], "Artist - Title");
+        assert_eq!(
+            payload.body(WebhookFormat::Slack),
+            serde_json::json!({ "text": "Groove Salad: Artist - Title" })
+        );
+        assert_eq!(
+            payload.body(WebhookFormat::Discord)["content"],
+            "Groove Salad: Artist - Title"
+        );
+
+        let stopped = WebhookPayload {
+            event: WebhookEvent::State,
+            state: NowState::Stopped,
+            ..payload
+        };
+        assert_eq!(stopped.message(), crate::i18n::t("stopped"));
+    }
+}
@@ -0,0 +1,329 @@
+//! WebSocket event stream
+//!
+//! Pushes playback state, station, track, volume and error events as JSON to
+//! every connected client so dashboards can follow somars without polling.
+//! Each message is an object tagged by `event`:
+//!
+//! ```json
+//! {"event":"state","state":"playing"}
+//! {"event":"track","station":"Groove Salad","title":"Artist - Title"}
+//! {"event":"recording","recording":{"file":"groovesalad-20240302-211400.mp3","seconds":42,"bytes":688128}}
+//! {"event":"progress","listened_seconds":1834,"buffered_ms":2400,"underruns":0}
+//! ```
+//!
+//! Progress is sent every few seconds while playing, so screen readers and
+//! dashboards can announce it without timing anything themselves. New clients
+//! first receive the latest state, station, track and volume.
+//! When senders without a token may not read the status, clients connect with
+//! a `token` query parameter or an `Authorization: Bearer` header carrying a
+//! token with the `status` permission.
+
+use crate::audio::recorder::RecordingStatus;
+use crate::control::{request_token, Permission, Permissions};
+use crate::{config::WebSocketConfig, station::Station, PlaybackState};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+/// Event pushed to WebSocket clients
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event {
+    State {
+        state: &'static str,
+    },
+    Station {
+        id: String,
+        title: String,
+        genre: String,
+    },
+    Track {
+        station: String,
+        title: String,
+    },
+    Volume {
+        volume: f32,
+    },
+    /// Sent every second while recording; `null` once it stops
+    Recording {
+        recording: Option<RecordingStatus>,
+    },
+    Error {
+        message: String,
+    },
+    /// Sent periodically while playing
+    Progress {
+        /// Time listened this session
+        listened_seconds: u64,
+        /// Decoded audio waiting to be played; 0 while rebuffering
+        buffered_ms: u64,
+        /// Times the buffer ran dry since the station started
+        underruns: u32,
+    },
+}
+
+impl Event {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// Handle for pushing events to WebSocket clients
+#[derive(Clone, Debug)]
+pub struct WebSocketHandle {
+    event_tx: Option<mpsc::UnboundedSender<Event>>,
+}
+
+impl WebSocketHandle {
+    /// Start the WebSocket server if it is enabled in the configuration.
+    pub fn start(config: &WebSocketConfig, permissions: Permissions) -> Self {
+        if !config.enabled {
+            return Self { event_tx: None };
+        }
+
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let address = format!("{}:{}", config.bind, config.port);
+        tokio::spawn(async move {
+            run(address, permissions, event_rx).await;
+        });
+        Self {
+            event_tx: Some(event_tx),
+        }
+    }
+
+    pub fn set_playback_state(&self, state: PlaybackState) {
+        let state = match state {
+            PlaybackState::Playing => "playing",
+            PlaybackState::Paused => "paused",
+            PlaybackState::Stopped => "stopped",
+        };
+        self.send(Event::State { state });
+    }
+
+    pub fn set_station(&self, station: Station) {
+        self.send(Event::Station {
+            id: station.id,
+            title: station.title,
+            genre: station.genre,
+        });
+    }
+
+    pub fn set_track_title(&self, station: String, title: String) {
+        self.send(Event::Track { station, title });
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.send(Event::Volume { volume });
+    }
+
+    pub fn set_recording(&self, recording: Option<RecordingStatus>) {
+        self.send(Event::Recording { recording });
+    }
+
+    pub fn error(&self, message: String) {
+        self.send(Event::Error { message });
+    }
+
+    pub fn progress(&self, listened: Duration, buffered: Duration, underruns: u32) {
+        self.send(Event::Progress {
+            listened_seconds: listened.as_secs(),
+            buffered_ms: buffered.as_millis() as u64,
+            underruns,
+        });
+    }
+
+    fn send(&self, event: Event) {
+        if let Some(event_tx) = &self.event_tx {
+            let _ = event_tx.send(event);
+        }
+    }
+}
+
+/// Latest value of each replayable event
+#[derive(Debug, Default)]
+struct Snapshot {
+    state: Option<Event>,
+    station: Option<Event>,
+    track: Option<Event>,
+    volume: Option<Event>,
+    recording: Option<Event>,
+}
+
+impl Snapshot {
+    fn apply(&mut self, event: &Event) {
+        let slot = match event {
+            Event::State { .. } => &mut self.state,
+            Event::Station { .. } => {
+                self.track = None;
+                &mut self.station
+            }
+            Event::Track { .. } => &mut self.track,
+            Event::Volume { .. } => &mut self.volume,
+            Event::Recording { .. } => &mut self.recording,
+            Event::Error { .. } | Event::Progress { .. } => return,
+        };
+        *slot = Some(event.clone());
+    }
+
+    fn events(&self) -> impl Iterator<Item = &Event> {
+        [
+            &self.state,
+            &self.station,
+            &self.track,
+            &self.volume,
+            &self.recording,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+async fn run(
+    address: String,
+    permissions: Permissions,
+    mut event_rx: mpsc::UnboundedReceiver<Event>,
+) {
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(error) => {
+            warn!(%error, %address, "Failed to start WebSocket server");
+            return;
+        }
+    };
+    info!(%address, "WebSocket server listening");
+
+    let mut snapshot = Snapshot::default();
+    let mut clients: Vec<mpsc::UnboundedSender<String>> = Vec::new();
+
+    loop {
+        tokio::select! {
+            event = event_rx.recv() => {
+                let Some(event) = event else { break };
+                snapshot.apply(&event);
+                let json = event.to_json();
+                clients.retain(|client| client.send(json.clone()).is_ok());
+            }
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(error) => {
+                        warn!(%error, "Failed to accept WebSocket connection");
+                        continue;
+                    }
+                };
+                let (client_tx, client_rx) = mpsc::unbounded_channel();
+                for event in snapshot.events() {
+                    let _ = client_tx.send(event.to_json());
+                }
+                clients.push(client_tx);
+                let permissions = permissions.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = serve_client(stream, &permissions, client_rx).await {
+                        debug!(%error, %peer, "WebSocket client disconnected");
+                    }
+                });
+            }
+        }
+    }
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    permissions: &Permissions,
+    mut client_rx: mpsc::UnboundedReceiver<String>,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+    use tokio_tungstenite::tungstenite::http::{header::AUTHORIZATION, StatusCode};
+
+    // The error type is fixed by tungstenite's handshake callback
+    #[allow(clippy::result_large_err)]
+    let authorize = |request: &Request, response: Response| {
+        let authorization = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        let token = request_token(authorization, request.uri().query().unwrap_or_default());
+        if permissions.allows(token.as_deref(), Permission::Status) {
+            return Ok(response);
+        }
+        let mut rejection = ErrorResponse::new(Some("unauthorized".to_string()));
+        *rejection.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(rejection)
+    };
+    let (mut sink, mut incoming) = tokio_tungstenite::accept_hdr_async(stream, authorize)
+        .await?
+        .split();
+    loop {
+        tokio::select! {
+            json = client_rx.recv() => {
+                let Some(json) = json else { break };
+                sink.send(Message::text(json)).await?;
+            }
+            message = incoming.next() => match message {
+                // Pings are answered by tungstenite; other client messages are ignored
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+            }
+        }
+    }
+    sink.close().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged() {
+        let event = Event::Track {
+            station: "Groove Salad".to_string(),
+            title: "Artist - Title".to_string(),
+        };
+        assert_eq!(
+            event.to_json(),
+            r#"{"event":"track","station":"Groove Salad","title":"Artist - Title"}"#
+        );
+        assert_eq!(
+            Event::State { state: "paused" }.to_json(),
+            r#"{"event":"state","state":"paused"}"#
+        );
+        assert_eq!(
+            Event::Progress {
+                listened_seconds: 90,
+                buffered_ms: 2500,
+                underruns: 1,
+            }
+            .to_json(),
+            r#"{"event":"progress","listened_seconds":90,"buffered_ms":2500,"underruns":1}"#
+        );
+    }
+
+    #[test]
+    fn snapshot_keeps_latest_state() {
+        let mut snapshot = Snapshot::default();
+        snapshot.apply(&Event::Track {
+            station: "Old".to_string(),
+            title: "Old track".to_string(),
+        });
+        snapshot.apply(&Event::Station {
+            id: "groovesalad".to_string(),
+            title: "Groove Salad".to_string(),
+            genre: "ambient".to_string(),
+        });
+        snapshot.apply(&Event::Volume { volume: 0.5 });
+        snapshot.apply(&Event::Volume { volume: 0.8 });
+        snapshot.apply(&Event::Error {
+            message: "oops".to_string(),
+        });
+
+        let events: Vec<&Event> = snapshot.events().collect();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], Event::Station { .. }));
+        assert_eq!(events[1], &Event::Volume { volume: 0.8 });
+    }
+}
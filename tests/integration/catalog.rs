@@ -0,0 +1,53 @@
+use crate::server::{MockIcecast, STATION_ID, STATION_TITLE};
+use somars_core::catalog_cache::CatalogCache;
+use somars_core::config::ApiConfig;
+use somars_core::providers::{SomaFmProvider, StationProvider};
+use somars_core::station::StreamQuality;
+
+fn provider(server: &MockIcecast) -> SomaFmProvider {
+    let api = ApiConfig {
+        base_url: server.base_url(),
+    };
+    // A TTL of zero keeps the test away from the user's catalog cache
+    SomaFmProvider::new(api, StreamQuality::Highest, CatalogCache::new(0, false))
+}
+
+#[tokio::test]
+async fn fetches_stations_from_the_configured_mirror() {
+    let server = MockIcecast::start().await;
+    let provider = provider(&server);
+
+    let stations = provider.fetch().await.unwrap();
+    assert!(!provider.offline());
+    assert_eq!(stations.len(), 1);
+    let station = &stations[0];
+    assert_eq!(station.id, STATION_ID);
+    assert_eq!(station.title, STATION_TITLE);
+    assert_eq!(station.listeners, 42);
+    assert_eq!(
+        station.playlist,
+        format!("{}/{}.pls", server.base_url(), STATION_ID)
+    );
+    assert_eq!(server.requests(), vec!["channels.json".to_string()]);
+}
+
+#[tokio::test]
+async fn resolves_the_playlist_to_the_stream() {
+    let server = MockIcecast::start().await;
+    let stations = provider(&server).fetch().await.unwrap();
+
+    assert_eq!(stations[0].stream_url().await, server.stream_url());
+    assert!(server.requests().contains(&format!("{}.pls", STATION_ID)));
+}
+
+#[tokio::test]
+async fn reports_an_unreachable_mirror() {
+    let server = MockIcecast::start().await;
+    let api = ApiConfig {
+        base_url: format!("{}/missing", server.base_url()),
+    };
+    let provider = SomaFmProvider::new(api, StreamQuality::Highest, CatalogCache::new(0, false));
+
+    assert!(provider.fetch().await.is_err());
+    assert!(provider.offline());
+}
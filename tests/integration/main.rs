@@ -0,0 +1,11 @@
+//! Integration tests
+//!
+//! These run the library against a local mock of the SomaFM API and an
+//! Icecast stream served by [`server::MockIcecast`], with the player on a
+//! sink that has no audio device behind it, so they need neither the network
+//! nor a sound card.
+
+mod catalog;
+mod playback;
+mod server;
+mod udp;
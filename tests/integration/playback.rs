@@ -0,0 +1,87 @@
+use crate::server::{MockIcecast, TRACK_TITLE};
+use rodio::Source;
+use somars_core::action::Action;
+use somars_core::audio::player::{self, PlayRequest, PlayerCommand, PlayerEvent};
+use somars_core::audio::stream::StreamConfig;
+use somars_core::audio::transition::Fader;
+use somars_core::audio::MetadataEvent;
+use somars_core::station::Station;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Longest wait for the player to get somewhere, well past the startup
+/// prefetch
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Wait for the player to report `wanted`, skipping other actions
+async fn wait_for(
+    actions: &mut mpsc::UnboundedReceiver<Action>,
+    wanted: impl Fn(&PlayerEvent) -> bool,
+) {
+    tokio::time::timeout(TIMEOUT, async {
+        loop {
+            match actions.recv().await {
+                Some(Action::Player(PlayerEvent::Ended { error, .. })) => {
+                    panic!("playback ended: {:?}", error)
+                }
+                Some(Action::Player(event)) if wanted(&event) => return,
+                Some(_) => {}
+                None => panic!("the player went away"),
+            }
+        }
+    })
+    .await
+    .expect("the player didn't get there in time");
+}
+
+#[tokio::test]
+async fn plays_and_stops_a_station() {
+    let server = MockIcecast::start().await;
+    let (metadata_tx, mut metadata_rx) = mpsc::channel(16);
+    let (log_tx, mut log_rx) = mpsc::channel(16);
+    let (action_tx, mut actions) = mpsc::unbounded_channel();
+    let (player, output) = player::spawn_detached(Fader::default(), metadata_tx, log_tx, action_tx);
+
+    // Play the output at about the pace a sound card would
+    let done = Arc::new(AtomicBool::new(false));
+    let listening = done.clone();
+    let listener = std::thread::spawn(move || {
+        let per_tick = output.sample_rate() as usize * output.channels() as usize / 100;
+        let mut output = output;
+        while !listening.load(Ordering::Relaxed) {
+            output.by_ref().take(per_tick).for_each(drop);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    });
+    tokio::spawn(async move { while log_rx.recv().await.is_some() {} });
+    let (title_tx, mut titles) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(event) = metadata_rx.recv().await {
+            if let MetadataEvent::Track { title, .. } = event {
+                let _ = title_tx.send(title);
+            }
+        }
+    });
+
+    player.send(PlayerCommand::Play(Box::new(PlayRequest {
+        station: Station::from_url(&server.stream_url()),
+        volume: 1.0,
+        config: StreamConfig::default(),
+    })));
+    wait_for(
+        &mut actions,
+        |event| matches!(event, PlayerEvent::Queued(queued) if *queued > 0),
+    )
+    .await;
+    let title = tokio::time::timeout(TIMEOUT, titles.recv()).await.unwrap();
+    assert_eq!(title.as_deref(), Some(TRACK_TITLE));
+
+    player.send(PlayerCommand::Stop);
+    wait_for(&mut actions, |event| *event == PlayerEvent::Queued(0)).await;
+
+    player.shutdown().await;
+    done.store(true, Ordering::Relaxed);
+    listener.join().unwrap();
+}
@@ -0,0 +1,136 @@
+//! Local stand-in for the SomaFM API and its Icecast servers
+//!
+//! Serves a one-channel `channels.json`, the channel's PLS playlist and an
+//! endless WAV stream of a sine tone with an ICY title, and remembers the
+//! paths asked for.
+
+use somars_core::mock_stream::{self, IcyFramer, BYTES_PER_SECOND, METAINT, SAMPLE_RATE};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+
+pub const STATION_ID: &str = "groovesalad";
+pub const STATION_TITLE: &str = "Groove Salad";
+pub const TRACK_TITLE: &str = "Mock Artist - Mock Track";
+/// Audio sent as soon as a listener connects, as Icecast servers do
+const BURST_SECONDS: usize = 4;
+
+pub struct MockIcecast {
+    port: u16,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockIcecast {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(respond(socket, port, seen.clone()));
+            }
+        });
+        Self { port, requests }
+    }
+
+    /// Base URL to configure as the API mirror
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    pub fn stream_url(&self) -> String {
+        format!("{}/{}", self.base_url(), STATION_ID)
+    }
+
+    /// Paths asked for so far, without the leading slash
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn respond(mut socket: TcpStream, port: u16, requests: Arc<Mutex<Vec<String>>>) {
+    let Ok(Some(path)) = mock_stream::read_request_path(&mut socket).await else {
+        return;
+    };
+    requests.lock().unwrap().push(path.clone());
+    let _ = match path.as_str() {
+        "channels.json" => send(&mut socket, "application/json", &channels()).await,
+        "groovesalad.pls" => {
+            let playlist = format!(
+                "[playlist]\nnumberofentries=1\nFile1=http://127.0.0.1:{}/{}\nTitle1={}\n",
+                port, STATION_ID, STATION_TITLE
+            );
+            send(&mut socket, "audio/x-scpls", &playlist).await
+        }
+        STATION_ID => stream(&mut socket).await,
+        _ => {
+            socket
+                .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                .await
+        }
+    };
+}
+
+async fn send(socket: &mut TcpStream, content_type: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await
+}
+
+/// The catalog, with playlist links on somafm.com as the real one has them
+fn channels() -> String {
+    serde_json::json!({
+        "channels": [{
+            "id": STATION_ID,
+            "title": STATION_TITLE,
+            "description": "A nicely chilled plate of ambient beats",
+            "dj": "Rusty Hodge",
+            "genre": "ambient|electronica",
+            "image": "https://api.somafm.com/img/groovesalad120.png",
+            "lastPlaying": TRACK_TITLE,
+            "listeners": "42",
+            "playlists": [{
+                "url": "https://somafm.com/groovesalad.pls",
+                "format": "mp3",
+                "quality": "highest"
+            }]
+        }]
+    })
+    .to_string()
+}
+
+/// A 440 Hz tone with the track title, until the listener goes away
+async fn stream(socket: &mut TcpStream) -> std::io::Result<()> {
+    socket
+        .write_all(mock_stream::response_header(STATION_TITLE).as_bytes())
+        .await?;
+    let mut framer = IcyFramer::new(METAINT);
+    let mut audio = mock_stream::wav_header();
+    let mut sample = 0u64;
+    let tone = |audio: &mut Vec<u8>, sample: &mut u64, bytes: usize| {
+        for _ in 0..bytes / 2 {
+            let t = *sample as f32 / SAMPLE_RATE as f32;
+            let value = ((t * 440.0 * std::f32::consts::TAU).sin() * 8_000.0) as i16;
+            audio.extend_from_slice(&value.to_le_bytes());
+            *sample += 1;
+        }
+    };
+    tone(&mut audio, &mut sample, BURST_SECONDS * BYTES_PER_SECOND);
+    socket
+        .write_all(&framer.frame(&std::mem::take(&mut audio), TRACK_TITLE))
+        .await?;
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        interval.tick().await;
+        tone(&mut audio, &mut sample, BYTES_PER_SECOND / 10);
+        socket
+            .write_all(&framer.frame(&std::mem::take(&mut audio), TRACK_TITLE))
+            .await?;
+    }
+}
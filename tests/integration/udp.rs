@@ -0,0 +1,117 @@
+use somars_core::config::RemoteConfig;
+use somars_core::control::{ControlCommand, Permission, Permissions};
+use somars_core::instance;
+use somars_core::udp::handle_udp_commands;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+
+/// Start the command listener on a free port
+async fn listen(permissions: Permissions) -> (u16, mpsc::Receiver<ControlCommand>) {
+    let port = std::net::UdpSocket::bind(("127.0.0.1", 0))
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let (tx, rx) = mpsc::channel(8);
+    tokio::spawn(handle_udp_commands(port, permissions, tx));
+    (port, rx)
+}
+
+/// Send `packet` until the listener answers, returning the answer
+async fn request(socket: &UdpSocket, port: u16, packet: &str) -> String {
+    let mut buffer = [0u8; 1024];
+    for _ in 0..20 {
+        socket
+            .send_to(packet.as_bytes(), ("127.0.0.1", port))
+            .await
+            .unwrap();
+        if let Ok(Ok(len)) =
+            tokio::time::timeout(Duration::from_millis(100), socket.recv(&mut buffer)).await
+        {
+            return String::from_utf8_lossy(&buffer[..len]).into_owned();
+        }
+    }
+    panic!("no answer to {:?}", packet);
+}
+
+#[tokio::test]
+async fn acknowledges_commands_once() {
+    let (port, mut commands) = listen(Permissions::default()).await;
+    let socket = UdpSocket::bind(("127.0.0.1", 0)).await.unwrap();
+
+    assert_eq!(
+        request(&socket, port, "seq 7 tune groovesalad").await,
+        "ok 7"
+    );
+    assert_eq!(
+        commands.recv().await,
+        Some(ControlCommand::Tune("groovesalad".to_string()))
+    );
+
+    // A retransmission is acknowledged again but not run again
+    assert_eq!(
+        request(&socket, port, "seq 7 tune groovesalad").await,
+        "ok 7"
+    );
+    assert_eq!(request(&socket, port, "seq 8 toggle pause").await, "ok 8");
+    assert_eq!(commands.recv().await, Some(ControlCommand::TogglePause));
+}
+
+#[tokio::test]
+async fn refuses_senders_without_a_token() {
+    let config = RemoteConfig {
+        anonymous: Permission::None,
+        tokens: [("secret".to_string(), Permission::Playback)].into(),
+    };
+    let (port, mut commands) = listen(Permissions::from_config(&config)).await;
+    let socket = UdpSocket::bind(("127.0.0.1", 0)).await.unwrap();
+
+    assert_eq!(
+        request(&socket, port, "seq 1 tune groovesalad").await,
+        "err 1 a token is required"
+    );
+    assert_eq!(
+        request(&socket, port, "token secret seq 2 tune groovesalad").await,
+        "ok 2"
+    );
+    assert_eq!(
+        commands.recv().await,
+        Some(ControlCommand::Tune("groovesalad".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn forwards_commands_to_a_running_instance() {
+    let config = RemoteConfig {
+        anonymous: Permission::Status,
+        tokens: [("secret".to_string(), Permission::Admin)].into(),
+    };
+    let (port, mut commands) = listen(Permissions::from_config(&config)).await;
+    let forwarded = [
+        "tune groovesalad".to_string(),
+        "tune url http://127.0.0.1/stream".to_string(),
+    ];
+
+    instance::forward(port, Some("secret"), &forwarded)
+        .await
+        .unwrap();
+    assert_eq!(
+        commands.recv().await,
+        Some(ControlCommand::Tune("groovesalad".to_string()))
+    );
+    assert_eq!(
+        commands.recv().await,
+        Some(ControlCommand::TuneUrl(
+            "http://127.0.0.1/stream".to_string()
+        ))
+    );
+
+    // The instance's refusal comes back as the error
+    let error = instance::forward(port, None, &forwarded).await.unwrap_err();
+    assert!(
+        error.to_string().contains("playback permission"),
+        "{}",
+        error
+    );
+}